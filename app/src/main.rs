@@ -1,7 +1,7 @@
 use std::f32::consts::PI;
 
 use game_engine::{
-    camera::Camera, gltf::load_gltf, mesh::{generate_mesh_normals, generate_mesh_plane}, obj_loader::load_model_static, GameEngine, MkGameEngine, ProjectionInit
+    camera::Camera, gltf::load_gltf, mesh::{generate_mesh_plane, generate_smooth_normals}, obj_loader::load_model_static, GameEngine, MkGameEngine, ProjectionInit
 };
 use glam::{vec3, Vec3};
 use noise::{NoiseFn, SuperSimplex};
@@ -11,6 +11,9 @@ use renderer::{
 use tracing_subscriber::{filter::EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 use winit::{event_loop::EventLoop, window::Window};
 
+/// Terrain slopes meeting at a sharper angle than this keep a hard edge.
+const TERRAIN_CREASE_ANGLE: f32 = PI / 3.0;
+
 pub struct GameState {
     noises: Vec<(u32, f32, f32)>,
     noises_detection: Vec<(u32, f32, f32)>,
@@ -34,6 +37,8 @@ fn setup(game_engine: &mut GameEngine) -> GameState {
             // TODO: Again think about how far to push the errors
             .create_3d_pipeline(&include_wgsl!("../shaders/cube.wgsl"))
             .unwrap(),
+        texture_id: None,
+        transparent: false,
     };
 
     let reload_cube_bundle = MeshBundle {
@@ -44,6 +49,8 @@ fn setup(game_engine: &mut GameEngine) -> GameState {
                 "app/shaders/cube_reload_test.wgsl".into(),
             ))
             .unwrap(),
+        texture_id: None,
+        transparent: false,
     };
 
     let loaded_objects = load_gltf(&mut game_engine.renderer, "app/assets/umbrella.glb").unwrap();
@@ -59,7 +66,7 @@ fn setup(game_engine: &mut GameEngine) -> GameState {
         v.z = z as f32;
     }
 
-    let normals = generate_mesh_normals(&vertices, &indices);
+    let normals = generate_smooth_normals(&vertices, &indices, TERRAIN_CREASE_ANGLE);
     let terain_bundle = MeshBundle {
         mesh_id: game_engine
             .renderer
@@ -70,6 +77,8 @@ fn setup(game_engine: &mut GameEngine) -> GameState {
             .renderer
             .create_3d_pipeline(&include_wgsl!("../shaders/terain.wgsl"))
             .unwrap(),
+        texture_id: None,
+        transparent: false,
     };
 
     GameState {
@@ -118,12 +127,14 @@ fn update(state: &mut GameState, game_engine: &mut GameEngine) {
             }
             v.z = z;
         }
-        let normals = generate_mesh_normals(&state.vertices, &state.indices);
+        let normals = generate_smooth_normals(&state.vertices, &state.indices, TERRAIN_CREASE_ANGLE);
         let gpu_mesh_id = game_engine
             .renderer
-            .rendering_context
-            .resource_store
-            .build_gpu_mesh(&state.vertices, &normals, &state.indices);
+            .add_mesh(&state.vertices, &normals, &state.indices);
+        game_engine
+            .renderer
+            .remove_mesh(state.terain_bundle.mesh_id)
+            .unwrap();
         state.terain_bundle.mesh_id = gpu_mesh_id;
     }
 
@@ -144,9 +155,7 @@ fn render(state: &GameState, renderer: &mut Renderer) {
         Transform::from_rotation_euler(&vec3(0.0, 0.0, state.cube_rotation));
     reload_cube_transform.set_translation(&vec3(-10.0, 80.0, 10.0));
     renderer.draw_mesh(&reload_cube_transform, &state.reload_cube_bundle);
-    for node in state.loaded_objects.iter() {
-        renderer.draw_scene_node(node);
-    }
+    renderer.submit_scene(&state.loaded_objects);
 }
 
 fn main() -> color_eyre::eyre::Result<()> {
@@ -2,15 +2,9 @@ use std::{f64::consts::PI, iter::repeat_with};
 
 use game_engine::{GameEngine, MkGameEngine};
 use glam::{dvec2, vec3, DVec2};
-use physics::{Engine, Particle, Shape};
+use physics::prelude::*;
 use rand::Rng;
-use renderer::line_rendering::Line;
-use renderer::Renderer;
-use renderer::{
-    circle_rendering::Circle,
-    colors::{RED, YELLOW},
-    transform::Transform,
-};
+use renderer::prelude::*;
 use tracing::debug;
 use tracing_subscriber::{filter::EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 use winit::{event_loop::EventLoop, window::Window};
@@ -55,7 +49,7 @@ fn setup(_game_engine: &mut GameEngine) -> GameState {
         })
         .take(CIRCLE_NUMBER),
     );
-    game_state.engine.particles.push(Particle {
+    game_state.engine.add_particle(Particle {
         inv_mass: 0.0,
         inv_inertia: 0.0,
         pos: dvec2(0.0, 500.0),
@@ -64,7 +58,7 @@ fn setup(_game_engine: &mut GameEngine) -> GameState {
         },
         ..Default::default()
     });
-    game_state.engine.particles.push(Particle {
+    game_state.engine.add_particle(Particle {
         inv_mass: 0.0,
         inv_inertia: 0.0,
         pos: dvec2(0.0, -500.0),
@@ -73,14 +67,14 @@ fn setup(_game_engine: &mut GameEngine) -> GameState {
         },
         ..Default::default()
     });
-    game_state.engine.particles.push(Particle {
+    game_state.engine.add_particle(Particle {
         inv_mass: 0.0,
         inv_inertia: 0.0,
         pos: dvec2(500.0, 0.0),
         shape: Shape::HalfPlane { normal_angle: -PI },
         ..Default::default()
     });
-    game_state.engine.particles.push(Particle {
+    game_state.engine.add_particle(Particle {
         inv_mass: 0.0,
         inv_inertia: 0.0,
         pos: dvec2(-500.0, 0.0),
@@ -114,12 +108,12 @@ fn render(state: &GameState, renderer: &mut Renderer) {
                 let to: DVec2 = p.pos - extent * tangent;
                 renderer.draw_line(
                     &Transform::IDENTITY,
-                    &Line {
-                        from: vec3(from.x as f32, from.y as f32, 0.0),
-                        to: vec3(to.x as f32, to.y as f32, 0.0),
-                        color: YELLOW,
-                        width: 3.,
-                    },
+                    &Line::new(
+                        vec3(from.x as f32, from.y as f32, 0.0),
+                        vec3(to.x as f32, to.y as f32, 0.0),
+                        YELLOW,
+                        3.,
+                    ),
                 );
             }
             _ => {
@@ -1,5 +1,5 @@
 use game_engine::{GameEngine, MkGameEngine};
-use renderer::Renderer;
+use renderer::prelude::*;
 use tracing_subscriber::{filter::EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 use winit::{event_loop::EventLoop, window::Window};
 
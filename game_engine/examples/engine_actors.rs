@@ -2,12 +2,7 @@ use std::f32::consts::PI;
 
 use game_engine::{GameEngine, MkGameEngine};
 use glam::{vec3, Quat, Vec2};
-use renderer::colors::{GREEN, PINK, RED};
-use renderer::line_rendering::Line;
-use renderer::rectangle_rendering::{Rectangle, RectangleLine};
-use renderer::scene_node::SceneNode;
-use renderer::transform::Transform;
-use renderer::Renderer;
+use renderer::prelude::*;
 use tracing::debug;
 use tracing_subscriber::{filter::EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 use winit::{event_loop::EventLoop, window::Window};
@@ -0,0 +1,58 @@
+use game_engine::{
+    camera::Camera, obj_loader::load_model_static, CameraControllerInit, GameEngine, MkGameEngine,
+    ProjectionInit,
+};
+use glam::{vec3, Vec3};
+use renderer::{include_wgsl, prelude::*};
+use tracing_subscriber::{filter::EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+use winit::{event_loop::EventLoop, window::Window};
+
+const CUBE: &str = include_str!("../../app/assets/cube.obj");
+const CUBE_MATERIALS: [(&str, &str); 1] = [("cube.mtl", include_str!("../../app/assets/cube.mtl"))];
+
+pub struct GameState {
+    cube_bundle: MeshBundle,
+}
+
+fn setup(game_engine: &mut GameEngine) -> GameState {
+    let cube_bundle = MeshBundle {
+        mesh_id: load_model_static(&mut game_engine.renderer, CUBE, &CUBE_MATERIALS).unwrap(),
+        pipeline_id: game_engine
+            .renderer
+            .create_3d_pipeline(&include_wgsl!("../src/test_shader/default_mesh.wgsl"))
+            .unwrap(),
+        texture_id: None,
+        transparent: false,
+    };
+    GameState { cube_bundle }
+}
+
+fn update(_state: &mut GameState, _game_engine: &mut GameEngine) {}
+
+fn render(state: &GameState, renderer: &mut Renderer) {
+    renderer.draw_mesh(&Transform::IDENTITY, &state.cube_bundle);
+}
+
+fn main() -> color_eyre::eyre::Result<()> {
+    let fmt_layer = fmt::layer().pretty();
+    let filter_layer = EnvFilter::from_default_env();
+    tracing_subscriber::registry()
+        .with(fmt_layer)
+        .with(filter_layer)
+        .init();
+    color_eyre::install()?;
+    let event_loop = EventLoop::new()?;
+    let window = Window::new(&event_loop)?;
+    // Drag with the left mouse button to orbit the cube, scroll to zoom.
+    let (mut game_engine, event_loop) = pollster::block_on(GameEngine::new(
+        event_loop,
+        &window,
+        MkGameEngine::new(
+            ProjectionInit::Perspective,
+            Camera::looking_at(vec3(4., -6., 3.), Vec3::ZERO),
+        )
+        .with_camera_controller(CameraControllerInit::Orbit { target: Vec3::ZERO }),
+    ))?;
+    game_engine.run(event_loop, setup, &update, &render)?;
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use std::f32::consts::TAU;
+
+use game_engine::{GameEngine, MkGameEngine};
+use glam::vec3;
+use renderer::prelude::*;
+use tracing_subscriber::{filter::EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+use winit::{event_loop::EventLoop, window::Window};
+
+/// Circles along each side of the static grid, 100 * 100 in total.
+const GRID_SIDE: usize = 100;
+const SPACING: f32 = 10.0;
+
+pub struct GameState {
+    time: f32,
+}
+
+/// Uploads the grid once, afterwards the frames only submit the orbiting
+/// circle.
+fn setup(game_engine: &mut GameEngine) -> GameState {
+    let offset = (GRID_SIDE - 1) as f32 * SPACING / 2.0;
+    let circles: Vec<(Transform, Circle)> = (0..GRID_SIDE * GRID_SIDE)
+        .map(|index| {
+            let (x, y) = (index % GRID_SIDE, index / GRID_SIDE);
+            let t = index as f32 / (GRID_SIDE * GRID_SIDE) as f32;
+            let position = vec3(
+                x as f32 * SPACING - offset,
+                y as f32 * SPACING - offset,
+                0.0,
+            );
+            (
+                Transform::from_translation(&position),
+                Circle::new(SPACING * 0.4, BLUE.lerp(WHITE, t)),
+            )
+        })
+        .collect();
+    game_engine.renderer.add_static_circles(&circles);
+    GameState { time: 0.0 }
+}
+
+fn update(state: &mut GameState, game_engine: &mut GameEngine) {
+    state.time += game_engine.last_frame_delta;
+}
+
+fn render(state: &GameState, renderer: &mut Renderer) {
+    let angle = state.time * TAU / 5.0;
+    renderer.draw_circle(
+        &Transform::from_translation(&vec3(angle.cos() * 300.0, angle.sin() * 300.0, 0.0)),
+        &Circle::new(30.0, RED),
+    );
+}
+
+fn main() -> color_eyre::eyre::Result<()> {
+    let fmt_layer = fmt::layer().pretty();
+    let filter_layer = EnvFilter::from_default_env();
+    tracing_subscriber::registry()
+        .with(fmt_layer)
+        .with(filter_layer)
+        .init();
+    color_eyre::install()?;
+    let event_loop = EventLoop::new()?;
+    let window = Window::new(&event_loop)?;
+    let (mut game_engine, event_loop) = pollster::block_on(GameEngine::new(
+        event_loop,
+        &window,
+        MkGameEngine::game_engine_2_5d_parameters(),
+    ))?;
+    game_engine.run(event_loop, setup, &update, &render)?;
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use std::f32::consts::PI;
+
+use game_engine::{
+    camera::Camera,
+    mesh::{generate_mesh_plane, generate_smooth_normals},
+    CameraControllerInit, GameEngine, MkGameEngine, ProjectionInit,
+};
+use glam::{vec3, Vec3};
+use renderer::prelude::*;
+use tracing_subscriber::{filter::EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+use winit::{event_loop::EventLoop, window::Window};
+
+const SIZE: u32 = 40;
+
+pub struct GameState {
+    terrain_bundle: MeshBundle,
+    wireframe: bool,
+}
+
+fn setup(game_engine: &mut GameEngine) -> GameState {
+    let (mut vertices, indices) = generate_mesh_plane(SIZE, SIZE, 1.);
+    for v in vertices.iter_mut() {
+        v.z = (v.x / 4.).sin() * (v.y / 6.).cos() * 2.;
+    }
+    let normals = generate_smooth_normals(&vertices, &indices, PI / 3.);
+    let colors: Vec<_> = vertices
+        .iter()
+        .map(|v| GREEN.lerp(WHITE, (v.z + 2.) / 4.))
+        .collect();
+    let renderer = &mut game_engine.renderer;
+    let terrain_bundle = MeshBundle {
+        mesh_id: renderer.add_mesh_colored(&vertices, &normals, &colors, &indices),
+        pipeline_id: renderer.create_3d_pipeline_vertex_colored().unwrap(),
+        texture_id: None,
+        transparent: false,
+    };
+    GameState {
+        terrain_bundle,
+        wireframe: false,
+    }
+}
+
+fn update(state: &mut GameState, game_engine: &mut GameEngine) {
+    let mut wireframe = state.wireframe;
+    egui::Window::new("Debug").show(game_engine.egui(), |ui| {
+        ui.checkbox(&mut wireframe, "Wireframe");
+    });
+    if wireframe != state.wireframe {
+        match game_engine.renderer.set_wireframe(wireframe) {
+            Ok(()) => state.wireframe = wireframe,
+            Err(err) => tracing::warn!("{err}"),
+        }
+    }
+}
+
+fn render(state: &GameState, renderer: &mut Renderer) {
+    let half_size = SIZE as f32 / 2.;
+    renderer.draw_mesh(
+        &Transform::from_translation(&vec3(-half_size, -half_size, 0.)),
+        &state.terrain_bundle,
+    );
+}
+
+fn main() -> color_eyre::eyre::Result<()> {
+    let fmt_layer = fmt::layer().pretty();
+    let filter_layer = EnvFilter::from_default_env();
+    tracing_subscriber::registry()
+        .with(fmt_layer)
+        .with(filter_layer)
+        .init();
+    color_eyre::install()?;
+    let event_loop = EventLoop::new()?;
+    let window = Window::new(&event_loop)?;
+    // Tick the checkbox to see the triangles of the terrain, drag with the
+    // left mouse button to orbit it.
+    let (mut game_engine, event_loop) = pollster::block_on(GameEngine::new(
+        event_loop,
+        &window,
+        MkGameEngine::new(
+            ProjectionInit::Perspective,
+            Camera::looking_at(vec3(0., -30., 20.), Vec3::ZERO),
+        )
+        .with_camera_controller(CameraControllerInit::Orbit { target: Vec3::ZERO }),
+    ))?;
+    game_engine.run(event_loop, setup, &update, &render)?;
+    Ok(())
+}
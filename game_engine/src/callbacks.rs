@@ -0,0 +1,87 @@
+use winit::dpi::PhysicalSize;
+
+type Callback<State, Arg> = Box<dyn FnMut(&mut State, Arg)>;
+
+/// Optional reactions of the game to window events, passed to
+/// [`GameEngine::run_with_callbacks`](crate::GameEngine::run_with_callbacks).
+/// Every callback defaults to doing nothing.
+pub struct WindowCallbacks<State> {
+    on_resize: Callback<State, PhysicalSize<u32>>,
+    on_focus: Callback<State, bool>,
+    on_close_requested: Callback<State, ()>,
+}
+
+impl<State> Default for WindowCallbacks<State> {
+    fn default() -> Self {
+        Self {
+            on_resize: Box::new(|_, _| {}),
+            on_focus: Box::new(|_, _| {}),
+            on_close_requested: Box::new(|_, _| {}),
+        }
+    }
+}
+
+impl<State> WindowCallbacks<State> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Called with the new size of the window in physical pixels, after the
+    /// surface and the renderer were reconfigured for it.
+    pub fn on_resize(self, on_resize: impl FnMut(&mut State, PhysicalSize<u32>) + 'static) -> Self {
+        Self {
+            on_resize: Box::new(on_resize),
+            ..self
+        }
+    }
+
+    /// Called with `true` when the window gains focus and `false` when it
+    /// loses it, e.g. to pause the game.
+    pub fn on_focus(self, on_focus: impl FnMut(&mut State, bool) + 'static) -> Self {
+        Self {
+            on_focus: Box::new(on_focus),
+            ..self
+        }
+    }
+
+    /// Called when the user asks to close the window, right before the event
+    /// loop exits.
+    pub fn on_close_requested(
+        self,
+        mut on_close_requested: impl FnMut(&mut State) + 'static,
+    ) -> Self {
+        Self {
+            on_close_requested: Box::new(move |state, ()| on_close_requested(state)),
+            ..self
+        }
+    }
+
+    pub(crate) fn resized(&mut self, state: &mut State, size: PhysicalSize<u32>) {
+        (self.on_resize)(state, size)
+    }
+
+    pub(crate) fn focused(&mut self, state: &mut State, focused: bool) {
+        (self.on_focus)(state, focused)
+    }
+
+    pub(crate) fn close_requested(&mut self, state: &mut State) {
+        (self.on_close_requested)(state, ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unset_callbacks_do_nothing() {
+        let mut state = 0;
+        let mut callbacks = WindowCallbacks::new()
+            .on_focus(|state: &mut i32, focused| *state += if focused { 1 } else { 10 });
+        callbacks.focused(&mut state, false);
+        callbacks.focused(&mut state, true);
+        callbacks.resized(&mut state, PhysicalSize::new(10, 10));
+        callbacks.close_requested(&mut state);
+        assert_eq!(state, 11);
+    }
+}
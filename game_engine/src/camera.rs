@@ -1,14 +1,18 @@
 use glam::{vec3, Mat4, Vec3};
 use std::f32::consts::FRAC_PI_2;
-use winit::{
-    dpi::PhysicalPosition,
-    event::{MouseButton, MouseScrollDelta},
-    keyboard::KeyCode,
-};
+use winit::{event::MouseButton, keyboard::KeyCode};
 
 use crate::inputs::Inputs;
 
 const SAFE_FRAC_PI_2: f32 = FRAC_PI_2 - 0.0001;
+/// Distance one line of scrolling moves the camera, as a fraction of its
+/// speed.
+const ZOOM_PER_LINE: f32 = 0.1;
+/// Factor one line of scrolling up scales the orbit distance by.
+const ORBIT_ZOOM_PER_LINE: f32 = 0.9;
+/// Closest the orbit camera gets to its target, so that zooming back out
+/// still works.
+const MIN_ORBIT_DISTANCE: f32 = 0.1;
 
 #[derive(Debug)]
 pub struct Camera {
@@ -26,43 +30,67 @@ impl Camera {
         }
     }
 
-    pub fn calc_matrix(&self) -> Mat4 {
+    /// Creates a camera at `position` aimed at `target`. The pitch is clamped
+    /// just short of straight up or down, so the camera never flips over.
+    pub fn looking_at<V: Into<Vec3>, T: Into<Vec3>>(position: V, target: T) -> Self {
+        let mut camera = Self::new(position, 0.0, 0.0);
+        camera.look_at(target);
+        camera
+    }
+
+    /// Rotates the camera in place so it faces `target`. Does nothing when
+    /// `target` coincides with the camera position.
+    pub fn look_at<T: Into<Vec3>>(&mut self, target: T) {
+        let Some(direction) = (target.into() - self.position).try_normalize() else {
+            return;
+        };
+        self.yaw = direction.x.atan2(direction.y);
+        self.pitch = direction.z.asin().clamp(-SAFE_FRAC_PI_2, SAFE_FRAC_PI_2);
+    }
+
+    /// Unit vector in the direction the camera is facing.
+    pub fn forward(&self) -> Vec3 {
         let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
         let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        vec3(sin_yaw * cos_pitch, cos_yaw * cos_pitch, sin_pitch)
+    }
 
+    pub fn calc_matrix(&self) -> Mat4 {
         Mat4::look_to_rh(
             self.position,
-            vec3(sin_yaw * cos_pitch, cos_yaw * cos_pitch, sin_pitch),
+            self.forward(),
             //vec3(sin_pitch * sin_yaw, sin_pitch, sin_pitch * cos_yaw).normalize(),
             vec3(0., 0., 1.),
         )
         //Mat4::IDENTITY
     }
 }
+/// Moves the camera every frame according to the user's inputs.
+pub trait CameraControl {
+    fn update_camera(&mut self, camera: &mut Camera, dt: f32, inputs: &Inputs);
+}
+
+/// Free flying FPS-style controller: WASD moves, space and left shift go up
+/// and down, dragging with the left mouse button looks around and scrolling
+/// moves along the view direction.
 #[derive(Debug)]
 pub struct CameraController {
-    scroll: f32,
     speed: f32,
     sensitivity: f32,
 }
 
 impl CameraController {
     pub fn new(speed: f32, sensitivity: f32) -> Self {
-        Self {
-            scroll: 0.0,
-            speed,
-            sensitivity,
-        }
+        Self { speed, sensitivity }
     }
 
-    pub fn process_scroll(&mut self, delta: &MouseScrollDelta) {
-        self.scroll = match delta {
-            MouseScrollDelta::LineDelta(_, scroll) => -scroll * 0.5,
-            MouseScrollDelta::PixelDelta(PhysicalPosition { y: scroll, .. }) => -*scroll as f32,
-        };
+    pub fn update_camera(&mut self, camera: &mut Camera, dt: f32, inputs: &Inputs) {
+        CameraControl::update_camera(self, camera, dt, inputs)
     }
+}
 
-    pub fn update_camera(&mut self, camera: &mut Camera, dt: f32, inputs: &Inputs) {
+impl CameraControl for CameraController {
+    fn update_camera(&mut self, camera: &mut Camera, dt: f32, inputs: &Inputs) {
         let mut forward_backward: f32 = 0.;
         let mut left_rigth: f32 = 0.;
         let mut up_down: f32 = 0.;
@@ -92,17 +120,13 @@ impl CameraController {
         camera.position += forward * forward_backward * self.speed * dt;
         camera.position += right * left_rigth * self.speed * dt;
 
-        // TODO: Fix zoom, it probably is still not translated to x-y horizontal
-        // plane.
-        //
         // Move in/out (aka. "zoom")
         // Note: this isn't an actual zoom. The camera's position
         // changes when zooming. I've added this to make it easier
-        // to get closer to an object you want to focus on.
-        let (pitch_sin, pitch_cos) = camera.pitch.sin_cos();
-        let scrollward = Vec3::new(pitch_cos * yaw_cos, pitch_sin, pitch_cos * yaw_sin).normalize();
-        camera.position += scrollward * self.scroll * self.speed * self.sensitivity * dt;
-        self.scroll = 0.0;
+        // to get closer to an object you want to focus on. Scrolling up moves
+        // towards where the camera looks, scroll is already a per frame
+        // amount so it isn't scaled by `dt`.
+        camera.position += camera.forward() * inputs.scroll_delta().y * self.speed * ZOOM_PER_LINE;
 
         // Move up/down. Since we don't use roll, we can just
         // modify the y coordinate directly.
@@ -118,3 +142,102 @@ impl CameraController {
         camera.pitch = camera.pitch.clamp(-SAFE_FRAC_PI_2, SAFE_FRAC_PI_2);
     }
 }
+
+/// Keeps the camera looking at a target point: dragging with the left mouse
+/// button rotates the camera around it and scrolling zooms in and out.
+///
+/// The orbit starts from wherever the camera is, keeping its view direction
+/// and distance to the target. The pitch stops just short of the poles, so
+/// the camera never flips over.
+#[derive(Debug)]
+pub struct OrbitCameraController {
+    target: Vec3,
+    /// Radians the camera turns per pixel of mouse movement.
+    sensitivity: f32,
+}
+
+impl OrbitCameraController {
+    pub fn new(target: Vec3, sensitivity: f32) -> Self {
+        Self {
+            target,
+            sensitivity,
+        }
+    }
+
+    pub fn target(&self) -> Vec3 {
+        self.target
+    }
+
+    pub fn set_target(&mut self, target: Vec3) {
+        self.target = target;
+    }
+}
+
+impl CameraControl for OrbitCameraController {
+    fn update_camera(&mut self, camera: &mut Camera, _dt: f32, inputs: &Inputs) {
+        if inputs.is_button_pressed(MouseButton::Left) {
+            let (dx, dy) = inputs.cursor_delta.unwrap_or_default();
+            camera.yaw -= dx * self.sensitivity;
+            camera.pitch -= dy * self.sensitivity;
+        }
+        camera.pitch = camera.pitch.clamp(-SAFE_FRAC_PI_2, SAFE_FRAC_PI_2);
+
+        let distance = (camera.position - self.target).length()
+            * ORBIT_ZOOM_PER_LINE.powf(inputs.scroll_delta().y);
+        camera.position = self.target - camera.forward() * distance.max(MIN_ORBIT_DISTANCE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looking_at_faces_target() {
+        let position = vec3(1.0, -2.0, 3.0);
+        let target = vec3(4.0, 5.0, -1.0);
+        let camera = Camera::looking_at(position, target);
+        assert!(camera
+            .forward()
+            .abs_diff_eq((target - position).normalize(), 1e-5));
+    }
+
+    #[test]
+    fn scrolling_up_moves_towards_the_view_direction() {
+        let mut camera = Camera::looking_at(Vec3::ZERO, vec3(1.0, 1.0, -1.0));
+        let mut inputs = Inputs::new();
+        inputs.update_scroll(&winit::event::MouseScrollDelta::LineDelta(0.0, 2.0));
+        CameraController::new(10.0, 1.0).update_camera(&mut camera, 0.016, &inputs);
+        assert!(camera
+            .position
+            .abs_diff_eq(camera.forward() * 2.0 * 10.0 * ZOOM_PER_LINE, 1e-5));
+    }
+
+    #[test]
+    fn orbit_keeps_distance_and_stops_at_the_poles() {
+        let target = vec3(1.0, 2.0, 0.0);
+        let mut camera = Camera::looking_at(target + vec3(0.0, -5.0, 0.0), target);
+        let mut controller = OrbitCameraController::new(target, 0.01);
+        let mut inputs = Inputs::new();
+        inputs.update_mouse_buttons(&MouseButton::Left, &winit::event::ElementState::Pressed);
+        inputs.update_cursor_delta((40.0, -1000.0));
+        controller.update_camera(&mut camera, 0.016, &inputs);
+
+        assert!((camera.position.distance(target) - 5.0).abs() < 1e-4);
+        assert!(camera
+            .forward()
+            .abs_diff_eq((target - camera.position).normalize(), 1e-5));
+        assert_eq!(camera.pitch, SAFE_FRAC_PI_2);
+    }
+
+    #[test]
+    fn orbit_zooms_towards_the_target() {
+        let target = Vec3::ZERO;
+        let mut camera = Camera::looking_at(vec3(0.0, -10.0, 0.0), target);
+        let mut controller = OrbitCameraController::new(target, 0.01);
+        let mut inputs = Inputs::new();
+        inputs.update_scroll(&winit::event::MouseScrollDelta::LineDelta(0.0, 2.0));
+        controller.update_camera(&mut camera, 0.016, &inputs);
+        assert!((camera.position.length() - 10.0 * ORBIT_ZOOM_PER_LINE.powi(2)).abs() < 1e-4);
+    }
+}
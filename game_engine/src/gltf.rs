@@ -31,7 +31,7 @@ fn primitive_to_scene_node(renderer: &mut Renderer, transform: &Transform, primi
     }).expect("normals missing in the model");
 
     let mesh_id = renderer.add_mesh(&vertices, &normals, &indices);
-    let mesh_bundle = MeshBundle{pipeline_id, mesh_id};
+    let mesh_bundle = MeshBundle{pipeline_id, mesh_id, texture_id: None, transparent: false};
     Ok(SceneNode::from_mesh_bundle(*transform, mesh_bundle))
 }
 
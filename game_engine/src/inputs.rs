@@ -1,23 +1,36 @@
 use std::collections::{HashMap, HashSet};
 
+use glam::{vec2, Vec2};
 use winit::{
     dpi::PhysicalPosition,
-    event::{ElementState, MouseButton},
-    keyboard::{KeyCode, PhysicalKey},
+    event::{ElementState, MouseButton, MouseScrollDelta},
+    keyboard::{KeyCode, ModifiersState, PhysicalKey},
 };
 
+/// How many pixels of touchpad scrolling count as one line of a wheel mouse.
+const PIXELS_PER_LINE: f32 = 20.0;
+
 #[derive(Default)]
 pub struct Inputs {
     // keyboard
     pub pressed_keys: HashSet<PhysicalKey>,
     pub key_events: HashMap<PhysicalKey, ElementState>,
+    /// Keys that went down or up since the last frame, key repeats of held
+    /// keys don't count.
+    pub just_pressed_keys: HashSet<PhysicalKey>,
+    pub just_released_keys: HashSet<PhysicalKey>,
 
     // mouse
     pub mouse_pressed_keys: HashSet<MouseButton>,
     pub mouse_events: HashMap<MouseButton, ElementState>,
+    pub mouse_just_pressed_keys: HashSet<MouseButton>,
+    pub mouse_just_released_keys: HashSet<MouseButton>,
     pub current_position: PhysicalPosition<f32>,
     pub cursor_delta: Option<(f32, f32)>,
-    // TODO: Scroll wheel
+    /// Scroll accumulated since the last frame, see [`Inputs::scroll_delta`].
+    scroll_delta: Vec2,
+    /// Currently held ctrl/shift/alt/super modifiers.
+    pub modifiers: ModifiersState,
 }
 
 impl Inputs {
@@ -28,10 +41,14 @@ impl Inputs {
     pub fn update_key(&mut self, key: &PhysicalKey, state: &ElementState) {
         match state {
             ElementState::Pressed => {
-                self.pressed_keys.insert(*key);
+                if self.pressed_keys.insert(*key) {
+                    self.just_pressed_keys.insert(*key);
+                }
             }
             ElementState::Released => {
-                self.pressed_keys.remove(key);
+                if self.pressed_keys.remove(key) {
+                    self.just_released_keys.insert(*key);
+                }
             }
         }
         self.key_events.insert(*key, *state);
@@ -40,10 +57,14 @@ impl Inputs {
     pub fn update_mouse_buttons(&mut self, button: &MouseButton, state: &ElementState) {
         match state {
             ElementState::Pressed => {
-                self.mouse_pressed_keys.insert(*button);
+                if self.mouse_pressed_keys.insert(*button) {
+                    self.mouse_just_pressed_keys.insert(*button);
+                }
             }
             ElementState::Released => {
-                self.mouse_pressed_keys.remove(button);
+                if self.mouse_pressed_keys.remove(button) {
+                    self.mouse_just_released_keys.insert(*button);
+                }
             }
         }
         self.mouse_events.insert(*button, *state);
@@ -57,10 +78,28 @@ impl Inputs {
         self.cursor_delta = Some(delta);
     }
 
+    pub fn update_scroll(&mut self, delta: &MouseScrollDelta) {
+        self.scroll_delta += match delta {
+            MouseScrollDelta::LineDelta(x, y) => vec2(*x, *y),
+            MouseScrollDelta::PixelDelta(position) => {
+                vec2(position.x as f32, position.y as f32) / PIXELS_PER_LINE
+            }
+        };
+    }
+
+    pub fn update_modifiers(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
+
     pub fn reset_events(&mut self) {
         self.key_events.clear();
+        self.just_pressed_keys.clear();
+        self.just_released_keys.clear();
         self.mouse_events.clear();
+        self.mouse_just_pressed_keys.clear();
+        self.mouse_just_released_keys.clear();
         self.cursor_delta = None;
+        self.scroll_delta = Vec2::ZERO;
     }
 
     pub fn is_key_pressed(&self, key: KeyCode) -> bool {
@@ -68,6 +107,25 @@ impl Inputs {
         self.pressed_keys.contains(&key)
     }
 
+    /// Whether the key went down since the last frame. A key pressed and
+    /// released within one frame is both just pressed and just released.
+    pub fn is_key_just_pressed(&self, key: KeyCode) -> bool {
+        self.just_pressed_keys.contains(&PhysicalKey::Code(key))
+    }
+
+    /// Whether the key went up since the last frame.
+    pub fn is_key_just_released(&self, key: KeyCode) -> bool {
+        self.just_released_keys.contains(&PhysicalKey::Code(key))
+    }
+
+    /// Scroll since the last frame in lines, positive `y` scrolls up and
+    /// positive `x` right. Touchpad scrolling in pixels is converted to lines.
+    /// It is reset after every frame and contains only scroll egui did not
+    /// consume.
+    pub fn scroll_delta(&self) -> Vec2 {
+        self.scroll_delta
+    }
+
     pub fn cursor_moved(&self) -> bool {
         self.cursor_delta.is_some()
     }
@@ -76,7 +134,83 @@ impl Inputs {
         self.mouse_pressed_keys.contains(&button)
     }
 
+    /// Like [`Inputs::is_key_just_pressed`] for mouse buttons.
+    pub fn is_button_just_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_just_pressed_keys.contains(&button)
+    }
+
+    /// Like [`Inputs::is_key_just_released`] for mouse buttons.
+    pub fn is_button_just_released(&self, button: MouseButton) -> bool {
+        self.mouse_just_released_keys.contains(&button)
+    }
+
     pub fn is_physical_key_pressed(&self, key: &PhysicalKey) -> bool {
         self.pressed_keys.contains(key)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scroll_accumulates_in_lines_until_reset() {
+        let mut inputs = Inputs::new();
+        inputs.update_scroll(&MouseScrollDelta::LineDelta(0.0, 1.0));
+        inputs.update_scroll(&MouseScrollDelta::PixelDelta(PhysicalPosition::new(
+            -10.0, 40.0,
+        )));
+        assert_eq!(inputs.scroll_delta(), vec2(-0.5, 3.0));
+        inputs.reset_events();
+        assert_eq!(inputs.scroll_delta(), Vec2::ZERO);
+    }
+
+    fn key(inputs: &mut Inputs, state: ElementState) {
+        inputs.update_key(&PhysicalKey::Code(KeyCode::KeyJ), &state);
+    }
+
+    #[test]
+    fn key_press_is_reported_only_on_the_transition_frame() {
+        let mut inputs = Inputs::new();
+        key(&mut inputs, ElementState::Pressed);
+        assert!(inputs.is_key_just_pressed(KeyCode::KeyJ));
+        assert!(inputs.is_key_pressed(KeyCode::KeyJ));
+        inputs.reset_events();
+
+        // Held, including a key repeat.
+        key(&mut inputs, ElementState::Pressed);
+        assert!(!inputs.is_key_just_pressed(KeyCode::KeyJ));
+        assert!(inputs.is_key_pressed(KeyCode::KeyJ));
+        inputs.reset_events();
+
+        key(&mut inputs, ElementState::Released);
+        assert!(inputs.is_key_just_released(KeyCode::KeyJ));
+        assert!(!inputs.is_key_just_pressed(KeyCode::KeyJ));
+        assert!(!inputs.is_key_pressed(KeyCode::KeyJ));
+        inputs.reset_events();
+        assert!(!inputs.is_key_just_released(KeyCode::KeyJ));
+    }
+
+    #[test]
+    fn tap_within_one_frame_is_pressed_and_released() {
+        let mut inputs = Inputs::new();
+        key(&mut inputs, ElementState::Pressed);
+        key(&mut inputs, ElementState::Released);
+        assert!(inputs.is_key_just_pressed(KeyCode::KeyJ));
+        assert!(inputs.is_key_just_released(KeyCode::KeyJ));
+        assert!(!inputs.is_key_pressed(KeyCode::KeyJ));
+    }
+
+    #[test]
+    fn mouse_button_transitions() {
+        let mut inputs = Inputs::new();
+        inputs.update_mouse_buttons(&MouseButton::Right, &ElementState::Pressed);
+        assert!(inputs.is_button_just_pressed(MouseButton::Right));
+        inputs.reset_events();
+        assert!(!inputs.is_button_just_pressed(MouseButton::Right));
+        assert!(inputs.is_button_pressed(MouseButton::Right));
+        inputs.update_mouse_buttons(&MouseButton::Right, &ElementState::Released);
+        assert!(inputs.is_button_just_released(MouseButton::Right));
+        assert!(!inputs.is_button_just_released(MouseButton::Left));
+    }
+}
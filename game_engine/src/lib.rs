@@ -1,3 +1,4 @@
+pub mod callbacks;
 pub mod camera;
 mod egui_integration;
 pub mod inputs;
@@ -5,31 +6,33 @@ pub mod mesh;
 pub mod obj_loader;
 pub mod gltf;
 
-use camera::{Camera, CameraController};
+use callbacks::WindowCallbacks;
+use camera::{Camera, CameraControl, CameraController, OrbitCameraController};
 use egui_integration::EguiIntegration;
-use glam::{vec2, vec3, Vec2};
+use glam::{vec2, vec3, Vec2, Vec3};
 use inputs::Inputs;
-use renderer::camera::PrimaryCamera;
+use renderer::camera::{DepthMode, PrimaryCamera};
 use renderer::gpu_context::GpuContext;
 use renderer::projection::{CameraProjection, Orthographic, Perspective};
 use renderer::Renderer;
 use std::f32::consts::PI;
 use std::sync::Arc;
 use std::time::Instant;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use wgpu::util::parse_backends_from_comma_list;
 use wgpu::{
-    DeviceDescriptor, Features, Gles3MinorVersion, Instance, InstanceDescriptor, InstanceFlags,
+    Features, Gles3MinorVersion, Instance, InstanceDescriptor, InstanceFlags, Limits,
     PowerPreference, PresentMode, RequestAdapterOptions, Surface, SurfaceConfiguration,
     TextureUsages,
 };
 
 use winit::event::WindowEvent::{
-    CloseRequested, KeyboardInput, MouseInput, RedrawRequested, Resized, ScaleFactorChanged,
+    self, CloseRequested, Focused, KeyboardInput, ModifiersChanged, MouseInput, MouseWheel,
+    RedrawRequested, Resized, ScaleFactorChanged,
 };
-use winit::keyboard::NamedKey;
+use winit::keyboard::{KeyCode, NamedKey, PhysicalKey};
 
-use winit::window::Window;
+use winit::window::{Fullscreen, Window};
 use winit::{dpi::PhysicalSize, event::Event, event_loop::EventLoop};
 
 pub struct GameEngine<'a> {
@@ -39,9 +42,12 @@ pub struct GameEngine<'a> {
     pub renderer: Renderer,
     surface_configuration: SurfaceConfiguration,
     surface: Surface<'a>,
+    /// Present modes the surface supports, besides the always available
+    /// `AutoVsync` and `AutoNoVsync`.
+    supported_present_modes: Vec<PresentMode>,
     size: PhysicalSize<u32>,
     inputs: Inputs,
-    camera_controler: CameraController,
+    camera_controler: Box<dyn CameraControl>,
     camera: Camera,
     egui_integration: EguiIntegration,
 }
@@ -53,6 +59,10 @@ fn size_to_vec2(size: &PhysicalSize<u32>) -> Vec2 {
 pub struct MkGameEngine {
     projection: ProjectionInit,
     camera: Camera,
+    camera_controller: CameraControllerInit,
+    present_mode: PresentMode,
+    required_features: Features,
+    required_limits: Limits,
 }
 
 impl MkGameEngine {
@@ -60,12 +70,60 @@ impl MkGameEngine {
         MkGameEngine {
             projection,
             camera,
+            camera_controller: CameraControllerInit::FreeFly,
+            present_mode: PresentMode::AutoNoVsync,
+            required_features: Features::empty(),
+            required_limits: Renderer::wgpu_limits(),
+        }
+    }
+
+    /// Requests how frames are presented, `AutoNoVsync` rendering as fast as
+    /// possible by default. `Fifo` caps the frame rate to the display's
+    /// refresh rate, which saves battery. Unsupported modes fall back to a
+    /// supported one, see [`GameEngine::set_present_mode`].
+    pub fn with_present_mode(self, present_mode: PresentMode) -> MkGameEngine {
+        MkGameEngine {
+            present_mode,
+            ..self
+        }
+    }
+
+    /// Requests optional `wgpu` features the game needs from the device,
+    /// creating the engine fails, naming them, when the adapter lacks them. Wireframes (see
+    /// [`Renderer::set_wireframe`]) are enabled whenever the adapter can draw
+    /// them, without requesting them here.
+    pub fn with_features(self, required_features: Features) -> MkGameEngine {
+        MkGameEngine {
+            required_features,
+            ..self
+        }
+    }
+
+    /// Requests `wgpu` limits beyond [`Renderer::wgpu_limits`], for example
+    /// bigger textures. Creating the engine fails, naming the offending
+    /// limit, when the adapter can't provide them.
+    pub fn with_limits(self, required_limits: Limits) -> MkGameEngine {
+        MkGameEngine {
+            required_limits,
+            ..self
+        }
+    }
+
+    /// Selects how the user moves the camera, free flying by default.
+    pub fn with_camera_controller(self, camera_controller: CameraControllerInit) -> MkGameEngine {
+        MkGameEngine {
+            camera_controller,
+            ..self
         }
     }
 pub fn game_engine_3d_parameters() -> MkGameEngine {
     MkGameEngine {
         projection: ProjectionInit::Perspective,
         camera: Camera::new(vec3(0., 10., 0.), 0., 0.),
+        camera_controller: CameraControllerInit::FreeFly,
+        present_mode: PresentMode::AutoNoVsync,
+        required_features: Features::empty(),
+        required_limits: Renderer::wgpu_limits(),
     }
 }
 
@@ -73,16 +131,81 @@ pub fn game_engine_2_5d_parameters() -> MkGameEngine {
     MkGameEngine {
         projection: ProjectionInit::Orthographic,
         camera: Camera::new(vec3(0., 0., 10.), 0., -PI / 2.),
+        camera_controller: CameraControllerInit::FreeFly,
+        present_mode: PresentMode::AutoNoVsync,
+        required_features: Features::empty(),
+        required_limits: Renderer::wgpu_limits(),
     }
 }
 }
 
 
+/// Decides whether a window event that egui has already seen should also reach
+/// the game.
+///
+/// Events egui consumed (scrolling an egui panel, typing into a text field)
+/// stop there so the game does not react to them too. The exception are events
+/// carrying state the game has to track regardless of what egui does with
+/// them: modifier changes (otherwise ctrl/shift could get stuck when egui has
+/// focus), window lifecycle and focus events and redraws.
+fn passes_through_to_game(event: &WindowEvent, consumed: bool) -> bool {
+    !consumed
+        || matches!(
+            event,
+            ModifiersChanged(_)
+                | Resized(_)
+                | ScaleFactorChanged { .. }
+                | CloseRequested
+                | Focused(_)
+                | RedrawRequested
+        )
+}
+
+/// Picks `requested` when the surface supports it. Otherwise modes that wait
+/// for the display fall back to `Fifo`, which is always supported, so they
+/// still don't tear, and `Immediate` falls back to the best uncapped mode.
+fn choose_present_mode(requested: PresentMode, supported: &[PresentMode]) -> PresentMode {
+    match requested {
+        PresentMode::AutoVsync | PresentMode::AutoNoVsync => requested,
+        _ if supported.contains(&requested) => requested,
+        PresentMode::Immediate => PresentMode::AutoNoVsync,
+        PresentMode::Fifo | PresentMode::FifoRelaxed | PresentMode::Mailbox => PresentMode::Fifo,
+    }
+}
+
 pub enum ProjectionInit {
     Perspective,
     Orthographic,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FullscreenMode {
+    /// Window covering the whole monitor it is on, without changing the
+    /// monitor's video mode. Switching in and out is quick.
+    Borderless,
+    /// Exclusive access to the monitor in its highest resolution and refresh
+    /// rate.
+    Exclusive,
+}
+
+pub enum CameraControllerInit {
+    /// [`CameraController`] flying freely through the scene.
+    FreeFly,
+    /// [`OrbitCameraController`] circling around `target`.
+    Orbit { target: Vec3 },
+}
+
+impl CameraControllerInit {
+    fn build(self) -> Box<dyn CameraControl> {
+        match self {
+            CameraControllerInit::FreeFly => Box::new(CameraController::new(10., 1.)),
+            CameraControllerInit::Orbit { target } => {
+                Box::new(OrbitCameraController::new(target, 0.005))
+            }
+        }
+    }
+}
+
 impl<'a> GameEngine<'a> {
     pub async fn new(
         event_loop: EventLoop<()>,
@@ -115,16 +238,15 @@ impl<'a> GameEngine<'a> {
             })
             .await
             .ok_or_eyre("Could not request adapter")?;
-        let (device, queue) = adapter
-            .request_device(
-                &DeviceDescriptor {
-                    label: Some("GPU device"),
-                    required_features: Features::empty(),
-                    required_limits: Renderer::wgpu_limits(),
-                },
-                None, // Trace path
-            )
-            .await?;
+        let gpu_context = GpuContext::request(
+            &adapter,
+            Some("GPU device"),
+            game_engine_parameters.required_features
+                | (adapter.features() & Features::POLYGON_MODE_LINE),
+            game_engine_parameters.required_limits,
+        )
+        .await?;
+        let gpu_context = Arc::new(gpu_context);
 
         let swap_chain_capablities = surface.get_capabilities(&adapter);
         info!("surface formats: {:?}", swap_chain_capablities.formats);
@@ -140,13 +262,21 @@ impl<'a> GameEngine<'a> {
             width: size.width,
             height: size.height,
             // vsync off
-            present_mode: PresentMode::AutoNoVsync,
+            present_mode: choose_present_mode(
+                game_engine_parameters.present_mode,
+                &swap_chain_capablities.present_modes,
+            ),
             alpha_mode: swap_chain_capablities.alpha_modes[0],
             view_formats: vec![],
             desired_maximum_frame_latency: 1,
         };
-        surface.configure(&device, &surface_configuration);
-        let gpu_context = Arc::new(GpuContext::new(device, queue));
+        info!(
+            "requested present mode {:?}, using {:?} out of supported {:?}",
+            game_engine_parameters.present_mode,
+            surface_configuration.present_mode,
+            swap_chain_capablities.present_modes
+        );
+        surface.configure(gpu_context.device(), &surface_configuration);
         let projection = match game_engine_parameters.projection {
             ProjectionInit::Perspective => CameraProjection::Perspective(Perspective {
                 fovy: std::f32::consts::FRAC_PI_2, // In radians
@@ -178,6 +308,8 @@ impl<'a> GameEngine<'a> {
                     }),
                     write_mask: wgpu::ColorWrites::ALL,
                 }),
+                depth_mode: DepthMode::Standard,
+                sample_count: 1,
             },
         )
         .unwrap();
@@ -190,9 +322,10 @@ impl<'a> GameEngine<'a> {
                 renderer,
                 surface_configuration,
                 surface,
+                supported_present_modes: swap_chain_capablities.present_modes,
                 size,
                 inputs: Inputs::new(),
-                camera_controler: CameraController::new(10., 1.),
+                camera_controler: game_engine_parameters.camera_controller.build(),
                 camera: game_engine_parameters.camera,
                 egui_integration,
             },
@@ -207,6 +340,24 @@ impl<'a> GameEngine<'a> {
         update: &FUpdate,
         render: &FRender,
     ) -> eyre::Result<()>
+    where
+        FSetup: FnOnce(&mut GameEngine) -> State,
+        FUpdate: Fn(&mut State, &mut GameEngine),
+        FRender: Fn(&State, &mut Renderer),
+    {
+        self.run_with_callbacks(event_loop, setup, update, render, WindowCallbacks::new())
+    }
+
+    /// Like [`GameEngine::run`], additionally letting the game react to
+    /// window events through `callbacks`.
+    pub fn run_with_callbacks<State, FSetup, FUpdate, FRender>(
+        &mut self,
+        event_loop: EventLoop<()>,
+        setup: FSetup,
+        update: &FUpdate,
+        render: &FRender,
+        mut callbacks: WindowCallbacks<State>,
+    ) -> eyre::Result<()>
     where
         FSetup: FnOnce(&mut GameEngine) -> State,
         FUpdate: Fn(&mut State, &mut GameEngine),
@@ -220,8 +371,7 @@ impl<'a> GameEngine<'a> {
         event_loop.run(move |event, elwt| match event {
             Event::WindowEvent { event, .. } => {
                 let res = self.egui_integration.on_window_event(self.window, &event);
-                if res.consumed {
-                } else {
+                if passes_through_to_game(&event, res.consumed) {
                     match event {
                         ScaleFactorChanged {
                             scale_factor,
@@ -234,14 +384,25 @@ impl<'a> GameEngine<'a> {
                         Resized(physical_size) => {
                             self.on_resize(physical_size);
                             self.egui_integration.on_resize(physical_size);
+                            callbacks.resized(&mut state, physical_size);
+                        }
+                        CloseRequested => {
+                            callbacks.close_requested(&mut state);
+                            elwt.exit()
                         }
-                        CloseRequested => elwt.exit(),
+                        Focused(focused) => callbacks.focused(&mut state, focused),
                         KeyboardInput {
                             device_id: _,
                             event,
                             is_synthetic: _,
                         } => {
                             self.inputs.update_key(&event.physical_key, &event.state);
+                            if event.state.is_pressed()
+                                && !event.repeat
+                                && event.physical_key == PhysicalKey::Code(KeyCode::F11)
+                            {
+                                self.toggle_fullscreen();
+                            }
                             info!("Escape was pressed; terminating the event loop");
                             if let winit::keyboard::Key::Named(NamedKey::Escape) = event.logical_key
                             {
@@ -266,18 +427,25 @@ impl<'a> GameEngine<'a> {
                             self.redraw_requested(&mut state, update, render);
                             self.inputs.reset_events();
                         }
+                        MouseWheel {
+                            device_id: _,
+                            delta,
+                            phase: _,
+                        } => {
+                            self.inputs.update_scroll(&delta);
+                        }
+                        ModifiersChanged(modifiers) => {
+                            self.inputs.update_modifiers(modifiers.state());
+                        }
                         //winit::event::WindowEvent::ActivationTokenDone { serial, token } => todo!(),
                         //winit::event::WindowEvent::Moved(_) => todo!(),
                         //winit::event::WindowEvent::Destroyed => todo!(),
                         //winit::event::WindowEvent::DroppedFile(_) => todo!(),
                         //winit::event::WindowEvent::HoveredFile(_) => todo!(),
                         //winit::event::WindowEvent::HoveredFileCancelled => todo!(),
-                        //winit::event::WindowEvent::Focused(_) => todo!(),
-                        //winit::event::WindowEvent::ModifiersChanged(_) => todo!(),
                         //winit::event::WindowEvent::Ime(_) => todo!(),
                         //winit::event::WindowEvent::CursorEntered { device_id } => todo!(),
                         //winit::event::WindowEvent::CursorLeft { device_id } => todo!(),
-                        //winit::event::WindowEvent::MouseWheel { device_id, delta, phase } => todo!(),
                         //winit::event::WindowEvent::TouchpadMagnify { device_id, delta, phase } => todo!(),
                         //winit::event::WindowEvent::SmartMagnify { device_id } => todo!(),
                         //winit::event::WindowEvent::TouchpadRotate { device_id, delta, phase } => todo!(),
@@ -373,7 +541,135 @@ impl<'a> GameEngine<'a> {
         self.renderer.on_scale_factor_change(scale_factor);
     }
 
+    /// Switches the window to fullscreen on its current monitor, or back to a
+    /// window with `None`. F11 toggles borderless fullscreen too.
+    ///
+    /// The window gets resized as a result, and the surface and the renderer
+    /// are reconfigured when the resize event arrives, like for any other
+    /// resize. Moving to a monitor with a different DPI also changes the
+    /// scale factor, which is handled before the resize.
+    pub fn set_fullscreen(&self, mode: Option<FullscreenMode>) {
+        let fullscreen = match mode {
+            None => None,
+            Some(FullscreenMode::Borderless) => Some(Fullscreen::Borderless(None)),
+            Some(FullscreenMode::Exclusive) => {
+                let video_mode = self.window.current_monitor().and_then(|monitor| {
+                    monitor.video_modes().max_by_key(|video_mode| {
+                        let size = video_mode.size();
+                        (
+                            size.width * size.height,
+                            video_mode.refresh_rate_millihertz(),
+                            video_mode.bit_depth(),
+                        )
+                    })
+                });
+                match video_mode {
+                    Some(video_mode) => Some(Fullscreen::Exclusive(video_mode)),
+                    None => {
+                        warn!("no video mode for exclusive fullscreen, using borderless");
+                        Some(Fullscreen::Borderless(None))
+                    }
+                }
+            }
+        };
+        info!("setting fullscreen to {:?}", mode);
+        self.window.set_fullscreen(fullscreen);
+    }
+
+    pub fn fullscreen(&self) -> Option<FullscreenMode> {
+        self.window.fullscreen().map(|fullscreen| match fullscreen {
+            Fullscreen::Borderless(_) => FullscreenMode::Borderless,
+            Fullscreen::Exclusive(_) => FullscreenMode::Exclusive,
+        })
+    }
+
+    fn toggle_fullscreen(&self) {
+        match self.fullscreen() {
+            Some(_) => self.set_fullscreen(None),
+            None => self.set_fullscreen(Some(FullscreenMode::Borderless)),
+        }
+    }
+
+    /// Changes how frames are presented. When the surface doesn't support
+    /// `present_mode`, modes waiting for the display fall back to `Fifo` and
+    /// `Immediate` to `AutoNoVsync`. Returns the mode actually used.
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) -> PresentMode {
+        let selected = choose_present_mode(present_mode, &self.supported_present_modes);
+        info!("requested present mode {:?}, using {:?}", present_mode, selected);
+        self.surface_configuration.present_mode = selected;
+        self.surface.configure(
+            self.renderer.rendering_context.gpu_context.device(),
+            &self.surface_configuration,
+        );
+        selected
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        self.surface_configuration.present_mode
+    }
+
     pub fn egui(&self) -> &egui::Context {
         self.egui_integration.egui_context()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use winit::event::{DeviceId, Modifiers, MouseScrollDelta, TouchPhase};
+
+    use super::*;
+
+    #[test]
+    fn consumed_scroll_stays_with_egui() {
+        let scroll = MouseWheel {
+            device_id: unsafe { DeviceId::dummy() },
+            delta: MouseScrollDelta::LineDelta(0.0, 1.0),
+            phase: TouchPhase::Moved,
+        };
+        assert!(!passes_through_to_game(&scroll, true));
+        assert!(passes_through_to_game(&scroll, false));
+    }
+
+    #[test]
+    fn modifiers_always_reach_the_game() {
+        let modifiers = ModifiersChanged(Modifiers::default());
+        assert!(passes_through_to_game(&modifiers, true));
+        assert!(passes_through_to_game(&modifiers, false));
+    }
+
+    #[test]
+    fn supported_present_mode_is_kept() {
+        let supported = [PresentMode::Fifo, PresentMode::Mailbox];
+        assert_eq!(
+            choose_present_mode(PresentMode::Mailbox, &supported),
+            PresentMode::Mailbox
+        );
+        assert_eq!(
+            choose_present_mode(PresentMode::AutoVsync, &supported),
+            PresentMode::AutoVsync
+        );
+    }
+
+    #[test]
+    fn unsupported_present_mode_falls_back() {
+        let supported = [PresentMode::Fifo];
+        assert_eq!(
+            choose_present_mode(PresentMode::Mailbox, &supported),
+            PresentMode::Fifo
+        );
+        assert_eq!(
+            choose_present_mode(PresentMode::FifoRelaxed, &supported),
+            PresentMode::Fifo
+        );
+        assert_eq!(
+            choose_present_mode(PresentMode::Immediate, &supported),
+            PresentMode::AutoNoVsync
+        );
+    }
+
+    #[test]
+    fn focus_changes_always_reach_the_game() {
+        assert!(passes_through_to_game(&Focused(false), true));
+        assert!(passes_through_to_game(&Focused(true), false));
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use glam::{vec3, Vec3};
 use itertools::Itertools;
 
@@ -50,3 +52,158 @@ pub fn generate_mesh_normals(vertices: &[Vec3], indices: &[u32]) -> Vec<Vec3> {
     }
     normals
 }
+
+/// Like [`generate_mesh_normals`], but every vertex gets the average normal,
+/// weighted by angle, of the triangles around its position whose normal is
+/// within `angle_threshold` radians of the triangles it belongs to. Edges
+/// sharper than the threshold stay hard, while gently curved surfaces like the
+/// terrain are smoothed.
+///
+/// Vertices are matched by position, so triangles only share a normal across
+/// an edge when its vertices are at the same place. A vertex used by several
+/// triangles in `indices` can only get one normal, so hard edges need their
+/// own vertices for every side.
+pub fn generate_smooth_normals(
+    vertices: &[Vec3],
+    indices: &[u32],
+    angle_threshold: f32,
+) -> Vec<Vec3> {
+    let face_normals: Vec<Vec3> = indices
+        .iter()
+        .tuples()
+        .map(|(i1, i2, i3)| {
+            let p1 = vertices[*i1 as usize];
+            let p2 = vertices[*i2 as usize];
+            let p3 = vertices[*i3 as usize];
+            (p3 - p1).cross(p2 - p1).normalize_or_zero()
+        })
+        .collect();
+
+    // Triangles of every vertex, and of every position together with their
+    // angle at it, which weights their normal so the result doesn't depend
+    // on how a surface is split into triangles.
+    let mut vertex_faces = vec![Vec::new(); vertices.len()];
+    let mut position_faces: HashMap<[u32; 3], Vec<(usize, f32)>> = HashMap::new();
+    for (face, face_indices) in indices.chunks_exact(3).enumerate() {
+        for corner in 0..3 {
+            let index = face_indices[corner] as usize;
+            let p = vertices[index];
+            let a = vertices[face_indices[(corner + 1) % 3] as usize];
+            let b = vertices[face_indices[(corner + 2) % 3] as usize];
+            let angle = if face_normals[face] == Vec3::ZERO {
+                0.0
+            } else {
+                (a - p).angle_between(b - p)
+            };
+            vertex_faces[index].push(face);
+            position_faces
+                .entry(p.to_array().map(f32::to_bits))
+                .or_default()
+                .push((face, angle));
+        }
+    }
+
+    let min_cos = angle_threshold.cos();
+    vertices
+        .iter()
+        .zip(vertex_faces)
+        .map(|(vertex, own_faces)| {
+            position_faces
+                .get(&vertex.to_array().map(f32::to_bits))
+                .into_iter()
+                .flatten()
+                .filter(|(face, _)| {
+                    own_faces
+                        .iter()
+                        .any(|own_face| face_normals[*face].dot(face_normals[*own_face]) >= min_cos)
+                })
+                .map(|(face, angle)| face_normals[*face] * *angle)
+                .sum::<Vec3>()
+                .normalize_or_zero()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    /// A cube of side 2 with its own four vertices for every face.
+    fn cube() -> (Vec<Vec3>, Vec<u32>) {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for normal in [
+            Vec3::X,
+            Vec3::NEG_X,
+            Vec3::Y,
+            Vec3::NEG_Y,
+            Vec3::Z,
+            Vec3::NEG_Z,
+        ] {
+            let u = normal.any_orthonormal_vector();
+            let v = normal.cross(u);
+            let first = vertices.len() as u32;
+            for (x, y) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                vertices.push(normal + x * u + y * v);
+            }
+            indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+        }
+        (vertices, indices)
+    }
+
+    /// Distinct pairs of position and normal, rounded to ignore float noise.
+    fn distinct(vertices: &[Vec3], normals: &[Vec3]) -> HashSet<[i32; 6]> {
+        let rounded = |v: Vec3| (v * 1e4).round().to_array().map(|x| x as i32);
+        vertices
+            .iter()
+            .zip(normals)
+            .map(|(vertex, normal)| {
+                let [a, b, c] = rounded(*vertex);
+                let [d, e, f] = rounded(*normal);
+                [a, b, c, d, e, f]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cube_keeps_hard_edges_below_threshold() {
+        let (vertices, indices) = cube();
+        let flat = generate_mesh_normals(&vertices, &indices);
+        let normals = generate_smooth_normals(&vertices, &indices, PI / 6.0);
+
+        assert_eq!(distinct(&vertices, &normals).len(), 24);
+        for (normal, flat) in normals.iter().zip(flat) {
+            assert!(normal.abs_diff_eq(flat, 1e-6), "{normal} != {flat}");
+        }
+    }
+
+    #[test]
+    fn cube_corners_are_smoothed_above_threshold() {
+        let (vertices, indices) = cube();
+        let normals = generate_smooth_normals(&vertices, &indices, PI * 0.6);
+
+        // Every corner ends up with a single normal along its diagonal,
+        // pointing the same way as the flat normals.
+        assert_eq!(distinct(&vertices, &normals).len(), 8);
+        let flat = generate_mesh_normals(&vertices, &indices);
+        let sign = flat[0].dot(vertices[0]).signum();
+        for (vertex, normal) in vertices.iter().zip(&normals) {
+            let diagonal = sign * vertex.normalize();
+            assert!(normal.abs_diff_eq(diagonal, 1e-6), "{normal} != {diagonal}");
+        }
+    }
+
+    #[test]
+    fn flat_plane_is_smoothed_across_triangles() {
+        let (vertices, indices) = generate_mesh_plane(3, 3, 1.0);
+        let normals = generate_smooth_normals(&vertices, &indices, PI / 6.0);
+        let flat = generate_mesh_normals(&vertices, &indices);
+
+        for (normal, flat) in normals.iter().zip(flat) {
+            assert!(normal.abs_diff_eq(flat, 1e-6), "{normal} != {flat}");
+        }
+    }
+}
@@ -1,14 +1,29 @@
 use eyre::Result;
-use glam::Vec3;
+use glam::{Vec2, Vec3, Vec4};
 use itertools::Itertools;
 use renderer::{resource_store::GpuMeshId, Renderer};
 use tobj::{load_mtl_buf, load_obj_buf, LoadError, LoadOptions};
+use tracing::warn;
 
-pub fn load_model_static(
-    renderer: &mut Renderer,
+/// Geometry of every object in an OBJ file merged into one mesh.
+#[derive(Debug, Default)]
+pub struct ObjMesh {
+    pub vertices: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    /// Texture coordinates, empty when no object has any.
+    pub uvs: Vec<Vec2>,
+    /// Diffuse color (`Kd`, with `d` as alpha) of the material of every vertex,
+    /// empty when no material has one. Vertices without it are white.
+    pub colors: Vec<Vec4>,
+    pub indices: Vec<u32>,
+}
+
+/// Parses an OBJ file, `materials` maps the names of the MTL files it uses to
+/// their contents.
+pub fn parse_obj_static(
     data: &'static str,
     materials: &[(&'static str, &'static str)],
-) -> Result<GpuMeshId> {
+) -> Result<ObjMesh> {
     let config = LoadOptions {
         single_index: true,
         triangulate: false,
@@ -16,7 +31,7 @@ pub fn load_model_static(
         ignore_lines: true,
     };
 
-    let data = load_obj_buf(&mut data.as_bytes(), &config, |path| {
+    let (models, obj_materials) = load_obj_buf(&mut data.as_bytes(), &config, |path| {
         let name = path.to_str().ok_or(LoadError::OpenFileFailed)?;
         let data = materials
             .iter()
@@ -24,26 +39,160 @@ pub fn load_model_static(
             .ok_or(LoadError::OpenFileFailed)?;
         load_mtl_buf(&mut data.as_bytes())
     })?;
-
-    let model = &data.0[0];
-    let vertices = model
-        .mesh
-        .positions
+    // Like a missing texture, a missing material only costs the colors.
+    let obj_materials = obj_materials.unwrap_or_else(|err| {
+        warn!("can't load the materials of an OBJ file: {err}");
+        Vec::new()
+    });
+    let diffuse_colors = obj_materials
         .iter()
-        .tuples()
-        .map(|(x, y, z)| Vec3::new(*x, *y, *z))
-        .collect::<Vec<Vec3>>();
-    let normals = model
-        .mesh
-        .normals
-        .iter()
-        .tuples()
-        .map(|(x, y, z)| Vec3::new(*x, *y, *z))
-        .collect::<Vec<Vec3>>();
-
-    Ok(renderer.rendering_context.resource_store.build_gpu_mesh(
-        &vertices,
-        &normals,
-        &model.mesh.indices,
-    ))
+        .map(|material| {
+            material
+                .diffuse
+                .map(|[r, g, b]| Vec4::new(r, g, b, material.dissolve.unwrap_or(1.0)))
+        })
+        .collect::<Vec<Option<Vec4>>>();
+    let has_uvs = models.iter().any(|model| !model.mesh.texcoords.is_empty());
+    let has_colors = diffuse_colors.iter().any(Option::is_some);
+
+    let mut mesh = ObjMesh::default();
+    for model in &models {
+        let first = mesh.vertices.len() as u32;
+        mesh.vertices.extend(
+            model
+                .mesh
+                .positions
+                .iter()
+                .tuples()
+                .map(|(x, y, z)| Vec3::new(*x, *y, *z)),
+        );
+        let vertex_count = mesh.vertices.len() - first as usize;
+        mesh.normals.extend(
+            model
+                .mesh
+                .normals
+                .iter()
+                .tuples()
+                .map(|(x, y, z)| Vec3::new(*x, *y, *z)),
+        );
+
+        // OBJ puts the origin of texture coordinates in the bottom left
+        // corner, wgpu in the top left one.
+        if has_uvs {
+            let uvs = model
+                .mesh
+                .texcoords
+                .iter()
+                .tuples()
+                .map(|(u, v)| Vec2::new(*u, 1.0 - *v))
+                .collect::<Vec<Vec2>>();
+            if uvs.is_empty() {
+                mesh.uvs.resize(mesh.vertices.len(), Vec2::ZERO);
+            } else {
+                mesh.uvs.extend(uvs);
+            }
+        }
+
+        if has_colors {
+            let color = model
+                .mesh
+                .material_id
+                .and_then(|material_id| diffuse_colors[material_id])
+                .unwrap_or(Vec4::ONE);
+            mesh.colors.extend(std::iter::repeat_n(color, vertex_count));
+        }
+
+        mesh.indices
+            .extend(model.mesh.indices.iter().map(|index| first + index));
+    }
+    Ok(mesh)
+}
+
+/// Loads an OBJ file with [`parse_obj_static`] and uploads it. Meshes with
+/// texture coordinates are built for a textured pipeline, otherwise meshes
+/// whose materials have a diffuse color are built with vertex colors.
+pub fn load_model_static(
+    renderer: &mut Renderer,
+    data: &'static str,
+    materials: &[(&'static str, &'static str)],
+) -> Result<GpuMeshId> {
+    let mesh = parse_obj_static(data, materials)?;
+    if !mesh.uvs.is_empty() {
+        Ok(renderer.add_mesh_textured(&mesh.vertices, &mesh.normals, &mesh.uvs, &mesh.indices))
+    } else if !mesh.colors.is_empty() {
+        Ok(renderer.add_mesh_colored(&mesh.vertices, &mesh.normals, &mesh.colors, &mesh.indices))
+    } else {
+        Ok(renderer.add_mesh(&mesh.vertices, &mesh.normals, &mesh.indices))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO_MATERIALS_MTL: &str = "
+newmtl Red
+Kd 1.0 0.0 0.0
+
+newmtl Translucent
+Kd 0.0 0.5 1.0
+d 0.25
+";
+
+    const TWO_TRIANGLES_OBJ: &str = "
+mtllib two.mtl
+o Left
+v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 1.0 0.0
+vn 0.0 0.0 1.0
+usemtl Red
+f 1//1 2//1 3//1
+o Right
+v 2.0 0.0 0.0
+v 3.0 0.0 0.0
+v 2.0 1.0 0.0
+usemtl Translucent
+f 4//1 5//1 6//1
+";
+
+    #[test]
+    fn material_colors_are_applied_per_object() {
+        let mesh = parse_obj_static(TWO_TRIANGLES_OBJ, &[("two.mtl", TWO_MATERIALS_MTL)]).unwrap();
+
+        assert_eq!(mesh.vertices.len(), 6);
+        assert_eq!(mesh.normals.len(), 6);
+        assert!(mesh.uvs.is_empty());
+        assert_eq!(mesh.indices, vec![0, 1, 2, 3, 4, 5]);
+        let red = Vec4::new(1.0, 0.0, 0.0, 1.0);
+        let translucent = Vec4::new(0.0, 0.5, 1.0, 0.25);
+        assert_eq!(
+            mesh.colors,
+            vec![red, red, red, translucent, translucent, translucent]
+        );
+    }
+
+    #[test]
+    fn missing_material_file_leaves_mesh_uncolored() {
+        let mesh = parse_obj_static(TWO_TRIANGLES_OBJ, &[]).unwrap();
+
+        assert_eq!(mesh.vertices.len(), 6);
+        assert!(mesh.colors.is_empty());
+    }
+
+    #[test]
+    fn cube_keeps_its_texture_coordinates() {
+        let mesh = parse_obj_static(
+            include_str!("../../app/assets/cube.obj"),
+            &[("cube.mtl", include_str!("../../app/assets/cube.mtl"))],
+        )
+        .unwrap();
+
+        assert_eq!(mesh.uvs.len(), mesh.vertices.len());
+        assert_eq!(mesh.colors.len(), mesh.vertices.len());
+        assert!(mesh
+            .colors
+            .iter()
+            .all(|color| *color == Vec4::new(0.8, 0.8, 0.8, 1.0)));
+    }
 }
@@ -1,4 +1,5 @@
 #import model_matrix::to_model_mesh_matrix;
+#import lighting::lambert;
 
 @group(0) @binding(0)
 var<uniform> projection: mat4x4<f32>;
@@ -34,10 +35,8 @@ fn vs_main(
 
     let world_matrix = projection * camera;
 
-    let light_direction = normalize(vec3(0.3, -1.0, -1.0));
-    let stupid_diffuse_strength = max(dot(model.normal, light_direction), 0.);
-    let color = vec3(1., 1., 1.);
-    out.color = color * 0.1 + color * stupid_diffuse_strength;
+    let normal = (model_matrix * vec4<f32>(model.normal, 0.0)).xyz;
+    out.color = lambert(normal, vec3(1., 1., 1.));
     out.clip_position = world_matrix * world_position;
 
     return out;
@@ -0,0 +1,115 @@
+//! Renders scenarios offscreen into numbered PNG frames, e.g. for documentation.
+
+use std::{fs, path::Path, sync::Arc};
+
+use color_eyre::eyre::{eyre, Result};
+use glam::{uvec2, UVec2};
+use physics::scenarios::Scenario;
+use renderer::{
+    camera::{DepthMode, PrimaryCamera},
+    gpu_context::GpuContext,
+    offscreen::OffscreenTarget,
+    projection::{CameraProjection, Orthographic},
+    Renderer,
+};
+
+use crate::render_engine_state;
+
+/// Size of the exported frames in pixels.
+pub const FRAME_SIZE: UVec2 = uvec2(600, 600);
+
+/// Requests a GPU device that is not tied to any window.
+fn headless_gpu_context() -> Option<GpuContext> {
+    let instance = wgpu::Instance::default();
+    let adapter =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))?;
+    pollster::block_on(GpuContext::request(
+        &adapter,
+        Some("export device"),
+        wgpu::Features::empty(),
+        Renderer::wgpu_limits(),
+    ))
+    .ok()
+}
+
+/// Runs `scenario` for `steps` steps of length `dt` and writes the state after
+/// each step to `out_dir` as `frame_0000.png`, `frame_0001.png`, ...
+/// The directory is created if it does not exist yet.
+pub fn export_scenario_frames(
+    scenario: &dyn Scenario,
+    steps: usize,
+    dt: f64,
+    out_dir: &Path,
+) -> Result<()> {
+    fs::create_dir_all(out_dir)?;
+    let gpu_context =
+        Arc::new(headless_gpu_context().ok_or_else(|| eyre!("Could not get a GPU device"))?);
+    let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+    let target = OffscreenTarget::new(&gpu_context, FRAME_SIZE, format);
+    let primary_camera = PrimaryCamera {
+        projection: CameraProjection::Orthographic(Orthographic {
+            depth: 100.0,
+            scale: 1.0,
+        }),
+        surface_format: format,
+        size: FRAME_SIZE.as_vec2(),
+        depth_buffer: None,
+        depth_mode: DepthMode::Standard,
+        sample_count: 1,
+    };
+    let mut renderer = Renderer::new(&gpu_context, primary_camera)?;
+
+    let mut engine = scenario.create();
+    for step in 0..steps {
+        scenario.update(&mut engine);
+        engine.step(dt);
+        render_engine_state(&engine, false, &mut renderer);
+        renderer.render(target.texture())?;
+        let pixels = target.read_pixels(&gpu_context);
+        let path = out_dir.join(format!("frame_{step:04}.png"));
+        image::save_buffer(
+            &path,
+            &pixels,
+            FRAME_SIZE.x,
+            FRAME_SIZE.y,
+            image::ColorType::Rgba8,
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use physics::scenarios::SimpleFall;
+
+    use super::*;
+
+    #[test]
+    fn exports_one_frame_per_step() {
+        if headless_gpu_context().is_none() {
+            // Same opt-out as the renderer's GPU tests.
+            assert!(
+                std::env::var_os("SKIP_GPU_TESTS").is_some(),
+                "no GPU adapter available, set SKIP_GPU_TESTS=1 to skip the GPU tests"
+            );
+            eprintln!("no GPU adapter available, skipping");
+            return;
+        }
+        let out_dir = std::env::temp_dir()
+            .join(format!("inspector-export-{}", std::process::id()))
+            .join("nested");
+        export_scenario_frames(&SimpleFall {}, 5, 1.0 / 60.0, &out_dir).unwrap();
+
+        let mut files: Vec<_> = fs::read_dir(&out_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        files.sort();
+        assert_eq!(files.len(), 5);
+        assert!(files[4].ends_with("frame_0004.png"));
+        for file in &files {
+            assert_eq!(image::image_dimensions(file).unwrap(), (600, 600));
+        }
+        fs::remove_dir_all(out_dir.parent().unwrap()).unwrap();
+    }
+}
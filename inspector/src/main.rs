@@ -1,66 +1,256 @@
+use std::{collections::VecDeque, f64::consts::FRAC_PI_2, mem::size_of, path::Path};
+
 use game_engine::{GameEngine, MkGameEngine};
-use glam::{vec3, DVec2, Vec3};
+use glam::{vec3, DVec2, Vec2, Vec3};
 use physics::{
+    constraint::{ConstraintEnum, PbdDistance, SpringConstraint},
     scenarios::{Collision, Scenario},
-    Engine, Shape,
+    Engine, Particle, Shape,
 };
 use renderer::{
     circle_rendering::CircleLine,
-    colors::{RED, YELLOW},
+    colors::{GREEN, MAGENTA, ORANGE, RED, SKYBLUE, YELLOW},
     line_rendering::Line,
+    primitives::BorderUnits,
     transform::Transform,
     Renderer,
 };
-use tracing::debug;
+use tracing::{debug, error};
 use tracing_subscriber::{filter::EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 use winit::{event_loop::EventLoop, window::Window};
 
+mod export;
+
+/// Seconds of motion represented by a velocity arrow in the overlay.
+const VELOCITY_ARROW_SCALE: f64 = 0.25;
+
+/// Diameter in pixels of the dots marking collision contacts.
+const CONTACT_POINT_SIZE: f32 = 8.0;
+
+/// Length in world units of the contact normals in the debug overlay.
+const CONTACT_NORMAL_LENGTH: f64 = 20.0;
+
+/// Steps exported by `--export` when no count is given, ten seconds at 60 FPS.
+const DEFAULT_EXPORT_STEPS: usize = 600;
+
+/// Number of frames kept by [`History::new`], one minute of simulation at 60 FPS.
+const DEFAULT_MAX_HISTORY_LENGTH: usize = 3600;
+
+/// File written by the "Save frame" button and read back by "Load frame".
+const SAVED_FRAME_PATH: &str = "inspector_frame.json";
+
+/// Height in points of the kinetic energy plot below the simulation.
+const ENERGY_PLOT_HEIGHT: f32 = 150.0;
+
+/// Files written by the "Export CSV" and "Export JSON" buttons.
+const EXPORTED_HISTORY_CSV_PATH: &str = "inspector_history.csv";
+const EXPORTED_HISTORY_JSON_PATH: &str = "inspector_history.json";
+
 pub struct History {
     engine: Engine,
-    history: Vec<(f64, Engine)>,
+    history: VecDeque<(f64, Engine)>,
     frame: usize,
+    max_len: usize,
+    /// Number of oldest frames dropped to respect `max_len`.
+    dropped_frames: usize,
 }
 
 impl History {
     pub fn new(engine: Engine) -> Self {
+        Self::with_max_len(engine, DEFAULT_MAX_HISTORY_LENGTH)
+    }
+
+    /// Creates a history that retains at most `max_len` frames,
+    /// dropping the oldest ones once the limit is reached.
+    pub fn with_max_len(engine: Engine, max_len: usize) -> Self {
+        assert!(max_len > 0, "History needs to retain at least one frame");
         Self {
             engine: engine.clone(),
-            history: vec![(0.0, engine)],
+            history: VecDeque::from([(0.0, engine)]),
             frame: 0,
+            max_len,
+            dropped_frames: 0,
         }
     }
 
     pub fn ui(&mut self, ui: &mut egui::Ui) {
         let max_frame = self.history.len() - 1;
-        ui.add(egui::Slider::new(&mut self.frame, 0..=max_frame).text("frame"));
+        let mut frame = self.frame;
+        ui.add(egui::Slider::new(&mut frame, 0..=max_frame).text("frame"));
+        if frame != self.frame {
+            self.select_frame(frame);
+        }
         let frame_delta = &self.history[self.frame].0;
         ui.label("Last delta: ");
         ui.label(format!("{:.4}", frame_delta));
+        ui.label(format!(
+            "Simulation frame: {}",
+            self.dropped_frames + self.frame
+        ));
+        let mut max_len = self.max_len;
+        ui.add(
+            egui::DragValue::new(&mut max_len)
+                .clamp_range(1..=1_000_000)
+                .prefix("max frames: "),
+        );
+        self.set_max_len(max_len);
+        ui.label(format!(
+            "History memory: {:.1} MiB",
+            self.approximate_memory() as f64 / (1024.0 * 1024.0)
+        ));
     }
 
     pub fn is_last_frame(&self) -> bool {
         self.frame == self.history.len() - 1
     }
 
-    pub fn update(&mut self, running: bool) {
-        if !self.is_last_frame() {
-            if running {
-                self.history.truncate(self.frame + 1);
-            }
-
-            self.engine = self.history[self.frame].1.clone();
-        }
+    /// Shows `frame`, restoring the engine to exactly the state recorded for
+    /// it. Stepping from there discards the frames after it.
+    pub fn select_frame(&mut self, frame: usize) {
+        self.frame = frame.min(self.history.len() - 1);
+        self.engine = self.history[self.frame].1.clone();
     }
 
     pub fn step(&mut self, dt: f64) {
         self.engine.step(dt);
-        self.history.push((dt, self.engine.clone()));
+        self.record_frame(dt);
+    }
+
+    /// Advances the engine by `real_dt` in fixed steps, see [`Engine::advance`],
+    /// and records the result as one frame.
+    pub fn advance(&mut self, real_dt: f64) {
+        self.engine.advance(real_dt);
+        self.record_frame(real_dt);
+    }
+
+    fn record_frame(&mut self, dt: f64) {
+        // The simulation continued from the selected frame, so the frames
+        // recorded after it belong to another timeline.
+        self.history.truncate(self.frame + 1);
+        self.history.push_back((dt, self.engine.clone()));
         self.frame += 1;
+        self.drop_excess_frames();
+    }
+
+    pub fn set_max_len(&mut self, max_len: usize) {
+        self.max_len = max_len.max(1);
+        self.drop_excess_frames();
+    }
+
+    fn drop_excess_frames(&mut self) {
+        while self.history.len() > self.max_len {
+            self.history.pop_front();
+            self.dropped_frames += 1;
+            self.frame = self.frame.saturating_sub(1);
+        }
+    }
+
+    /// Writes the currently shown frame as JSON, e.g. to reproduce a bug later.
+    pub fn save_frame(&self, path: &Path) -> color_eyre::Result<()> {
+        let json = serde_json::to_string_pretty(&self.history[self.frame].1)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Starts a new history from a frame written by [`History::save_frame`].
+    pub fn load_frame(path: &Path) -> color_eyre::Result<Self> {
+        let engine = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        Ok(Self::new(engine))
+    }
+
+    /// Writes the positions and velocities of the particles in every recorded
+    /// frame as CSV, one row per particle and frame, for analyzing
+    /// trajectories in other tools. Frames are numbered like "Simulation
+    /// frame" in the UI and particles by their index within the frame.
+    pub fn export_csv(&self, path: &Path) -> color_eyre::Result<()> {
+        let mut csv = String::from("frame,dt,particle,x,y,vx,vy\n");
+        for (frame, dt, engine) in self.numbered_frames() {
+            for (particle, p) in engine.particles.iter().enumerate() {
+                csv.push_str(&format!(
+                    "{frame},{dt},{particle},{},{},{},{}\n",
+                    p.pos.x, p.pos.y, p.vel.x, p.vel.y
+                ));
+            }
+        }
+        std::fs::write(path, csv)?;
+        Ok(())
+    }
+
+    /// Writes the same data as [`History::export_csv`] as a JSON array with
+    /// one object per frame.
+    pub fn export_json(&self, path: &Path) -> color_eyre::Result<()> {
+        let frames: Vec<serde_json::Value> = self
+            .numbered_frames()
+            .map(|(frame, dt, engine)| {
+                let particles: Vec<serde_json::Value> = engine
+                    .particles
+                    .iter()
+                    .map(|p| {
+                        serde_json::json!({
+                            "pos": [p.pos.x, p.pos.y],
+                            "vel": [p.vel.x, p.vel.y],
+                        })
+                    })
+                    .collect();
+                serde_json::json!({ "frame": frame, "dt": dt, "particles": particles })
+            })
+            .collect();
+        std::fs::write(path, serde_json::to_string_pretty(&frames)?)?;
+        Ok(())
+    }
+
+    /// Total kinetic energy of every recorded frame against its simulation
+    /// frame number, see [`Engine::total_kinetic_energy`].
+    pub fn kinetic_energies(&self) -> Vec<[f64; 2]> {
+        self.numbered_frames()
+            .map(|(frame, _, engine)| [frame as f64, engine.total_kinetic_energy()])
+            .collect()
+    }
+
+    /// Plots [`History::kinetic_energies`], marking the selected frame. An
+    /// elastic scenario should give a flat line, slopes show the solver adding
+    /// or removing energy.
+    pub fn energy_plot_ui(&self, ui: &mut egui::Ui) {
+        let selected_frame = (self.dropped_frames + self.frame) as f64;
+        egui_plot::Plot::new("kinetic energy")
+            .height(ENERGY_PLOT_HEIGHT)
+            .x_axis_label("frame")
+            .y_axis_label("kinetic energy")
+            .show(ui, |plot_ui| {
+                plot_ui.line(egui_plot::Line::new(egui_plot::PlotPoints::from(
+                    self.kinetic_energies(),
+                )));
+                plot_ui.vline(egui_plot::VLine::new(selected_frame));
+            });
+    }
+
+    /// Recorded frames with their simulation frame number and delta.
+    fn numbered_frames(&self) -> impl Iterator<Item = (usize, f64, &Engine)> {
+        self.history
+            .iter()
+            .enumerate()
+            .map(|(index, (dt, engine))| (self.dropped_frames + index, *dt, engine))
+    }
+
+    /// Rough estimate of the heap and inline memory held by the recorded frames.
+    pub fn approximate_memory(&self) -> usize {
+        self.history
+            .iter()
+            .map(|(_, engine)| {
+                size_of::<(f64, Engine)>()
+                    + engine.particles.capacity() * size_of::<Particle>()
+                    + engine.constraints.capacity() * size_of::<ConstraintEnum>()
+                    + engine.pbd_constraints.capacity() * size_of::<PbdDistance>()
+                    + engine.springs.capacity() * size_of::<SpringConstraint>()
+            })
+            .sum()
     }
 }
 
 pub struct GameState {
     running: bool,
+    show_velocities: bool,
+    show_debug_overlay: bool,
     scenarios: Scenarios,
     active_scenario: usize,
     history: History,
@@ -69,6 +259,8 @@ pub struct GameState {
 fn setup(_game_engine: &mut GameEngine) -> GameState {
     GameState {
         running: true,
+        show_velocities: false,
+        show_debug_overlay: false,
         scenarios: Scenarios::new(),
         active_scenario: 0,
         history: History::new(Collision {}.create()),
@@ -87,6 +279,7 @@ impl Scenarios {
             Box::new(Pendulum {}) as Box<dyn Scenario>,
             Box::new(Penetration {}) as Box<dyn Scenario>,
             Box::new(Resting {}) as Box<dyn Scenario>,
+            Box::new(Rope {}) as Box<dyn Scenario>,
             Box::new(SimpleFall {}) as Box<dyn Scenario>,
             Box::new(Springs {}) as Box<dyn Scenario>,
         ];
@@ -105,18 +298,48 @@ impl Scenarios {
     }
 }
 
+/// Edits gravity as a magnitude and a tilt in degrees, zero pointing down.
+fn gravity_ui(engine: &mut Engine, ui: &mut egui::Ui) {
+    let (mut magnitude, angle) = engine.gravity_polar();
+    let mut tilt = if magnitude == 0.0 {
+        0.0
+    } else {
+        (angle + FRAC_PI_2).to_degrees()
+    };
+    if tilt > 180.0 {
+        tilt -= 360.0;
+    }
+    let magnitude_changed = ui
+        .add(egui::Slider::new(&mut magnitude, 0.0..=1000.0).text("gravity"))
+        .changed();
+    let tilt_changed = ui
+        .add(egui::Slider::new(&mut tilt, -180.0..=180.0).text("gravity tilt (°)"))
+        .changed();
+    if magnitude_changed || tilt_changed {
+        engine.set_gravity_polar(magnitude, tilt.to_radians() - FRAC_PI_2);
+    }
+    ui.horizontal(|ui| {
+        if ui.button("Earth").clicked() {
+            engine.set_gravity_down(9.81);
+        }
+        if ui.button("Zero").clicked() {
+            engine.set_gravity_down(0.0);
+        }
+    });
+}
+
 fn update(state: &mut GameState, game_engine: &mut GameEngine) {
     // GUI
     let egui_context = game_engine.egui();
     egui::SidePanel::right("panel").show(egui_context, |ui| {
         ui.heading("Simulation controls");
 
-        ui.add(
-            egui::Slider::new(&mut state.history.engine.gravity.y, -100.0..=100.0).text("gravity"),
-        );
+        gravity_ui(&mut state.history.engine, ui);
 
         state.history.ui(ui);
 
+        // Looking at a past frame pauses, pressing Play continues the
+        // simulation from it, see `History::select_frame`.
         if !state.history.is_last_frame() {
             state.running = false;
         }
@@ -126,38 +349,176 @@ fn update(state: &mut GameState, game_engine: &mut GameEngine) {
             state.running = !state.running;
         }
 
+        ui.checkbox(&mut state.show_velocities, "Show velocities");
+        ui.checkbox(
+            &mut state.show_debug_overlay,
+            "Show contacts and constraints",
+        );
+
+        ui.horizontal(|ui| {
+            let path = Path::new(SAVED_FRAME_PATH);
+            if ui.button("Save frame").clicked() {
+                if let Err(err) = state.history.save_frame(path) {
+                    error!("Failed to save frame to {}: {err}", path.display());
+                }
+            }
+            if ui.button("Load frame").clicked() {
+                match History::load_frame(path) {
+                    Ok(history) => {
+                        state.history = history;
+                        state.running = false;
+                    }
+                    Err(err) => error!("Failed to load frame from {}: {err}", path.display()),
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Export CSV").clicked() {
+                let path = Path::new(EXPORTED_HISTORY_CSV_PATH);
+                if let Err(err) = state.history.export_csv(path) {
+                    error!("Failed to export history to {}: {err}", path.display());
+                }
+            }
+            if ui.button("Export JSON").clicked() {
+                let path = Path::new(EXPORTED_HISTORY_JSON_PATH);
+                if let Err(err) = state.history.export_json(path) {
+                    error!("Failed to export history to {}: {err}", path.display());
+                }
+            }
+        });
+
         state
             .scenarios
             .ui(&mut state.history, &mut state.active_scenario, ui);
     });
-
-    // History management
-    state.history.update(state.running);
+    egui::TopBottomPanel::bottom("energy").show(egui_context, |ui| {
+        state.history.energy_plot_ui(ui);
+    });
 
     // Simulation
     if state.running {
         let scenario = &state.scenarios.0[state.active_scenario];
         scenario.update(&mut state.history.engine);
         let dt = game_engine.last_frame_delta as f64;
-        state.history.step(dt);
+        state.history.advance(dt);
     }
 }
 
 fn render(state: &GameState, renderer: &mut Renderer) {
     debug!("main render");
-    for p in &state.history.engine.particles {
+    render_engine_state(&state.history.engine, state.show_velocities, renderer);
+    if state.show_debug_overlay {
+        render_debug_overlay(&state.history.engine, renderer);
+    }
+}
+
+/// Draws what the solver works with: every current collision contact as a
+/// dot with its normal, and every distance constraint and spring as a line
+/// between the bodies it connects.
+pub fn render_debug_overlay(engine: &Engine, renderer: &mut Renderer) {
+    let to_vec3 = |v: DVec2| vec3(v.x as f32, v.y as f32, 0.0);
+    let draw_link = |renderer: &mut Renderer, id_a, id_b, color| {
+        if let (Some(a), Some(b)) = (engine.particles.get(id_a), engine.particles.get(id_b)) {
+            renderer.draw_line(
+                &Transform::IDENTITY,
+                &Line::new(to_vec3(a.pos), to_vec3(b.pos), color, 2.0)
+                    .with_width_units(BorderUnits::Pixels),
+            );
+        }
+    };
+
+    for constraint in &engine.constraints {
+        if let ConstraintEnum::Distance(distance) = constraint {
+            draw_link(renderer, distance.id_a, distance.id_b, SKYBLUE);
+        }
+    }
+    for distance in &engine.pbd_constraints {
+        draw_link(renderer, distance.id_a, distance.id_b, SKYBLUE);
+    }
+    for spring in &engine.springs {
+        draw_link(renderer, spring.id_a, spring.id_b, ORANGE);
+    }
+
+    for collision in engine.detect_collisions() {
+        let contact = &collision.contact;
+        renderer.draw_line(
+            &Transform::IDENTITY,
+            &Line::new(
+                to_vec3(contact.pos),
+                to_vec3(contact.pos + contact.normal * CONTACT_NORMAL_LENGTH),
+                MAGENTA,
+                2.0,
+            )
+            .with_width_units(BorderUnits::Pixels),
+        );
+        renderer.draw_point(to_vec3(contact.pos), CONTACT_POINT_SIZE, MAGENTA);
+    }
+}
+
+/// Draws the particles of `engine`, optionally with arrows showing their velocities.
+pub fn render_engine_state(engine: &Engine, show_velocities: bool, renderer: &mut Renderer) {
+    for p in &engine.particles {
         match p.shape {
             Shape::Circle { radius } => {
                 let transform = Transform::from_translation_rotation_z(
                     &(p.pos.as_vec2(), 0.0).into(),
                     p.angle as f32,
                 );
-                renderer.draw_circle_line(&transform, &CircleLine::new(radius as f32, RED, 3.0));
+                renderer.draw_circle_line(
+                    &transform,
+                    &CircleLine::new(radius as f32, RED, 3.0)
+                        .with_border_units(BorderUnits::Pixels),
+                );
                 renderer.draw_line(
                     &transform,
                     &Line::new(Vec3::ZERO, vec3(radius as f32, 0.0, 0.0), RED, 1.0),
                 );
             }
+            Shape::Capsule { length, radius } => {
+                let transform = Transform::from_translation_rotation_z(
+                    &(p.pos.as_vec2(), 0.0).into(),
+                    p.angle as f32,
+                );
+                let (half_length, radius) = (length as f32 / 2.0, radius as f32);
+                for x in [-half_length, half_length] {
+                    renderer.draw_circle_line(
+                        &Transform::from_translation_rotation_z(
+                            &(p.pos.as_vec2() + Vec2::from_angle(p.angle as f32) * x, 0.0).into(),
+                            p.angle as f32,
+                        ),
+                        &CircleLine::new(radius, RED, 3.0).with_border_units(BorderUnits::Pixels),
+                    );
+                }
+                for y in [-radius, radius] {
+                    renderer.draw_line(
+                        &transform,
+                        &Line::new(
+                            vec3(-half_length, y, 0.0),
+                            vec3(half_length, y, 0.0),
+                            RED,
+                            3.0,
+                        ),
+                    );
+                }
+            }
+            Shape::Polygon { .. } => {
+                let physics::geometry::Shape::Polygon(polygon) = p.to_geometry_shape() else {
+                    unreachable!("polygon particles have polygon geometry")
+                };
+                let vertices = &polygon.vertices;
+                for (i, from) in vertices.iter().enumerate() {
+                    let to = vertices[(i + 1) % vertices.len()];
+                    renderer.draw_line(
+                        &Transform::IDENTITY,
+                        &Line::new(
+                            vec3(from.x as f32, from.y as f32, 0.0),
+                            vec3(to.x as f32, to.y as f32, 0.0),
+                            RED,
+                            3.0,
+                        ),
+                    );
+                }
+            }
             Shape::HalfPlane { normal_angle } => {
                 let extent = 10000.0;
                 let tangent = DVec2::from_angle(normal_angle).perp();
@@ -177,6 +538,17 @@ fn render(state: &GameState, renderer: &mut Renderer) {
                 unimplemented!("Render unknown shape {:?}", p.shape)
             }
         }
+
+        if show_velocities {
+            let to = p.pos + p.vel * VELOCITY_ARROW_SCALE;
+            renderer.draw_arrow(
+                vec3(p.pos.x as f32, p.pos.y as f32, 0.0),
+                vec3(to.x as f32, to.y as f32, 0.0),
+                GREEN,
+                2.0,
+                10.0,
+            );
+        }
     }
 }
 
@@ -188,6 +560,25 @@ fn main() -> color_eyre::eyre::Result<()> {
         .with(filter_layer)
         .init();
     color_eyre::install()?;
+
+    // `inspector --export <out_dir> [steps]` renders the default scenario to
+    // PNG frames instead of opening a window.
+    let args: Vec<String> = std::env::args().collect();
+    if let [_, flag, out_dir, rest @ ..] = args.as_slice() {
+        if flag == "--export" {
+            let steps = match rest.first() {
+                Some(steps) => steps.parse()?,
+                None => DEFAULT_EXPORT_STEPS,
+            };
+            return export::export_scenario_frames(
+                &Collision {},
+                steps,
+                1.0 / 60.0,
+                Path::new(out_dir),
+            );
+        }
+    }
+
     let event_loop = EventLoop::new()?;
     let window = Window::new(&event_loop)?;
     let (mut game_engine, event_loop) = pollster::block_on(GameEngine::new(
@@ -198,3 +589,156 @@ fn main() -> color_eyre::eyre::Result<()> {
     game_engine.run(event_loop, setup, &update, &render)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_is_bounded() {
+        let mut history = History::with_max_len(Collision {}.create(), 5);
+        for _ in 0..20 {
+            history.step(0.01);
+        }
+        assert_eq!(history.history.len(), 5);
+        assert_eq!(history.dropped_frames, 16);
+        assert!(history.is_last_frame());
+        // The last slider position maps to the most recent frame.
+        let last = &history.history[history.frame].1;
+        assert_eq!(last.particles[0].pos, history.engine.particles[0].pos);
+        // The first slider position maps to the oldest retained frame.
+        let mut expected = Collision {}.create();
+        for _ in 0..16 {
+            expected.step(0.01);
+        }
+        assert_eq!(
+            history.history[0].1.particles[0].pos,
+            expected.particles[0].pos
+        );
+    }
+
+    #[test]
+    fn saved_frame_loads_back() {
+        let mut history = History::new(Collision {}.create());
+        for _ in 0..10 {
+            history.step(0.01);
+        }
+        let path = std::env::temp_dir().join("inspector_saved_frame_loads_back.json");
+        history.save_frame(&path).unwrap();
+        let loaded = History::load_frame(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.history.len(), 1);
+        for (a, b) in history
+            .engine
+            .particles
+            .iter()
+            .zip(&loaded.engine.particles)
+        {
+            assert_eq!(a.pos, b.pos);
+            assert_eq!(a.vel, b.vel);
+        }
+    }
+
+    #[test]
+    fn exported_history_has_a_row_per_particle_and_frame() {
+        let mut history = History::with_max_len(Collision {}.create(), 3);
+        for _ in 0..5 {
+            history.step(0.01);
+        }
+        let path = std::env::temp_dir().join("inspector_exported_history.csv");
+        history.export_csv(&path).unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "frame,dt,particle,x,y,vx,vy");
+        let particles = history.engine.particles.len();
+        assert_eq!(lines.len(), 1 + 3 * particles);
+        // The oldest retained frame is the third simulation frame.
+        let first: Vec<&str> = lines[1].split(',').collect();
+        assert_eq!(first[..3], ["3", "0.01", "0"]);
+        let last: Vec<f64> = lines[lines.len() - 1]
+            .split(',')
+            .map(|value| value.parse().unwrap())
+            .collect();
+        let p = &history.engine.particles[particles - 1];
+        assert_eq!(last[3..], [p.pos.x, p.pos.y, p.vel.x, p.vel.y]);
+    }
+
+    #[test]
+    fn exported_json_matches_csv() {
+        let mut history = History::new(Collision {}.create());
+        for _ in 0..4 {
+            history.step(0.01);
+        }
+        let path = std::env::temp_dir().join("inspector_exported_history.json");
+        history.export_json(&path).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let frames = json.as_array().unwrap();
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[0]["dt"], 0.0);
+        assert_eq!(frames[4]["frame"], 4);
+        let p = &history.engine.particles[0];
+        assert_eq!(frames[4]["particles"][0]["vel"][0], p.vel.x);
+    }
+
+    #[test]
+    fn selecting_a_frame_restores_its_state() {
+        let mut history = History::new(Collision {}.create());
+        for _ in 0..10 {
+            history.step(0.01);
+        }
+        let last_pos = history.engine.particles[0].pos;
+        history.select_frame(4);
+        assert_eq!(
+            history.engine.particles[0].pos,
+            history.history[4].1.particles[0].pos
+        );
+        // Going forward again restores the latest state, not the one of the
+        // frame looked at before.
+        history.select_frame(10);
+        assert!(history.is_last_frame());
+        assert_eq!(history.engine.particles[0].pos, last_pos);
+    }
+
+    #[test]
+    fn playing_from_a_past_frame_discards_the_later_ones() {
+        let mut history = History::new(Collision {}.create());
+        for _ in 0..10 {
+            history.step(0.01);
+        }
+        history.select_frame(4);
+        assert_eq!(history.history.len(), 11);
+        history.step(0.01);
+        history.step(0.01);
+        assert_eq!(history.history.len(), 7);
+        assert_eq!(history.frame, 6);
+        assert!(history.is_last_frame());
+
+        // Re-simulating gives the same states as the original run.
+        let mut expected = Collision {}.create();
+        for _ in 0..6 {
+            expected.step(0.01);
+        }
+        for (a, b) in history.engine.particles.iter().zip(&expected.particles) {
+            assert_eq!(a.pos, b.pos);
+            assert_eq!(a.vel, b.vel);
+        }
+    }
+
+    #[test]
+    fn kinetic_energies_cover_the_retained_frames() {
+        let mut history = History::with_max_len(Collision {}.create(), 4);
+        for _ in 0..6 {
+            history.step(0.01);
+        }
+        let energies = history.kinetic_energies();
+        assert_eq!(energies.len(), 4);
+        assert_eq!(energies[0][0], 3.0);
+        assert_eq!(energies[3], [6.0, history.engine.total_kinetic_energy()]);
+    }
+}
@@ -0,0 +1,110 @@
+//! Broad-phase collision detection: cheaply finds the pairs of bodies that may
+//! touch, so that the exact (and expensive) narrow-phase test runs only on those.
+use std::collections::HashMap;
+
+use glam::{DVec2, IVec2};
+
+use crate::geometry::Aabb;
+
+/// Boxes covering more grid cells than this are not hashed. Like unbounded
+/// boxes they are paired with every other box instead.
+const MAX_CELLS_PER_BOX: usize = 64;
+
+/// Returns the sorted pairs `(i, j)`, `i < j`, of bounding boxes that share a
+/// cell of a uniform grid, a superset of the pairs whose boxes overlap.
+///
+/// See [`crate::geometry::Shape::aabb`] for the boxes. The cell size is the
+/// median extent of the finite boxes, so that a typical box covers only a few
+/// cells while a few big ones (e.g. walls) do not blow it up.
+/// Unbounded boxes, e.g. of half-planes, pair with all others.
+pub fn find_pairs(aabbs: &[Aabb]) -> Vec<(usize, usize)> {
+    let mut extents: Vec<_> = aabbs
+        .iter()
+        .filter(|aabb| !aabb.is_infinite())
+        .map(|aabb| (aabb.max - aabb.min).max_element())
+        .collect();
+    extents.sort_unstable_by(f64::total_cmp);
+    let cell_size = extents.get(extents.len() / 2).copied().unwrap_or(0.0);
+
+    let mut pairs = vec![];
+    let mut large = vec![];
+    let mut grid: HashMap<IVec2, Vec<usize>> = HashMap::new();
+    for (i, aabb) in aabbs.iter().enumerate() {
+        let cells = (!aabb.is_infinite())
+            .then(|| cell_range(aabb.min, aabb.max, cell_size))
+            .flatten();
+        let Some((first, last)) = cells else {
+            large.push(i);
+            continue;
+        };
+        for x in first.x..=last.x {
+            for y in first.y..=last.y {
+                grid.entry(IVec2::new(x, y)).or_default().push(i);
+            }
+        }
+    }
+
+    for bucket in grid.values() {
+        for (k, &i) in bucket.iter().enumerate() {
+            pairs.extend(bucket[k + 1..].iter().map(|&j| (i, j)));
+        }
+    }
+    for &i in &large {
+        pairs.extend(
+            (0..aabbs.len())
+                .filter(|&j| j != i)
+                .map(|j| (i.min(j), i.max(j))),
+        );
+    }
+    pairs.sort_unstable();
+    pairs.dedup();
+    pairs
+}
+
+/// First and last grid cell covered by the box, or `None` if there are too many
+/// cells in between (or the cell size is degenerate).
+fn cell_range(min: DVec2, max: DVec2, cell_size: f64) -> Option<(IVec2, IVec2)> {
+    if cell_size <= 0.0 {
+        return None;
+    }
+    let first = (min / cell_size).floor();
+    let last = (max / cell_size).floor();
+    let span = last - first + DVec2::ONE;
+    let too_many = span.x * span.y > MAX_CELLS_PER_BOX as f64;
+    let out_of_range = first.abs().max(last.abs()).max_element() > i32::MAX as f64;
+    if too_many || out_of_range {
+        return None;
+    }
+    Some((first.as_ivec2(), last.as_ivec2()))
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::dvec2;
+
+    use super::*;
+
+    #[test]
+    fn pairs_share_cells() {
+        let aabbs = [
+            Aabb::new(dvec2(0.0, 0.0), dvec2(1.0, 1.0)),
+            Aabb::new(dvec2(0.5, 0.5), dvec2(1.5, 1.5)),
+            Aabb::new(dvec2(10.0, 10.0), dvec2(11.0, 11.0)),
+            Aabb::INFINITE,
+            Aabb::new(dvec2(-100.0, 0.0), dvec2(100.0, 1.0)),
+        ];
+        assert_eq!(
+            find_pairs(&aabbs),
+            vec![
+                (0, 1),
+                (0, 3),
+                (0, 4),
+                (1, 3),
+                (1, 4),
+                (2, 3),
+                (2, 4),
+                (3, 4)
+            ]
+        );
+    }
+}
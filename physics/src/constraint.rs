@@ -4,12 +4,15 @@ use dyn_clone::DynClone;
 use glam::{dvec3, DVec3};
 use tracing::trace;
 
-use crate::{geometry::Contact, Particle};
+use crate::{geometry::Contact, particles::ParticleId, Particle, SolverConfig};
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ConstraintEnum {
     Distance(DistanceConstraint),
     Collision(CollisionConstraint),
+    /// Not serializable, serializing an engine containing one fails.
+    #[cfg_attr(feature = "serde", serde(skip))]
     Custom(Box<dyn Constraint>),
 }
 
@@ -24,7 +27,7 @@ macro_rules! dispatch_constraint {
 }
 
 impl Constraint for ConstraintEnum {
-    fn get_ids(&self) -> (usize, usize) {
+    fn get_ids(&self) -> (ParticleId, ParticleId) {
         dispatch_constraint!(self, get_ids,)
     }
 
@@ -54,8 +57,8 @@ impl Constraint for ConstraintEnum {
 /// also called 'Jacobian', as dC/dt = J * (da/dt, db/dt) = J * V.
 ///
 /// An inequality constraint works similarly but we require C(a, b) >= 0.
-pub trait Constraint: fmt::Debug + DynClone {
-    fn get_ids(&self) -> (usize, usize);
+pub trait Constraint: fmt::Debug + DynClone + Send + Sync {
+    fn get_ids(&self) -> (ParticleId, ParticleId);
 
     fn is_equality(&self) -> bool;
 
@@ -78,14 +81,15 @@ pub trait Constraint: fmt::Debug + DynClone {
 dyn_clone::clone_trait_object!(Constraint);
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DistanceConstraint {
-    pub id_a: usize,
-    pub id_b: usize,
+    pub id_a: ParticleId,
+    pub id_b: ParticleId,
     pub distance: f64,
 }
 
 impl DistanceConstraint {
-    pub fn new(id_a: usize, id_b: usize, distance: f64) -> DistanceConstraint {
+    pub fn new(id_a: ParticleId, id_b: ParticleId, distance: f64) -> DistanceConstraint {
         DistanceConstraint {
             id_a,
             id_b,
@@ -101,7 +105,7 @@ const CONSTRAINT_TOLERANCE: f64 = 1e-6;
 /// TODO: maybe it could be more useful if one could also specify
 /// which points on the bodies should be constrained.
 impl Constraint for DistanceConstraint {
-    fn get_ids(&self) -> (usize, usize) {
+    fn get_ids(&self) -> (ParticleId, ParticleId) {
         (self.id_a, self.id_b)
     }
 
@@ -134,34 +138,60 @@ impl Constraint for DistanceConstraint {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CollisionConstraint {
-    pub id_a: usize,
-    pub id_b: usize,
+    pub id_a: ParticleId,
+    pub id_b: ParticleId,
     pub contact: Contact,
+    /// Index of the contact within the manifold of the pair, in the order along
+    /// the contact tangent. Identifies the contact across frames for warm starting.
+    pub feature: usize,
     pub dynamic: bool,
+    /// Combined restitution of both bodies, see `Engine::restitution_mix`.
+    pub restitution: f64,
+    /// Combined friction of both bodies, see `Engine::friction_mix`.
+    pub friction: f64,
+    /// See `SolverConfig::baumgarte_beta`.
+    pub baumgarte_beta: f64,
+    /// See `SolverConfig::penetration_slop`.
+    pub penetration_slop: f64,
 }
 
 impl CollisionConstraint {
-    pub fn new(a: usize, b: usize, contact: Contact, dynamic: bool) -> CollisionConstraint {
+    /// Creates a perfectly elastic and frictionless collision.
+    pub fn new(
+        a: ParticleId,
+        b: ParticleId,
+        contact: Contact,
+        dynamic: bool,
+    ) -> CollisionConstraint {
         CollisionConstraint {
             id_a: a,
             id_b: b,
             contact,
+            feature: 0,
             dynamic,
+            restitution: 1.0,
+            friction: 0.0,
+            baumgarte_beta: SolverConfig::default().baumgarte_beta,
+            penetration_slop: SolverConfig::default().penetration_slop,
         }
     }
-}
 
-// TODO: should be more like 0.8 but it doesn't behave well because
-// it produces high velocities and we treat them as dynamic collisions
-// in the next frame.
-// Once we remember static contacts we can treat them as static collisions
-// and handle them properly.
-// see https://github.com/orbital-simulations/experimental/issues/58
-const PENETRATION_RELAXATION_FACTOR: f64 = 0.02;
+    /// Jacobian of the relative velocity along the contact tangent,
+    /// which friction drives towards zero.
+    pub fn tangent_jacobian(&self, a: &Particle, b: &Particle) -> (DVec3, DVec3) {
+        let r1 = self.contact.pos - a.pos;
+        let r2 = self.contact.pos - b.pos;
+        let t = self.contact.normal.perp();
+        let j1 = dvec3(-t.x, -t.y, -r1.perp_dot(t));
+        let j2 = dvec3(t.x, t.y, r2.perp_dot(t));
+        (j1, j2)
+    }
+}
 
 impl Constraint for CollisionConstraint {
-    fn get_ids(&self) -> (usize, usize) {
+    fn get_ids(&self) -> (ParticleId, ParticleId) {
         (self.id_a, self.id_b)
     }
 
@@ -177,17 +207,15 @@ impl Constraint for CollisionConstraint {
 
     fn target_velocity(&self, a: &Particle, b: &Particle, dt: f64) -> f64 {
         if self.dynamic {
-            // TODO: compute restitution from some particle properties
-            // see https://github.com/orbital-simulations/experimental/issues/53
-            let restitution = 1.0;
             let v_rel = self.relative_velocity(a, b);
-            -restitution * v_rel
+            -self.restitution * v_rel
         }
         // We treat static constraints as inequality position constraints
         // To first order C(t+dt) ~ C(t) + dC/dt * dt = C(t) + J * v * dt = C(t) + v_rel * dt
         // If we want to achieve C(t+dt) = 0 we get v_rel = -C(t) / dt
         else {
-            -PENETRATION_RELAXATION_FACTOR * self.value(a, b) / dt
+            let penetration = (self.value(a, b) + self.penetration_slop).min(0.0);
+            -self.baumgarte_beta * penetration / dt
         }
     }
 
@@ -195,8 +223,109 @@ impl Constraint for CollisionConstraint {
         let r1 = self.contact.pos - a.pos;
         let r2 = self.contact.pos - b.pos;
         let n = self.contact.normal;
-        let j1 = dvec3(-n.x, -n.y, -n.perp_dot(r1));
-        let j2 = dvec3(n.x, n.y, n.perp_dot(r2));
+        let j1 = dvec3(-n.x, -n.y, -r1.perp_dot(n));
+        let j2 = dvec3(n.x, n.y, r2.perp_dot(n));
         (j1, j2)
     }
 }
+
+/// A damped spring between the centers of two particles, see `Engine::springs`.
+///
+/// Unlike the constraints above it is soft: rather than being solved exactly,
+/// it adds a force proportional to its stretch and to the speed of stretching
+/// to both particles at the start of each step.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpringConstraint {
+    pub id_a: ParticleId,
+    pub id_b: ParticleId,
+    pub rest_length: f64,
+    /// Force per unit of stretch.
+    pub stiffness: f64,
+    /// Force per unit of stretching speed, opposing the motion.
+    pub damping: f64,
+}
+
+impl SpringConstraint {
+    pub fn new(
+        id_a: ParticleId,
+        id_b: ParticleId,
+        rest_length: f64,
+        stiffness: f64,
+        damping: f64,
+    ) -> SpringConstraint {
+        SpringConstraint {
+            id_a,
+            id_b,
+            rest_length,
+            stiffness,
+            damping,
+        }
+    }
+
+    /// Adds the spring force to the `force` of both particles.
+    pub fn apply_forces(&self, a: &mut Particle, b: &mut Particle) {
+        let diff = b.pos - a.pos;
+        let distance = diff.length();
+        // TODO: decide how to handle coinciding particles
+        // see https://github.com/orbital-simulations/experimental/issues/54
+        if distance < CONSTRAINT_TOLERANCE {
+            return;
+        }
+        let n = diff / distance;
+        let stretching_speed = (b.vel - a.vel).dot(n);
+        let force =
+            (self.stiffness * (distance - self.rest_length) + self.damping * stretching_speed) * n;
+        a.force += force;
+        b.force -= force;
+    }
+}
+
+/// A distance constraint for the position-based dynamics pass,
+/// see [`crate::Engine::project_constraints`].
+///
+/// Unlike [`DistanceConstraint`] it is not fed to the impulse solver.
+/// Instead particle positions are moved directly after integration,
+/// which makes it suitable for soft, jelly-like structures and ropes.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PbdDistance {
+    pub id_a: ParticleId,
+    pub id_b: ParticleId,
+    pub rest_length: f64,
+    /// Fraction of the constraint error corrected per projection, in `[0, 1]`.
+    /// One gives a rigid link, smaller values make the link softer.
+    pub stiffness: f64,
+}
+
+impl PbdDistance {
+    pub fn new(id_a: ParticleId, id_b: ParticleId, rest_length: f64) -> PbdDistance {
+        PbdDistance {
+            id_a,
+            id_b,
+            rest_length,
+            stiffness: 1.0,
+        }
+    }
+
+    /// Moves both particles along the line between them so that their distance
+    /// approaches `rest_length`. Corrections are distributed by inverse mass.
+    pub fn project(&self, a: &mut Particle, b: &mut Particle) {
+        let (w_a, w_b) = (a.effective_inv_mass(), b.effective_inv_mass());
+        let w = w_a + w_b;
+        if w == 0.0 {
+            return;
+        }
+        let diff = b.pos - a.pos;
+        let distance = diff.length();
+        // TODO: decide how to handle coinciding particles
+        // see https://github.com/orbital-simulations/experimental/issues/54
+        if distance < CONSTRAINT_TOLERANCE {
+            return;
+        }
+        let n = diff / distance;
+        let correction = self.stiffness * (distance - self.rest_length) / w * n;
+        a.pos += w_a * correction;
+        b.pos -= w_b * correction;
+    }
+}
@@ -1,21 +1,138 @@
 //! This module provides basic shapes and methods for testing overlaps between them.
 use glam::DVec2;
-use tracing::{instrument, trace, warn};
+use tracing::{instrument, trace};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Contact {
     pub pos: DVec2,
     pub normal: DVec2,
     pub separation: f64,
 }
 
+impl Contact {
+    pub fn new(pos: DVec2, normal: DVec2, separation: f64) -> Contact {
+        Contact {
+            pos,
+            normal,
+            separation,
+        }
+    }
+}
+
+/// Contacts closer to each other than this are considered duplicates by
+/// [`ContactManifold::reduce`].
+pub const CONTACT_MERGE_TOLERANCE: f64 = 1e-6;
+
+/// All contacts between a pair of shapes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ContactManifold {
+    pub contacts: Vec<Contact>,
+}
+
+impl From<Vec<Contact>> for ContactManifold {
+    fn from(contacts: Vec<Contact>) -> Self {
+        ContactManifold { contacts }
+    }
+}
+
+impl ContactManifold {
+    /// Merges contacts closer than [`CONTACT_MERGE_TOLERANCE`], keeping the
+    /// deepest one of each cluster, and then keeps at most `max_points` of
+    /// them. The deepest contact is always kept, the rest are picked greedily
+    /// to be as far apart as possible, which keeps the manifold stable.
+    pub fn reduce(&mut self, max_points: usize) {
+        let mut candidates = std::mem::take(&mut self.contacts);
+        candidates.sort_by(|a, b| a.separation.total_cmp(&b.separation));
+
+        let mut merged: Vec<Contact> = Vec::with_capacity(candidates.len());
+        for contact in candidates {
+            if merged
+                .iter()
+                .all(|kept| kept.pos.distance(contact.pos) > CONTACT_MERGE_TOLERANCE)
+            {
+                merged.push(contact);
+            }
+        }
+
+        if merged.len() <= max_points {
+            self.contacts = merged;
+            return;
+        }
+        let mut remaining = merged.into_iter();
+        self.contacts
+            .extend(remaining.next().filter(|_| max_points > 0));
+        let mut remaining: Vec<Contact> = remaining.collect();
+        while self.contacts.len() < max_points {
+            let spread = |candidate: &Contact| {
+                self.contacts
+                    .iter()
+                    .map(|kept| kept.pos.distance_squared(candidate.pos))
+                    .fold(f64::INFINITY, f64::min)
+            };
+            let widest = (0..remaining.len())
+                .max_by(|&i, &j| spread(&remaining[i]).total_cmp(&spread(&remaining[j])))
+                .expect("more contacts remain than max_points");
+            self.contacts.push(remaining.swap_remove(widest));
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum Shape {
     Circle(Circle),
+    Capsule(Capsule),
+    Polygon(Polygon),
     HalfPlane(HalfPlane),
 }
 
+/// Axis-aligned bounding box given by its `min` and `max` corners.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: DVec2,
+    pub max: DVec2,
+}
+
+impl Aabb {
+    /// Bounding box of shapes without a finite extent, e.g. half-planes.
+    /// Its corners are infinite, so it overlaps every other box.
+    pub const INFINITE: Aabb = Aabb {
+        min: DVec2::NEG_INFINITY,
+        max: DVec2::INFINITY,
+    };
+
+    pub fn new(min: DVec2, max: DVec2) -> Aabb {
+        Aabb { min, max }
+    }
+
+    /// Whether the box is unbounded, like [`Aabb::INFINITE`]. Such boxes
+    /// should be treated as overlapping everything rather than be hashed or
+    /// measured.
+    pub fn is_infinite(&self) -> bool {
+        !(self.min.is_finite() && self.max.is_finite())
+    }
+
+    /// Whether the boxes share at least a point of their boundaries.
+    /// Infinite boxes overlap every box.
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.is_infinite()
+            || other.is_infinite()
+            || (self.min.cmple(other.max).all() && other.min.cmple(self.max).all())
+    }
+}
+
 impl Shape {
+    /// Axis-aligned bounding box in the same coordinates as the shape.
+    /// Unbounded shapes return [`Aabb::INFINITE`].
+    pub fn aabb(&self) -> Aabb {
+        match self {
+            Shape::Circle(c) => c.aabb(),
+            Shape::Capsule(c) => c.aabb(),
+            Shape::Polygon(p) => p.aabb(),
+            Shape::HalfPlane(_) => Aabb::INFINITE,
+        }
+    }
+
     #[instrument(level = "trace")]
     pub fn test_overlap(&self, other: &Shape) -> Vec<Contact> {
         /*
@@ -35,12 +152,132 @@ impl Shape {
             (Shape::HalfPlane(h1), Shape::Circle(c1)) => {
                 h1.test_overlap_with_circle(c1).into_iter().collect()
             }
-            (Shape::HalfPlane(_h1), Shape::HalfPlane(_h2)) => {
-                warn!("Half-plane vs half-plane overlap testing not supported");
-                vec![]
+            (Shape::Circle(c1), Shape::Capsule(c2)) => {
+                c1.test_overlap_with_capsule(c2).into_iter().collect()
+            }
+            (Shape::Capsule(c1), Shape::Circle(c2)) => {
+                c1.test_overlap_with_circle(c2).into_iter().collect()
+            }
+            (Shape::Capsule(c1), Shape::Capsule(c2)) => c1.test_overlap_with_capsule(c2),
+            (Shape::Capsule(c1), Shape::HalfPlane(h2)) => c1.test_overlap_with_half_plane(h2),
+            (Shape::HalfPlane(h1), Shape::Capsule(c2)) => h1.test_overlap_with_capsule(c2),
+            (Shape::Polygon(p1), Shape::Polygon(p2)) => p1.test_overlap_with_polygon(p2),
+            (Shape::Polygon(p1), Shape::Circle(c2)) => {
+                p1.test_overlap_with_circle(c2).into_iter().collect()
+            }
+            (Shape::Circle(c1), Shape::Polygon(p2)) => {
+                c1.test_overlap_with_polygon(p2).into_iter().collect()
+            }
+            // Half-planes are static, see `Engine::detect_collisions`.
+            (Shape::HalfPlane(_), Shape::HalfPlane(_)) => vec![],
+            (Shape::Polygon(p1), Shape::HalfPlane(h2)) => p1.test_overlap_with_half_plane(h2),
+            (Shape::HalfPlane(h1), Shape::Polygon(p2)) => h1.test_overlap_with_polygon(p2),
+            (Shape::Capsule(c1), Shape::Polygon(p2)) => c1.test_overlap_with_polygon(p2),
+            (Shape::Polygon(p1), Shape::Capsule(c2)) => p1.test_overlap_with_capsule(c2),
+        }
+    }
+
+    /// Distance along the ray from `origin` in the normalized direction `dir`
+    /// at which it enters the shape, with the outward normal there. Rays that
+    /// start inside the shape, only touch it, or reach it beyond `max_dist`
+    /// do not hit.
+    pub fn raycast(&self, origin: DVec2, dir: DVec2, max_dist: f64) -> Option<(f64, DVec2)> {
+        match self {
+            Shape::Circle(c) => c.raycast(origin, dir, max_dist),
+            Shape::Capsule(c) => c.raycast(origin, dir, max_dist),
+            Shape::Polygon(p) => p.raycast(origin, dir, max_dist),
+            Shape::HalfPlane(h) => h.raycast(origin, dir, max_dist),
+        }
+    }
+
+    /// The nearest point of `self`, the nearest point of `other` and the signed
+    /// distance between the shapes, negative when they overlap. Overlapping
+    /// shapes return their deepest contact from [`Shape::test_overlap`]: a
+    /// point on the boundary of `self` and the point of `other` furthest
+    /// inside it. Two half-planes that are not parallel always overlap, they
+    /// return the position of `self` twice and a distance of negative infinity.
+    pub fn closest_points(&self, other: &Shape) -> (DVec2, DVec2, f64) {
+        match (self, other) {
+            (Shape::HalfPlane(h1), Shape::HalfPlane(h2)) => h1.closest_points_to_half_plane(h2),
+            (Shape::HalfPlane(h), other) => h.closest_points_to(other),
+            (shape, Shape::HalfPlane(h)) => {
+                let (on_plane, on_shape, distance) = h.closest_points_to(shape);
+                (on_shape, on_plane, distance)
+            }
+            (s1, s2) => {
+                let deepest = s1
+                    .test_overlap(s2)
+                    .into_iter()
+                    .min_by(|c1, c2| c1.separation.total_cmp(&c2.separation));
+                if let Some(c) = deepest {
+                    return (c.pos, c.pos + c.separation * c.normal, c.separation);
+                }
+                // Separated shapes are nearest where their cores are, each
+                // point then moves out to the surface by the radius.
+                let (edges1, radius1) = s1.rounded_core();
+                let (edges2, radius2) = s2.rounded_core();
+                let (p1, p2) = edges1
+                    .iter()
+                    .flat_map(|e1| edges2.iter().map(|e2| closest_points_on_segments(*e1, *e2)))
+                    .min_by(|(a1, b1), (a2, b2)| {
+                        a1.distance_squared(*b1)
+                            .total_cmp(&a2.distance_squared(*b2))
+                    })
+                    .expect("bounded shapes have at least one edge");
+                // Cores that touch, e.g. concentric circles, have no direction
+                // between them.
+                let normal = (p2 - p1).try_normalize().unwrap_or(DVec2::X);
+                (
+                    p1 + radius1 * normal,
+                    p2 - radius2 * normal,
+                    p1.distance(p2) - radius1 - radius2,
+                )
             }
         }
     }
+
+    /// Edges of the convex core of a bounded shape, the shape being all points
+    /// within the returned radius of it. Circles have a single degenerate edge.
+    fn rounded_core(&self) -> (Vec<(DVec2, DVec2)>, f64) {
+        match self {
+            Shape::Circle(c) => (vec![(c.pos, c.pos)], c.radius),
+            Shape::Capsule(c) => (vec![c.endpoints()], c.radius),
+            Shape::Polygon(p) => (p.edges().map(|(a, b, _)| (a, b)).collect(), 0.0),
+            Shape::HalfPlane(_) => unreachable!("half-planes have no bounded core"),
+        }
+    }
+
+    /// Whether `point` lies inside the shape or on its boundary. Unlike testing
+    /// the overlap with a zero sized circle this includes the centers of
+    /// circles.
+    pub fn contains_point(&self, point: DVec2) -> bool {
+        let in_circle = |c: &Circle| c.pos.distance_squared(point) <= c.radius * c.radius;
+        match self {
+            Shape::Circle(c) => in_circle(c),
+            Shape::Capsule(c) => in_circle(&c.closest_circle(point)),
+            Shape::Polygon(p) => p
+                .edges()
+                .all(|(start, _, normal)| normal.dot(point - start) <= 0.0),
+            Shape::HalfPlane(h) => DVec2::from_angle(h.normal_angle).dot(point - h.pos) <= 0.0,
+        }
+    }
+
+    /// The shape as a [`SupportMap`], if it is bounded and convex.
+    pub fn support_map(&self) -> Option<&dyn SupportMap> {
+        match self {
+            Shape::Circle(c) => Some(c),
+            Shape::Capsule(c) => Some(c),
+            Shape::Polygon(p) => Some(p),
+            Shape::HalfPlane(_) => None,
+        }
+    }
+}
+
+/// A convex shape described by its support function, as used by [`gjk_epa`].
+pub trait SupportMap {
+    /// The point of the shape that is furthest along `dir`.
+    /// `dir` does not need to be normalized.
+    fn support(&self, dir: DVec2) -> DVec2;
 }
 
 #[derive(Clone, Debug)]
@@ -49,6 +286,22 @@ pub struct Circle {
     pub radius: f64,
 }
 
+/// A line segment of `length` centered on `pos` and rotated by `angle` from the
+/// x-axis, swept by a circle of `radius`.
+#[derive(Clone, Debug)]
+pub struct Capsule {
+    pub pos: DVec2,
+    pub angle: f64,
+    pub length: f64,
+    pub radius: f64,
+}
+
+/// A convex polygon given by its vertices in counter-clockwise order.
+#[derive(Clone, Debug)]
+pub struct Polygon {
+    pub vertices: Vec<DVec2>,
+}
+
 #[derive(Clone, Debug)]
 pub struct HalfPlane {
     pub pos: DVec2,
@@ -56,6 +309,11 @@ pub struct HalfPlane {
 }
 
 impl Circle {
+    pub fn aabb(&self) -> Aabb {
+        let extent = DVec2::splat(self.radius);
+        Aabb::new(self.pos - extent, self.pos + extent)
+    }
+
     fn try_make_contact(&self, normal: DVec2, separation: f64) -> Option<Contact> {
         // No collision
         if separation > 0.0 {
@@ -64,11 +322,7 @@ impl Circle {
         // Overlap
         else {
             let pos = self.pos + self.radius * normal;
-            Some(Contact {
-                pos,
-                normal,
-                separation,
-            })
+            Some(Contact::new(pos, normal, separation))
         }
     }
 
@@ -90,9 +344,53 @@ impl Circle {
         trace!("Overlap result: normal {normal}, separation {separation}");
         self.try_make_contact(normal, separation)
     }
+
+    pub fn test_overlap_with_capsule(&self, other: &Capsule) -> Option<Contact> {
+        self.test_overlap_with_circle(&other.closest_circle(self.pos))
+    }
+
+    pub fn test_overlap_with_polygon(&self, other: &Polygon) -> Option<Contact> {
+        let contact = other.test_overlap_with_circle(self)?;
+        self.try_make_contact(-contact.normal, contact.separation)
+    }
+
+    /// See [`Shape::raycast`].
+    pub fn raycast(&self, origin: DVec2, dir: DVec2, max_dist: f64) -> Option<(f64, DVec2)> {
+        // Solve |origin + t * dir - pos| = radius, i.e. t^2 + 2bt + c = 0.
+        let offset = origin - self.pos;
+        let b = offset.dot(dir);
+        let c = offset.length_squared() - self.radius * self.radius;
+        let discriminant = b * b - c;
+        if c < 0.0 || discriminant <= 0.0 {
+            return None;
+        }
+        let t = -b - discriminant.sqrt();
+        if t < 0.0 || t > max_dist {
+            return None;
+        }
+        Some((t, (offset + t * dir) / self.radius))
+    }
+}
+
+impl SupportMap for Circle {
+    fn support(&self, dir: DVec2) -> DVec2 {
+        self.pos + self.radius * dir.normalize_or_zero()
+    }
 }
 
 impl HalfPlane {
+    /// See [`Shape::raycast`].
+    pub fn raycast(&self, origin: DVec2, dir: DVec2, max_dist: f64) -> Option<(f64, DVec2)> {
+        let normal = DVec2::from_angle(self.normal_angle);
+        let height = (origin - self.pos).dot(normal);
+        let approach = dir.dot(normal);
+        if height < 0.0 || approach >= 0.0 {
+            return None;
+        }
+        let t = -height / approach;
+        (t <= max_dist).then_some((t, normal))
+    }
+
     pub fn test_overlap_with_circle(&self, other: &Circle) -> Option<Contact> {
         other.test_overlap_with_half_plane(self).map(|mut c| {
             // c.normal points from `other` to `self`, so we need to flip it.
@@ -100,4 +398,1305 @@ impl HalfPlane {
             c
         })
     }
+
+    /// See [`Shape::closest_points`], `other` needs to be bounded. Returns the
+    /// point of the boundary nearest to `other`'s deepest point, that point and
+    /// their signed distance.
+    fn closest_points_to(&self, other: &Shape) -> (DVec2, DVec2, f64) {
+        let normal = DVec2::from_angle(self.normal_angle);
+        let deepest = other
+            .support_map()
+            .expect("bounded shapes have a support map")
+            .support(-normal);
+        let distance = (deepest - self.pos).dot(normal);
+        (deepest - distance * normal, deepest, distance)
+    }
+
+    /// See [`Shape::closest_points`]. Only half-planes facing away from each
+    /// other can be apart, by the width of the gap between them.
+    fn closest_points_to_half_plane(&self, other: &HalfPlane) -> (DVec2, DVec2, f64) {
+        let normal = DVec2::from_angle(self.normal_angle);
+        let other_normal = DVec2::from_angle(other.normal_angle);
+        if normal.dot(other_normal) > -1.0 + 1e-12 {
+            return (self.pos, self.pos, f64::NEG_INFINITY);
+        }
+        let distance = (other.pos - self.pos).dot(normal);
+        (self.pos, self.pos + distance * normal, distance)
+    }
+
+    pub fn test_overlap_with_polygon(&self, other: &Polygon) -> Vec<Contact> {
+        other
+            .test_overlap_with_half_plane(self)
+            .into_iter()
+            .map(|mut c| {
+                // c.normal points from `other` to `self`, so we need to flip it.
+                c.normal = -c.normal;
+                c
+            })
+            .collect()
+    }
+
+    pub fn test_overlap_with_capsule(&self, other: &Capsule) -> Vec<Contact> {
+        other
+            .test_overlap_with_half_plane(self)
+            .into_iter()
+            .map(|mut c| {
+                // c.normal points from `other` to `self`, so we need to flip it.
+                c.normal = -c.normal;
+                c
+            })
+            .collect()
+    }
+}
+
+impl Capsule {
+    /// End points of the capsule's segment.
+    pub fn endpoints(&self) -> (DVec2, DVec2) {
+        let half_segment = 0.5 * self.length * DVec2::from_angle(self.angle);
+        (self.pos - half_segment, self.pos + half_segment)
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        let (a, b) = self.endpoints();
+        let extent = DVec2::splat(self.radius);
+        Aabb::new(a.min(b) - extent, a.max(b) + extent)
+    }
+
+    /// The capsule's cap circle centered at the point of its segment closest to `point`.
+    fn closest_circle(&self, point: DVec2) -> Circle {
+        self.cap_at(closest_point_on_segment(point, self.endpoints()))
+    }
+
+    pub fn test_overlap_with_circle(&self, other: &Circle) -> Option<Contact> {
+        self.closest_circle(other.pos)
+            .test_overlap_with_circle(other)
+    }
+
+    /// Produces two contacts, at the ends of the overlapping part of the
+    /// segments, when the capsules are near-parallel and one contact between the
+    /// closest points of the segments otherwise. Crossing segments have no
+    /// closest points to take a normal from, so their penetration is found by
+    /// [`gjk_epa`] instead.
+    pub fn test_overlap_with_capsule(&self, other: &Capsule) -> Vec<Contact> {
+        let (a1, b1) = self.endpoints();
+        let (a2, b2) = other.endpoints();
+        let (d1, d2) = (b1 - a1, b2 - a2);
+
+        let sine = d1.perp_dot(d2).abs() / (d1.length() * d2.length());
+        if sine < CAPSULE_PARALLEL_TOLERANCE {
+            // Overlap of the other segment's projection with this segment.
+            let project = |p: DVec2| (p - a1).dot(d1) / d1.length_squared();
+            let (t_a, t_b) = (project(a2), project(b2));
+            let (t_min, t_max) = (t_a.min(t_b).max(0.0), t_a.max(t_b).min(1.0));
+            if t_max - t_min > CAPSULE_PARALLEL_TOLERANCE {
+                let contacts: Vec<Contact> = [t_min, t_max]
+                    .into_iter()
+                    .filter_map(|t| {
+                        let pos = a1 + d1 * t;
+                        self.cap_at(pos)
+                            .test_overlap_with_circle(&other.closest_circle(pos))
+                    })
+                    .collect();
+                if !contacts.is_empty() {
+                    return contacts;
+                }
+            }
+        }
+
+        let (p1, p2) = closest_points_on_segments((a1, b1), (a2, b2));
+        if p1.distance(p2) > CAPSULE_PARALLEL_TOLERANCE * (self.radius + other.radius) {
+            self.cap_at(p1)
+                .test_overlap_with_circle(&other.cap_at(p2))
+                .into_iter()
+                .collect()
+        } else {
+            gjk_epa(self, other).into_iter().collect()
+        }
+    }
+
+    /// The capsule's cap circle centered at `pos`, which should lie on its segment.
+    fn cap_at(&self, pos: DVec2) -> Circle {
+        Circle {
+            pos,
+            radius: self.radius,
+        }
+    }
+
+    /// See [`Shape::raycast`]. The capsule is the union of its two end caps
+    /// and the rectangle between them, the ray enters it where it first enters one of them.
+    pub fn raycast(&self, origin: DVec2, dir: DVec2, max_dist: f64) -> Option<(f64, DVec2)> {
+        let (a, b) = self.endpoints();
+        if closest_point_on_segment(origin, (a, b)).distance_squared(origin)
+            < self.radius * self.radius
+        {
+            return None;
+        }
+        let side = self.radius * DVec2::from_angle(self.angle).perp();
+        let body = if self.length > 0.0 {
+            let rectangle = Polygon {
+                vertices: vec![a - side, b - side, b + side, a + side],
+            };
+            rectangle.raycast(origin, dir, max_dist)
+        } else {
+            None
+        };
+        let caps = [a, b].map(|end| self.cap_at(end).raycast(origin, dir, max_dist));
+        caps.into_iter()
+            .chain([body])
+            .flatten()
+            .min_by(|(t1, _), (t2, _)| t1.total_cmp(t2))
+    }
+
+    /// Tests both end caps against the half-plane, so a capsule lying flat on
+    /// it gets a contact at each end.
+    pub fn test_overlap_with_half_plane(&self, other: &HalfPlane) -> Vec<Contact> {
+        let (a, b) = self.endpoints();
+        [a, b]
+            .into_iter()
+            .filter_map(|pos| {
+                Circle {
+                    pos,
+                    radius: self.radius,
+                }
+                .test_overlap_with_half_plane(other)
+            })
+            .collect()
+    }
+
+    /// Treats the capsule as a circle swept along its segment against the
+    /// polygon edge closest to the segment. A segment parallel to that edge is
+    /// clipped to it and yields a contact at each end of the clipped part, like
+    /// in [`Capsule::test_overlap_with_capsule`], otherwise there is one
+    /// contact between the closest points. A segment reaching into the polygon
+    /// has no closest points to take a normal from, so its penetration is
+    /// found by [`gjk_epa`] instead.
+    pub fn test_overlap_with_polygon(&self, other: &Polygon) -> Vec<Contact> {
+        let segment @ (a, b) = self.endpoints();
+        let Some((p1, p2)) = other
+            .edges()
+            .map(|(start, end, _)| closest_points_on_segments(segment, (start, end)))
+            .min_by(|(a1, b1), (a2, b2)| {
+                a1.distance_squared(*b1)
+                    .total_cmp(&a2.distance_squared(*b2))
+            })
+        else {
+            return vec![];
+        };
+        let distance = p1.distance(p2);
+        if distance > self.radius {
+            return vec![];
+        }
+        let inside = |p: DVec2| {
+            other
+                .edges()
+                .all(|(start, _, normal)| normal.dot(p - start) <= 0.0)
+        };
+        if distance <= CAPSULE_PARALLEL_TOLERANCE * self.radius || inside(a) || inside(b) {
+            return gjk_epa(self, other).into_iter().collect();
+        }
+
+        // Near a vertex the segment is as close to both of its edges, the one
+        // parallel to the segment is the one it rests on.
+        let direction = b - a;
+        let parallel_edge = other.edges().find(|&(start, end, normal)| {
+            let edge = end - start;
+            let sine = direction.perp_dot(edge).abs() / (direction.length() * edge.length());
+            let (q1, q2) = closest_points_on_segments(segment, (start, end));
+            sine < CAPSULE_PARALLEL_TOLERANCE
+                && q1.distance(q2) <= distance + CAPSULE_PARALLEL_TOLERANCE * self.radius
+                && normal.dot(a - start) > 0.0
+        });
+        if let Some((start, end, normal)) = parallel_edge {
+            let tangent = (end - start).normalize();
+            let clipped = clip_segment(segment, tangent, tangent.dot(start), tangent.dot(end));
+            if let Some((c1, c2)) = clipped
+                .filter(|(c1, c2)| c1.distance(*c2) > CAPSULE_PARALLEL_TOLERANCE * self.radius)
+            {
+                let contacts: Vec<Contact> = [c1, c2]
+                    .into_iter()
+                    .filter_map(|pos| {
+                        let separation = normal.dot(pos - start) - self.radius;
+                        (separation <= 0.0)
+                            .then(|| Contact::new(pos - self.radius * normal, -normal, separation))
+                    })
+                    .collect();
+                if !contacts.is_empty() {
+                    return contacts;
+                }
+            }
+        }
+
+        let normal = (p2 - p1) / distance;
+        let separation = distance - self.radius;
+        trace!("Overlap result: normal {normal}, separation {separation}");
+        vec![Contact::new(p1 + self.radius * normal, normal, separation)]
+    }
+}
+
+impl SupportMap for Capsule {
+    fn support(&self, dir: DVec2) -> DVec2 {
+        let (a, b) = self.endpoints();
+        let end = if a.dot(dir) > b.dot(dir) { a } else { b };
+        end + self.radius * dir.normalize_or_zero()
+    }
+}
+
+impl Polygon {
+    pub fn aabb(&self) -> Aabb {
+        self.vertices.iter().fold(
+            Aabb::new(DVec2::INFINITY, DVec2::NEG_INFINITY),
+            |aabb, &v| Aabb::new(aabb.min.min(v), aabb.max.max(v)),
+        )
+    }
+
+    /// See [`Shape::raycast`]. Clips the ray against the half-planes of all
+    /// edges (Cyrus-Beck), the last edge to move the entry point is the one hit.
+    pub fn raycast(&self, origin: DVec2, dir: DVec2, max_dist: f64) -> Option<(f64, DVec2)> {
+        let (mut enter, mut exit) = (0.0, max_dist);
+        let mut hit_normal = None;
+        for (start, _, normal) in self.edges() {
+            // The ray is inside this edge's half-plane for `t * approach <= height`.
+            let height = normal.dot(start - origin);
+            let approach = normal.dot(dir);
+            if approach == 0.0 {
+                if height < 0.0 {
+                    return None;
+                }
+            } else if approach < 0.0 && height < enter * approach {
+                enter = height / approach;
+                hit_normal = Some(normal);
+            } else if approach > 0.0 && height < exit * approach {
+                exit = height / approach;
+            }
+            if exit < enter {
+                return None;
+            }
+        }
+        // Without an entering edge the ray starts inside.
+        hit_normal.map(|normal| (enter, normal))
+    }
+
+    /// Edges as `(start, end, outward normal)`.
+    fn edges(&self) -> impl Iterator<Item = (DVec2, DVec2, DVec2)> + '_ {
+        let n = self.vertices.len();
+        (0..n).map(move |i| {
+            let (start, end) = (self.vertices[i], self.vertices[(i + 1) % n]);
+            // The polygon is counter-clockwise, so the outside is on the right.
+            (start, end, -(end - start).perp().normalize())
+        })
+    }
+
+    /// The edge of `self` along whose normal `other` is separated the most,
+    /// with that separation. Negative separations mean penetration.
+    fn max_separation(&self, other: &Polygon) -> (f64, (DVec2, DVec2, DVec2)) {
+        self.edges()
+            .map(|edge @ (start, _, normal)| {
+                let separation = other
+                    .vertices
+                    .iter()
+                    .map(|&v| normal.dot(v - start))
+                    .fold(f64::INFINITY, f64::min);
+                (separation, edge)
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .expect("a polygon has at least one edge")
+    }
+
+    /// Separating axis test. The contacts lie on the incident edge clipped to
+    /// the reference edge, i.e. the edge of least penetration, so edges
+    /// resting on each other yield two contacts.
+    pub fn test_overlap_with_polygon(&self, other: &Polygon) -> Vec<Contact> {
+        let (self_separation, self_edge) = self.max_separation(other);
+        let (other_separation, other_edge) = other.max_separation(self);
+        if self_separation > 0.0 || other_separation > 0.0 {
+            return vec![];
+        }
+        // Prefer `self` as the reference so that the result does not flip
+        // between frames because of rounding.
+        let self_is_reference = self_separation >= other_separation - SAT_REFERENCE_TOLERANCE;
+        let (reference, (start, end, normal), incident) = if self_is_reference {
+            (self, self_edge, other)
+        } else {
+            (other, other_edge, self)
+        };
+        trace!("SAT reference edge {start} -> {end} of {reference:?}");
+
+        // Incident edge is the one facing the reference edge the most.
+        let (incident_start, incident_end, _) = incident
+            .edges()
+            .min_by(|a, b| a.2.dot(normal).total_cmp(&b.2.dot(normal)))
+            .expect("a polygon has at least one edge");
+        let tangent = (end - start).normalize();
+        let Some((clipped_start, clipped_end)) = clip_segment(
+            (incident_start, incident_end),
+            tangent,
+            tangent.dot(start),
+            tangent.dot(end),
+        ) else {
+            return vec![];
+        };
+
+        [clipped_start, clipped_end]
+            .into_iter()
+            .filter_map(|p| {
+                let separation = normal.dot(p - start);
+                if separation > 0.0 {
+                    return None;
+                }
+                Some(if self_is_reference {
+                    Contact::new(p - separation * normal, normal, separation)
+                } else {
+                    Contact::new(p, -normal, separation)
+                })
+            })
+            .collect()
+    }
+
+    pub fn test_overlap_with_circle(&self, other: &Circle) -> Option<Contact> {
+        let (separation, (start, _, normal)) = self
+            .edges()
+            .map(|edge @ (start, _, normal)| (normal.dot(other.pos - start), edge))
+            .max_by(|a, b| a.0.total_cmp(&b.0))?;
+        if separation > other.radius {
+            return None;
+        }
+        if separation <= 0.0 {
+            // The center is inside, push out through the closest edge.
+            let pos = other.pos - separation * normal;
+            trace!("Overlap result: normal {normal}, separation {separation}, edge {start}");
+            return Some(Contact::new(pos, normal, separation - other.radius));
+        }
+        // The center is outside, the closest point may be a vertex.
+        let pos = self
+            .edges()
+            .map(|(start, end, _)| closest_point_on_segment(other.pos, (start, end)))
+            .min_by(|a, b| {
+                a.distance_squared(other.pos)
+                    .total_cmp(&b.distance_squared(other.pos))
+            })?;
+        let diff = other.pos - pos;
+        let normal = diff.try_normalize()?;
+        let separation = diff.length() - other.radius;
+        trace!("Overlap result: normal {normal}, separation {separation}");
+        (separation <= 0.0).then(|| Contact::new(pos, normal, separation))
+    }
+
+    /// See [`Capsule::test_overlap_with_polygon`].
+    pub fn test_overlap_with_capsule(&self, other: &Capsule) -> Vec<Contact> {
+        other
+            .test_overlap_with_polygon(self)
+            .into_iter()
+            // Move the contacts from the capsule's boundary to this one's.
+            .map(|c| Contact::new(c.pos + c.separation * c.normal, -c.normal, c.separation))
+            .collect()
+    }
+
+    /// Reports every vertex inside the half-plane.
+    pub fn test_overlap_with_half_plane(&self, other: &HalfPlane) -> Vec<Contact> {
+        let outward = DVec2::from_angle(other.normal_angle);
+        self.vertices
+            .iter()
+            .filter_map(|&v| {
+                let separation = (v - other.pos).dot(outward);
+                (separation <= 0.0).then(|| Contact::new(v, -outward, separation))
+            })
+            .collect()
+    }
+}
+
+impl SupportMap for Polygon {
+    fn support(&self, dir: DVec2) -> DVec2 {
+        self.vertices
+            .iter()
+            .copied()
+            .max_by(|a, b| a.dot(dir).total_cmp(&b.dot(dir)))
+            .unwrap_or(DVec2::ZERO)
+    }
+}
+
+/// How much deeper the penetration along an edge of the second polygon has to
+/// be for [`Polygon::test_overlap_with_polygon`] to choose it as the reference.
+const SAT_REFERENCE_TOLERANCE: f64 = 1e-9;
+
+/// Cuts the part of segment `(a, b)` whose projection onto `axis` lies outside
+/// `[min, max]`, or returns `None` if nothing remains.
+fn clip_segment((a, b): (DVec2, DVec2), axis: DVec2, min: f64, max: f64) -> Option<(DVec2, DVec2)> {
+    let (pa, pb) = (axis.dot(a), axis.dot(b));
+    if pa.max(pb) < min || pa.min(pb) > max {
+        return None;
+    }
+    let at = |projection: f64| {
+        if pa == pb {
+            a
+        } else {
+            a.lerp(b, (projection - pa) / (pb - pa))
+        }
+    };
+    let clip = |p: DVec2, projection: f64| {
+        if projection < min {
+            at(min)
+        } else if projection > max {
+            at(max)
+        } else {
+            p
+        }
+    };
+    Some((clip(a, pa), clip(b, pb)))
+}
+
+/// Point of segment `(a, b)` closest to `point`.
+fn closest_point_on_segment(point: DVec2, (a, b): (DVec2, DVec2)) -> DVec2 {
+    let segment = b - a;
+    let t = (point - a).dot(segment) / segment.length_squared();
+    // A zero-length segment yields a NaN `t`, which `clamp` would keep.
+    a + segment * if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) }
+}
+
+/// Sine of the angle below which two capsules are treated as parallel.
+const CAPSULE_PARALLEL_TOLERANCE: f64 = 1e-3;
+
+/// Closest points of segments `(a1, b1)` and `(a2, b2)`, one on each.
+/// See Ericson, Real-Time Collision Detection, section 5.1.9.
+fn closest_points_on_segments(
+    (a1, b1): (DVec2, DVec2),
+    (a2, b2): (DVec2, DVec2),
+) -> (DVec2, DVec2) {
+    let (d1, d2, r) = (b1 - a1, b2 - a2, a1 - a2);
+    let (l1, l2) = (d1.length_squared(), d2.length_squared());
+    let (f, c) = (d2.dot(r), d1.dot(r));
+    let (s, t) = if l1 <= f64::EPSILON && l2 <= f64::EPSILON {
+        (0.0, 0.0)
+    } else if l1 <= f64::EPSILON {
+        (0.0, (f / l2).clamp(0.0, 1.0))
+    } else if l2 <= f64::EPSILON {
+        ((-c / l1).clamp(0.0, 1.0), 0.0)
+    } else {
+        let b = d1.dot(d2);
+        let denominator = l1 * l2 - b * b;
+        // Parallel segments have no unique closest points, any `s` will do.
+        let s = if denominator > 0.0 {
+            ((b * f - c * l2) / denominator).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let t = (b * s + f) / l2;
+        if t < 0.0 {
+            ((-c / l1).clamp(0.0, 1.0), 0.0)
+        } else if t > 1.0 {
+            (((b - c) / l1).clamp(0.0, 1.0), 1.0)
+        } else {
+            (s, t)
+        }
+    };
+    (a1 + d1 * s, a2 + d2 * t)
+}
+
+const GJK_MAX_ITERATIONS: usize = 32;
+const EPA_MAX_ITERATIONS: usize = 64;
+const EPA_TOLERANCE: f64 = 1e-9;
+
+/// Tests two convex shapes for overlap with GJK and, if they overlap, finds the
+/// penetration with EPA (Expanding Polytope Algorithm).
+///
+/// Follows the same conventions as [`Shape::test_overlap`]: the normal points
+/// from `a` to `b` and the contact position lies on `a`'s boundary. Shapes that
+/// merely touch are not reported. Curved shapes are approximated by the
+/// polytope, so the result is accurate up to `EPA_TOLERANCE` only when EPA
+/// converges within `EPA_MAX_ITERATIONS`.
+pub fn gjk_epa(a: &dyn SupportMap, b: &dyn SupportMap) -> Option<Contact> {
+    let simplex = gjk(a, b)?;
+    Some(epa(a, b, simplex))
+}
+
+/// Support function of the Minkowski difference `a - b`.
+fn minkowski_support(a: &dyn SupportMap, b: &dyn SupportMap, dir: DVec2) -> DVec2 {
+    a.support(dir) - b.support(-dir)
+}
+
+/// Returns a triangle of Minkowski difference points enclosing the origin,
+/// or `None` if the shapes do not overlap.
+fn gjk(a: &dyn SupportMap, b: &dyn SupportMap) -> Option<[DVec2; 3]> {
+    let mut simplex = vec![minkowski_support(a, b, DVec2::X)];
+    let mut dir = -simplex[0];
+    for _ in 0..GJK_MAX_ITERATIONS {
+        if dir == DVec2::ZERO {
+            // The origin lies on the boundary, i.e. the shapes are touching.
+            return None;
+        }
+        let p = minkowski_support(a, b, dir);
+        if p.dot(dir) <= 0.0 {
+            return None;
+        }
+        simplex.push(p);
+        match update_simplex(&mut simplex) {
+            Some(new_dir) => dir = new_dir,
+            None => return Some([simplex[0], simplex[1], simplex[2]]),
+        }
+    }
+    None
+}
+
+/// Reduces `simplex` (newest point last) to the feature closest to the origin
+/// and returns the next search direction, or `None` once the simplex is a
+/// triangle containing the origin.
+fn update_simplex(simplex: &mut Vec<DVec2>) -> Option<DVec2> {
+    let a = *simplex.last().unwrap();
+    let ao = -a;
+    match simplex[..] {
+        [b, _] => {
+            let ab = b - a;
+            if ab.dot(ao) > 0.0 {
+                let perp = ab.perp();
+                Some(if perp.dot(ao) >= 0.0 { perp } else { -perp })
+            } else {
+                *simplex = vec![a];
+                Some(ao)
+            }
+        }
+        [c, b, _] => {
+            let ab = b - a;
+            let ac = c - a;
+            let ab_perp = if ab.perp().dot(ac) > 0.0 {
+                -ab.perp()
+            } else {
+                ab.perp()
+            };
+            let ac_perp = if ac.perp().dot(ab) > 0.0 {
+                -ac.perp()
+            } else {
+                ac.perp()
+            };
+            if ab_perp.dot(ao) > 0.0 {
+                *simplex = vec![b, a];
+                Some(ab_perp)
+            } else if ac_perp.dot(ao) > 0.0 {
+                *simplex = vec![c, a];
+                Some(ac_perp)
+            } else {
+                None
+            }
+        }
+        _ => unreachable!("GJK simplex has between 2 and 3 points"),
+    }
+}
+
+/// Expands the GJK triangle towards the boundary of the Minkowski difference
+/// until the edge closest to the origin is found.
+fn epa(a: &dyn SupportMap, b: &dyn SupportMap, triangle: [DVec2; 3]) -> Contact {
+    let mut polytope = triangle.to_vec();
+    // Keep the polytope counter-clockwise so `-perp` of an edge faces outwards.
+    if (polytope[1] - polytope[0]).perp_dot(polytope[2] - polytope[0]) < 0.0 {
+        polytope.swap(0, 1);
+    }
+    let mut normal = DVec2::X;
+    let mut depth = f64::INFINITY;
+    for _ in 0..EPA_MAX_ITERATIONS {
+        let mut closest_edge = 0;
+        depth = f64::INFINITY;
+        for i in 0..polytope.len() {
+            let edge = polytope[(i + 1) % polytope.len()] - polytope[i];
+            let Some(edge_normal) = (-edge.perp()).try_normalize() else {
+                continue;
+            };
+            let distance = edge_normal.dot(polytope[i]);
+            if distance < depth {
+                depth = distance;
+                normal = edge_normal;
+                closest_edge = i;
+            }
+        }
+        let p = minkowski_support(a, b, normal);
+        if p.dot(normal) - depth < EPA_TOLERANCE {
+            break;
+        }
+        polytope.insert(closest_edge + 1, p);
+    }
+    trace!("EPA result: normal {normal}, depth {depth}");
+    Contact {
+        pos: a.support(normal),
+        normal,
+        separation: -depth,
+    }
+}
+
+/// Computes the convex hull of `points` using Andrew's monotone chain algorithm.
+///
+/// The hull vertices are returned in counter-clockwise order starting from the
+/// lowest-leftmost point. Duplicate points and points lying on a hull edge are
+/// dropped, so collinear inputs yield just the two extreme points. Inputs with
+/// fewer than three distinct points are returned deduplicated and sorted.
+pub fn convex_hull(points: &[DVec2]) -> Vec<DVec2> {
+    let mut points = points.to_vec();
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    let cross = |o: DVec2, a: DVec2, b: DVec2| (a - o).perp_dot(b - o);
+    let mut hull: Vec<DVec2> = Vec::with_capacity(2 * points.len());
+    // Lower hull
+    for &p in &points {
+        while hull.len() >= 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0 {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    // Upper hull
+    let lower_len = hull.len() + 1;
+    for &p in points.iter().rev().skip(1) {
+        while hull.len() >= lower_len && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0
+        {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    // The last point is the starting point again.
+    hull.pop();
+    hull
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use glam::dvec2;
+
+    use super::*;
+
+    #[test]
+    fn circle_aabb() {
+        let circle = Shape::Circle(Circle {
+            pos: dvec2(10.0, -5.0),
+            radius: 2.0,
+        });
+        assert_eq!(
+            circle.aabb(),
+            Aabb::new(dvec2(8.0, -7.0), dvec2(12.0, -3.0))
+        );
+        assert!(!circle.aabb().is_infinite());
+    }
+
+    #[test]
+    fn contains_point_of_every_shape() {
+        let capsule = Shape::Capsule(Capsule {
+            pos: dvec2(0.0, 0.0),
+            angle: PI / 2.0,
+            length: 4.0,
+            radius: 1.0,
+        });
+        assert!(capsule.contains_point(dvec2(0.5, 2.5)));
+        assert!(!capsule.contains_point(dvec2(1.5, 0.0)));
+
+        let triangle = Shape::Polygon(Polygon {
+            vertices: vec![dvec2(0.0, 0.0), dvec2(2.0, 0.0), dvec2(0.0, 2.0)],
+        });
+        assert!(triangle.contains_point(dvec2(0.5, 0.5)));
+        assert!(!triangle.contains_point(dvec2(1.5, 1.5)));
+
+        let half_plane = Shape::HalfPlane(HalfPlane {
+            pos: dvec2(0.0, 1.0),
+            normal_angle: PI / 2.0,
+        });
+        assert!(half_plane.contains_point(dvec2(100.0, 0.0)));
+        assert!(!half_plane.contains_point(dvec2(0.0, 2.0)));
+    }
+
+    #[test]
+    fn closest_points_of_circles() {
+        let c1 = Shape::Circle(Circle {
+            pos: dvec2(0.0, 0.0),
+            radius: 1.0,
+        });
+        let c2 = Shape::Circle(Circle {
+            pos: dvec2(3.0, 4.0),
+            radius: 2.0,
+        });
+        let (p1, p2, distance) = c1.closest_points(&c2);
+        assert!(p1.abs_diff_eq(dvec2(0.6, 0.8), 1e-12));
+        assert!(p2.abs_diff_eq(dvec2(1.8, 2.4), 1e-12));
+        assert!((distance - 2.0).abs() < 1e-12);
+
+        let overlapping = Shape::Circle(Circle {
+            pos: dvec2(2.5, 0.0),
+            radius: 2.0,
+        });
+        let (p1, p2, distance) = c1.closest_points(&overlapping);
+        assert!(p1.abs_diff_eq(dvec2(1.0, 0.0), 1e-12));
+        assert!(p2.abs_diff_eq(dvec2(0.5, 0.0), 1e-12));
+        assert!((distance + 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn closest_points_of_circle_and_capsule() {
+        let circle = Shape::Circle(Circle {
+            pos: dvec2(0.5, 3.0),
+            radius: 1.0,
+        });
+        // Horizontal from (-2, 0) to (2, 0).
+        let capsule = Shape::Capsule(Capsule {
+            pos: dvec2(0.0, 0.0),
+            angle: 0.0,
+            length: 4.0,
+            radius: 0.5,
+        });
+        let (p1, p2, distance) = circle.closest_points(&capsule);
+        assert!(p1.abs_diff_eq(dvec2(0.5, 2.0), 1e-12));
+        assert!(p2.abs_diff_eq(dvec2(0.5, 0.5), 1e-12));
+        assert!((distance - 1.5).abs() < 1e-12);
+
+        // Beyond the end of the segment the cap is nearest, and the order of
+        // the shapes only swaps the points.
+        let beyond_end = Shape::Circle(Circle {
+            pos: dvec2(5.0, 0.0),
+            radius: 1.0,
+        });
+        let (p1, p2, distance) = capsule.closest_points(&beyond_end);
+        assert!(p1.abs_diff_eq(dvec2(2.5, 0.0), 1e-12));
+        assert!(p2.abs_diff_eq(dvec2(4.0, 0.0), 1e-12));
+        assert!((distance - 1.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn closest_points_of_polygons_and_half_planes() {
+        let square = |x: f64| {
+            Shape::Polygon(Polygon {
+                vertices: vec![
+                    dvec2(x, 0.0),
+                    dvec2(x + 1.0, 0.0),
+                    dvec2(x + 1.0, 1.0),
+                    dvec2(x, 1.0),
+                ],
+            })
+        };
+        let (p1, p2, distance) = square(0.0).closest_points(&square(3.0));
+        assert_eq!((p1.x, p2.x), (1.0, 3.0));
+        assert_eq!(p1.y, p2.y);
+        assert!((distance - 2.0).abs() < 1e-12);
+
+        let ground = Shape::HalfPlane(HalfPlane {
+            pos: dvec2(0.0, -2.0),
+            normal_angle: PI / 2.0,
+        });
+        let (p1, p2, distance) = square(0.0).closest_points(&ground);
+        assert_eq!(p1.y, 0.0);
+        assert!(p2.abs_diff_eq(dvec2(p1.x, -2.0), 1e-12));
+        assert!((distance - 2.0).abs() < 1e-12);
+
+        let ceiling = Shape::HalfPlane(HalfPlane {
+            pos: dvec2(0.0, 1.0),
+            normal_angle: -PI / 2.0,
+        });
+        let (_, _, distance) = ground.closest_points(&ceiling);
+        assert!((distance - 3.0).abs() < 1e-12);
+        let wall = Shape::HalfPlane(HalfPlane {
+            pos: dvec2(0.0, 0.0),
+            normal_angle: 0.0,
+        });
+        assert_eq!(ground.closest_points(&wall).2, f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn half_plane_aabb_is_infinite() {
+        let half_plane = Shape::HalfPlane(HalfPlane {
+            pos: DVec2::ZERO,
+            normal_angle: 0.0,
+        });
+        let aabb = half_plane.aabb();
+        assert_eq!(aabb, Aabb::INFINITE);
+        assert!(aabb.is_infinite());
+        let far = Aabb::new(dvec2(1e9, 1e9), dvec2(1e9 + 1.0, 1e9 + 1.0));
+        assert!(aabb.overlaps(&far));
+        assert!(far.overlaps(&aabb));
+    }
+
+    #[test]
+    fn aabbs_overlap_when_they_share_a_point() {
+        let unit = Aabb::new(DVec2::ZERO, DVec2::ONE);
+        assert!(unit.overlaps(&Aabb::new(dvec2(0.5, 0.5), dvec2(2.0, 2.0))));
+        assert!(unit.overlaps(&Aabb::new(dvec2(1.0, 1.0), dvec2(2.0, 2.0))));
+        assert!(!unit.overlaps(&Aabb::new(dvec2(1.5, 0.0), dvec2(2.0, 1.0))));
+        assert!(!unit.overlaps(&Aabb::new(dvec2(0.0, -2.0), dvec2(1.0, -0.5))));
+    }
+
+    #[test]
+    fn capsule_aabb() {
+        let capsule = Shape::Capsule(Capsule {
+            pos: dvec2(1.0, 1.0),
+            angle: std::f64::consts::FRAC_PI_2,
+            length: 4.0,
+            radius: 1.0,
+        });
+        let Aabb { min, max } = capsule.aabb();
+        assert!(min.abs_diff_eq(dvec2(0.0, -2.0), 1e-12));
+        assert!(max.abs_diff_eq(dvec2(2.0, 4.0), 1e-12));
+    }
+
+    #[test]
+    fn capsule_lying_on_half_plane_touches_at_both_ends() {
+        let capsule = Shape::Capsule(Capsule {
+            pos: dvec2(0.0, 0.9),
+            angle: 0.0,
+            length: 4.0,
+            radius: 1.0,
+        });
+        let ground = Shape::HalfPlane(HalfPlane {
+            pos: DVec2::ZERO,
+            normal_angle: std::f64::consts::FRAC_PI_2,
+        });
+        let contacts = capsule.test_overlap(&ground);
+        assert_eq!(contacts.len(), 2);
+        for (contact, x) in contacts.iter().zip([-2.0, 2.0]) {
+            assert!(contact.pos.abs_diff_eq(dvec2(x, -0.1), 1e-12));
+            assert!(contact.normal.abs_diff_eq(-DVec2::Y, 1e-12));
+            assert!((contact.separation + 0.1).abs() < 1e-12);
+        }
+        assert_eq!(ground.test_overlap(&capsule).len(), 2);
+    }
+
+    #[test]
+    fn circle_touching_capsule_side() {
+        let capsule = Capsule {
+            pos: DVec2::ZERO,
+            angle: 0.0,
+            length: 4.0,
+            radius: 1.0,
+        };
+        let circle = Circle {
+            pos: dvec2(1.5, 1.5),
+            radius: 1.0,
+        };
+        let contact = capsule.test_overlap_with_circle(&circle).unwrap();
+        assert!(contact.pos.abs_diff_eq(dvec2(1.5, 1.0), 1e-12));
+        assert!(contact.normal.abs_diff_eq(DVec2::Y, 1e-12));
+        assert!((contact.separation + 0.5).abs() < 1e-12);
+
+        let flipped = circle.test_overlap_with_capsule(&capsule).unwrap();
+        assert!(flipped.normal.abs_diff_eq(-DVec2::Y, 1e-12));
+        assert!((flipped.separation + 0.5).abs() < 1e-12);
+    }
+
+    fn horizontal_capsule(pos: DVec2) -> Capsule {
+        Capsule {
+            pos,
+            angle: 0.0,
+            length: 4.0,
+            radius: 0.5,
+        }
+    }
+
+    #[test]
+    fn crossing_capsules() {
+        let horizontal = horizontal_capsule(DVec2::ZERO);
+        let vertical = Capsule {
+            angle: std::f64::consts::FRAC_PI_2,
+            ..horizontal_capsule(dvec2(1.0, 0.0))
+        };
+        let contacts = horizontal.test_overlap_with_capsule(&vertical);
+        assert_eq!(contacts.len(), 1);
+        // Pushing the vertical capsule past the horizontal one's right cap is
+        // shorter than pushing it up or down.
+        assert!(contacts[0].normal.abs_diff_eq(DVec2::X, 1e-6));
+        assert!((contacts[0].separation + 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn parallel_overlapping_capsules() {
+        let lower = horizontal_capsule(DVec2::ZERO);
+        let upper = horizontal_capsule(dvec2(1.0, 0.8));
+        let contacts = lower.test_overlap_with_capsule(&upper);
+        assert_eq!(contacts.len(), 2);
+        for (contact, x) in contacts.iter().zip([-1.0, 2.0]) {
+            assert!(contact.pos.abs_diff_eq(dvec2(x, 0.5), 1e-12));
+            assert!(contact.normal.abs_diff_eq(DVec2::Y, 1e-12));
+            assert!((contact.separation + 0.2).abs() < 1e-12);
+        }
+
+        let flipped = upper.test_overlap_with_capsule(&lower);
+        assert_eq!(flipped.len(), 2);
+        assert!(flipped[0].normal.abs_diff_eq(-DVec2::Y, 1e-12));
+    }
+
+    #[test]
+    fn capsules_touching_end_to_end() {
+        let left = horizontal_capsule(DVec2::ZERO);
+        let right = horizontal_capsule(dvec2(4.5, 0.0));
+        let contacts = left.test_overlap_with_capsule(&right);
+        assert_eq!(contacts.len(), 1);
+        assert!(contacts[0].pos.abs_diff_eq(dvec2(2.5, 0.0), 1e-12));
+        assert!(contacts[0].normal.abs_diff_eq(DVec2::X, 1e-12));
+        assert!((contacts[0].separation + 0.5).abs() < 1e-12);
+
+        let tilted = Capsule {
+            angle: std::f64::consts::FRAC_PI_4,
+            ..horizontal_capsule(dvec2(2.0, 0.0) + 2.5 * DVec2::from_angle(0.25 * PI))
+        };
+        let contacts = left.test_overlap_with_capsule(&tilted);
+        assert_eq!(contacts.len(), 1);
+        assert!(contacts[0]
+            .normal
+            .abs_diff_eq(DVec2::from_angle(0.25 * PI), 1e-12));
+        assert!((contacts[0].separation + 0.5).abs() < 1e-12);
+
+        let far = horizontal_capsule(dvec2(5.5, 0.0));
+        assert!(left.test_overlap_with_capsule(&far).is_empty());
+    }
+
+    fn rectangle(min: DVec2, max: DVec2) -> Polygon {
+        Polygon {
+            vertices: vec![min, dvec2(max.x, min.y), max, dvec2(min.x, max.y)],
+        }
+    }
+
+    #[test]
+    fn capsule_resting_flat_on_box() {
+        let capsule = Shape::Capsule(Capsule {
+            pos: dvec2(0.0, 0.4),
+            angle: 0.0,
+            length: 2.0,
+            radius: 0.5,
+        });
+        let bx = Shape::Polygon(rectangle(dvec2(-3.0, -2.0), dvec2(3.0, 0.0)));
+        let contacts = capsule.test_overlap(&bx);
+        assert_eq!(contacts.len(), 2);
+        for (contact, x) in contacts.iter().zip([-1.0, 1.0]) {
+            assert!(contact.pos.abs_diff_eq(dvec2(x, -0.1), 1e-12));
+            assert!(contact.normal.abs_diff_eq(-DVec2::Y, 1e-12));
+            assert!((contact.separation + 0.1).abs() < 1e-12);
+        }
+
+        let flipped = bx.test_overlap(&capsule);
+        assert_eq!(flipped.len(), 2);
+        for (contact, x) in flipped.iter().zip([-1.0, 1.0]) {
+            assert!(contact.pos.abs_diff_eq(dvec2(x, 0.0), 1e-12));
+            assert!(contact.normal.abs_diff_eq(DVec2::Y, 1e-12));
+        }
+
+        // Hanging over the box's corner it still rests on the top edge.
+        let overhanging = Capsule {
+            pos: dvec2(3.5, 0.4),
+            angle: 0.0,
+            length: 2.0,
+            radius: 0.5,
+        };
+        let Shape::Polygon(polygon) = &bx else {
+            unreachable!()
+        };
+        let contacts = overhanging.test_overlap_with_polygon(polygon);
+        assert_eq!(contacts.len(), 2);
+        for (contact, x) in contacts.iter().zip([2.5, 3.0]) {
+            assert!(contact.pos.abs_diff_eq(dvec2(x, -0.1), 1e-12));
+            assert!(contact.normal.abs_diff_eq(-DVec2::Y, 1e-12));
+        }
+    }
+
+    #[test]
+    fn capsule_tip_poking_polygon_edge() {
+        let capsule = Shape::Capsule(Capsule {
+            pos: dvec2(0.5, 1.4),
+            angle: std::f64::consts::FRAC_PI_2,
+            length: 2.0,
+            radius: 0.5,
+        });
+        let polygon = Shape::Polygon(Polygon {
+            vertices: vec![dvec2(0.0, -3.0), dvec2(2.0, 0.0), dvec2(-2.0, 0.0)],
+        });
+        let contacts = capsule.test_overlap(&polygon);
+        assert_eq!(contacts.len(), 1);
+        assert!(contacts[0].pos.abs_diff_eq(dvec2(0.5, -0.1), 1e-12));
+        assert!(contacts[0].normal.abs_diff_eq(-DVec2::Y, 1e-12));
+        assert!((contacts[0].separation + 0.1).abs() < 1e-12);
+
+        let flipped = polygon.test_overlap(&capsule);
+        assert_eq!(flipped.len(), 1);
+        assert!(flipped[0].pos.abs_diff_eq(dvec2(0.5, 0.0), 1e-12));
+        assert!(flipped[0].normal.abs_diff_eq(DVec2::Y, 1e-12));
+    }
+
+    #[test]
+    fn polygon_resting_on_polygon() {
+        let lower = rectangle(dvec2(0.0, 0.0), dvec2(2.0, 2.0));
+        let upper = rectangle(dvec2(1.0, 1.8), dvec2(3.0, 3.8));
+        let contacts = lower.test_overlap_with_polygon(&upper);
+        assert_eq!(contacts.len(), 2);
+        for (contact, x) in contacts.iter().zip([1.0, 2.0]) {
+            assert!(contact.pos.abs_diff_eq(dvec2(x, 2.0), 1e-12));
+            assert!(contact.normal.abs_diff_eq(DVec2::Y, 1e-12));
+            assert!((contact.separation + 0.2).abs() < 1e-12);
+        }
+
+        let flipped = upper.test_overlap_with_polygon(&lower);
+        assert_eq!(flipped.len(), 2);
+        for contact in &flipped {
+            assert!(contact.normal.abs_diff_eq(-DVec2::Y, 1e-12));
+            assert!((contact.pos.y - 1.8).abs() < 1e-12);
+            assert!((contact.separation + 0.2).abs() < 1e-12);
+        }
+
+        let apart = rectangle(dvec2(2.5, 0.0), dvec2(4.0, 2.0));
+        assert!(lower.test_overlap_with_polygon(&apart).is_empty());
+    }
+
+    #[test]
+    fn polygon_corner_poking_polygon() {
+        let square = rectangle(dvec2(-1.0, -1.0), dvec2(1.0, 1.0));
+        let diamond = Polygon {
+            vertices: vec![
+                dvec2(0.0, 0.9),
+                dvec2(1.0, 1.9),
+                dvec2(0.0, 2.9),
+                dvec2(-1.0, 1.9),
+            ],
+        };
+        let contacts = square.test_overlap_with_polygon(&diamond);
+        assert_eq!(contacts.len(), 1);
+        assert!(contacts[0].pos.abs_diff_eq(dvec2(0.0, 1.0), 1e-12));
+        assert!(contacts[0].normal.abs_diff_eq(DVec2::Y, 1e-12));
+        assert!((contacts[0].separation + 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn polygon_and_circle() {
+        let square = rectangle(dvec2(0.0, 0.0), dvec2(2.0, 2.0));
+        let above = Circle {
+            pos: dvec2(1.0, 2.5),
+            radius: 1.0,
+        };
+        let contact = square.test_overlap_with_circle(&above).unwrap();
+        assert!(contact.pos.abs_diff_eq(dvec2(1.0, 2.0), 1e-12));
+        assert!(contact.normal.abs_diff_eq(DVec2::Y, 1e-12));
+        assert!((contact.separation + 0.5).abs() < 1e-12);
+
+        let inside = Circle {
+            pos: dvec2(1.0, 1.8),
+            radius: 0.5,
+        };
+        let contact = square.test_overlap_with_circle(&inside).unwrap();
+        assert!(contact.pos.abs_diff_eq(dvec2(1.0, 2.0), 1e-12));
+        assert!((contact.separation + 0.7).abs() < 1e-12);
+
+        let corner = Circle {
+            pos: dvec2(2.5, 2.5),
+            radius: 1.0,
+        };
+        let contact = square.test_overlap_with_circle(&corner).unwrap();
+        assert!(contact.pos.abs_diff_eq(dvec2(2.0, 2.0), 1e-12));
+        assert!(contact
+            .normal
+            .abs_diff_eq(DVec2::from_angle(0.25 * PI), 1e-12));
+
+        let flipped = corner.test_overlap_with_polygon(&square).unwrap();
+        assert!(flipped
+            .normal
+            .abs_diff_eq(-DVec2::from_angle(0.25 * PI), 1e-12));
+        assert!((flipped.separation - contact.separation).abs() < 1e-12);
+
+        let far = Circle {
+            pos: dvec2(3.0, 3.0),
+            radius: 1.0,
+        };
+        assert!(square.test_overlap_with_circle(&far).is_none());
+    }
+
+    #[test]
+    fn polygon_on_half_plane() {
+        let square = Shape::Polygon(rectangle(dvec2(0.0, -0.1), dvec2(2.0, 1.9)));
+        let ground = Shape::HalfPlane(HalfPlane {
+            pos: DVec2::ZERO,
+            normal_angle: 0.5 * PI,
+        });
+        let contacts = square.test_overlap(&ground);
+        assert_eq!(contacts.len(), 2);
+        for contact in &contacts {
+            assert!(contact.normal.abs_diff_eq(-DVec2::Y, 1e-12));
+            assert!((contact.separation + 0.1).abs() < 1e-12);
+        }
+        assert!(ground
+            .test_overlap(&square)
+            .iter()
+            .all(|contact| contact.normal.abs_diff_eq(DVec2::Y, 1e-12)));
+    }
+
+    #[test]
+    fn convex_hull_of_square_with_interior_points() {
+        let points = [
+            dvec2(0.5, 0.5),
+            dvec2(1.0, 1.0),
+            dvec2(0.0, 0.0),
+            dvec2(0.2, 0.7),
+            dvec2(1.0, 0.0),
+            dvec2(0.5, 0.0),
+            dvec2(0.0, 1.0),
+            dvec2(1.0, 1.0),
+        ];
+        assert_eq!(
+            convex_hull(&points),
+            vec![
+                dvec2(0.0, 0.0),
+                dvec2(1.0, 0.0),
+                dvec2(1.0, 1.0),
+                dvec2(0.0, 1.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn convex_hull_of_collinear_points() {
+        let points = [
+            dvec2(2.0, 2.0),
+            dvec2(0.0, 0.0),
+            dvec2(1.0, 1.0),
+            dvec2(3.0, 3.0),
+        ];
+        assert_eq!(convex_hull(&points), vec![dvec2(0.0, 0.0), dvec2(3.0, 3.0)]);
+        assert_eq!(convex_hull(&points[..1]), vec![dvec2(2.0, 2.0)]);
+        assert!(convex_hull(&[]).is_empty());
+    }
+
+    #[test]
+    fn gjk_epa_matches_circle_circle() {
+        let a = Circle {
+            pos: dvec2(1.0, -2.0),
+            radius: 3.0,
+        };
+        for (pos, radius) in [
+            (dvec2(4.0, 1.0), 2.0),
+            (dvec2(-3.0, -2.5), 1.5),
+            (dvec2(1.5, 0.0), 5.0),
+        ] {
+            let b = Circle { pos, radius };
+            let analytic = a.test_overlap_with_circle(&b).unwrap();
+            let general = gjk_epa(&a, &b).unwrap();
+            assert!(general.normal.abs_diff_eq(analytic.normal, 1e-3));
+            assert!(general.pos.abs_diff_eq(analytic.pos, 1e-3));
+            assert!((general.separation - analytic.separation).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn gjk_epa_separated_circles() {
+        let a = Circle {
+            pos: DVec2::ZERO,
+            radius: 1.0,
+        };
+        let b = Circle {
+            pos: dvec2(1.5, 1.5),
+            radius: 1.0,
+        };
+        assert!(gjk_epa(&a, &b).is_none());
+    }
+
+    #[test]
+    fn manifold_reduction_merges_and_spreads() {
+        let normal = DVec2::Y;
+        let mut manifold = ContactManifold::from(vec![
+            Contact::new(dvec2(0.0, 0.0), normal, -0.1),
+            // Duplicate of the first contact, e.g. from two edges sharing a vertex.
+            Contact::new(dvec2(0.0, 1e-9), normal, -0.2),
+            Contact::new(dvec2(1.0, 0.0), normal, -0.05),
+            Contact::new(dvec2(2.0, 0.0), normal, -0.01),
+            Contact::new(dvec2(2.0, 0.0), normal, -0.01),
+        ]);
+
+        let mut merged = manifold.clone();
+        merged.reduce(usize::MAX);
+        assert_eq!(merged.contacts.len(), 3);
+
+        manifold.reduce(2);
+        assert_eq!(
+            manifold.contacts,
+            vec![
+                Contact::new(dvec2(0.0, 1e-9), normal, -0.2),
+                Contact::new(dvec2(2.0, 0.0), normal, -0.01),
+            ]
+        );
+
+        manifold.reduce(0);
+        assert!(manifold.contacts.is_empty());
+    }
+
+    fn assert_hit(hit: Option<(f64, DVec2)>, distance: f64, normal: DVec2) {
+        let (t, n) = hit.expect("ray should hit");
+        assert!((t - distance).abs() < 1e-9, "distance {t} != {distance}");
+        assert!(n.abs_diff_eq(normal, 1e-9), "normal {n} != {normal}");
+    }
+
+    #[test]
+    fn ray_hits_circle_head_on() {
+        let circle = Circle {
+            pos: dvec2(5.0, 0.0),
+            radius: 1.0,
+        };
+        assert_hit(circle.raycast(DVec2::ZERO, DVec2::X, 10.0), 4.0, -DVec2::X);
+        assert!(circle.raycast(DVec2::ZERO, DVec2::X, 3.0).is_none());
+        assert!(circle.raycast(DVec2::ZERO, -DVec2::X, 10.0).is_none());
+        // Rays starting inside do not hit.
+        assert!(circle.raycast(dvec2(5.0, 0.5), DVec2::X, 10.0).is_none());
+    }
+
+    #[test]
+    fn ray_grazing_circle_misses() {
+        let circle = Circle {
+            pos: dvec2(5.0, 0.0),
+            radius: 1.0,
+        };
+        assert!(circle.raycast(dvec2(0.0, 1.0), DVec2::X, 10.0).is_none());
+        assert!(circle.raycast(dvec2(0.0, 1.5), DVec2::X, 10.0).is_none());
+    }
+
+    #[test]
+    fn ray_hits_half_plane() {
+        let half_plane = HalfPlane {
+            pos: dvec2(0.0, -2.0),
+            normal_angle: PI / 2.0,
+        };
+        let down_right = dvec2(1.0, -1.0).normalize();
+        assert_hit(
+            half_plane.raycast(DVec2::ZERO, down_right, 10.0),
+            2.0 * 2f64.sqrt(),
+            DVec2::Y,
+        );
+        assert!(half_plane.raycast(DVec2::ZERO, DVec2::Y, 10.0).is_none());
+        assert!(half_plane.raycast(DVec2::ZERO, DVec2::X, 10.0).is_none());
+    }
+
+    #[test]
+    fn ray_hits_capsule_side_and_cap() {
+        let capsule = Capsule {
+            pos: DVec2::ZERO,
+            angle: 0.0,
+            length: 4.0,
+            radius: 1.0,
+        };
+        assert_hit(
+            capsule.raycast(dvec2(1.0, 5.0), -DVec2::Y, 10.0),
+            4.0,
+            DVec2::Y,
+        );
+        assert_hit(
+            capsule.raycast(dvec2(-6.0, 0.0), DVec2::X, 10.0),
+            3.0,
+            -DVec2::X,
+        );
+        assert!(capsule.raycast(dvec2(2.5, 0.0), DVec2::X, 10.0).is_none());
+    }
+
+    #[test]
+    fn ray_hits_polygon_edge() {
+        let square = Polygon {
+            vertices: vec![
+                dvec2(-0.5, -0.5),
+                dvec2(0.5, -0.5),
+                dvec2(0.5, 0.5),
+                dvec2(-0.5, 0.5),
+            ],
+        };
+        assert_hit(
+            square.raycast(dvec2(-3.0, 0.2), DVec2::X, 10.0),
+            2.5,
+            -DVec2::X,
+        );
+        assert!(square.raycast(dvec2(-3.0, 0.7), DVec2::X, 10.0).is_none());
+        assert!(square.raycast(DVec2::ZERO, DVec2::X, 10.0).is_none());
+    }
 }
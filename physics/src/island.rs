@@ -0,0 +1,168 @@
+//! Partitioning of bodies and constraints into islands: groups that interact
+//! only among themselves and can therefore be solved independently.
+use crate::Particle;
+
+/// A connected component of the graph whose nodes are dynamic bodies and whose
+/// edges are constraints (joints and contacts) between them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Island {
+    /// Sorted indices of the dynamic particles in the island.
+    pub bodies: Vec<usize>,
+    /// Sorted indices of the constraints acting on the island's bodies.
+    pub constraints: Vec<usize>,
+}
+
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            // Path halving
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        // Keep the lowest index as the root so the result is deterministic.
+        self.parent[a.max(b)] = a.min(b);
+    }
+}
+
+/// Groups the constraints, given as pairs of particle indices, into islands.
+///
+/// Static and kinematic bodies (see [`Particle::is_immovable`]) do not merge
+/// islands, e.g. two stacks resting on the same ground are separate islands,
+/// each of which also references the ground through its constraints. Bodies
+/// without constraints need no solving and belong to no island, neither do
+/// constraints between two immovable bodies. Islands are ordered by their first constraint.
+pub fn find_islands(particles: &[Particle], pairs: &[(usize, usize)]) -> Vec<Island> {
+    let mut union_find = UnionFind::new(particles.len());
+    for &(a, b) in pairs {
+        if !particles[a].is_immovable() && !particles[b].is_immovable() {
+            union_find.union(a, b);
+        }
+    }
+
+    let mut island_of_root = vec![None; particles.len()];
+    let mut islands: Vec<Island> = vec![];
+    for (constraint, &(a, b)) in pairs.iter().enumerate() {
+        let Some(body) = [a, b].into_iter().find(|&i| !particles[i].is_immovable()) else {
+            continue;
+        };
+        let root = union_find.find(body);
+        let island = *island_of_root[root].get_or_insert_with(|| {
+            islands.push(Island::default());
+            islands.len() - 1
+        });
+        islands[island].constraints.push(constraint);
+    }
+    for (body, particle) in particles.iter().enumerate() {
+        if particle.is_immovable() {
+            continue;
+        }
+        if let Some(island) = island_of_root[union_find.find(body)] {
+            islands[island].bodies.push(body);
+        }
+    }
+    islands
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::dvec2;
+
+    use super::*;
+    use crate::{
+        constraint::{Constraint, ConstraintEnum, DistanceConstraint},
+        particles::ParticleSet,
+        solver::{ConstraintData, SequentialImpulseSolver, Solver},
+        Shape,
+    };
+
+    /// Two pendulum chains hanging from a shared static anchor, far apart.
+    fn two_clusters() -> (ParticleSet, Vec<ConstraintEnum>) {
+        let circle = Shape::Circle { radius: 5.0 };
+        let mut particles = ParticleSet::new();
+        particles.insert(Particle::new(0.0, 0.0, circle.clone()));
+        for (i, x) in [-100.0, -50.0, 50.0, 100.0, 150.0].into_iter().enumerate() {
+            let mut p = Particle::new(1.0 + i as f64, 1.0, circle.clone());
+            p.pos = dvec2(x, -10.0 * i as f64);
+            p.vel = dvec2(i as f64, -2.0 * i as f64);
+            particles.insert(p);
+        }
+        let ids = particles.ids();
+        let distance = |a: usize, b: usize| {
+            ConstraintEnum::Distance(DistanceConstraint::new(ids[a], ids[b], 40.0))
+        };
+        let constraints = vec![
+            distance(0, 2),
+            distance(0, 3),
+            distance(2, 1),
+            distance(3, 4),
+            distance(4, 5),
+        ];
+        (particles, constraints)
+    }
+
+    #[test]
+    fn static_bodies_do_not_merge_islands() {
+        let (particles, constraints) = two_clusters();
+        let pairs: Vec<_> = constraints
+            .iter()
+            .map(|c| {
+                let (a, b) = c.get_ids();
+                (particles.expect_index(a), particles.expect_index(b))
+            })
+            .collect();
+        assert_eq!(
+            find_islands(&particles, &pairs),
+            vec![
+                Island {
+                    bodies: vec![1, 2],
+                    constraints: vec![0, 2],
+                },
+                Island {
+                    bodies: vec![3, 4, 5],
+                    constraints: vec![1, 3, 4],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn island_solve_matches_global_solve() {
+        let (particles, constraints) = two_clusters();
+        let dt = 1.0 / 60.0;
+        let solver = SequentialImpulseSolver { dt, iterations: 10 };
+        let constraint_data: Vec<_> = constraints
+            .iter()
+            .map(|c| ConstraintData::from_constraint(c, &particles, dt))
+            .collect();
+
+        let mut global = particles.clone();
+        let mut global_data = constraint_data.clone();
+        solver.solve(&mut global, &mut global_data);
+        let mut by_island = particles.clone();
+        let mut island_data = constraint_data.clone();
+        solver.solve_islands(&mut by_island, &mut island_data);
+
+        for (a, b) in global.iter().zip(&by_island) {
+            assert_eq!(a.vel, b.vel);
+            assert_eq!(a.omega, b.omega);
+        }
+        for (a, b) in global_data.iter().zip(&island_data) {
+            assert_eq!(a.total_impulse(), b.total_impulse());
+        }
+    }
+}
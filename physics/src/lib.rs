@@ -1,20 +1,49 @@
-use constraint::{CollisionConstraint, Constraint, ConstraintEnum};
-use geometry::{Circle, HalfPlane};
+use std::{collections::HashMap, f64::consts::PI};
+
+use constraint::{CollisionConstraint, Constraint, ConstraintEnum, PbdDistance, SpringConstraint};
+use geometry::{convex_hull, Capsule, Circle, Contact, ContactManifold, HalfPlane, Polygon};
 use glam::DVec2;
-use solver::{ConstraintData, SequentialImpulseSolver, Solver};
+use particles::{ParticleId, ParticleSet};
+use solver::{get_pair_mut, ConstraintData, SequentialImpulseSolver};
+use thiserror::Error;
 use tracing::{instrument, trace, trace_span};
 
+pub mod broad_phase;
+
 pub mod constraint;
 
 pub mod geometry;
 
+pub mod island;
+
+pub mod particles;
+
+pub mod prelude;
+
+pub mod rng;
+
 pub mod solver;
 
 pub mod scenarios;
 
+/// How a body takes part in the simulation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BodyType {
+    /// Moved by forces and collisions according to `inv_mass` and `inv_inertia`,
+    /// which may be zero for static bodies.
+    #[default]
+    Dynamic,
+    /// Moved only by its `vel` and `omega`, set by the user, e.g. a moving platform.
+    /// The solver treats its mass and inertia as infinite, so it pushes dynamic
+    /// bodies but is not pushed back, and gravity and forces do not affect it.
+    Kinematic,
+}
+
 /// A representation of a rigid body possessing geometry (`pos`, `angle`, `shape`),
 /// kinematics (`vel`, `omega`) and dynamics (`inv_mass`, `force`, `inv_inertia`, `torque`).
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Particle {
     /// A non-negative number that represents `mass = 1.0 / inv_mass` if it is positive
     /// and an infinite mass (i.e. immovable object) when it is zero.
@@ -28,9 +57,8 @@ pub struct Particle {
     pub force: DVec2,
     /// A non-negative number representing inverse of object's moment of inertia.
     /// Zero corresponds to infinite inertia (i.e. immovable object).
-    /// Moment inertia depends on object's geometry and mass density distribution.
-    /// TODO: provide helper functions to calculate inertia for common shapes with uniform density.
-    /// see <https://github.com/orbital-simulations/experimental/issues/56>
+    /// Moment inertia depends on object's geometry and mass density distribution,
+    /// see e.g. [`Particle::circle`] for bodies of uniform density.
     pub inv_inertia: f64,
     /// Orientation
     pub angle: f64,
@@ -41,6 +69,34 @@ pub struct Particle {
     pub torque: f64,
     /// Geometry of the rigid body.
     pub shape: Shape,
+    /// Elasticity of collisions, one for perfectly elastic and zero for perfectly inelastic.
+    /// Combined with the other body's value using `Engine::restitution_mix`.
+    pub restitution: f64,
+    /// Rate at which velocity decays, e.g. due to air drag. Zero disables it.
+    pub linear_damping: f64,
+    /// Rate at which angular velocity decays. Zero disables it.
+    pub angular_damping: f64,
+    /// Coefficient of friction.
+    /// Combined with the other body's value using `Engine::friction_mix`.
+    pub friction: f64,
+    /// Arbitrary value for mapping the body back to caller-side state, e.g. an
+    /// entity id. Ignored by the simulation.
+    pub user_data: u64,
+    /// See [`BodyType`].
+    pub body_type: BodyType,
+    /// Sensors detect overlaps, reported by `Engine::sensor_events`,
+    /// but neither push nor are pushed by other bodies.
+    pub is_sensor: bool,
+    /// Bitset of the layers the body belongs to, all layers by default.
+    pub collision_layer: u32,
+    /// Bitset of the layers the body collides with, all layers by default.
+    /// Two bodies collide only if each one's mask contains a layer of the other.
+    pub collision_mask: u32,
+    /// Set by the engine when the body has rested long enough, see `Engine::sleep`.
+    /// Sleeping bodies are neither moved nor tested against each other.
+    pub sleeping: bool,
+    /// How long the body has been moving slower than the `Engine::sleep` thresholds.
+    pub sleep_timer: f64,
 }
 
 impl Particle {
@@ -55,23 +111,208 @@ impl Particle {
             omega: 0.0,
             torque: 0.0,
             shape,
+            restitution: 1.0,
+            friction: 0.0,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            user_data: 0,
+            body_type: BodyType::Dynamic,
+            is_sensor: false,
+            collision_layer: u32::MAX,
+            collision_mask: u32::MAX,
+            sleeping: false,
+            sleep_timer: 0.0,
         }
     }
+
+    /// A uniform disc of `mass` and `radius`, with moment of inertia `0.5 * m * r^2`.
+    /// Zero mass makes the particle immovable.
+    pub fn circle(mass: f64, radius: f64) -> Particle {
+        let inertia = 0.5 * mass * radius * radius;
+        Particle::new(
+            inverse_or_zero(mass),
+            inverse_or_zero(inertia),
+            Shape::Circle { radius },
+        )
+    }
+
+    /// A uniform capsule of `mass` whose segment has `length` and whose caps have `radius`.
+    /// Zero mass makes the particle immovable.
+    pub fn capsule(mass: f64, length: f64, radius: f64) -> Particle {
+        // The capsule is a `length x 2r` rectangle plus two half-discs, the mass
+        // split between them by area.
+        let rectangle_area = 2.0 * radius * length;
+        let discs_area = PI * radius * radius;
+        let rectangle_mass = mass * rectangle_area / (rectangle_area + discs_area);
+        let discs_mass = mass - rectangle_mass;
+        // Rectangle: `m * (w^2 + h^2) / 12`.
+        let rectangle_inertia = rectangle_mass * (length * length + 4.0 * radius * radius) / 12.0;
+        // Each half-disc has its centroid `d = 4r / 3pi` from its flat side, so
+        // by the parallel axis theorem the pair contributes
+        // `m * (r^2 / 2 - d^2 + (l / 2 + d)^2) = m * (r^2 / 2 + l^2 / 4 + l * d)`.
+        let centroid_offset = 4.0 * radius / (3.0 * PI);
+        let discs_inertia = discs_mass
+            * (0.5 * radius * radius + 0.25 * length * length + length * centroid_offset);
+        Particle::new(
+            inverse_or_zero(mass),
+            inverse_or_zero(rectangle_inertia + discs_inertia),
+            Shape::Capsule { length, radius },
+        )
+    }
+
+    /// A uniform convex polygon of `mass` spanning the convex hull of `vertices`.
+    /// The vertices are shifted so that their centroid, the center of mass, is at
+    /// the particle's position. Zero mass makes the particle immovable.
+    ///
+    /// Panics if the vertices do not span a polygon with a positive area.
+    pub fn polygon(mass: f64, vertices: &[DVec2]) -> Particle {
+        let mut vertices = convex_hull(vertices);
+        let n = vertices.len();
+        let edges = || (0..n).map(move |i| (i, (i + 1) % n));
+        // Twice the signed area of the triangle spanned by the origin and each edge.
+        let double_area: f64 = edges()
+            .map(|(i, j)| vertices[i].perp_dot(vertices[j]))
+            .sum();
+        assert!(
+            vertices.len() >= 3 && double_area > 0.0,
+            "Polygon vertices need to span a positive area"
+        );
+        let centroid = edges()
+            .map(|(i, j)| (vertices[i] + vertices[j]) * vertices[i].perp_dot(vertices[j]))
+            .sum::<DVec2>()
+            / (3.0 * double_area);
+        for v in &mut vertices {
+            *v -= centroid;
+        }
+        // Sum of the origin-edge triangles' moments: `m * sum(a_i * (p_i^2 + p_i.p_j + p_j^2)) / (6 * sum(a_i))`.
+        let inertia = mass
+            * edges()
+                .map(|(i, j)| {
+                    let (p, q) = (vertices[i], vertices[j]);
+                    p.perp_dot(q) * (p.dot(p) + p.dot(q) + q.dot(q))
+                })
+                .sum::<f64>()
+            / (6.0 * double_area);
+        Particle::new(
+            inverse_or_zero(mass),
+            inverse_or_zero(inertia),
+            Shape::Polygon { vertices },
+        )
+    }
+
+    /// Whether the particle is immovable, i.e. is dynamic and has both infinite
+    /// mass and inertia.
+    pub fn is_static(&self) -> bool {
+        self.body_type == BodyType::Dynamic && self.inv_mass == 0.0 && self.inv_inertia == 0.0
+    }
+
+    pub fn is_kinematic(&self) -> bool {
+        self.body_type == BodyType::Kinematic
+    }
+
+    /// Whether contacts and constraints cannot move the particle, i.e. it is
+    /// static or kinematic.
+    pub fn is_immovable(&self) -> bool {
+        self.effective_inv_mass() == 0.0 && self.effective_inv_inertia() == 0.0
+    }
+
+    /// `inv_mass` as seen by the solver, zero for kinematic bodies.
+    pub fn effective_inv_mass(&self) -> f64 {
+        match self.body_type {
+            BodyType::Dynamic => self.inv_mass,
+            BodyType::Kinematic => 0.0,
+        }
+    }
+
+    /// `inv_inertia` as seen by the solver, zero for kinematic bodies.
+    pub fn effective_inv_inertia(&self) -> f64 {
+        match self.body_type {
+            BodyType::Dynamic => self.inv_inertia,
+            BodyType::Kinematic => 0.0,
+        }
+    }
+
+    /// Whether the engine has put the particle to sleep, see `Engine::sleep`.
+    pub fn is_sleeping(&self) -> bool {
+        self.sleeping
+    }
+
+    /// Makes a sleeping particle take part in the simulation again. The engine
+    /// does so itself when the particle is hit or pushed by a force, but not
+    /// when its position or velocity are changed directly.
+    pub fn wake_up(&mut self) {
+        self.sleeping = false;
+        self.sleep_timer = 0.0;
+    }
+
+    /// Adds `force` acting at `world_point` for the next step, together with
+    /// the torque it exerts around the center of mass.
+    pub fn apply_force_at_point(&mut self, force: DVec2, world_point: DVec2) {
+        self.force += force;
+        self.torque += (world_point - self.pos).perp_dot(force);
+    }
+
+    /// Changes the velocities right away as if `impulse` hit the particle at
+    /// `world_point`, waking it up.
+    pub fn apply_impulse_at_point(&mut self, impulse: DVec2, world_point: DVec2) {
+        self.vel += impulse * self.effective_inv_mass();
+        self.omega += (world_point - self.pos).perp_dot(impulse) * self.effective_inv_inertia();
+        self.wake_up();
+    }
+
+    fn fall_asleep(&mut self) {
+        self.sleeping = true;
+        self.vel = DVec2::ZERO;
+        self.omega = 0.0;
+    }
+
+    /// Whether the particle moves, i.e. is neither static nor sleeping.
+    fn is_awake(&self) -> bool {
+        !self.is_static() && !self.sleeping
+    }
 }
 
 impl Particle {
-    fn to_geometry_shape(&self) -> geometry::Shape {
+    /// Geometry of the particle placed at its current position and orientation.
+    pub fn to_geometry_shape(&self) -> geometry::Shape {
         match self.shape {
             Shape::Circle { radius } => geometry::Shape::Circle(Circle {
                 pos: self.pos,
                 radius,
             }),
+            Shape::Capsule { length, radius } => geometry::Shape::Capsule(Capsule {
+                pos: self.pos,
+                angle: self.angle,
+                length,
+                radius,
+            }),
+            Shape::Polygon { ref vertices } => {
+                let rotation = DVec2::from_angle(self.angle);
+                geometry::Shape::Polygon(Polygon {
+                    vertices: vertices
+                        .iter()
+                        .map(|&v| self.pos + rotation.rotate(v))
+                        .collect(),
+                })
+            }
             Shape::HalfPlane { normal_angle } => geometry::Shape::HalfPlane(HalfPlane {
                 pos: self.pos,
                 normal_angle,
             }),
         }
     }
+
+    /// Whether the layers and masks of the two bodies allow them to collide.
+    pub fn can_collide_with(&self, other: &Particle) -> bool {
+        self.collision_mask & other.collision_layer != 0
+            && other.collision_mask & self.collision_layer != 0
+    }
+
+    /// World-space axis-aligned bounding box. Half-planes are unbounded and
+    /// return [`geometry::Aabb::INFINITE`].
+    pub fn aabb(&self) -> geometry::Aabb {
+        self.to_geometry_shape().aabb()
+    }
 }
 
 impl Default for Particle {
@@ -81,23 +322,282 @@ impl Default for Particle {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum Shape {
     Circle {
         radius: f64,
     },
+    /// A segment of `length` along the particle's local x-axis, centered on its
+    /// position, swept by a circle of `radius`.
+    Capsule {
+        length: f64,
+        radius: f64,
+    },
+    /// A convex polygon with counter-clockwise `vertices` relative to the
+    /// particle's position and orientation.
+    Polygon {
+        vertices: Vec<DVec2>,
+    },
+    /// Everything on one side of a line through the particle's position.
+    /// Half-planes are unbounded and must be immovable, i.e. static or
+    /// kinematic; like other pairs of immovable bodies two half-planes are
+    /// never tested against each other.
     HalfPlane {
         /// normal's angle with the x-axis in counter-clock-wise direction, in radians
         normal_angle: f64,
     },
 }
 
+/// A rule for combining a material property of two bodies in contact,
+/// such as restitution or friction, into a single value for the contact.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MixRule {
+    /// `(a + b) / 2`
+    Average,
+    /// `sqrt(a * b)`
+    GeometricMean,
+    /// `min(a, b)`
+    Min,
+    /// `max(a, b)`
+    Max,
+    /// `a * b`
+    Multiply,
+}
+
+impl MixRule {
+    pub fn mix(&self, a: f64, b: f64) -> f64 {
+        match self {
+            MixRule::Average => 0.5 * (a + b),
+            MixRule::GeometricMean => (a * b).sqrt(),
+            MixRule::Min => a.min(b),
+            MixRule::Max => a.max(b),
+            MixRule::Multiply => a * b,
+        }
+    }
+}
+
+/// When resting bodies are put to sleep, see `Engine::sleep`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SleepConfig {
+    /// Speed below which a body counts as resting.
+    pub linear_threshold: f64,
+    /// Angular speed below which a body counts as resting.
+    pub angular_threshold: f64,
+    /// How long a body, and everything it touches, must rest before falling asleep.
+    pub time_to_sleep: f64,
+}
+
+impl Default for SleepConfig {
+    fn default() -> Self {
+        Self {
+            linear_threshold: 0.05,
+            angular_threshold: 0.05,
+            time_to_sleep: 0.5,
+        }
+    }
+}
+
+/// Tuning of the contact solver, see `Engine::solver`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolverConfig {
+    /// Passes over all constraints per step, by both the impulse and the
+    /// position based solver.
+    pub iterations: usize,
+    /// Fraction of the penetration of a resting contact corrected per step,
+    /// between 0 and 1. Large values push bodies apart quickly but may make
+    /// them jitter.
+    pub baumgarte_beta: f64,
+    /// Contacts whose relative speed is below this multiple of the speed
+    /// gained from gravity in one step are treated as resting rather than
+    /// bouncing.
+    pub static_speed_factor: f64,
+    /// Penetration depth left uncorrected, keeping resting contacts alive
+    /// from step to step.
+    pub penetration_slop: f64,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 10,
+            // TODO: should be more like 0.8 but it doesn't behave well because
+            // it produces high velocities and we treat them as dynamic collisions
+            // in the next frame.
+            // Once we remember static contacts we can treat them as static collisions
+            // and handle them properly.
+            // see https://github.com/orbital-simulations/experimental/issues/58
+            baumgarte_beta: 0.02,
+            static_speed_factor: 2.0,
+            penetration_slop: 0.0,
+        }
+    }
+}
+
+/// What happens to a dynamic body that leaves `Engine::bounds`. Kinematic
+/// bodies are neither reported nor frozen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutOfBoundsAction {
+    /// Only report an [`OutOfBounds`] event.
+    #[default]
+    Report,
+    /// Report the event and turn the body static: it stops moving and no longer
+    /// takes part in solving. Useful for bodies that would otherwise fall forever.
+    Freeze,
+}
+
+/// The first body hit by a ray, see [`Engine::raycast`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RayHit {
+    pub id: ParticleId,
+    /// [`Particle::user_data`] of the body that was hit.
+    pub user_data: u64,
+    /// Where the ray enters the body.
+    pub pos: DVec2,
+    /// Outward normal of the body's boundary at `pos`.
+    pub normal: DVec2,
+    /// Distance from the ray's origin to `pos`.
+    pub distance: f64,
+}
+
+/// A body found by [`Engine::query_point`] or [`Engine::query_shape`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QueryHit {
+    pub id: ParticleId,
+    /// [`Particle::user_data`] of the body.
+    pub user_data: u64,
+}
+
+/// A dynamic body crossed `Engine::bounds` during the last step.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutOfBounds {
+    pub id: ParticleId,
+    /// Position of the body at the end of the step.
+    pub pos: DVec2,
+}
+
+/// How the touching of two bodies changed, see [`CollisionEvent`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CollisionPhase {
+    /// The bodies touch and did not during the previous step.
+    Begin,
+    /// The bodies touch and did during the previous step as well.
+    Stay,
+    /// The bodies touched during the previous step but no longer do.
+    End,
+}
+
+/// Two solid bodies touching, or just stopping to, during the last step, see
+/// [`Engine::collision_events`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollisionEvent {
+    pub id_a: ParticleId,
+    pub id_b: ParticleId,
+    /// [`Particle::user_data`] of the bodies. For [`CollisionPhase::End`] it
+    /// is the data they had during the previous step.
+    pub user_data_a: u64,
+    pub user_data_b: u64,
+    pub phase: CollisionPhase,
+    /// Average of the contact points of the pair. For [`CollisionPhase::End`]
+    /// it is where the bodies touched during the previous step.
+    pub pos: DVec2,
+    /// Contact normal, pointing from `id_a` to `id_b`.
+    pub normal: DVec2,
+}
+
+/// A sensor overlapping another body during the last step, see
+/// [`Engine::sensor_events`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SensorEvent {
+    pub id_a: ParticleId,
+    pub id_b: ParticleId,
+    /// [`Particle::user_data`] of the bodies.
+    pub user_data_a: u64,
+    pub user_data_b: u64,
+}
+
+impl CollisionEvent {
+    fn is_between(&self, id_a: ParticleId, id_b: ParticleId) -> bool {
+        (self.id_a, self.id_b) == (id_a, id_b) || (self.id_a, self.id_b) == (id_b, id_a)
+    }
+}
+
+/// With the `serde` feature the whole state, including the contacts remembered
+/// for warm starting, can be saved and restored. Custom constraints
+/// ([`ConstraintEnum::Custom`]) cannot be serialized.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Engine {
-    pub particles: Vec<Particle>,
+    /// Bodies addressed by the [`ParticleId`]s stored in constraints,
+    /// see [`Engine::add_particle`] and [`Engine::remove_particle`].
+    pub particles: ParticleSet,
     pub constraints: Vec<ConstraintEnum>,
+    /// Distance constraints solved positionally after integration,
+    /// complementing the impulse-based `constraints`.
+    pub pbd_constraints: Vec<PbdDistance>,
+    /// Soft springs applying forces at the start of each step.
+    pub springs: Vec<SpringConstraint>,
     pub gravity: DVec2,
-    pub solver_iterations: usize,
+    pub solver: SolverConfig,
+    /// Time step of the simulation when driven by [`Engine::advance`], 1/60 s by default.
+    pub fixed_dt: f64,
+    /// Most steps run by a single [`Engine::advance`], 8 by default. When the
+    /// simulation cannot keep up, the remaining time is dropped rather than
+    /// making the next frame even slower.
+    pub max_substeps: usize,
+    /// Real time passed to [`Engine::advance`] not simulated yet.
+    accumulator: f64,
+    /// How restitution of two colliding bodies is combined, `MixRule::Max` by default.
+    pub restitution_mix: MixRule,
+    /// How friction of two colliding bodies is combined, `MixRule::GeometricMean` by default.
+    pub friction_mix: MixRule,
+    /// Optional `(min, max)` corners of the simulated region.
+    /// Dynamic bodies whose position leaves it are handled by `out_of_bounds_action`.
+    pub bounds: Option<(DVec2, DVec2)>,
+    pub out_of_bounds_action: OutOfBoundsAction,
+    /// Bodies resting for a while are put to sleep and skipped by the
+    /// simulation until an awake body runs into them. Disabled by default.
+    pub sleep: Option<SleepConfig>,
+    out_of_bounds_events: Vec<OutOfBounds>,
+    sensor_events: Vec<SensorEvent>,
+    collision_events: Vec<CollisionEvent>,
+    /// Accumulated normal and friction impulses of the last step's contacts,
+    /// keyed by `(id_a, id_b, feature)`, used to warm start the solver.
+    #[cfg_attr(feature = "serde", serde(with = "contact_cache_entries"))]
+    contact_cache: HashMap<(ParticleId, ParticleId, usize), (f64, f64)>,
+}
+
+/// Serializes `Engine::contact_cache` as a list of entries,
+/// since formats like JSON only allow string keys in maps.
+#[cfg(feature = "serde")]
+mod contact_cache_entries {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::ParticleId;
+
+    type Cache = HashMap<(ParticleId, ParticleId, usize), (f64, f64)>;
+
+    pub fn serialize<S: Serializer>(cache: &Cache, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut entries: Vec<_> = cache.iter().collect();
+        entries.sort_by_key(|(key, _)| **key);
+        entries.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Cache, D::Error> {
+        let entries =
+            Vec::<((ParticleId, ParticleId, usize), (f64, f64))>::deserialize(deserializer)?;
+        Ok(entries.into_iter().collect())
+    }
 }
 
 impl Default for Engine {
@@ -105,31 +605,263 @@ impl Default for Engine {
         Self {
             particles: Default::default(),
             constraints: Default::default(),
+            pbd_constraints: Default::default(),
+            springs: Default::default(),
             gravity: Default::default(),
-            solver_iterations: 10,
+            solver: SolverConfig::default(),
+            fixed_dt: 1.0 / 60.0,
+            max_substeps: 8,
+            accumulator: 0.0,
+            restitution_mix: MixRule::Max,
+            friction_mix: MixRule::GeometricMean,
+            bounds: None,
+            out_of_bounds_action: OutOfBoundsAction::default(),
+            sleep: None,
+            out_of_bounds_events: vec![],
+            sensor_events: vec![],
+            collision_events: vec![],
+            contact_cache: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum EngineConfigError {
+    #[error("Solver needs at least one iteration")]
+    NoSolverIterations,
+    #[error("Baumgarte factor must be within [0, 1], got {0}")]
+    InvalidBaumgarteBeta(f64),
+    #[error("Fixed time step must be positive and finite, got {0}")]
+    InvalidFixedDt(f64),
+    #[error("Advancing needs at least one substep")]
+    NoSubsteps,
+    #[error("Gravity must be finite, got {0}")]
+    NonFiniteGravity(DVec2),
+    #[error("Bounds minimum {0} exceeds maximum {1}")]
+    InvalidBounds(DVec2, DVec2),
+}
+
+/// Chainable construction of an [`Engine`]. Unset knobs keep the values of
+/// `Engine::default()`, which are documented on the corresponding `Engine` fields.
+#[derive(Clone, Debug, Default)]
+pub struct EngineBuilder {
+    engine: Engine,
+}
+
+impl EngineBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn particles(mut self, particles: Vec<Particle>) -> Self {
+        self.engine.particles = particles.into();
+        self
+    }
+
+    pub fn constraints(mut self, constraints: Vec<ConstraintEnum>) -> Self {
+        self.engine.constraints = constraints;
+        self
+    }
+
+    pub fn pbd_constraints(mut self, pbd_constraints: Vec<PbdDistance>) -> Self {
+        self.engine.pbd_constraints = pbd_constraints;
+        self
+    }
+
+    pub fn springs(mut self, springs: Vec<SpringConstraint>) -> Self {
+        self.engine.springs = springs;
+        self
+    }
+
+    pub fn gravity(mut self, gravity: DVec2) -> Self {
+        self.engine.gravity = gravity;
+        self
+    }
+
+    pub fn solver(mut self, solver: SolverConfig) -> Self {
+        self.engine.solver = solver;
+        self
+    }
+
+    pub fn solver_iterations(mut self, solver_iterations: usize) -> Self {
+        self.engine.solver.iterations = solver_iterations;
+        self
+    }
+
+    pub fn fixed_dt(mut self, fixed_dt: f64) -> Self {
+        self.engine.fixed_dt = fixed_dt;
+        self
+    }
+
+    pub fn max_substeps(mut self, max_substeps: usize) -> Self {
+        self.engine.max_substeps = max_substeps;
+        self
+    }
+
+    pub fn restitution_mix(mut self, restitution_mix: MixRule) -> Self {
+        self.engine.restitution_mix = restitution_mix;
+        self
+    }
+
+    pub fn friction_mix(mut self, friction_mix: MixRule) -> Self {
+        self.engine.friction_mix = friction_mix;
+        self
+    }
+
+    pub fn bounds(mut self, min: DVec2, max: DVec2) -> Self {
+        self.engine.bounds = Some((min, max));
+        self
+    }
+
+    pub fn out_of_bounds_action(mut self, action: OutOfBoundsAction) -> Self {
+        self.engine.out_of_bounds_action = action;
+        self
+    }
+
+    pub fn sleep(mut self, sleep: SleepConfig) -> Self {
+        self.engine.sleep = Some(sleep);
+        self
+    }
+
+    pub fn build(self) -> Result<Engine, EngineConfigError> {
+        let engine = self.engine;
+        if engine.solver.iterations == 0 {
+            return Err(EngineConfigError::NoSolverIterations);
+        }
+        if !(0.0..=1.0).contains(&engine.solver.baumgarte_beta) {
+            return Err(EngineConfigError::InvalidBaumgarteBeta(
+                engine.solver.baumgarte_beta,
+            ));
+        }
+        if !(engine.fixed_dt > 0.0 && engine.fixed_dt.is_finite()) {
+            return Err(EngineConfigError::InvalidFixedDt(engine.fixed_dt));
+        }
+        if engine.max_substeps == 0 {
+            return Err(EngineConfigError::NoSubsteps);
+        }
+        if !engine.gravity.is_finite() {
+            return Err(EngineConfigError::NonFiniteGravity(engine.gravity));
+        }
+        if let Some((min, max)) = engine.bounds {
+            if min.cmpgt(max).any() {
+                return Err(EngineConfigError::InvalidBounds(min, max));
+            }
         }
+        Ok(engine)
     }
 }
 
-const STATIC_SPEED_FACTOR: f64 = 2.0;
+/// `1 / x`, except that zero, standing for an infinite mass or inertia, stays zero.
+fn inverse_or_zero(x: f64) -> f64 {
+    if x == 0.0 {
+        0.0
+    } else {
+        1.0 / x
+    }
+}
+
+/// Two contacts are enough to keep a pair of 2D bodies from rotating into each other.
+const MAX_MANIFOLD_POINTS: usize = 2;
+
+/// Key identifying a contact across frames in `Engine::contact_cache`.
+fn contact_key(constraint: &ConstraintEnum) -> Option<(ParticleId, ParticleId, usize)> {
+    match constraint {
+        ConstraintEnum::Collision(c) => Some((c.id_a, c.id_b, c.feature)),
+        _ => None,
+    }
+}
+
+/// Sleeping bodies only need testing against awake ones, which may wake them up.
+fn is_resting_pair(a: &Particle, b: &Particle) -> bool {
+    (a.sleeping && !b.is_awake()) || (b.sleeping && !a.is_awake())
+}
+
+fn is_in_bounds(pos: DVec2, (min, max): (DVec2, DVec2)) -> bool {
+    pos.cmpge(min).all() && pos.cmple(max).all()
+}
 
 impl Engine {
+    pub fn builder() -> EngineBuilder {
+        EngineBuilder::new()
+    }
+
+    /// Points gravity straight down, towards -y, with the given magnitude.
+    pub fn set_gravity_down(&mut self, magnitude: f64) {
+        self.gravity = DVec2::new(0.0, -magnitude);
+    }
+
+    /// Sets gravity from its magnitude and the angle of its direction in
+    /// radians, counterclockwise from +x, so `-PI / 2` points down.
+    pub fn set_gravity_polar(&mut self, magnitude: f64, angle: f64) {
+        self.gravity = magnitude * DVec2::from_angle(angle);
+    }
+
+    /// Magnitude and angle of gravity as taken by [`Engine::set_gravity_polar`].
+    /// The angle of zero gravity is 0.
+    pub fn gravity_polar(&self) -> (f64, f64) {
+        (self.gravity.length(), self.gravity.y.atan2(self.gravity.x))
+    }
+
+    /// Finds the contacts between all pairs of particles. A uniform grid broad phase
+    /// (see [`broad_phase::find_pairs`]) skips pairs that are far apart, pairs of
+    /// immovable bodies are skipped as well.
+    ///
+    /// The contacts are sorted by `(id_a, id_b, feature)`, independently of
+    /// the order the broad phase finds the pairs in. The solver sees them in
+    /// this order, so identical engines produce bit-identical steps.
     #[instrument(level = "trace", skip_all)]
     pub fn detect_collisions(&self) -> Vec<CollisionConstraint> {
-        let mut collisions = vec![];
-        for (i, a) in self.particles.iter().enumerate() {
-            for (j, b) in self.particles.iter().enumerate() {
-                if i >= j {
-                    continue;
-                }
+        let aabbs: Vec<_> = self.particles.iter().map(Particle::aabb).collect();
+        let mut collisions = self.detect_collisions_between(broad_phase::find_pairs(&aabbs));
+        collisions.sort_by_key(|c| (c.id_a, c.id_b, c.feature));
+        collisions
+    }
 
-                let contacts = a
-                    .to_geometry_shape()
-                    .test_overlap(&b.to_geometry_shape())
-                    .into_iter()
-                    .map(|contact| CollisionConstraint::new(i, j, contact, true));
-                collisions.extend(contacts)
+    /// Runs the narrow phase on the given pairs of particle positions in
+    /// `particles`, `i < j`.
+    fn detect_collisions_between(
+        &self,
+        pairs: impl IntoIterator<Item = (usize, usize)>,
+    ) -> Vec<CollisionConstraint> {
+        let mut collisions = vec![];
+        for (i, j) in pairs {
+            let (a, b) = (&self.particles[i], &self.particles[j]);
+            if !a.can_collide_with(b) {
+                continue;
             }
+            // Immovable bodies can't resolve contacts between them.
+            if is_resting_pair(a, b) || (a.is_immovable() && b.is_immovable()) {
+                continue;
+            }
+            let restitution = self.restitution_mix.mix(a.restitution, b.restitution);
+            let friction = self.friction_mix.mix(a.friction, b.friction);
+            let mut manifold =
+                ContactManifold::from(a.to_geometry_shape().test_overlap(&b.to_geometry_shape()));
+            manifold.reduce(MAX_MANIFOLD_POINTS);
+            // Order the contacts along the surface so that their features
+            // stay the same from frame to frame.
+            let along_tangent = |c: &Contact| c.normal.perp().dot(c.pos);
+            manifold
+                .contacts
+                .sort_by(|c1, c2| along_tangent(c1).total_cmp(&along_tangent(c2)));
+            let contacts = manifold
+                .contacts
+                .into_iter()
+                .enumerate()
+                .map(|(feature, contact)| CollisionConstraint {
+                    restitution,
+                    friction,
+                    baumgarte_beta: self.solver.baumgarte_beta,
+                    penetration_slop: self.solver.penetration_slop,
+                    feature,
+                    ..CollisionConstraint::new(
+                        self.particles.id_at(i),
+                        self.particles.id_at(j),
+                        contact,
+                        true,
+                    )
+                });
+            collisions.extend(contacts)
         }
         collisions
     }
@@ -159,7 +891,7 @@ impl Engine {
     fn resolve_collisions(&mut self, collisions: &[CollisionConstraint]) {
         use glam::{dvec3, DMat3};
 
-        for iter in 0..self.solver_iterations {
+        for iter in 0..self.solver.iterations {
             let span = trace_span!("Iteration", iter);
             let _enter = span.enter();
             for col in collisions {
@@ -206,8 +938,10 @@ impl Engine {
                     // applying more impulse than necessary to achieve target v_rel
                     continue;
                 }
-                let m1_inv = DMat3::from_diagonal(dvec3(p1.inv_mass, p1.inv_mass, p1.inv_inertia));
-                let m2_inv = DMat3::from_diagonal(dvec3(p2.inv_mass, p2.inv_mass, p2.inv_inertia));
+                let (m1, i1) = (p1.effective_inv_mass(), p1.effective_inv_inertia());
+                let (m2, i2) = (p2.effective_inv_mass(), p2.effective_inv_inertia());
+                let m1_inv = DMat3::from_diagonal(dvec3(m1, m1, i1));
+                let m2_inv = DMat3::from_diagonal(dvec3(m2, m2, i2));
                 // Supporting only dynamic contacts with ellastic collision for now
                 let restitution = 1.0;
                 let lambda =
@@ -232,22 +966,78 @@ impl Engine {
     /// Simulates movement of particles for a duration `dt`.
     /// Besides free movement we also apply forces, satisfy constraints and resolve collisions.
     pub fn step(&mut self, dt: f64) {
-        // 1. Update velocities from forces
+        let was_in_bounds: Vec<_> = match self.bounds {
+            Some(bounds) => self
+                .particles
+                .iter()
+                .map(|p| is_in_bounds(p.pos, bounds))
+                .collect(),
+            None => vec![],
+        };
+
+        // 1. Detect collisions, waking up sleeping bodies pushed by forces or
+        // linked to awake ones
         for p in &mut self.particles {
+            let pushed = p.force != DVec2::ZERO || p.torque != 0.0;
+            if p.sleeping && (pushed || self.sleep.is_none()) {
+                p.wake_up();
+            }
+        }
+        let joints = self.joint_pairs();
+        let mut collisions = self.detect_collisions();
+        while self.wake_linked(
+            joints
+                .iter()
+                .copied()
+                .chain(collisions.iter().map(|c| (c.id_a, c.id_b))),
+        ) {
+            collisions = self.detect_collisions();
+        }
+
+        // 2. Update velocities from forces, including those of springs
+        for spring in &self.springs {
+            let index_a = self.particles.expect_index(spring.id_a);
+            let index_b = self.particles.expect_index(spring.id_b);
+            if index_a != index_b {
+                let (a, b) = get_pair_mut(&mut self.particles, index_a, index_b);
+                spring.apply_forces(a, b);
+            }
+        }
+        for p in self.particles.iter_mut().filter(|p| !p.sleeping) {
             let force = self.gravity + p.force;
-            let acc = force * p.inv_mass;
+            let acc = force * p.effective_inv_mass();
             p.vel += dt * acc;
 
-            let alpha = p.torque * p.inv_inertia;
+            let alpha = p.torque * p.effective_inv_inertia();
             p.omega += dt * alpha;
         }
 
         // TODO: should we predict positions using the updated velocities before detecting collisions?
         // see https://github.com/orbital-simulations/experimental/issues/55
 
-        // 2. Detect collisions
-        let collision_constraints: Vec<_> = self
-            .detect_collisions()
+        // 3. Report collisions of sensors instead of resolving them
+        self.sensor_events.clear();
+        collisions.retain(|c| {
+            let (a, b) = (&self.particles[c.id_a], &self.particles[c.id_b]);
+            let is_sensor = a.is_sensor || b.is_sensor;
+            // Contacts of a pair are consecutive, report the pair only once.
+            if is_sensor
+                && self
+                    .sensor_events
+                    .last()
+                    .is_none_or(|e| (e.id_a, e.id_b) != (c.id_a, c.id_b))
+            {
+                self.sensor_events.push(SensorEvent {
+                    id_a: c.id_a,
+                    id_b: c.id_b,
+                    user_data_a: a.user_data,
+                    user_data_b: b.user_data,
+                });
+            }
+            !is_sensor
+        });
+        self.update_collision_events(&collisions);
+        let collision_constraints: Vec<_> = collisions
             .into_iter()
             .filter_map(|mut c| {
                 let a = &self.particles[c.id_a];
@@ -257,7 +1047,8 @@ impl Engine {
                 // TODO: a better approach might be to track collisions over multiple frames
                 // and consider only new ones as dynamic,
                 // see https://github.com/orbital-simulations/experimental/issues/58
-                let static_speed_limit = STATIC_SPEED_FACTOR * self.gravity.length() * dt;
+                let static_speed_limit =
+                    self.solver.static_speed_factor * self.gravity.length() * dt;
                 let v_rel = c.relative_velocity(a, b);
                 if v_rel.abs() < static_speed_limit {
                     c.dynamic = false;
@@ -277,28 +1068,1634 @@ impl Engine {
             })
             .collect();
 
-        // Prepare both collision and user constraints for the solver
+        // Prepare both collision and user constraints for the solver,
+        // warm starting contacts that persist from the previous step
         let mut constraint_data: Vec<_> = self
             .constraints
             .iter()
             .chain(collision_constraints.iter())
-            .map(|c| ConstraintData::from_constraint(c, &self.particles, dt))
+            .map(|c| {
+                let data = ConstraintData::from_constraint(c, &self.particles, dt);
+                match contact_key(c).and_then(|key| self.contact_cache.get(&key)) {
+                    Some(&(normal, friction)) => data.with_warm_start(normal, friction),
+                    None => data,
+                }
+            })
             .collect();
 
-        // 3. Solve all constraints
+        // 4. Solve all constraints, island by island
         let solver = SequentialImpulseSolver {
             dt,
-            iterations: self.solver_iterations,
+            iterations: self.solver.iterations,
         };
-        solver.solve(&mut self.particles, &mut constraint_data);
+        solver.solve_islands(&mut self.particles, &mut constraint_data);
+        self.contact_cache = collision_constraints
+            .iter()
+            .zip(&constraint_data[self.constraints.len()..])
+            .filter(|(c, data)| !c.is_equality() && data.total_impulse() > 0.0)
+            .filter_map(|(c, data)| {
+                let impulses = (data.total_impulse(), data.friction_impulse());
+                Some((contact_key(c)?, impulses))
+            })
+            .collect();
 
-        // 4. Update positions & reset forces
+        // 5. Apply damping, update positions & reset forces
         for p in &mut self.particles {
-            p.pos += dt * p.vel;
             p.force = DVec2::ZERO;
+            p.torque = 0.0;
+            if p.sleeping {
+                // Undo anything the solver did to bodies that are not moving.
+                p.vel = DVec2::ZERO;
+                p.omega = 0.0;
+                continue;
+            }
+            // Implicit integration of dv/dt = -damping * v, stable for any dt.
+            if !p.is_kinematic() {
+                p.vel /= 1.0 + dt * p.linear_damping;
+                p.omega /= 1.0 + dt * p.angular_damping;
+            }
 
+            p.pos += dt * p.vel;
             p.angle += dt * p.omega;
-            p.torque = 0.0;
         }
+
+        // 6. Project position-based constraints
+        self.project_constraints(dt);
+
+        // 7. Put resting bodies to sleep
+        if let Some(sleep) = self.sleep {
+            let contacts = collision_constraints.iter().map(|c| c.get_ids());
+            let links: Vec<_> = joints.into_iter().chain(contacts).collect();
+            self.update_sleep(sleep, dt, &links);
+        }
+
+        // 8. Handle bodies that left the bounds
+        self.out_of_bounds_events.clear();
+        if let Some(bounds) = self.bounds {
+            self.handle_out_of_bounds(bounds, &was_in_bounds);
+        }
+    }
+
+    /// Pairs of bodies linked by user constraints, position-based constraints and springs.
+    fn joint_pairs(&self) -> Vec<(ParticleId, ParticleId)> {
+        let constraints = self.constraints.iter().map(|c| c.get_ids());
+        let pbd_constraints = self.pbd_constraints.iter().map(|c| (c.id_a, c.id_b));
+        let springs = self.springs.iter().map(|c| (c.id_a, c.id_b));
+        constraints.chain(pbd_constraints).chain(springs).collect()
+    }
+
+    /// Wakes up sleeping bodies linked to awake ones, returning whether any woke up.
+    fn wake_linked(&mut self, links: impl IntoIterator<Item = (ParticleId, ParticleId)>) -> bool {
+        let mut woken = false;
+        for (id_a, id_b) in links {
+            for (sleeper, other) in [(id_a, id_b), (id_b, id_a)] {
+                if self.particles[sleeper].sleeping && self.particles[other].is_awake() {
+                    trace!("Particle {sleeper:?} woken up by {other:?}");
+                    self.particles[sleeper].wake_up();
+                    woken = true;
+                }
+            }
+        }
+        woken
+    }
+
+    /// Advances the sleep timers of awake bodies and puts islands (see
+    /// [`island::find_islands`]) whose bodies have all rested long enough to sleep,
+    /// so that waking up one body of a pile wakes up the whole pile.
+    fn update_sleep(&mut self, sleep: SleepConfig, dt: f64, links: &[(ParticleId, ParticleId)]) {
+        // Kinematic bodies keep following their velocity, even when it is zero.
+        for p in self
+            .particles
+            .iter_mut()
+            .filter(|p| p.is_awake() && !p.is_kinematic())
+        {
+            if p.vel.length() < sleep.linear_threshold && p.omega.abs() < sleep.angular_threshold {
+                p.sleep_timer += dt;
+            } else {
+                p.sleep_timer = 0.0;
+            }
+        }
+
+        let pairs: Vec<_> = links
+            .iter()
+            .map(|&(a, b)| {
+                (
+                    self.particles.expect_index(a),
+                    self.particles.expect_index(b),
+                )
+            })
+            .collect();
+        let islands = island::find_islands(&self.particles, &pairs);
+        let mut in_island = vec![false; self.particles.len()];
+        let mut groups = vec![];
+        for island in islands {
+            for &body in &island.bodies {
+                in_island[body] = true;
+            }
+            groups.push(island.bodies);
+        }
+        groups.extend(
+            (0..self.particles.len())
+                .filter(|&i| !in_island[i])
+                .map(|i| vec![i]),
+        );
+        for bodies in groups {
+            let resting = bodies.iter().all(|&i| {
+                let p = &self.particles[i];
+                p.is_awake() && p.sleep_timer >= sleep.time_to_sleep
+            });
+            if resting {
+                for i in bodies {
+                    self.particles[i].fall_asleep();
+                }
+            }
+        }
+    }
+
+    fn handle_out_of_bounds(&mut self, bounds: (DVec2, DVec2), was_in_bounds: &[bool]) {
+        let ids = self.particles.ids().to_vec();
+        for ((p, &was_in), id) in self.particles.iter_mut().zip(was_in_bounds).zip(ids) {
+            // Kinematic bodies are moved by the user, who knows where they go.
+            if p.is_static() || p.is_kinematic() || !was_in || is_in_bounds(p.pos, bounds) {
+                continue;
+            }
+            trace!("Particle {id:?} left the bounds at {}", p.pos);
+            self.out_of_bounds_events
+                .push(OutOfBounds { id, pos: p.pos });
+            if self.out_of_bounds_action == OutOfBoundsAction::Freeze {
+                p.inv_mass = 0.0;
+                p.inv_inertia = 0.0;
+                p.vel = DVec2::ZERO;
+                p.omega = 0.0;
+            }
+        }
+    }
+
+    /// Advances the simulation by `real_dt` seconds in steps of `fixed_dt`,
+    /// so that its behavior does not depend on the frame rate. Time that does
+    /// not fill a whole step is carried over to the next call.
+    ///
+    /// Returns how far, as a fraction of `fixed_dt`, the real time is past the
+    /// last step, for interpolating rendered positions between steps.
+    pub fn advance(&mut self, real_dt: f64) -> f64 {
+        self.accumulator += real_dt;
+        let mut substeps = 0;
+        while self.accumulator >= self.fixed_dt {
+            if substeps == self.max_substeps {
+                trace!(
+                    "Dropping {}s the simulation could not keep up with",
+                    self.accumulator
+                );
+                self.accumulator %= self.fixed_dt;
+                break;
+            }
+            self.step(self.fixed_dt);
+            self.accumulator -= self.fixed_dt;
+            substeps += 1;
+        }
+        self.accumulator / self.fixed_dt
+    }
+
+    /// Runs `count` steps of `dt`, e.g. to fast-forward a headless simulation.
+    pub fn step_many(&mut self, dt: f64, count: usize) {
+        for _ in 0..count {
+            self.step(dt);
+        }
+    }
+
+    /// Like [`Engine::step_many`], but also returns the positions of all
+    /// particles, in the order of `particles`, after each step.
+    pub fn step_many_recording(&mut self, dt: f64, count: usize) -> Vec<Vec<DVec2>> {
+        (0..count)
+            .map(|_| {
+                self.step(dt);
+                self.particles.iter().map(|p| p.pos).collect()
+            })
+            .collect()
+    }
+
+    /// Finds the nearest body hit by the ray from `origin` in direction `dir`
+    /// within `max_dist`. Bodies containing `origin` are ignored, e.g. the one
+    /// casting a line of sight ray. Returns `None` for a zero `dir`.
+    pub fn raycast(&self, origin: DVec2, dir: DVec2, max_dist: f64) -> Option<RayHit> {
+        let dir = dir.try_normalize()?;
+        self.particles
+            .iter()
+            .zip(self.particles.ids())
+            .filter_map(|(p, &id)| {
+                let (distance, normal) = p.to_geometry_shape().raycast(origin, dir, max_dist)?;
+                Some(RayHit {
+                    id,
+                    user_data: p.user_data,
+                    pos: origin + distance * dir,
+                    normal,
+                    distance,
+                })
+            })
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+
+    /// Bodies containing `point`, e.g. the ones under the cursor, in the order
+    /// of `particles`. Does not change the simulation.
+    pub fn query_point(&self, point: DVec2) -> Vec<QueryHit> {
+        self.particles
+            .iter()
+            .zip(self.particles.ids())
+            .filter(|(p, _)| p.to_geometry_shape().contains_point(point))
+            .map(|(p, &id)| QueryHit {
+                id,
+                user_data: p.user_data,
+            })
+            .collect()
+    }
+
+    /// Bodies overlapping `shape`, as found by [`geometry::Shape::test_overlap`],
+    /// in the order of `particles`. Does not change the simulation, so it can
+    /// check where a body could be placed.
+    pub fn query_shape(&self, shape: &geometry::Shape) -> Vec<QueryHit> {
+        let aabb = shape.aabb();
+        self.particles
+            .iter()
+            .zip(self.particles.ids())
+            .filter(|(p, _)| {
+                aabb.overlaps(&p.aabb()) && !shape.test_overlap(&p.to_geometry_shape()).is_empty()
+            })
+            .map(|(p, &id)| QueryHit {
+                id,
+                user_data: p.user_data,
+            })
+            .collect()
+    }
+
+    /// Adds a particle to the simulation, returning the handle by which
+    /// constraints can refer to it.
+    pub fn add_particle(&mut self, particle: Particle) -> ParticleId {
+        self.particles.insert(particle)
+    }
+
+    /// Removes a particle together with all constraints attached to it.
+    /// Handles of the other particles stay valid.
+    pub fn remove_particle(&mut self, id: ParticleId) -> Option<Particle> {
+        let particle = self.particles.remove(id)?;
+        self.constraints.retain(|c| {
+            let (id_a, id_b) = c.get_ids();
+            id_a != id && id_b != id
+        });
+        self.pbd_constraints
+            .retain(|c| c.id_a != id && c.id_b != id);
+        self.springs.retain(|c| c.id_a != id && c.id_b != id);
+        self.contact_cache
+            .retain(|&(id_a, id_b, _), _| id_a != id && id_b != id);
+        self.collision_events
+            .retain(|e| e.id_a != id && e.id_b != id);
+        Some(particle)
+    }
+
+    /// Forgets the contacts remembered for warm starting, e.g. after
+    /// rearranging the particles so that old contacts no longer apply.
+    pub fn reset_contacts(&mut self) {
+        self.contact_cache.clear();
+    }
+
+    /// Pairs of overlapping bodies, at least one of them a sensor (see
+    /// [`Particle::is_sensor`]), found during the last `step`.
+    pub fn sensor_events(&self) -> &[SensorEvent] {
+        &self.sensor_events
+    }
+
+    /// Pairs of solid bodies that started touching, kept touching or stopped
+    /// touching during the last `step`. Unlike [`Engine::sensor_events`] this
+    /// covers collisions that are resolved, a pair resting on each other while
+    /// asleep keeps reporting [`CollisionPhase::Stay`].
+    pub fn collision_events(&self) -> &[CollisionEvent] {
+        &self.collision_events
+    }
+
+    /// Classifies the solid contacts of this step against the pairs that
+    /// touched during the previous one.
+    fn update_collision_events(&mut self, collisions: &[CollisionConstraint]) {
+        let previous = std::mem::take(&mut self.collision_events);
+        let touched = |id_a, id_b| {
+            previous
+                .iter()
+                .any(|e| e.phase != CollisionPhase::End && e.is_between(id_a, id_b))
+        };
+        // Contacts of a pair are consecutive.
+        for contacts in collisions.chunk_by(|c1, c2| (c1.id_a, c1.id_b) == (c2.id_a, c2.id_b)) {
+            let (id_a, id_b) = (contacts[0].id_a, contacts[0].id_b);
+            let pos = contacts.iter().map(|c| c.contact.pos).sum::<DVec2>() / contacts.len() as f64;
+            self.collision_events.push(CollisionEvent {
+                id_a,
+                id_b,
+                user_data_a: self.particles[id_a].user_data,
+                user_data_b: self.particles[id_b].user_data,
+                phase: if touched(id_a, id_b) {
+                    CollisionPhase::Stay
+                } else {
+                    CollisionPhase::Begin
+                },
+                pos,
+                normal: contacts[0].contact.normal,
+            });
+        }
+        for event in previous {
+            if event.phase == CollisionPhase::End
+                || self
+                    .collision_events
+                    .iter()
+                    .any(|e| e.is_between(event.id_a, event.id_b))
+            {
+                continue;
+            }
+            // Resting pairs are not tested for collisions but keep touching.
+            let phase = if is_resting_pair(&self.particles[event.id_a], &self.particles[event.id_b])
+            {
+                CollisionPhase::Stay
+            } else {
+                CollisionPhase::End
+            };
+            self.collision_events
+                .push(CollisionEvent { phase, ..event });
+        }
+    }
+
+    /// Bodies that crossed `bounds` during the last `step`.
+    pub fn out_of_bounds_events(&self) -> &[OutOfBounds] {
+        &self.out_of_bounds_events
+    }
+
+    /// Sum of the translational and rotational kinetic energies of all bodies.
+    /// Like the momenta below, it leaves out the infinite masses and inertias
+    /// of static and kinematic bodies.
+    pub fn total_kinetic_energy(&self) -> f64 {
+        self.particles
+            .iter()
+            .map(|p| {
+                let mass = inverse_or_zero(p.effective_inv_mass());
+                let inertia = inverse_or_zero(p.effective_inv_inertia());
+                0.5 * (mass * p.vel.length_squared() + inertia * p.omega * p.omega)
+            })
+            .sum()
+    }
+
+    pub fn total_momentum(&self) -> DVec2 {
+        self.particles
+            .iter()
+            .map(|p| inverse_or_zero(p.effective_inv_mass()) * p.vel)
+            .sum()
+    }
+
+    /// Angular momentum around the origin, i.e. the sum of the bodies' spins
+    /// and of the moments of their momenta.
+    pub fn total_angular_momentum(&self) -> f64 {
+        self.particles
+            .iter()
+            .map(|p| {
+                let momentum = inverse_or_zero(p.effective_inv_mass()) * p.vel;
+                p.pos.perp_dot(momentum) + inverse_or_zero(p.effective_inv_inertia()) * p.omega
+            })
+            .sum()
+    }
+
+    /// Position-based dynamics pass: iteratively (Gauss-Seidel) moves particles
+    /// to satisfy `pbd_constraints` and updates velocities to match the displacement.
+    #[instrument(level = "trace", skip_all)]
+    pub fn project_constraints(&mut self, dt: f64) {
+        if self.pbd_constraints.is_empty() {
+            return;
+        }
+        let initial_positions: Vec<_> = self.particles.iter().map(|p| p.pos).collect();
+        for _ in 0..self.solver.iterations {
+            for c in &self.pbd_constraints {
+                if c.id_a == c.id_b {
+                    continue;
+                }
+                let index_a = self.particles.expect_index(c.id_a);
+                let index_b = self.particles.expect_index(c.id_b);
+                let (a, b) = get_pair_mut(&mut self.particles, index_a, index_b);
+                c.project(a, b);
+            }
+        }
+        for (p, initial_pos) in self.particles.iter_mut().zip(initial_positions) {
+            p.vel += (p.pos - initial_pos) / dt;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::dvec2;
+    use rand::Rng as _;
+
+    use super::*;
+    use crate::{
+        constraint::DistanceConstraint,
+        rng::DeterministicRng,
+        scenarios::{Collision, ManyParticles, Penetration, Resting, Rope, Scenario},
+    };
+
+    #[test]
+    fn mix_rules() {
+        let (a, b) = (0.2, 0.8);
+        assert_eq!(MixRule::Average.mix(a, b), 0.5);
+        assert!((MixRule::GeometricMean.mix(a, b) - 0.4).abs() < 1e-12);
+        assert_eq!(MixRule::Min.mix(a, b), 0.2);
+        assert_eq!(MixRule::Max.mix(a, b), 0.8);
+        assert!((MixRule::Multiply.mix(a, b) - 0.16).abs() < 1e-12);
+    }
+
+    /// Moment of inertia of a uniform body of `mass` around the origin, by
+    /// summing over a fine grid of cells of `shape`.
+    fn integrate_inertia(mass: f64, shape: Shape) -> f64 {
+        let particle = Particle::new(1.0, 1.0, shape);
+        let geometry = particle.to_geometry_shape();
+        let geometry::Aabb { min, max } = geometry.aabb();
+        let cells = 1000;
+        let cell = (max - min) / cells as f64;
+        let (mut area, mut second_moment) = (0.0, 0.0);
+        for i in 0..cells {
+            for j in 0..cells {
+                let p = min + cell * dvec2(i as f64 + 0.5, j as f64 + 0.5);
+                let probe = geometry::Shape::Circle(Circle {
+                    pos: p,
+                    radius: 0.0,
+                });
+                if !probe.test_overlap(&geometry).is_empty() {
+                    area += 1.0;
+                    second_moment += p.length_squared();
+                }
+            }
+        }
+        mass * second_moment / area
+    }
+
+    #[test]
+    fn uniform_shape_inertia() {
+        for (mass, radius) in [(1.0, 1.0), (2.0, 0.5), (10.0, 3.0)] {
+            let circle = Particle::circle(mass, radius);
+            assert_eq!(circle.inv_mass, 1.0 / mass);
+            assert_eq!(circle.inv_inertia, 2.0 / (mass * radius * radius));
+            let integrated = integrate_inertia(mass, circle.shape.clone());
+            assert!((1.0 / circle.inv_inertia - integrated).abs() < 1e-3 * integrated);
+        }
+
+        for (mass, length, radius) in [(1.0, 2.0, 1.0), (3.0, 4.0, 0.5)] {
+            let capsule = Particle::capsule(mass, length, radius);
+            let integrated = integrate_inertia(mass, capsule.shape.clone());
+            assert!((1.0 / capsule.inv_inertia - integrated).abs() < 1e-3 * integrated);
+        }
+        // A capsule without a segment is a disc.
+        let degenerate = Particle::capsule(2.0, 0.0, 1.5).inv_inertia;
+        assert!((degenerate - Particle::circle(2.0, 1.5).inv_inertia).abs() < 1e-12);
+    }
+
+    #[test]
+    fn uniform_polygon_inertia() {
+        // Off-center rectangle: `m * (w^2 + h^2) / 12` around its centroid.
+        let rectangle = Particle::polygon(
+            3.0,
+            &[
+                dvec2(1.0, 1.0),
+                dvec2(5.0, 1.0),
+                dvec2(5.0, 3.0),
+                dvec2(1.0, 3.0),
+            ],
+        );
+        assert!((1.0 / rectangle.inv_inertia - 3.0 * (16.0 + 4.0) / 12.0).abs() < 1e-12);
+        let Shape::Polygon { vertices } = &rectangle.shape else {
+            panic!("not a polygon");
+        };
+        assert_eq!(vertices[0], dvec2(-2.0, -1.0));
+
+        let triangle = Particle::polygon(2.0, &[dvec2(0.0, 0.0), dvec2(3.0, 0.0), dvec2(0.0, 1.0)]);
+        let integrated = integrate_inertia(2.0, triangle.shape.clone());
+        assert!((1.0 / triangle.inv_inertia - integrated).abs() < 1e-3 * integrated);
+    }
+
+    #[test]
+    fn zero_mass_shapes_are_static() {
+        assert!(Particle::circle(0.0, 1.0).is_static());
+        assert!(Particle::capsule(0.0, 2.0, 1.0).is_static());
+        let square = [
+            dvec2(0.0, 0.0),
+            dvec2(1.0, 0.0),
+            dvec2(1.0, 1.0),
+            dvec2(0.0, 1.0),
+        ];
+        assert!(Particle::polygon(0.0, &square).is_static());
+    }
+
+    #[test]
+    fn off_center_impulse_spins_the_particle() {
+        // Mass 2 and inertia 2 * 1^2 / 2 = 1.
+        let mut particle = Particle {
+            pos: dvec2(3.0, 4.0),
+            ..Particle::circle(2.0, 1.0)
+        };
+        particle.apply_impulse_at_point(dvec2(0.0, 1.0), dvec2(4.0, 4.0));
+        assert_eq!(particle.vel, dvec2(0.0, 0.5));
+        assert_eq!(particle.omega, 1.0);
+
+        // Pushing through the center only moves it.
+        particle.apply_impulse_at_point(dvec2(2.0, 0.0), dvec2(5.0, 4.0));
+        assert_eq!(particle.vel, dvec2(1.0, 0.5));
+        assert_eq!(particle.omega, 1.0);
+
+        // The opposite side spins it the other way.
+        particle.apply_impulse_at_point(dvec2(0.0, 1.0), dvec2(2.0, 4.0));
+        assert_eq!(particle.vel, dvec2(1.0, 1.0));
+        assert_eq!(particle.omega, 0.0);
+    }
+
+    #[test]
+    fn off_center_force_adds_torque() {
+        let mut engine = Engine::default();
+        let mut particle = Particle::circle(2.0, 1.0);
+        particle.apply_force_at_point(dvec2(0.0, 2.0), dvec2(1.0, 0.0));
+        particle.apply_force_at_point(dvec2(1.0, 0.0), dvec2(0.0, -1.0));
+        assert_eq!(particle.force, dvec2(1.0, 2.0));
+        assert_eq!(particle.torque, 3.0);
+
+        let id = engine.add_particle(particle);
+        engine.step(0.5);
+        let particle = &engine.particles[id];
+        assert_eq!(particle.vel, dvec2(0.25, 0.5));
+        assert_eq!(particle.omega, 1.5);
+    }
+
+    /// Sum of the particle speeds over the last half of `steps` steps of the
+    /// resting scenario, i.e. how much the stacked bodies jitter.
+    fn resting_jitter(steps: usize, warm_start: bool) -> f64 {
+        let mut engine = Resting {}.create();
+        let mut jitter = 0.0;
+        for step in 0..steps {
+            if !warm_start {
+                engine.reset_contacts();
+            }
+            engine.step(1.0 / 60.0);
+            if step >= steps / 2 {
+                jitter += engine.particles.iter().map(|p| p.vel.length()).sum::<f64>();
+            }
+        }
+        jitter
+    }
+
+    #[test]
+    fn warm_starting_reduces_jitter() {
+        let warm = resting_jitter(600, true);
+        let cold = resting_jitter(600, false);
+        assert!(warm < cold);
+    }
+
+    /// Distance a unit box slides down a plane inclined by `slope` radians
+    /// in `steps` steps, with `friction` on both bodies.
+    fn incline_slide(slope: f64, friction: f64, steps: usize) -> f64 {
+        let normal = DVec2::from_angle(PI / 2.0 - slope);
+        let square = [
+            dvec2(-0.5, -0.5),
+            dvec2(0.5, -0.5),
+            dvec2(0.5, 0.5),
+            dvec2(-0.5, 0.5),
+        ];
+        let start = 0.499 * normal;
+        let mut engine = Engine {
+            particles: vec![
+                Particle {
+                    pos: start,
+                    angle: -slope,
+                    friction,
+                    ..Particle::polygon(1.0, &square)
+                },
+                Particle {
+                    inv_mass: 0.0,
+                    inv_inertia: 0.0,
+                    shape: Shape::HalfPlane {
+                        normal_angle: PI / 2.0 - slope,
+                    },
+                    friction,
+                    ..Default::default()
+                },
+            ]
+            .into(),
+            gravity: dvec2(0.0, -10.0),
+            ..Default::default()
+        };
+        for _ in 0..steps {
+            engine.step(1.0 / 60.0);
+        }
+        (engine.particles[0].pos - start).length()
+    }
+
+    #[test]
+    fn friction_holds_box_on_incline() {
+        // tan(0.3) ~ 0.31 is below the friction coefficient.
+        let slide = incline_slide(0.3, 0.5, 120);
+        assert!(slide < 1e-2, "slid by {slide}");
+        assert!(incline_slide(0.3, 0.0, 120) > 1.0);
+    }
+
+    #[test]
+    fn friction_makes_spinning_ball_roll() {
+        let mut engine = Engine {
+            particles: vec![
+                Particle {
+                    pos: dvec2(0.0, 0.999),
+                    omega: 10.0,
+                    friction: 0.5,
+                    ..Particle::circle(1.0, 1.0)
+                },
+                Particle {
+                    inv_mass: 0.0,
+                    inv_inertia: 0.0,
+                    shape: Shape::HalfPlane {
+                        normal_angle: PI / 2.0,
+                    },
+                    friction: 0.5,
+                    ..Default::default()
+                },
+            ]
+            .into(),
+            gravity: dvec2(0.0, -10.0),
+            ..Default::default()
+        };
+        for _ in 0..120 {
+            engine.step(1.0 / 60.0);
+        }
+        // Counter-clockwise spin rolls to the left, with the contact point at rest.
+        let ball = &engine.particles[0];
+        assert!(ball.vel.x < 0.0);
+        assert!((ball.vel.x + ball.omega).abs() < 1e-3);
+    }
+
+    #[test]
+    fn broad_phase_matches_brute_force() {
+        let mut rng = DeterministicRng::new(1);
+        let mut particles: Vec<_> = (0..200)
+            .map(|i| {
+                let size = rng.gen_range(0.5..3.0);
+                let particle = match i % 3 {
+                    0 => Particle::circle(1.0, size),
+                    1 => Particle::capsule(1.0, size, 0.5 * size),
+                    _ => Particle::polygon(
+                        1.0,
+                        &[dvec2(0.0, 0.0), dvec2(size, 0.0), dvec2(0.0, size)],
+                    ),
+                };
+                Particle {
+                    pos: dvec2(rng.gen_range(-30.0..30.0), rng.gen_range(-30.0..30.0)),
+                    angle: rng.gen_range(0.0..2.0 * PI),
+                    ..particle
+                }
+            })
+            .collect();
+        particles.push(Particle {
+            inv_mass: 0.0,
+            inv_inertia: 0.0,
+            pos: dvec2(0.0, -25.0),
+            shape: Shape::HalfPlane {
+                normal_angle: PI / 2.0,
+            },
+            ..Default::default()
+        });
+        let engine = Engine {
+            particles: particles.into(),
+            ..Default::default()
+        };
+
+        let n = engine.particles.len();
+        let all_pairs = (0..n).flat_map(|i| (i + 1..n).map(move |j| (i, j)));
+        let key = |c: &CollisionConstraint| (c.id_a, c.id_b, c.feature, c.contact.clone());
+        let brute_force: Vec<_> = engine
+            .detect_collisions_between(all_pairs)
+            .iter()
+            .map(key)
+            .collect();
+        let broad_phase: Vec<_> = engine.detect_collisions().iter().map(key).collect();
+        assert!(brute_force.len() > 20);
+        assert_eq!(broad_phase, brute_force);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_preserves_simulation() {
+        let mut engine = Resting {}.create();
+        engine.add_particle(Particle {
+            pos: dvec2(-200.0, 150.0),
+            friction: 0.5,
+            ..Particle::polygon(1.0, &[dvec2(0.0, 0.0), dvec2(60.0, 0.0), dvec2(0.0, 60.0)])
+        });
+        let ids = engine.particles.ids().to_vec();
+        engine
+            .constraints
+            .push(ConstraintEnum::Distance(DistanceConstraint::new(
+                ids[0], ids[1], 200.0,
+            )));
+        engine
+            .pbd_constraints
+            .push(PbdDistance::new(ids[3], ids[4], 100.0));
+        for _ in 0..30 {
+            engine.step(1.0 / 60.0);
+        }
+
+        let json = serde_json::to_string(&engine).unwrap();
+        let mut restored: Engine = serde_json::from_str(&json).unwrap();
+        for _ in 0..30 {
+            engine.step(1.0 / 60.0);
+            restored.step(1.0 / 60.0);
+        }
+        for (a, b) in engine.particles.iter().zip(&restored.particles) {
+            assert_eq!(a.pos, b.pos);
+            assert_eq!(a.vel, b.vel);
+            assert_eq!(a.angle, b.angle);
+            assert_eq!(a.omega, b.omega);
+        }
+    }
+
+    #[test]
+    fn user_data_survives_step() {
+        let mut engine = Engine::builder()
+            .particles(vec![
+                Particle {
+                    user_data: 7,
+                    ..Default::default()
+                },
+                Particle {
+                    pos: dvec2(1.5, 0.0),
+                    user_data: u64::MAX,
+                    ..Default::default()
+                },
+            ])
+            .build()
+            .unwrap();
+        engine.step(0.1);
+        assert_eq!(engine.particles[0].user_data, 7);
+        assert_eq!(engine.particles[1].user_data, u64::MAX);
+        assert_eq!(Particle::default().user_data, 0);
+
+        // The circles overlap, so the step reported them touching.
+        let [event] = engine.collision_events() else {
+            panic!("{:?}", engine.collision_events());
+        };
+        let tags = [
+            (event.id_a, event.user_data_a),
+            (event.id_b, event.user_data_b),
+        ];
+        for (id, user_data) in tags {
+            assert_eq!(engine.particles[id].user_data, user_data);
+        }
+        let mut user_data = [event.user_data_a, event.user_data_b];
+        user_data.sort();
+        assert_eq!(user_data, [7, u64::MAX]);
+
+        let hit = engine
+            .raycast(dvec2(-10.0, 0.0), DVec2::X, 100.0)
+            .expect("the ray passes through both circles");
+        assert_eq!(hit.id, engine.particles.ids()[0]);
+        assert_eq!(hit.user_data, 7);
+    }
+
+    #[test]
+    fn collision_layers_filter_pairs() {
+        let mut engine = Engine::builder()
+            .particles(vec![
+                Particle {
+                    collision_layer: 0b01,
+                    collision_mask: 0b01,
+                    ..Default::default()
+                },
+                Particle {
+                    pos: dvec2(1.5, 0.0),
+                    collision_layer: 0b10,
+                    ..Default::default()
+                },
+            ])
+            .build()
+            .unwrap();
+        assert!(engine.detect_collisions().is_empty());
+
+        // Both masks need to accept the other body's layer.
+        engine.particles[0].collision_mask = 0b11;
+        assert_eq!(engine.detect_collisions().len(), 1);
+        engine.particles[1].collision_mask = 0b10;
+        assert!(engine.detect_collisions().is_empty());
+    }
+
+    #[test]
+    fn falling_ball_reports_collision_phases() {
+        let mut engine = Engine {
+            gravity: dvec2(0.0, -10.0),
+            ..Default::default()
+        };
+        let ground = engine.add_particle(Particle {
+            inv_mass: 0.0,
+            inv_inertia: 0.0,
+            shape: Shape::HalfPlane {
+                normal_angle: PI / 2.0,
+            },
+            restitution: 0.0,
+            user_data: 1,
+            ..Default::default()
+        });
+        let ball = engine.add_particle(Particle {
+            pos: dvec2(0.0, 3.0),
+            restitution: 0.0,
+            user_data: 42,
+            ..Particle::circle(1.0, 1.0)
+        });
+
+        let hit = (0..120).any(|_| {
+            engine.step(1.0 / 60.0);
+            !engine.collision_events().is_empty()
+        });
+        assert!(hit, "the ball never hit the ground");
+        let [event] = engine.collision_events() else {
+            panic!("{:?}", engine.collision_events());
+        };
+        assert_eq!(event.phase, CollisionPhase::Begin);
+        assert_eq!((event.id_a, event.id_b), (ground, ball));
+        assert_eq!((event.user_data_a, event.user_data_b), (1, 42));
+        assert!(event.pos.abs_diff_eq(dvec2(0.0, 0.0), 0.1), "{event:?}");
+        assert!(event.normal.abs_diff_eq(dvec2(0.0, 1.0), 1e-9), "{event:?}");
+
+        for _ in 0..30 {
+            engine.step(1.0 / 60.0);
+            let phases: Vec<_> = engine.collision_events().iter().map(|e| e.phase).collect();
+            assert_eq!(phases, [CollisionPhase::Stay]);
+        }
+
+        engine.particles[ball].vel = dvec2(0.0, 20.0);
+        engine.step(1.0 / 60.0);
+        // The pair is no longer touching, the data is the one captured while
+        // it was.
+        engine.particles[ball].user_data = 43;
+        engine.step(1.0 / 60.0);
+        let [event] = engine.collision_events() else {
+            panic!("{:?}", engine.collision_events());
+        };
+        assert_eq!(event.phase, CollisionPhase::End);
+        assert_eq!((event.user_data_a, event.user_data_b), (1, 42));
+        engine.step(1.0 / 60.0);
+        assert!(engine.collision_events().is_empty());
+    }
+
+    #[test]
+    fn static_half_planes_only_collide_with_dynamic_bodies() {
+        let static_half_plane = |pos, normal_angle| Particle {
+            pos,
+            inv_mass: 0.0,
+            inv_inertia: 0.0,
+            shape: Shape::HalfPlane { normal_angle },
+            ..Default::default()
+        };
+        let mut engine = Engine::default();
+        let floor = engine.add_particle(static_half_plane(dvec2(0.0, 0.0), PI / 2.0));
+        let wall = engine.add_particle(static_half_plane(dvec2(0.0, 0.0), 0.0));
+        let ball = engine.add_particle(Particle {
+            pos: dvec2(0.5, 0.5),
+            ..Particle::circle(1.0, 1.0)
+        });
+
+        let pairs: Vec<_> = engine
+            .detect_collisions()
+            .iter()
+            .map(|c| (c.id_a, c.id_b))
+            .collect();
+        assert_eq!(pairs, [(floor, ball), (wall, ball)]);
+
+        engine.step(1.0 / 60.0);
+        assert!(engine.collision_events().iter().all(|e| e.id_b == ball));
+        assert_eq!(engine.collision_events().len(), 2);
+    }
+
+    #[test]
+    fn kinematic_half_plane_carries_resting_circle() {
+        let mut engine = Engine {
+            gravity: dvec2(0.0, -10.0),
+            ..Default::default()
+        };
+        // Its mass is ignored, neither gravity nor the circle slow it down.
+        let lift = engine.add_particle(Particle {
+            vel: dvec2(0.0, 1.0),
+            shape: Shape::HalfPlane {
+                normal_angle: PI / 2.0,
+            },
+            restitution: 0.0,
+            body_type: BodyType::Kinematic,
+            ..Default::default()
+        });
+        let ball = engine.add_particle(Particle {
+            pos: dvec2(0.0, 1.0),
+            restitution: 0.0,
+            ..Particle::circle(1.0, 1.0)
+        });
+
+        for _ in 0..120 {
+            engine.step(1.0 / 60.0);
+        }
+        let (lift, ball) = (&engine.particles[lift], &engine.particles[ball]);
+        assert_eq!(lift.vel, dvec2(0.0, 1.0));
+        assert!((lift.pos.y - 2.0).abs() < 1e-9, "{}", lift.pos);
+        assert!((ball.pos.y - lift.pos.y - 1.0).abs() < 0.05, "{}", ball.pos);
+        assert!((ball.vel.y - 1.0).abs() < 0.2, "{}", ball.vel);
+        assert!(ball.pos.x.abs() < 1e-9, "{}", ball.pos);
+    }
+
+    #[test]
+    fn sensors_report_overlaps_without_impulses() {
+        let mut engine = Engine::default();
+        let sensor = engine.add_particle(Particle {
+            inv_mass: 0.0,
+            inv_inertia: 0.0,
+            shape: Shape::Circle { radius: 5.0 },
+            is_sensor: true,
+            user_data: 3,
+            ..Default::default()
+        });
+        let ball = engine.add_particle(Particle {
+            pos: dvec2(2.0, 0.0),
+            vel: dvec2(-1.0, 0.0),
+            user_data: 4,
+            ..Default::default()
+        });
+        engine.step(0.1);
+        assert_eq!(
+            engine.sensor_events(),
+            &[SensorEvent {
+                id_a: sensor,
+                id_b: ball,
+                user_data_a: 3,
+                user_data_b: 4,
+            }]
+        );
+        assert_eq!(engine.particles[ball].vel, dvec2(-1.0, 0.0));
+        assert_eq!(engine.particles[ball].omega, 0.0);
+    }
+
+    #[test]
+    fn query_point_finds_bodies_containing_it() {
+        let mut engine = Engine::default();
+        let ball = engine.add_particle(Particle {
+            pos: dvec2(2.0, 1.0),
+            user_data: 1,
+            ..Particle::circle(1.0, 1.0)
+        });
+        let big_ball = engine.add_particle(Particle {
+            pos: dvec2(10.0, 0.0),
+            user_data: 2,
+            ..Particle::circle(1.0, 5.0)
+        });
+        let ball = QueryHit {
+            id: ball,
+            user_data: 1,
+        };
+
+        assert_eq!(engine.query_point(dvec2(2.5, 1.5)), [ball]);
+        assert_eq!(engine.query_point(dvec2(2.0, 1.0)), [ball]);
+        assert_eq!(
+            engine.query_point(dvec2(6.0, 0.0)),
+            [QueryHit {
+                id: big_ball,
+                user_data: 2,
+            }]
+        );
+        assert!(engine.query_point(dvec2(3.0, 2.0)).is_empty());
+        assert!(engine.query_point(dvec2(-5.0, 0.0)).is_empty());
+    }
+
+    #[test]
+    fn query_shape_finds_overlapping_bodies_without_stepping() {
+        let mut engine = Engine::default();
+        let left = engine.add_particle(Particle {
+            pos: dvec2(-2.0, 0.0),
+            user_data: 10,
+            ..Particle::circle(1.0, 1.0)
+        });
+        let right = engine.add_particle(Particle {
+            pos: dvec2(2.0, 0.0),
+            user_data: 11,
+            ..Particle::circle(1.0, 1.0)
+        });
+        let far = engine.add_particle(Particle {
+            pos: dvec2(20.0, 0.0),
+            user_data: 12,
+            ..Particle::circle(1.0, 1.0)
+        });
+        let before: Vec<_> = engine.particles.iter().map(|p| (p.pos, p.vel)).collect();
+
+        // A wide box between the circles reaches into both.
+        let query = geometry::Shape::Polygon(geometry::Polygon {
+            vertices: vec![
+                dvec2(-1.5, -0.5),
+                dvec2(1.5, -0.5),
+                dvec2(1.5, 0.5),
+                dvec2(-1.5, 0.5),
+            ],
+        });
+        let hits: Vec<_> = engine
+            .query_shape(&query)
+            .iter()
+            .map(|hit| (hit.id, hit.user_data))
+            .collect();
+        assert_eq!(hits, [(left, 10), (right, 11)]);
+        let small = geometry::Shape::Circle(geometry::Circle {
+            pos: dvec2(20.5, 0.0),
+            radius: 0.1,
+        });
+        assert_eq!(
+            engine.query_shape(&small),
+            [QueryHit {
+                id: far,
+                user_data: 12,
+            }]
+        );
+        let empty_space = geometry::Shape::Circle(geometry::Circle {
+            pos: dvec2(10.0, 0.0),
+            radius: 2.0,
+        });
+        assert!(engine.query_shape(&empty_space).is_empty());
+
+        let after: Vec<_> = engine.particles.iter().map(|p| (p.pos, p.vel)).collect();
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn raycast_finds_nearest_body() {
+        let mut engine = Engine::default();
+        let far = engine.add_particle(Particle {
+            pos: dvec2(10.0, 0.0),
+            ..Default::default()
+        });
+        let near = engine.add_particle(Particle {
+            pos: dvec2(5.0, 0.0),
+            user_data: 42,
+            ..Default::default()
+        });
+        let hit = engine.raycast(DVec2::ZERO, dvec2(2.0, 0.0), 100.0).unwrap();
+        assert_eq!(hit.id, near);
+        assert_eq!(hit.user_data, 42);
+        assert_eq!(hit.pos, dvec2(4.0, 0.0));
+        assert_eq!(hit.normal, -DVec2::X);
+        assert_eq!(hit.distance, 4.0);
+
+        // Starting inside the near body only the far one is visible.
+        let hit = engine.raycast(dvec2(5.0, 0.0), DVec2::X, 100.0).unwrap();
+        assert_eq!(hit.id, far);
+        assert!(engine.raycast(DVec2::ZERO, DVec2::Y, 100.0).is_none());
+        assert!(engine.raycast(DVec2::ZERO, DVec2::X, 3.0).is_none());
+    }
+
+    #[test]
+    fn damping_slows_free_body_down() {
+        let mut engine = Engine::default();
+        let id = engine.add_particle(Particle {
+            vel: dvec2(10.0, -5.0),
+            omega: 3.0,
+            linear_damping: 2.0,
+            angular_damping: 2.0,
+            ..Default::default()
+        });
+        let mut speed = engine.particles[id].vel.length();
+        for _ in 0..600 {
+            engine.step(1.0 / 60.0);
+            let new_speed = engine.particles[id].vel.length();
+            assert!(new_speed < speed);
+            speed = new_speed;
+        }
+        assert!(speed < 1e-3);
+        assert!(engine.particles[id].omega.abs() < 1e-3);
+    }
+
+    #[test]
+    fn collisions_use_mixed_materials() {
+        let mut engine = Engine {
+            particles: vec![
+                Particle {
+                    restitution: 0.2,
+                    friction: 0.1,
+                    ..Default::default()
+                },
+                Particle {
+                    pos: dvec2(1.5, 0.0),
+                    restitution: 0.8,
+                    friction: 0.4,
+                    ..Default::default()
+                },
+            ]
+            .into(),
+            ..Default::default()
+        };
+        let collision = &engine.detect_collisions()[0];
+        assert_eq!(collision.restitution, 0.8);
+        assert!((collision.friction - 0.2).abs() < 1e-12);
+
+        engine.restitution_mix = MixRule::Min;
+        engine.friction_mix = MixRule::Max;
+        let collision = &engine.detect_collisions()[0];
+        assert_eq!(collision.restitution, 0.2);
+        assert_eq!(collision.friction, 0.4);
+    }
+
+    #[test]
+    fn restitution_scales_rebound_height() {
+        let drop_height = 5.0;
+        let mut engine = Engine {
+            particles: vec![
+                Particle {
+                    pos: dvec2(0.0, 1.0 + drop_height),
+                    restitution: 0.5,
+                    ..Particle::circle(1.0, 1.0)
+                },
+                Particle {
+                    inv_mass: 0.0,
+                    inv_inertia: 0.0,
+                    shape: Shape::HalfPlane {
+                        normal_angle: PI / 2.0,
+                    },
+                    restitution: 0.5,
+                    ..Default::default()
+                },
+            ]
+            .into(),
+            gravity: dvec2(0.0, -10.0),
+            ..Default::default()
+        };
+        // Falling takes 1s, the rebound with half the speed reaches its top 0.5s later.
+        let mut bounced = false;
+        let mut rebound_height: f64 = 0.0;
+        for _ in 0..120 {
+            engine.step(1.0 / 60.0);
+            let ball = &engine.particles[0];
+            bounced |= ball.vel.y > 0.0;
+            if bounced {
+                rebound_height = rebound_height.max(ball.pos.y - 1.0);
+            }
+        }
+        assert!(bounced);
+        let ratio = rebound_height / drop_height;
+        assert!((ratio - 0.25).abs() < 0.05, "rebound ratio {ratio}");
+    }
+
+    #[test]
+    fn resting_stack_sleeps_until_hit() {
+        let square = [
+            dvec2(-0.5, -0.5),
+            dvec2(0.5, -0.5),
+            dvec2(0.5, 0.5),
+            dvec2(-0.5, 0.5),
+        ];
+        let box_at = |y| Particle {
+            pos: dvec2(0.0, y),
+            restitution: 0.0,
+            friction: 0.5,
+            ..Particle::polygon(1.0, &square)
+        };
+        let mut engine = Engine {
+            gravity: dvec2(0.0, -10.0),
+            sleep: Some(SleepConfig::default()),
+            ..Default::default()
+        };
+        engine.add_particle(Particle {
+            inv_mass: 0.0,
+            inv_inertia: 0.0,
+            shape: Shape::HalfPlane {
+                normal_angle: PI / 2.0,
+            },
+            restitution: 0.0,
+            friction: 0.5,
+            ..Default::default()
+        });
+        let stack = [
+            engine.add_particle(box_at(0.5)),
+            engine.add_particle(box_at(1.5)),
+        ];
+        for _ in 0..120 {
+            engine.step(1.0 / 60.0);
+        }
+        assert!(stack.iter().all(|&id| engine.particles[id].is_sleeping()));
+        let settled: Vec<_> = stack.iter().map(|&id| engine.particles[id].pos).collect();
+        engine.step(1.0 / 60.0);
+        for (&id, pos) in stack.iter().zip(&settled) {
+            assert_eq!(engine.particles[id].pos, *pos);
+        }
+
+        // A box dropped on top wakes up the whole stack, which falls asleep
+        // again once everything has settled.
+        let falling = engine.add_particle(box_at(4.0));
+        let mut woken = false;
+        for _ in 0..60 {
+            engine.step(1.0 / 60.0);
+            woken |= stack.iter().all(|&id| !engine.particles[id].is_sleeping());
+        }
+        assert!(woken);
+        for _ in 0..180 {
+            engine.step(1.0 / 60.0);
+        }
+        assert!(engine.particles.iter().skip(1).all(Particle::is_sleeping));
+        assert!((engine.particles[falling].pos.y - 2.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn constraints_survive_removing_a_middle_particle() {
+        let mut engine = Engine {
+            gravity: dvec2(0.0, -10.0),
+            ..Default::default()
+        };
+        let anchor = engine.add_particle(Particle {
+            inv_mass: 0.0,
+            inv_inertia: 0.0,
+            ..Default::default()
+        });
+        let a = engine.add_particle(Particle {
+            pos: dvec2(10.0, 0.0),
+            ..Default::default()
+        });
+        let removed = engine.add_particle(Particle {
+            pos: dvec2(0.0, 50.0),
+            ..Default::default()
+        });
+        let b = engine.add_particle(Particle {
+            pos: dvec2(20.0, 0.0),
+            ..Default::default()
+        });
+        engine.constraints = [(anchor, a), (a, b), (removed, b)]
+            .into_iter()
+            .map(|(id_a, id_b)| ConstraintEnum::Distance(DistanceConstraint::new(id_a, id_b, 10.0)))
+            .collect();
+
+        assert!(engine.remove_particle(removed).is_some());
+        assert!(engine.remove_particle(removed).is_none());
+        assert_eq!(engine.particles.len(), 3);
+        assert_eq!(engine.constraints.len(), 2);
+
+        for _ in 0..120 {
+            engine.step(1.0 / 60.0);
+        }
+        let distance = |id_a: ParticleId, id_b: ParticleId| {
+            (engine.particles[id_a].pos - engine.particles[id_b].pos).length()
+        };
+        assert!((distance(anchor, a) - 10.0).abs() < 0.1);
+        assert!((distance(a, b) - 10.0).abs() < 0.1);
+        // The chain swung down from its horizontal initial position.
+        assert!(engine.particles[b].pos.y < -5.0);
+    }
+
+    #[test]
+    fn head_on_elastic_collision_conserves_momentum_and_energy() {
+        let mut engine = Engine::default();
+        let a = engine.add_particle(Particle {
+            pos: dvec2(-5.0, 0.0),
+            vel: dvec2(3.0, 0.0),
+            ..Particle::circle(2.0, 1.0)
+        });
+        let b = engine.add_particle(Particle {
+            pos: dvec2(5.0, 0.0),
+            vel: dvec2(-1.0, 0.0),
+            ..Particle::circle(2.0, 1.0)
+        });
+        assert_eq!(engine.total_momentum(), dvec2(4.0, 0.0));
+        assert_eq!(engine.total_kinetic_energy(), 10.0);
+        assert_eq!(engine.total_angular_momentum(), 0.0);
+
+        for _ in 0..180 {
+            engine.step(1.0 / 60.0);
+        }
+        // Equal masses swap their velocities.
+        assert!((engine.particles[a].vel - dvec2(-1.0, 0.0)).length() < 1e-9);
+        assert!((engine.particles[b].vel - dvec2(3.0, 0.0)).length() < 1e-9);
+        assert!((engine.total_momentum() - dvec2(4.0, 0.0)).length() < 1e-9);
+        assert!((engine.total_kinetic_energy() - 10.0).abs() < 1e-9);
+        assert!(engine.total_angular_momentum().abs() < 1e-9);
+    }
+
+    #[test]
+    fn collision_scenario_conserves_momenta() {
+        let mut engine = Collision {}.create();
+        let momentum = engine.total_momentum();
+        let angular_momentum = engine.total_angular_momentum();
+        let energy = engine.total_kinetic_energy();
+        for _ in 0..180 {
+            engine.step(1.0 / 60.0);
+        }
+        // The off-center hit deflected the bodies.
+        assert!(engine.particles[0].vel.y.abs() > 1.0);
+        assert!((engine.total_momentum() - momentum).length() < 1e-6 * momentum.length());
+        assert!(
+            (engine.total_angular_momentum() - angular_momentum).abs()
+                < 1e-6 * angular_momentum.abs()
+        );
+        assert!((engine.total_kinetic_energy() - energy).abs() < 1e-6 * energy);
+    }
+
+    #[test]
+    fn damped_spring_oscillates_around_rest_length() {
+        let mut engine = Engine::default();
+        let a = engine.add_particle(Particle::default());
+        let b = engine.add_particle(Particle {
+            pos: dvec2(15.0, 0.0),
+            ..Default::default()
+        });
+        engine
+            .springs
+            .push(SpringConstraint::new(a, b, 10.0, 20.0, 0.5));
+
+        // Record the stretch at each turning point of the oscillation.
+        let stretch =
+            |engine: &Engine| (engine.particles[b].pos - engine.particles[a].pos).length() - 10.0;
+        let mut extremes = vec![stretch(&engine)];
+        let mut previous = stretch(&engine);
+        let mut growing = false;
+        for _ in 0..600 {
+            engine.step(1.0 / 60.0);
+            let current = stretch(&engine);
+            if (current > previous) != growing {
+                growing = !growing;
+                extremes.push(previous);
+            }
+            previous = current;
+        }
+        assert!(extremes.len() > 4, "no oscillation: {extremes:?}");
+        // Alternating sides of the rest length with decaying amplitude.
+        for pair in extremes.windows(2) {
+            assert!(pair[0] * pair[1] < 0.0, "{extremes:?}");
+            assert!(pair[1].abs() < pair[0].abs(), "{extremes:?}");
+        }
+        // Momentum is conserved, the center of mass stays put.
+        let center = 0.5 * (engine.particles[a].pos + engine.particles[b].pos);
+        assert!(center.abs_diff_eq(dvec2(7.5, 0.0), 1e-9));
+    }
+
+    #[test]
+    fn pbd_rope_keeps_its_length() {
+        let mut engine = Rope {}.create();
+        let rest_length: f64 = engine.pbd_constraints.iter().map(|c| c.rest_length).sum();
+        for _ in 0..300 {
+            engine.step(1.0 / 60.0);
+        }
+        let length: f64 = engine
+            .pbd_constraints
+            .iter()
+            .map(|c| (engine.particles[c.id_b].pos - engine.particles[c.id_a].pos).length())
+            .sum();
+        assert!(
+            (length - rest_length).abs() < 0.01 * rest_length,
+            "rope length {length} differs from rest length {rest_length}"
+        );
+        // The rope should have swung down from its horizontal initial position.
+        assert!(engine.particles.last().unwrap().pos.y < 100.0);
+    }
+
+    #[test]
+    fn leaving_bounds_is_reported_on_the_crossing_step() {
+        let mut engine = Engine {
+            bounds: Some((dvec2(-100.0, -100.0), dvec2(100.0, 100.0))),
+            out_of_bounds_action: OutOfBoundsAction::Freeze,
+            ..Default::default()
+        };
+        let mut particle = Particle::new(1.0, 1.0, Shape::Circle { radius: 1.0 });
+        particle.pos = dvec2(90.5, 0.0);
+        particle.vel = dvec2(60.0, 0.0);
+        let id = engine.add_particle(particle);
+
+        let dt = 1.0 / 60.0;
+        for _ in 0..9 {
+            engine.step(dt);
+            assert!(engine.out_of_bounds_events().is_empty());
+        }
+        engine.step(dt);
+        let events = engine.out_of_bounds_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, id);
+        assert!(events[0].pos.x > 100.0);
+        assert!(engine.particles[0].is_static());
+
+        engine.step(dt);
+        assert!(engine.out_of_bounds_events().is_empty());
+    }
+
+    #[test]
+    fn kinematic_bodies_may_leave_bounds() {
+        let mut engine = Engine {
+            bounds: Some((dvec2(-100.0, -100.0), dvec2(100.0, 100.0))),
+            out_of_bounds_action: OutOfBoundsAction::Freeze,
+            ..Default::default()
+        };
+        engine.add_particle(Particle {
+            pos: dvec2(90.5, 0.0),
+            vel: dvec2(60.0, 0.0),
+            body_type: BodyType::Kinematic,
+            ..Particle::circle(1.0, 1.0)
+        });
+
+        for _ in 0..20 {
+            engine.step(1.0 / 60.0);
+            assert!(engine.out_of_bounds_events().is_empty());
+        }
+        let platform = &engine.particles[0];
+        assert!(platform.pos.x > 100.0);
+        assert!(platform.is_kinematic());
+        assert_eq!(platform.vel, dvec2(60.0, 0.0));
+    }
+
+    #[test]
+    fn builder_defaults_and_overrides() {
+        let default = Engine::default();
+        let built = Engine::builder().build().unwrap();
+        assert_eq!(built.gravity, default.gravity);
+        assert_eq!(built.solver, default.solver);
+        assert_eq!(built.restitution_mix, default.restitution_mix);
+        assert_eq!(built.friction_mix, default.friction_mix);
+        assert_eq!(built.bounds, default.bounds);
+        assert_eq!(built.out_of_bounds_action, default.out_of_bounds_action);
+        assert_eq!(built.fixed_dt, default.fixed_dt);
+        assert_eq!(built.max_substeps, default.max_substeps);
+
+        let engine = Engine::builder()
+            .gravity(dvec2(0.0, -9.81))
+            .solver_iterations(4)
+            .restitution_mix(MixRule::Min)
+            .bounds(dvec2(-1.0, -1.0), dvec2(1.0, 1.0))
+            .build()
+            .unwrap();
+        assert_eq!(engine.gravity, dvec2(0.0, -9.81));
+        assert_eq!(engine.solver.iterations, 4);
+        assert_eq!(engine.restitution_mix, MixRule::Min);
+        assert_eq!(engine.bounds, Some((dvec2(-1.0, -1.0), dvec2(1.0, 1.0))));
+
+        assert_eq!(
+            Engine::builder().solver_iterations(0).build().unwrap_err(),
+            EngineConfigError::NoSolverIterations
+        );
+        assert_eq!(
+            Engine::builder()
+                .solver(SolverConfig {
+                    baumgarte_beta: 1.5,
+                    ..Default::default()
+                })
+                .build()
+                .unwrap_err(),
+            EngineConfigError::InvalidBaumgarteBeta(1.5)
+        );
+        assert_eq!(
+            Engine::builder().fixed_dt(0.0).build().unwrap_err(),
+            EngineConfigError::InvalidFixedDt(0.0)
+        );
+        assert_eq!(
+            Engine::builder().max_substeps(0).build().unwrap_err(),
+            EngineConfigError::NoSubsteps
+        );
+    }
+
+    /// Deepest penetration of a circle into the ground of the `Penetration`
+    /// scenario after `steps` steps.
+    fn residual_penetration(solver: SolverConfig, steps: usize) -> f64 {
+        let mut engine = Penetration {}.create();
+        engine.solver = SolverConfig {
+            iterations: engine.solver.iterations,
+            ..solver
+        };
+        for _ in 0..steps {
+            engine.step(1.0 / 60.0);
+        }
+        engine
+            .particles
+            .iter()
+            .filter_map(|p| match p.shape {
+                Shape::Circle { radius } => Some(radius - p.pos.y),
+                _ => None,
+            })
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn higher_baumgarte_beta_resolves_penetration_faster() {
+        let initial = residual_penetration(SolverConfig::default(), 0);
+        let default = residual_penetration(SolverConfig::default(), 30);
+        let stiff = residual_penetration(
+            SolverConfig {
+                baumgarte_beta: 0.2,
+                ..Default::default()
+            },
+            30,
+        );
+        assert!(default < initial, "{default} >= {initial}");
+        assert!(stiff < default, "{stiff} >= {default}");
+    }
+
+    #[test]
+    fn gravity_down_points_towards_negative_y() {
+        let mut engine = Engine::default();
+        engine.set_gravity_down(9.81);
+        assert_eq!(engine.gravity, DVec2::new(0.0, -9.81));
+        let (magnitude, angle) = engine.gravity_polar();
+        assert_eq!(magnitude, 9.81);
+        assert_eq!(angle, -PI / 2.0);
+    }
+
+    #[test]
+    fn polar_gravity_round_trips() {
+        let mut engine = Engine::default();
+        engine.set_gravity_polar(5.0, 0.3);
+        let (magnitude, angle) = engine.gravity_polar();
+        assert!((magnitude - 5.0).abs() < 1e-12);
+        assert!((angle - 0.3).abs() < 1e-12);
+        engine.set_gravity_polar(2.0, PI);
+        assert!(engine.gravity.abs_diff_eq(dvec2(-2.0, 0.0), 1e-12));
+    }
+
+    #[test]
+    fn step_many_matches_single_steps() {
+        let dt = 1.0 / 60.0;
+        let mut stepped = ManyParticles {}.create();
+        let mut expected = vec![];
+        for _ in 0..10 {
+            stepped.step(dt);
+            expected.push(stepped.particles.iter().map(|p| p.pos).collect::<Vec<_>>());
+        }
+
+        let mut recorded = ManyParticles {}.create();
+        assert_eq!(recorded.step_many_recording(dt, 10), expected);
+        let mut batched = ManyParticles {}.create();
+        batched.step_many(dt, 10);
+        for (a, b) in batched.particles.iter().zip(stepped.particles.iter()) {
+            assert_eq!(
+                (a.pos, a.vel, a.angle, a.omega),
+                (b.pos, b.vel, b.angle, b.omega)
+            );
+        }
+    }
+
+    #[test]
+    fn identical_engines_step_identically() {
+        let run = || {
+            let mut engine = ManyParticles {}.create();
+            // Removing particles moves others, so that the order of the ids
+            // no longer matches the order of the particles.
+            let ids: Vec<_> = engine.particles.ids().iter().copied().step_by(7).collect();
+            for id in ids {
+                engine.remove_particle(id);
+            }
+            for _ in 0..300 {
+                engine.step(1.0 / 60.0);
+            }
+            engine
+                .particles
+                .iter()
+                .map(|p| {
+                    (
+                        p.pos.to_array().map(f64::to_bits),
+                        p.vel.to_array().map(f64::to_bits),
+                        p.angle.to_bits(),
+                        p.omega.to_bits(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(), run());
+    }
+
+    fn falling_body(fixed_dt: f64) -> Engine {
+        let mut engine = Engine::builder()
+            .gravity(dvec2(0.0, -10.0))
+            .fixed_dt(fixed_dt)
+            .build()
+            .unwrap();
+        engine.add_particle(Particle {
+            vel: dvec2(1.0, 2.0),
+            ..Default::default()
+        });
+        engine
+    }
+
+    #[test]
+    fn advance_matches_fixed_stepping() {
+        // Powers of two keep the sums exact.
+        let fixed_dt = 0.25;
+        let mut advanced = falling_body(fixed_dt);
+        let deltas = [0.125, 0.5, 0.375, 0.0625, 0.0, 0.75, 0.25];
+        let alphas: Vec<_> = deltas.iter().map(|&dt| advanced.advance(dt)).collect();
+        assert_eq!(alphas, [0.5, 0.5, 0.0, 0.25, 0.25, 0.25, 0.25]);
+
+        // 2.0625 s of real time make 8 steps.
+        let mut stepped = falling_body(fixed_dt);
+        for _ in 0..8 {
+            stepped.step(fixed_dt);
+        }
+        assert_eq!(advanced.particles[0].pos, stepped.particles[0].pos);
+        assert_eq!(advanced.particles[0].vel, stepped.particles[0].vel);
+    }
+
+    #[test]
+    fn advance_limits_substeps() {
+        let mut engine = falling_body(0.25);
+        engine.max_substeps = 2;
+        let alpha = engine.advance(10.1);
+        let mut stepped = falling_body(0.25);
+        stepped.step(0.25);
+        stepped.step(0.25);
+        assert_eq!(engine.particles[0].pos, stepped.particles[0].pos);
+        assert!(alpha < 1.0);
+        // The dropped time is not made up for later.
+        engine.advance(0.0);
+        assert_eq!(engine.particles[0].pos, stepped.particles[0].pos);
     }
 }
@@ -0,0 +1,252 @@
+//! Storage of the simulated bodies with stable handles.
+//!
+//! Particles live in a dense array, so that the engine can iterate over them
+//! (and hand them to the solver) as a plain slice, while [`ParticleId`]s keep
+//! referring to the same particle when others are removed.
+use std::ops::{Deref, DerefMut, Index, IndexMut};
+
+use crate::Particle;
+
+/// A stable handle of a particle in a [`ParticleSet`].
+///
+/// Handles of removed particles are never reused: a slot freed by a removal
+/// gets a new generation, so stale handles are detected instead of silently
+/// referring to a newer particle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParticleId {
+    slot: u32,
+    generation: u32,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Slot {
+    generation: u32,
+    /// Index into the dense arrays, `None` if the slot is free.
+    index: Option<usize>,
+}
+
+/// Particles stored densely and addressed by [`ParticleId`] handles.
+///
+/// Dereferences to a slice of the particles, which can also be indexed by
+/// position. Positions are not stable: removing a particle moves the last
+/// one into its place.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParticleSet {
+    particles: Vec<Particle>,
+    /// Handle of the particle at the same position in `particles`.
+    ids: Vec<ParticleId>,
+    slots: Vec<Slot>,
+    free_slots: Vec<u32>,
+}
+
+impl ParticleSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a particle at the end of the set and returns its handle.
+    pub fn insert(&mut self, particle: Particle) -> ParticleId {
+        let index = Some(self.particles.len());
+        let id = match self.free_slots.pop() {
+            Some(slot) => {
+                let entry = &mut self.slots[slot as usize];
+                entry.index = index;
+                ParticleId {
+                    slot,
+                    generation: entry.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    index,
+                });
+                ParticleId {
+                    slot: (self.slots.len() - 1) as u32,
+                    generation: 0,
+                }
+            }
+        };
+        self.particles.push(particle);
+        self.ids.push(id);
+        id
+    }
+
+    /// Removes the particle, returning it if the handle was valid.
+    pub fn remove(&mut self, id: ParticleId) -> Option<Particle> {
+        let index = self.index_of(id)?;
+        let slot = &mut self.slots[id.slot as usize];
+        slot.index = None;
+        slot.generation += 1;
+        self.free_slots.push(id.slot);
+
+        self.ids.swap_remove(index);
+        if let Some(moved) = self.ids.get(index) {
+            self.slots[moved.slot as usize].index = Some(index);
+        }
+        Some(self.particles.swap_remove(index))
+    }
+
+    /// Current position of the particle in the slice, `None` for stale handles.
+    pub fn index_of(&self, id: ParticleId) -> Option<usize> {
+        self.slots
+            .get(id.slot as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.index)
+    }
+
+    /// Handle of the particle at `index` in the slice.
+    pub fn id_at(&self, index: usize) -> ParticleId {
+        self.ids[index]
+    }
+
+    /// Handles of all particles, in the order of the slice.
+    pub fn ids(&self) -> &[ParticleId] {
+        &self.ids
+    }
+
+    pub fn contains(&self, id: ParticleId) -> bool {
+        self.index_of(id).is_some()
+    }
+
+    pub fn get(&self, id: ParticleId) -> Option<&Particle> {
+        self.index_of(id).map(|index| &self.particles[index])
+    }
+
+    pub fn get_mut(&mut self, id: ParticleId) -> Option<&mut Particle> {
+        self.index_of(id).map(|index| &mut self.particles[index])
+    }
+
+    /// Number of particles the set can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.particles.capacity()
+    }
+
+    /// Position of the particle in the slice, panicking with a helpful message
+    /// for stale handles.
+    pub(crate) fn expect_index(&self, id: ParticleId) -> usize {
+        self.index_of(id)
+            .unwrap_or_else(|| panic!("{id:?} does not refer to a particle in the set"))
+    }
+}
+
+impl Deref for ParticleSet {
+    type Target = [Particle];
+
+    fn deref(&self) -> &[Particle] {
+        &self.particles
+    }
+}
+
+impl DerefMut for ParticleSet {
+    fn deref_mut(&mut self) -> &mut [Particle] {
+        &mut self.particles
+    }
+}
+
+impl Index<usize> for ParticleSet {
+    type Output = Particle;
+
+    fn index(&self, index: usize) -> &Particle {
+        &self.particles[index]
+    }
+}
+
+impl IndexMut<usize> for ParticleSet {
+    fn index_mut(&mut self, index: usize) -> &mut Particle {
+        &mut self.particles[index]
+    }
+}
+
+impl Index<ParticleId> for ParticleSet {
+    type Output = Particle;
+
+    fn index(&self, id: ParticleId) -> &Particle {
+        &self.particles[self.expect_index(id)]
+    }
+}
+
+impl IndexMut<ParticleId> for ParticleSet {
+    fn index_mut(&mut self, id: ParticleId) -> &mut Particle {
+        let index = self.expect_index(id);
+        &mut self.particles[index]
+    }
+}
+
+impl Extend<Particle> for ParticleSet {
+    fn extend<T: IntoIterator<Item = Particle>>(&mut self, iter: T) {
+        for particle in iter {
+            self.insert(particle);
+        }
+    }
+}
+
+impl FromIterator<Particle> for ParticleSet {
+    fn from_iter<T: IntoIterator<Item = Particle>>(iter: T) -> Self {
+        let mut set = ParticleSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl From<Vec<Particle>> for ParticleSet {
+    fn from(particles: Vec<Particle>) -> Self {
+        particles.into_iter().collect()
+    }
+}
+
+impl<'a> IntoIterator for &'a ParticleSet {
+    type Item = &'a Particle;
+    type IntoIter = std::slice::Iter<'a, Particle>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.particles.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut ParticleSet {
+    type Item = &'a mut Particle;
+    type IntoIter = std::slice::IterMut<'a, Particle>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.particles.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::dvec2;
+
+    use super::*;
+
+    fn at(x: f64) -> Particle {
+        Particle {
+            pos: dvec2(x, 0.0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn handles_survive_removal() {
+        let mut set = ParticleSet::new();
+        let ids: Vec<_> = (0..4).map(|i| set.insert(at(i as f64))).collect();
+
+        assert_eq!(set.remove(ids[1]).unwrap().pos.x, 1.0);
+        assert_eq!(set.len(), 3);
+        assert!(!set.contains(ids[1]));
+        assert!(set.remove(ids[1]).is_none());
+        for &i in &[0, 2, 3] {
+            assert_eq!(set[ids[i]].pos.x, i as f64);
+        }
+
+        // The freed slot is reused with a new generation.
+        let new = set.insert(at(4.0));
+        assert_ne!(new, ids[1]);
+        assert!(set.get(ids[1]).is_none());
+        assert_eq!(set[new].pos.x, 4.0);
+        assert_eq!(set.ids().len(), set.len());
+    }
+}
@@ -0,0 +1,27 @@
+//! Commonly used physics types, re-exported so downstream code can get going
+//! with a single `use physics::prelude::*;`.
+//!
+//! The prelude contains the simulation core ([`Engine`], [`EngineBuilder`],
+//! [`Particle`], [`BodyType`], [`ParticleId`], [`ParticleSet`], [`Shape`],
+//! [`MixRule`], [`CollisionEvent`], [`CollisionPhase`], [`OutOfBounds`],
+//! [`OutOfBoundsAction`], [`QueryHit`], [`RayHit`], [`SensorEvent`],
+//! [`SleepConfig`], [`SolverConfig`]),
+//! the constraint types ([`Constraint`], [`ConstraintEnum`],
+//! [`DistanceConstraint`], [`PbdDistance`], [`SpringConstraint`]) and the
+//! [`Scenario`] trait with all bundled scenarios.
+//!
+//! ```
+//! use physics::prelude::*;
+//!
+//! let mut engine = Engine::default();
+//! engine.add_particle(Particle::new(1.0, 1.0, Shape::Circle { radius: 1.0 }));
+//! engine.step(1.0 / 60.0);
+//! ```
+
+pub use crate::{
+    constraint::{Constraint, ConstraintEnum, DistanceConstraint, PbdDistance, SpringConstraint},
+    particles::{ParticleId, ParticleSet},
+    scenarios::*,
+    BodyType, CollisionEvent, CollisionPhase, Engine, EngineBuilder, MixRule, OutOfBounds,
+    OutOfBoundsAction, Particle, QueryHit, RayHit, SensorEvent, Shape, SleepConfig, SolverConfig,
+};
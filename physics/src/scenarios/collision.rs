@@ -28,7 +28,8 @@ impl Scenario for Collision {
                     shape: Shape::Circle { radius: 60.0 },
                     ..Default::default()
                 },
-            ],
+            ]
+            .into(),
             ..Default::default()
         }
     }
@@ -19,6 +19,7 @@ impl Scenario for InclinedFall {
                     pos: dvec2(0.0, 50.0),
                     vel: dvec2(0.0, 0.0),
                     shape: Shape::Circle { radius: 50.0 },
+                    friction: 0.5,
                     ..Default::default()
                 },
                 Particle {
@@ -26,9 +27,11 @@ impl Scenario for InclinedFall {
                     inv_inertia: 0.0,
                     pos: dvec2(0.0, -50.0),
                     shape: Shape::HalfPlane { normal_angle: 1.0 },
+                    friction: 0.5,
                     ..Default::default()
                 },
-            ],
+            ]
+            .into(),
             gravity: GRAVITY,
             ..Default::default()
         }
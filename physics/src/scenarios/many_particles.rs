@@ -3,11 +3,12 @@ use std::f64::consts::PI;
 use glam::{dvec2, DVec2};
 use rand::Rng as _;
 
-use crate::{Engine, Particle, Shape};
+use crate::{rng::DeterministicRng, Engine, OutOfBoundsAction, Particle, Shape, SleepConfig};
 
 use super::Scenario;
 
 const CIRCLE_NUMBER: usize = 100;
+const SEED: u64 = 0;
 const GRAVITY: DVec2 = dvec2(0.0, -9.81);
 
 pub struct ManyParticles {}
@@ -18,12 +19,17 @@ impl Scenario for ManyParticles {
     }
 
     fn create(&self) -> Engine {
+        // Particles occasionally tunnel through the walls, freeze them
+        // instead of simulating their fall forever.
         let mut engine = Engine {
             gravity: GRAVITY,
+            bounds: Some((dvec2(-1000.0, -1000.0), dvec2(1000.0, 1000.0))),
+            out_of_bounds_action: OutOfBoundsAction::Freeze,
+            sleep: Some(SleepConfig::default()),
             ..Default::default()
         };
 
-        let mut rng = rand::thread_rng();
+        let mut rng = DeterministicRng::new(SEED);
         let pos_limit = 500.0;
         let vel_limit = 50.0;
         engine.particles.extend(
@@ -42,7 +48,7 @@ impl Scenario for ManyParticles {
             })
             .take(CIRCLE_NUMBER),
         );
-        engine.particles.push(Particle {
+        engine.add_particle(Particle {
             inv_mass: 0.0,
             inv_inertia: 0.0,
             pos: dvec2(0.0, 500.0),
@@ -51,7 +57,7 @@ impl Scenario for ManyParticles {
             },
             ..Default::default()
         });
-        engine.particles.push(Particle {
+        engine.add_particle(Particle {
             inv_mass: 0.0,
             inv_inertia: 0.0,
             pos: dvec2(0.0, -500.0),
@@ -60,14 +66,14 @@ impl Scenario for ManyParticles {
             },
             ..Default::default()
         });
-        engine.particles.push(Particle {
+        engine.add_particle(Particle {
             inv_mass: 0.0,
             inv_inertia: 0.0,
             pos: dvec2(500.0, 0.0),
             shape: Shape::HalfPlane { normal_angle: -PI },
             ..Default::default()
         });
-        engine.particles.push(Particle {
+        engine.add_particle(Particle {
             inv_mass: 0.0,
             inv_inertia: 0.0,
             pos: dvec2(-500.0, 0.0),
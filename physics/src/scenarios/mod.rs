@@ -6,6 +6,7 @@ pub mod many_particles;
 pub mod pendulum;
 pub mod penetration;
 pub mod resting;
+pub mod rope;
 pub mod simple_fall;
 pub mod springs;
 
@@ -15,6 +16,7 @@ pub use many_particles::*;
 pub use pendulum::*;
 pub use penetration::*;
 pub use resting::*;
+pub use rope::*;
 pub use simple_fall::*;
 pub use springs::*;
 
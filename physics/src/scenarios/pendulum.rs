@@ -42,17 +42,19 @@ impl Scenario for Pendulum {
                 shape: Shape::Circle { radius: 20.0 },
                 ..Default::default()
             },
-        ];
+        ]
+        .into();
 
+        let ids = engine.particles.ids();
         engine.constraints = vec![
             ConstraintEnum::Distance(DistanceConstraint {
-                id_a: 0,
-                id_b: 1,
+                id_a: ids[0],
+                id_b: ids[1],
                 distance: 100.0,
             }),
             ConstraintEnum::Distance(DistanceConstraint {
-                id_a: 1,
-                id_b: 2,
+                id_a: ids[1],
+                id_b: ids[2],
                 distance: 100.0,
             }),
         ];
@@ -27,7 +27,7 @@ impl Scenario for Penetration {
             gravity: GRAVITY,
             ..Default::default()
         };
-        engine.solver_iterations = 2;
+        engine.solver.iterations = 2;
         engine.particles = vec![
             make_circle(dvec2(-200.0, -100.0)),
             make_circle(dvec2(0.0, -50.0)),
@@ -41,7 +41,8 @@ impl Scenario for Penetration {
                 },
                 ..Default::default()
             },
-        ];
+        ]
+        .into();
         engine
     }
 }
@@ -27,7 +27,7 @@ impl Scenario for Resting {
             gravity: GRAVITY,
             ..Default::default()
         };
-        engine.solver_iterations = 2;
+        engine.solver.iterations = 2;
         engine.particles = vec![
             make_circle(dvec2(-200.0, 0.0)),
             make_circle(dvec2(0.0, 0.0)),
@@ -44,7 +44,8 @@ impl Scenario for Resting {
                 },
                 ..Default::default()
             },
-        ];
+        ]
+        .into();
         engine
     }
 }
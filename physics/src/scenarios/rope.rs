@@ -0,0 +1,42 @@
+use crate::{constraint::PbdDistance, Engine, Particle, Shape};
+use glam::{dvec2, DVec2};
+
+use super::Scenario;
+
+const GRAVITY: DVec2 = dvec2(0.0, -1000.0);
+const SEGMENT_COUNT: usize = 10;
+const SEGMENT_LENGTH: f64 = 30.0;
+
+pub struct Rope {}
+
+impl Scenario for Rope {
+    fn name(&self) -> &str {
+        "Rope"
+    }
+
+    fn create(&self) -> Engine {
+        let mut engine = Engine {
+            gravity: GRAVITY,
+            ..Default::default()
+        };
+        let mut previous = engine.add_particle(Particle {
+            inv_mass: 0.0,
+            inv_inertia: 0.0,
+            pos: dvec2(0.0, 200.0),
+            shape: Shape::Circle { radius: 5.0 },
+            ..Default::default()
+        });
+        for i in 1..=SEGMENT_COUNT {
+            let segment = engine.add_particle(Particle {
+                pos: dvec2(i as f64 * SEGMENT_LENGTH, 200.0),
+                shape: Shape::Circle { radius: 5.0 },
+                ..Default::default()
+            });
+            engine
+                .pbd_constraints
+                .push(PbdDistance::new(previous, segment, SEGMENT_LENGTH));
+            previous = segment;
+        }
+        engine
+    }
+}
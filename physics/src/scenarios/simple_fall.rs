@@ -20,7 +20,8 @@ impl Scenario for SimpleFall {
                 pos: dvec2(0.0 - half_width, 0.0),
                 shape: Shape::Circle { radius: 40.0 },
                 ..Default::default()
-            }],
+            }]
+            .into(),
             gravity: GRAVITY,
             ..Default::default()
         }
@@ -27,7 +27,8 @@ impl Scenario for Springs {
                     shape: Shape::Circle { radius: 50.0 },
                     ..Default::default()
                 },
-            ],
+            ]
+            .into(),
             ..Default::default()
         }
     }
@@ -1,8 +1,14 @@
+use std::collections::HashMap;
+
 use glam::{dvec3, DMat3, DVec3};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use tracing::{instrument, trace, trace_span, warn};
 
 use crate::{
     constraint::{Constraint, ConstraintEnum},
+    island::{find_islands, Island},
+    particles::ParticleSet,
     Particle,
 };
 
@@ -14,37 +20,106 @@ pub trait Solver {
 // so we precompute them at the beginning and store them.
 #[derive(Clone, Debug)]
 pub struct ConstraintData<'a> {
+    /// Positions of the constrained particles in the particle slice.
+    ids: (usize, usize),
     jacobian: (DVec3, DVec3),
     target_velocity: f64,
     total_impulse: f64,
+    friction: Option<FrictionData>,
     constraint: &'a ConstraintEnum,
 }
 
+/// Coulomb friction along the contact tangent of a collision constraint.
+/// The accumulated impulse is kept within `coefficient` times the accumulated
+/// normal impulse of the collision.
+#[derive(Clone, Debug)]
+struct FrictionData {
+    jacobian: (DVec3, DVec3),
+    coefficient: f64,
+    total_impulse: f64,
+}
+
 impl<'a> ConstraintData<'a> {
     pub fn from_constraint(
         c: &'a ConstraintEnum,
-        particles: &[Particle],
+        particles: &ParticleSet,
         dt: f64,
     ) -> ConstraintData<'a> {
         let (id_a, id_b) = c.get_ids();
-        let a = &particles[id_a];
-        let b = &particles[id_b];
+        let ids = (particles.expect_index(id_a), particles.expect_index(id_b));
+        let a = &particles[ids.0];
+        let b = &particles[ids.1];
+        let friction = match c {
+            ConstraintEnum::Collision(collision) if collision.friction > 0.0 => {
+                Some(FrictionData {
+                    jacobian: collision.tangent_jacobian(a, b),
+                    coefficient: collision.friction,
+                    total_impulse: 0.0,
+                })
+            }
+            _ => None,
+        };
         ConstraintData {
+            ids,
             jacobian: c.jacobian(a, b),
             target_velocity: c.target_velocity(a, b, dt),
             constraint: c,
             total_impulse: 0.0,
+            friction,
         }
     }
 
-    fn relative_velocity(&self, a: &Particle, b: &Particle) -> f64 {
-        let (j1, j2) = self.jacobian;
-        let v1 = dvec3(a.vel.x, a.vel.y, a.omega);
-        let v2 = dvec3(b.vel.x, b.vel.y, b.omega);
-        let v_rel = j1.dot(v1) + j2.dot(v2);
-        trace!("Velocity 1: {v1}, velocity 2: {v2}, relative velocity: {v_rel}");
-        v_rel
+    /// Starts the solver from the normal and friction impulses accumulated in the
+    /// previous frame, which converges faster for persistent contacts.
+    /// Only inequality constraints are warm started, for others this has no effect.
+    pub fn with_warm_start(mut self, total_impulse: f64, friction_impulse: f64) -> Self {
+        if !self.constraint.is_equality() {
+            self.total_impulse = total_impulse.max(0.0);
+            if let Some(friction) = &mut self.friction {
+                let limit = friction.coefficient * self.total_impulse;
+                friction.total_impulse = friction_impulse.clamp(-limit, limit);
+            }
+        }
+        self
     }
+
+    /// Impulse accumulated over all solver iterations.
+    pub fn total_impulse(&self) -> f64 {
+        self.total_impulse
+    }
+
+    /// Friction impulse accumulated over all solver iterations,
+    /// zero for constraints without friction.
+    pub fn friction_impulse(&self) -> f64 {
+        self.friction.as_ref().map_or(0.0, |f| f.total_impulse)
+    }
+}
+
+fn relative_velocity(jacobian: (DVec3, DVec3), a: &Particle, b: &Particle) -> f64 {
+    let (j1, j2) = jacobian;
+    let v1 = dvec3(a.vel.x, a.vel.y, a.omega);
+    let v2 = dvec3(b.vel.x, b.vel.y, b.omega);
+    let v_rel = j1.dot(v1) + j2.dot(v2);
+    trace!("Velocity 1: {v1}, velocity 2: {v2}, relative velocity: {v_rel}");
+    v_rel
+}
+
+/// Inverse of the effective mass `J * M^-1 * J^T` along `jacobian`.
+fn inverse_effective_mass(jacobian: (DVec3, DVec3), a: &Particle, b: &Particle) -> f64 {
+    // TODO: matrices should be precomputed
+    // see https://github.com/orbital-simulations/experimental/issues/52
+    let m1_inv = DMat3::from_diagonal(dvec3(
+        a.effective_inv_mass(),
+        a.effective_inv_mass(),
+        a.effective_inv_inertia(),
+    ));
+    let m2_inv = DMat3::from_diagonal(dvec3(
+        b.effective_inv_mass(),
+        b.effective_inv_mass(),
+        b.effective_inv_inertia(),
+    ));
+    let (j1, j2) = jacobian;
+    j1.dot(m1_inv * j1) + j2.dot(m2_inv * j2)
 }
 
 #[derive(Clone, Debug)]
@@ -57,34 +132,54 @@ pub struct SequentialImpulseSolver {
 // see https://github.com/orbital-simulations/experimental/issues/50
 impl SequentialImpulseSolver {
     fn find_impulse(&self, a: &Particle, b: &Particle, c: &mut ConstraintData<'_>) -> f64 {
-        // TODO: matrices should be precomputed
-        // see https://github.com/orbital-simulations/experimental/issues/52
-        let m1_inv = DMat3::from_diagonal(dvec3(a.inv_mass, a.inv_mass, a.inv_inertia));
-        let m2_inv = DMat3::from_diagonal(dvec3(b.inv_mass, b.inv_mass, b.inv_inertia));
-        let v_rel = c.relative_velocity(a, b);
+        let v_rel = relative_velocity(c.jacobian, a, b);
         let v_target = c.target_velocity;
-        let (j1, j2) = c.jacobian;
-        let new_lambda = (v_target - v_rel) / (j1.dot(m1_inv * j1) + j2.dot(m2_inv * j2));
+        let new_lambda = (v_target - v_rel) / inverse_effective_mass(c.jacobian, a, b);
         let lambda = if c.constraint.is_equality() {
             new_lambda
         }
         // For inequality constraints the total impulse applied should be positive.
         else {
-            let new_total = (c.total_impulse + new_lambda).max(0.0);
-            let lambda = new_total - c.total_impulse;
-            c.total_impulse += lambda;
-            lambda
+            (c.total_impulse + new_lambda).max(0.0) - c.total_impulse
         };
+        c.total_impulse += lambda;
         trace!("Impulse magnitude: {lambda}");
         lambda
     }
 
-    fn apply(&self, a: &mut Particle, b: &mut Particle, c: &ConstraintData<'_>, impulse: f64) {
+    /// Impulse along the contact tangent that stops the bodies from sliding,
+    /// limited by the Coulomb friction cone `|friction| <= mu * |normal|`.
+    fn find_friction_impulse(
+        &self,
+        a: &Particle,
+        b: &Particle,
+        normal_impulse: f64,
+        friction: &mut FrictionData,
+    ) -> f64 {
+        let v_rel = relative_velocity(friction.jacobian, a, b);
+        let new_lambda = -v_rel / inverse_effective_mass(friction.jacobian, a, b);
+        let limit = friction.coefficient * normal_impulse.abs();
+        let lambda =
+            (friction.total_impulse + new_lambda).clamp(-limit, limit) - friction.total_impulse;
+        friction.total_impulse += lambda;
+        trace!("Friction impulse magnitude: {lambda}");
+        lambda
+    }
+
+    fn apply(&self, a: &mut Particle, b: &mut Particle, jacobian: (DVec3, DVec3), impulse: f64) {
         // TODO: matrices should be precomputed
         // see https://github.com/orbital-simulations/experimental/issues/52
-        let m1_inv = DMat3::from_diagonal(dvec3(a.inv_mass, a.inv_mass, a.inv_inertia));
-        let m2_inv = DMat3::from_diagonal(dvec3(b.inv_mass, b.inv_mass, b.inv_inertia));
-        let (j1, j2) = c.jacobian;
+        let m1_inv = DMat3::from_diagonal(dvec3(
+            a.effective_inv_mass(),
+            a.effective_inv_mass(),
+            a.effective_inv_inertia(),
+        ));
+        let m2_inv = DMat3::from_diagonal(dvec3(
+            b.effective_inv_mass(),
+            b.effective_inv_mass(),
+            b.effective_inv_inertia(),
+        ));
+        let (j1, j2) = jacobian;
         let delta1 = m1_inv * j1 * impulse;
         let delta2 = m2_inv * j2 * impulse;
         trace!("Velocity delta 1: {delta1}, delta 2: {delta2}");
@@ -99,35 +194,135 @@ impl SequentialImpulseSolver {
     }
 }
 
-fn get_pair_mut<T>(v: &mut [T], index1: usize, index2: usize) -> (&mut T, &mut T) {
-    assert_ne!(
-        index1, index2,
-        "Cannot get two mutable references to the same index"
-    );
-    let first = index1.min(index2);
-    let second = index1.max(index2);
-    let (a, b) = v.split_at_mut(second);
-    (&mut a[first], &mut b[0])
-}
-
-impl Solver for SequentialImpulseSolver {
+impl SequentialImpulseSolver {
+    /// Splits the constraints into islands (see [`find_islands`]) and solves each
+    /// of them separately. Islands share no dynamic bodies, so the result is the
+    /// same as solving everything at once with [`Solver::solve`]. With the
+    /// `parallel` feature the islands are solved concurrently on rayon's thread pool.
+    /// The accumulated impulses end up in `constraints`.
     #[instrument(level = "trace", skip_all)]
-    fn solve(&self, particles: &mut [Particle], constraints: &mut [ConstraintData]) {
+    pub fn solve_islands(&self, particles: &mut [Particle], constraints: &mut [ConstraintData]) {
+        let pairs: Vec<_> = constraints.iter().map(|c| c.ids).collect();
+        let islands = find_islands(particles, &pairs);
+        let shared: &[Particle] = particles;
+        let shared_constraints: &[ConstraintData] = constraints;
+        let solve_island = |island: &Island| self.solve_island(shared, shared_constraints, island);
+        #[cfg(feature = "parallel")]
+        let solved: Vec<_> = islands.par_iter().map(solve_island).collect();
+        #[cfg(not(feature = "parallel"))]
+        let solved: Vec<_> = islands.iter().map(solve_island).collect();
+
+        for (island, (bodies, island_constraints)) in islands.iter().zip(solved) {
+            for (&id, body) in island.bodies.iter().zip(bodies) {
+                particles[id] = body;
+            }
+            for (&c, solved) in island.constraints.iter().zip(island_constraints) {
+                constraints[c] = solved;
+            }
+        }
+    }
+
+    /// Solves a single island on local copies of its particles and returns
+    /// the updated dynamic bodies in the order of `island.bodies`, together
+    /// with the solved constraints in the order of `island.constraints`.
+    fn solve_island<'a>(
+        &self,
+        particles: &[Particle],
+        constraints: &[ConstraintData<'a>],
+        island: &Island,
+    ) -> (Vec<Particle>, Vec<ConstraintData<'a>>) {
+        let mut local_index = HashMap::new();
+        let mut local_particles = vec![];
+        // Dynamic bodies go first, static ones referenced by the constraints after them.
+        let referenced = island.constraints.iter().flat_map(|&c| {
+            let (id_a, id_b) = constraints[c].ids;
+            [id_a, id_b]
+        });
+        for id in island.bodies.iter().copied().chain(referenced) {
+            local_index.entry(id).or_insert_with(|| {
+                local_particles.push(particles[id].clone());
+                local_particles.len() - 1
+            });
+        }
+        let mut local_constraints: Vec<_> = island
+            .constraints
+            .iter()
+            .map(|&c| constraints[c].clone())
+            .collect();
+
+        self.solve_mapped(&mut local_particles, &mut local_constraints, |id| {
+            local_index[&id]
+        });
+        local_particles.truncate(island.bodies.len());
+        (local_particles, local_constraints)
+    }
+
+    /// Sequential impulse iterations where the constraints' particle positions
+    /// are translated to indices into `particles` by `index`.
+    fn solve_mapped(
+        &self,
+        particles: &mut [Particle],
+        constraints: &mut [ConstraintData],
+        index: impl Fn(usize) -> usize,
+    ) {
+        // Warm start: apply the impulses remembered from the previous frame.
+        for c in &*constraints {
+            let (id_a, id_b) = c.ids;
+            if id_a == id_b {
+                continue;
+            }
+            let (a, b) = get_pair_mut(particles, index(id_a), index(id_b));
+            if c.total_impulse != 0.0 {
+                self.apply(a, b, c.jacobian, c.total_impulse);
+            }
+            if let Some(friction) = c.friction.as_ref().filter(|f| f.total_impulse != 0.0) {
+                self.apply(a, b, friction.jacobian, friction.total_impulse);
+            }
+        }
+
         for iter in 0..(self.iterations) {
             let span = trace_span!("Iteration", iter);
             let _enter = span.enter();
             for c in &mut *constraints {
-                let (id_a, id_b) = c.constraint.get_ids();
+                let (id_a, id_b) = c.ids;
                 if id_a == id_b {
                     warn!("Constraint uses identical indices: {:?}", c);
                     continue;
                 }
+                let (id_a, id_b) = (index(id_a), index(id_b));
                 let a = &particles[id_a];
                 let b = &particles[id_b];
                 let impulse = self.find_impulse(a, b, c);
                 let (a, b) = get_pair_mut(particles, id_a, id_b);
-                self.apply(a, b, c, impulse);
+                self.apply(a, b, c.jacobian, impulse);
+                if let Some(friction) = &mut c.friction {
+                    let impulse = self.find_friction_impulse(a, b, c.total_impulse, friction);
+                    self.apply(a, b, friction.jacobian, impulse);
+                }
             }
         }
     }
 }
+
+pub(crate) fn get_pair_mut<T>(v: &mut [T], index1: usize, index2: usize) -> (&mut T, &mut T) {
+    assert_ne!(
+        index1, index2,
+        "Cannot get two mutable references to the same index"
+    );
+    let first = index1.min(index2);
+    let second = index1.max(index2);
+    let (a, b) = v.split_at_mut(second);
+    // Return the references in the order the indices were requested.
+    if index1 < index2 {
+        (&mut a[first], &mut b[0])
+    } else {
+        (&mut b[0], &mut a[first])
+    }
+}
+
+impl Solver for SequentialImpulseSolver {
+    #[instrument(level = "trace", skip_all)]
+    fn solve(&self, particles: &mut [Particle], constraints: &mut [ConstraintData]) {
+        self.solve_mapped(particles, constraints, |id| id);
+    }
+}
@@ -1,14 +1,7 @@
 use std::f32::consts::PI;
 
 use glam::{vec3, Vec2, Vec3};
-use renderer::{
-    circle_rendering::Circle,
-    colors::{GREEN, PINK, RED, YELLOW},
-    line_rendering::Line,
-    rectangle_rendering::RectangleLine,
-    scene_node::SceneNode,
-    transform::Transform,
-};
+use renderer::prelude::*;
 
 mod shared;
 
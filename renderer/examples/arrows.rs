@@ -0,0 +1,23 @@
+use glam::{vec3, Vec3};
+use renderer::prelude::*;
+
+mod shared;
+
+fn main() -> color_eyre::eyre::Result<()> {
+    pollster::block_on(shared::run(|renderer| {
+        renderer.draw_arrow(Vec3::ZERO, vec3(200.0, 0.0, 0.0), RED, 4.0, 30.0);
+        renderer.draw_arrow(Vec3::ZERO, vec3(0.0, 200.0, 0.0), GREEN, 4.0, 30.0);
+        renderer.draw_arrow(Vec3::ZERO, vec3(-150.0, -150.0, 0.0), BLUE, 4.0, 30.0);
+        // Short arrows shrink their head so it fits.
+        renderer.draw_arrow(
+            vec3(-200.0, 100.0, 0.0),
+            vec3(-180.0, 120.0, 0.0),
+            YELLOW,
+            2.0,
+            30.0,
+        );
+        // Zero-length arrows are skipped.
+        renderer.draw_arrow(Vec3::ZERO, Vec3::ZERO, PINK, 4.0, 30.0);
+    }))?;
+    Ok(())
+}
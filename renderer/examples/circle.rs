@@ -1,5 +1,5 @@
 use glam::vec3;
-use renderer::{circle_rendering::Circle, colors::GREEN, transform::Transform};
+use renderer::prelude::*;
 
 mod shared;
 
@@ -0,0 +1,50 @@
+use color_eyre::eyre::{ensure, OptionExt};
+use glam::{uvec2, vec3};
+use image::{imageops, ImageBuffer, Rgba};
+use renderer::prelude::*;
+
+mod shared;
+
+/// The circles are rendered this small and scaled up for the saved image, so
+/// that the individual edge pixels can be seen.
+const SIZE: u32 = 48;
+const ZOOM: u32 = 8;
+
+fn main() -> color_eyre::eyre::Result<()> {
+    color_eyre::install()?;
+    let gpu_context = pollster::block_on(shared::gpu_context())?;
+    let (mut renderer, target) = Renderer::new_headless(
+        &gpu_context,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+        uvec2(SIZE, SIZE),
+    )?;
+    renderer.set_clear_color(wgpu::Color::WHITE);
+    for (x, radius) in [(-15.0, 3.0), (-3.0, 5.5), (13.5, 8.25)] {
+        renderer.draw_circle(
+            &Transform::from_translation(&vec3(x, 8.0, 0.0)),
+            &Circle::new(radius, BLACK),
+        );
+    }
+    renderer.draw_circle_line(
+        &Transform::from_translation(&vec3(0.0, -10.0, 0.0)),
+        &CircleLine::new(10.0, BLUE, 1.5),
+    );
+    renderer.render(target.texture())?;
+
+    let pixels = target.read_pixels(&gpu_context);
+    // Pixels the circles only partly cover are neither white nor fully
+    // black or blue.
+    let blended = pixels
+        .chunks(4)
+        .filter(|pixel| pixel[0] != 255 && pixel[0] != 0)
+        .count();
+    println!("{blended} edge pixels are blended");
+    ensure!(blended > 0, "the circle edges aren't antialiased");
+
+    let image = ImageBuffer::<Rgba<u8>, _>::from_raw(SIZE, SIZE, pixels)
+        .ok_or_eyre("Could not create an image buffer")?;
+    let image = imageops::resize(&image, SIZE * ZOOM, SIZE * ZOOM, imageops::Nearest);
+    println!("Saving rendered image to circle_antialiasing.png");
+    image.save("circle_antialiasing.png")?;
+    Ok(())
+}
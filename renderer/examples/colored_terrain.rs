@@ -0,0 +1,76 @@
+use glam::{vec3, vec4, Mat4, Vec3, Vec4};
+use renderer::prelude::*;
+
+mod shared;
+
+const GRID_SIZE: u32 = 80;
+const CELL_SIZE: f32 = 0.25;
+
+fn height(x: f32, y: f32) -> f32 {
+    (x * 0.6).sin() * (y * 0.4).cos() * 1.5 + (x * 0.25 + y * 0.35).sin()
+}
+
+/// Blends from deep water blue over grass green to snowy white with height.
+fn height_color(z: f32) -> Vec4 {
+    let stops = [
+        (-2.5, vec4(0.05, 0.15, 0.6, 1.0)),
+        (-0.5, vec4(0.85, 0.8, 0.5, 1.0)),
+        (0.0, vec4(0.2, 0.6, 0.2, 1.0)),
+        (1.5, vec4(0.45, 0.35, 0.25, 1.0)),
+        (2.5, vec4(1.0, 1.0, 1.0, 1.0)),
+    ];
+    for window in stops.windows(2) {
+        let ((low, low_color), (high, high_color)) = (window[0], window[1]);
+        if z <= high {
+            return low_color.lerp(high_color, ((z - low) / (high - low)).clamp(0.0, 1.0));
+        }
+    }
+    stops[stops.len() - 1].1
+}
+
+fn main() -> color_eyre::eyre::Result<()> {
+    pollster::block_on(shared::run(|renderer| {
+        let half = GRID_SIZE as f32 * CELL_SIZE / 2.0;
+        let mut vertices = Vec::new();
+        let mut normals = Vec::new();
+        for row in 0..=GRID_SIZE {
+            for column in 0..=GRID_SIZE {
+                let x = column as f32 * CELL_SIZE - half;
+                let y = row as f32 * CELL_SIZE - half;
+                vertices.push(vec3(x, y, height(x, y)));
+                let dx = height(x + 0.01, y) - height(x - 0.01, y);
+                let dy = height(x, y + 0.01) - height(x, y - 0.01);
+                normals.push(vec3(-dx, -dy, 0.02).normalize());
+            }
+        }
+        let colors: Vec<Vec4> = vertices.iter().map(|v| height_color(v.z)).collect();
+        let mut indices = Vec::new();
+        for row in 0..GRID_SIZE {
+            for column in 0..GRID_SIZE {
+                let i = row * (GRID_SIZE + 1) + column;
+                let above = i + GRID_SIZE + 1;
+                indices.extend([i, i + 1, above, i + 1, above + 1, above]);
+            }
+        }
+
+        renderer.set_primary_camera_projection(&CameraProjection::Perspective(Perspective {
+            fovy: 45f32.to_radians(),
+            znear: 0.1,
+            zfar: 100.0,
+            scale: 1.0,
+        }));
+        renderer.set_primary_camera_matrix(&Mat4::look_at_rh(
+            vec3(0.0, -14.0, 12.0),
+            Vec3::ZERO,
+            Vec3::Z,
+        ));
+        let mesh_bundle = MeshBundle {
+            mesh_id: renderer.add_mesh_colored(&vertices, &normals, &colors, &indices),
+            pipeline_id: renderer.create_3d_pipeline_vertex_colored().unwrap(),
+            texture_id: None,
+            transparent: false,
+        };
+        renderer.draw_mesh(&Transform::IDENTITY, &mesh_bundle);
+    }))?;
+    Ok(())
+}
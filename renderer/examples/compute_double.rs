@@ -0,0 +1,53 @@
+use renderer::{include_wgsl, prelude::*};
+use wgpu::util::DeviceExt;
+
+mod shared;
+
+const WORKGROUP_SIZE: u32 = 64;
+
+fn main() -> color_eyre::eyre::Result<()> {
+    color_eyre::install()?;
+    let gpu_context = pollster::block_on(shared::gpu_context())?;
+    let mut renderer = Renderer::new(
+        &gpu_context,
+        shared::primary_camera(wgpu::TextureFormat::Rgba8UnormSrgb),
+    )?;
+
+    let values: Vec<f32> = (0..100).map(|i| i as f32).collect();
+    let size = std::mem::size_of_val(values.as_slice()) as wgpu::BufferAddress;
+    let device = gpu_context.device();
+    let storage_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("values"),
+        contents: bytemuck::cast_slice(&values),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+    });
+    let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("values readback"),
+        size,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let pipeline_id = renderer.create_compute_pipeline(&include_wgsl!("shaders/double.wgsl"))?;
+    let workgroups = (values.len() as u32).div_ceil(WORKGROUP_SIZE);
+    renderer.dispatch_compute(
+        pipeline_id,
+        &[&storage_buffer],
+        glam::uvec3(workgroups, 1, 1),
+    );
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("readback encoder"),
+    });
+    encoder.copy_buffer_to_buffer(&storage_buffer, 0, &readback_buffer, 0, size);
+    gpu_context.queue().submit(Some(encoder.finish()));
+    let buffer_slice = readback_buffer.slice(..);
+    buffer_slice.map_async(wgpu::MapMode::Read, |result| {
+        result.expect("GPU didn't copy the values to the readback buffer");
+    });
+    device.poll(wgpu::Maintain::Wait);
+    let doubled: Vec<f32> = bytemuck::cast_slice(&buffer_slice.get_mapped_range()).to_vec();
+
+    println!("{values:?}\ndoubled on the GPU:\n{doubled:?}");
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use glam::vec3;
+use renderer::prelude::*;
+
+mod shared;
+
+fn main() -> color_eyre::eyre::Result<()> {
+    pollster::block_on(shared::run(|renderer| {
+        let line =
+            |y: f32, color| Line::new(vec3(-250.0, y, 0.0), vec3(250.0, y + 60.0, 0.0), color, 6.0);
+        renderer.draw_line(&Transform::IDENTITY, &line(120.0, GREEN));
+        renderer.draw_line(
+            &Transform::IDENTITY,
+            &line(0.0, YELLOW).with_dashes(30.0, 15.0),
+        );
+        // Dots are dashes as long as the line is wide.
+        renderer.draw_line(
+            &Transform::IDENTITY,
+            &line(-120.0, SKYBLUE).with_dashes(6.0, 10.0),
+        );
+    }))?;
+    Ok(())
+}
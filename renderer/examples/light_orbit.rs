@@ -0,0 +1,46 @@
+use std::f32::consts::TAU;
+
+use glam::{vec3, Mat4, Quat, Vec3};
+use renderer::prelude::*;
+
+mod shared;
+
+const ANGLES: [&str; 4] = ["0", "90", "180", "270"];
+
+fn main() -> color_eyre::eyre::Result<()> {
+    // Saves one image per light angle, the light circles the cube around the
+    // z axis, slightly from above.
+    pollster::block_on(shared::run_variants(&ANGLES, |variant, renderer| {
+        let angle = variant.parse::<f32>().unwrap().to_radians();
+        renderer.set_primary_camera_projection(&CameraProjection::Perspective(Perspective {
+            fovy: 45f32.to_radians(),
+            znear: 0.1,
+            zfar: 100.0,
+            scale: 1.0,
+        }));
+        renderer.set_primary_camera_matrix(&Mat4::look_at_rh(
+            vec3(4.0, -5.0, 4.0),
+            Vec3::ZERO,
+            Vec3::Z,
+        ));
+        renderer.set_directional_light(&DirectionalLight {
+            direction: Quat::from_rotation_z(angle) * vec3(-1.0, 0.0, -0.5),
+            color: vec3(1.0, 0.95, 0.8),
+            ambient: Vec3::splat(0.1),
+        });
+
+        let (vertices, normals, _, indices) = shared::cube();
+        let colors = vec![ORANGE; vertices.len()];
+        let mesh_bundle = MeshBundle {
+            mesh_id: renderer.add_mesh_colored(&vertices, &normals, &colors, &indices),
+            pipeline_id: renderer.create_3d_pipeline_vertex_colored().unwrap(),
+            texture_id: None,
+            transparent: false,
+        };
+        renderer.draw_mesh(
+            &Transform::from_rotation(&Quat::from_rotation_z(TAU / 16.0)),
+            &mesh_bundle,
+        );
+    }))?;
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use glam::{vec2, vec3, Quat};
+use renderer::prelude::*;
+
+mod shared;
+
+fn main() -> color_eyre::eyre::Result<()> {
+    // Saves msaa_1x.png and msaa_4x.png, compare the edges of the shapes.
+    pollster::block_on(shared::run_variants(&["1x", "4x"], |variant, renderer| {
+        let sample_count = match variant {
+            "4x" => 4,
+            _ => 1,
+        };
+        renderer.set_sample_count(sample_count).unwrap();
+        renderer.draw_circle(
+            &Transform::from_translation(&vec3(-120.0, 0.0, 0.0)),
+            &Circle::new(100., RED),
+        );
+        renderer.draw_rectangle(
+            &Transform::from_translation_rotation(
+                &vec3(120.0, 0.0, 0.0),
+                &Quat::from_rotation_z(0.3),
+            ),
+            &Rectangle::new(vec2(160.0, 160.0), BLUE),
+        );
+        renderer.draw_line(
+            &Transform::IDENTITY,
+            &Line::new(
+                vec3(-250.0, -200.0, 0.0),
+                vec3(250.0, -150.0, 0.0),
+                WHITE,
+                3.0,
+            ),
+        );
+    }))?;
+    Ok(())
+}
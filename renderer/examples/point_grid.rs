@@ -0,0 +1,22 @@
+use glam::vec3;
+use renderer::prelude::*;
+
+mod shared;
+
+fn main() -> color_eyre::eyre::Result<()> {
+    pollster::block_on(shared::run(|renderer| {
+        // Points grow from the bottom left to the top right corner and fade
+        // from red to blue.
+        for x in -10..=10 {
+            for y in -10..=10 {
+                let t = (x + y + 20) as f32 / 40.0;
+                renderer.draw_point(
+                    vec3(x as f32 * 25.0, y as f32 * 25.0, 0.0),
+                    2.0 + 10.0 * t,
+                    RED.lerp(BLUE, t),
+                );
+            }
+        }
+    }))?;
+    Ok(())
+}
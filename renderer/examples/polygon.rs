@@ -0,0 +1,27 @@
+use std::f32::consts::TAU;
+
+use glam::{vec3, Vec2};
+use renderer::prelude::*;
+
+mod shared;
+
+/// Corners of a regular polygon with the given number of sides.
+fn regular_polygon(sides: u32, radius: f32) -> Vec<Vec2> {
+    (0..sides)
+        .map(|i| Vec2::from_angle(TAU * i as f32 / sides as f32) * radius)
+        .collect()
+}
+
+fn main() -> color_eyre::eyre::Result<()> {
+    pollster::block_on(shared::run(|renderer| {
+        renderer.draw_polygon(
+            &Transform::from_translation(&vec3(-130.0, 0.0, 0.0)),
+            &Polygon::new(&regular_polygon(5, 110.0), GREEN),
+        );
+        renderer.draw_polygon(
+            &Transform::from_translation(&vec3(130.0, 0.0, 0.0)),
+            &Polygon::new(&regular_polygon(6, 110.0), BLUE),
+        );
+    }))?;
+    Ok(())
+}
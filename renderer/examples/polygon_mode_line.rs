@@ -0,0 +1,28 @@
+use renderer::prelude::*;
+
+mod shared;
+
+/// Requests a device with `POLYGON_MODE_LINE` and reports whether the adapter
+/// provided it, the same way a game asks for wireframes up front.
+fn main() -> color_eyre::eyre::Result<()> {
+    color_eyre::install()?;
+    let requested = pollster::block_on(shared::gpu_context_with(
+        wgpu::Features::POLYGON_MODE_LINE,
+        Renderer::wgpu_limits(),
+    ));
+    let gpu_context = match requested {
+        Ok(gpu_context) => gpu_context,
+        Err(error) => {
+            println!("POLYGON_MODE_LINE is not available: {error}");
+            return Ok(());
+        }
+    };
+
+    let mut renderer = Renderer::new(
+        &gpu_context,
+        shared::primary_camera(wgpu::TextureFormat::Rgba8UnormSrgb),
+    )?;
+    renderer.set_wireframe(true)?;
+    println!("POLYGON_MODE_LINE is available, meshes can be drawn as wireframes");
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use glam::{vec3, vec4, Mat4, Quat, Vec3};
+use renderer::{include_wgsl, prelude::*};
+
+mod shared;
+
+fn main() -> color_eyre::eyre::Result<()> {
+    // The tint lives in push constants, which have to be requested from the
+    // device together with room for them.
+    let required_limits = wgpu::Limits {
+        max_push_constant_size: 16,
+        ..Renderer::wgpu_limits()
+    };
+    pollster::block_on(shared::run_with(
+        wgpu::Features::PUSH_CONSTANTS,
+        required_limits,
+        |renderer| {
+            renderer.set_primary_camera_projection(&CameraProjection::Perspective(Perspective {
+                fovy: 45f32.to_radians(),
+                znear: 0.1,
+                zfar: 100.0,
+                scale: 1.0,
+            }));
+            renderer.set_primary_camera_matrix(&Mat4::look_at_rh(
+                vec3(0.0, -8.0, 6.0),
+                Vec3::ZERO,
+                Vec3::Z,
+            ));
+            renderer.set_directional_light(&DirectionalLight {
+                direction: vec3(-0.3, 0.6, -1.0).normalize(),
+                color: Vec3::splat(0.8),
+                ambient: Vec3::splat(0.2),
+            });
+
+            let pipeline_id = renderer
+                .create_3d_pipeline_with_push_constants(
+                    &include_wgsl!("shaders/tinted_mesh.wgsl"),
+                    &[wgpu::PushConstantRange {
+                        stages: wgpu::ShaderStages::FRAGMENT,
+                        range: 0..16,
+                    }],
+                )
+                .unwrap();
+            let (vertices, normals, _, indices) = shared::cube();
+            // A single mesh, colored differently on every draw.
+            let mesh_bundle = MeshBundle {
+                mesh_id: renderer.add_mesh(&vertices, &normals, &indices),
+                pipeline_id,
+                texture_id: None,
+                transparent: false,
+            };
+            for i in 0..5 {
+                let t = i as f32 / 4.0;
+                let transform = Transform::from_translation_rotation_scale(
+                    &vec3(6.0 * (t - 0.5), 0.0, 0.0),
+                    &Quat::from_rotation_z(t),
+                    0.6,
+                );
+                let tint = vec4(1.0 - t, 0.3 + 0.4 * t, t, 1.0);
+                renderer.draw_mesh_with_push_constants(
+                    &transform,
+                    &mesh_bundle,
+                    bytemuck::bytes_of(&tint),
+                );
+            }
+        },
+    ))?;
+    Ok(())
+}
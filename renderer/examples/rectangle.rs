@@ -1,5 +1,5 @@
 use glam::{vec2, vec3};
-use renderer::{colors::GREEN, rectangle_rendering::Rectangle, transform::Transform};
+use renderer::prelude::*;
 
 mod shared;
 
@@ -1,10 +1,5 @@
 use glam::{vec2, vec3};
-use renderer::{
-    circle_rendering::Circle,
-    colors::{BLUE, RED},
-    rectangle_rendering::Rectangle,
-    transform::Transform,
-};
+use renderer::prelude::*;
 
 mod shared;
 
@@ -0,0 +1,39 @@
+use std::f32::consts::PI;
+
+use glam::{vec2, vec3};
+use renderer::prelude::*;
+
+mod shared;
+
+/// A sun with a planet and its moon, built once and kept between frames.
+fn build_scene() -> Vec<SceneNode> {
+    let moon = SceneNode::from_circle(
+        Transform::from_translation(&vec3(60.0, 0.0, 0.0)),
+        Circle::new(10.0, LIGHTGRAY),
+    );
+    let planet = SceneNode::from_circle_children(
+        Transform::from_translation_rotation_z(&vec3(200.0, 0.0, 0.0), PI / 3.0),
+        Circle::new(30.0, BLUE),
+        vec![moon],
+    );
+    let sun = SceneNode::from_circle_children(
+        Transform::IDENTITY,
+        Circle::new(80.0, YELLOW),
+        vec![planet],
+    );
+    let frame = SceneNode::from_rectangle_line(
+        Transform::IDENTITY,
+        RectangleLine::new(vec2(600.0, 600.0), RED, 5.0),
+    );
+    vec![sun, frame]
+}
+
+fn main() -> color_eyre::eyre::Result<()> {
+    let scene = build_scene();
+    // Every frame hands the same scene over instead of re-issuing the draws.
+    pollster::block_on(shared::run_variants(
+        &["frame_0", "frame_1"],
+        |_frame, renderer| renderer.submit_scene(&scene),
+    ))?;
+    Ok(())
+}
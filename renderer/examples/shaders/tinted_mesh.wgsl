@@ -0,0 +1,46 @@
+#import model_matrix::to_model_mesh_matrix;
+#import lighting::lambert;
+
+@group(0) @binding(0)
+var<uniform> projection: mat4x4<f32>;
+@group(0) @binding(1)
+var<uniform> camera: mat4x4<f32>;
+@group(1) @binding(0)
+var<uniform> transform: Transform;
+
+// Color of the mesh, set for every draw instead of stored per vertex.
+var<push_constant> tint: vec4<f32>;
+
+struct Transform {
+    affine1: vec4<f32>,
+    affine2: vec4<f32>,
+    affine3: vec4<f32>,
+}
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    let model_matrix = to_model_mesh_matrix(transform.affine1, transform.affine2, transform.affine3);
+    out.normal = (model_matrix * vec4<f32>(model.normal, 0.0)).xyz;
+    out.clip_position = projection * camera * model_matrix * vec4<f32>(model.position, 1.0);
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(lambert(in.normal, tint.rgb), tint.a);
+}
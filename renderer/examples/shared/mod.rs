@@ -1,15 +1,10 @@
-use std::{env::args, iter, path::Path, sync::Arc};
+use std::{env::args, path::Path, sync::Arc};
 
 use color_eyre::eyre::Result;
 use eyre::OptionExt;
-use glam::Vec2;
+use glam::{vec2, Vec2, Vec3};
 use image::{ImageBuffer, Rgba};
-use renderer::{
-    camera::PrimaryCamera,
-    gpu_context::GpuContext,
-    projection::{CameraProjection, Orthographic},
-    Renderer,
-};
+use renderer::prelude::*;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 use wgpu::util::parse_backends_from_comma_list;
 
@@ -26,17 +21,50 @@ fn get_program_stem() -> Result<String> {
     Ok(string.to_owned())
 }
 
-pub async fn run<FRender>(render: FRender) -> Result<()>
-where
-    FRender: Fn(&mut Renderer),
-{
-    let fmt_layer = tracing_subscriber::fmt::layer().pretty();
-    let filter_layer = EnvFilter::from_default_env();
-    tracing_subscriber::registry()
-        .with(fmt_layer)
-        .with(filter_layer)
-        .init();
-    color_eyre::install()?;
+/// A unit cube with its own four vertices for every face, so that each face
+/// shows the whole texture.
+#[allow(dead_code)]
+pub fn cube() -> (Vec<Vec3>, Vec<Vec3>, Vec<Vec2>, Vec<u32>) {
+    let mut vertices = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut indices = Vec::new();
+    for normal in [
+        Vec3::X,
+        Vec3::NEG_X,
+        Vec3::Y,
+        Vec3::NEG_Y,
+        Vec3::Z,
+        Vec3::NEG_Z,
+    ] {
+        // Two axes spanning the face, ordered so the triangles wind counter
+        // clockwise when seen from outside.
+        let u = normal.any_orthonormal_vector();
+        let v = normal.cross(u);
+        let first = vertices.len() as u32;
+        for (x, y) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+            vertices.push(normal + (2.0 * x - 1.0) * u + (2.0 * y - 1.0) * v);
+            normals.push(normal);
+            uvs.push(vec2(x, 1.0 - y));
+        }
+        indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+    (vertices, normals, uvs, indices)
+}
+
+/// Creates a GPU context on the backends listed in `WGPU_BACKEND`, or the
+/// default ones.
+#[allow(dead_code)]
+pub async fn gpu_context() -> Result<Arc<GpuContext>> {
+    gpu_context_with(wgpu::Features::empty(), Renderer::wgpu_limits()).await
+}
+
+/// Like [`gpu_context`], but the device is created with `required_features`
+/// and `required_limits`, failing when the adapter can't provide them.
+pub async fn gpu_context_with(
+    required_features: wgpu::Features,
+    required_limits: wgpu::Limits,
+) -> Result<Arc<GpuContext>> {
     let backends = std::env::var("WGPU_BACKEND")
         .as_deref()
         .map(str::to_lowercase)
@@ -52,63 +80,19 @@ where
         .request_adapter(&wgpu::RequestAdapterOptions::default())
         .await
         .ok_or_eyre("Could not get adapter")?;
-    let (device, queue) = adapter
-        .request_device(
-            &wgpu::DeviceDescriptor {
-                label: Some("Device"),
-                required_features: wgpu::Features::empty(),
-                required_limits: Renderer::wgpu_limits(),
-            },
-            None,
-        )
-        .await?;
-
-    let texture_format = wgpu::TextureFormat::Rgba8UnormSrgb;
-
-    let texture_descriptor = wgpu::TextureDescriptor {
-        size: wgpu::Extent3d {
-            width: OUTPUT_WIDTH,
-            height: OUTPUT_HEIGH,
-            depth_or_array_layers: 1,
-        },
-        mip_level_count: 1,
-        sample_count: 1,
-        dimension: wgpu::TextureDimension::D2,
-        format: texture_format,
-        usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
-        label: None,
-        view_formats: &[],
-    };
-
-    let texture = device.create_texture(&texture_descriptor);
-
-    // wgpu requires texture -> buffer copies to be aligned using
-    // wgpu::COPY_BYTES_PER_ROW_ALIGNMENT. Because of this we'll
-    // need to save both the padded_bytes_per_row as well as the
-    // unpadded_bytes_per_row.
-    let pixel_size: u32 = 4;
-    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
-    let unpadded_bytes_per_row = pixel_size * OUTPUT_WIDTH;
-    let padding = (align - unpadded_bytes_per_row % align) % align;
-    let padded_bytes_per_row = unpadded_bytes_per_row + padding;
-
-    // Create a buffer to copy the texture to so we can get the data.
-    let buffer_size = (padded_bytes_per_row * OUTPUT_HEIGH) as wgpu::BufferAddress;
-    let output_buffer_descriptor = wgpu::BufferDescriptor {
-        size: buffer_size,
-        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-        label: Some("Output Buffer"),
-        mapped_at_creation: false,
-    };
-    let output_buffer = device.create_buffer(&output_buffer_descriptor);
-
-    let gpu_context = Arc::new(GpuContext::new(device, queue));
+    let gpu_context =
+        GpuContext::request(&adapter, Some("Device"), required_features, required_limits).await?;
+    Ok(Arc::new(gpu_context))
+}
 
+/// Camera the examples render with, looking at a `OUTPUT_WIDTH` by
+/// `OUTPUT_HEIGH` target of `texture_format`.
+pub fn primary_camera(texture_format: wgpu::TextureFormat) -> PrimaryCamera {
     let projection = CameraProjection::Orthographic(Orthographic {
         depth: 2.0,
         scale: 1.0,
     });
-    let primary_camera = PrimaryCamera {
+    PrimaryCamera {
         projection,
         surface_format: texture_format,
         size: Vec2::new(OUTPUT_WIDTH as f32, OUTPUT_HEIGH as f32),
@@ -120,65 +104,111 @@ where
             }),
             write_mask: wgpu::ColorWrites::ALL,
         }),
-    };
+        depth_mode: DepthMode::Standard,
+        sample_count: 1,
+    }
+}
+
+// Every example includes this module but uses only one of the entry points.
+#[allow(dead_code)]
+pub async fn run<FRender>(render: FRender) -> Result<()>
+where
+    FRender: Fn(&mut Renderer),
+{
+    run_variants(&[""], |_variant, renderer| render(renderer)).await
+}
 
-    let mut renderer = Renderer::new(&gpu_context, primary_camera).unwrap();
+/// Like [`run`], on a device created with `required_features` and
+/// `required_limits`.
+#[allow(dead_code)]
+pub async fn run_with<FRender>(
+    required_features: wgpu::Features,
+    required_limits: wgpu::Limits,
+    render: FRender,
+) -> Result<()>
+where
+    FRender: Fn(&mut Renderer),
+{
+    run_variants_with(
+        required_features,
+        required_limits,
+        &[""],
+        |_variant, renderer| render(renderer),
+    )
+    .await
+}
 
-    render(&mut renderer);
+/// Renders one image per variant, `render` sets the renderer up for the
+/// variant and draws. Images are saved as `<example>_<variant>.png`.
+#[allow(dead_code)]
+pub async fn run_variants<FRender>(variants: &[&str], render: FRender) -> Result<()>
+where
+    FRender: Fn(&str, &mut Renderer),
+{
+    run_variants_with(
+        wgpu::Features::empty(),
+        Renderer::wgpu_limits(),
+        variants,
+        render,
+    )
+    .await
+}
 
-    renderer.render(&texture).unwrap();
+async fn run_variants_with<FRender>(
+    required_features: wgpu::Features,
+    required_limits: wgpu::Limits,
+    variants: &[&str],
+    render: FRender,
+) -> Result<()>
+where
+    FRender: Fn(&str, &mut Renderer),
+{
+    let fmt_layer = tracing_subscriber::fmt::layer().pretty();
+    let filter_layer = EnvFilter::from_default_env();
+    tracing_subscriber::registry()
+        .with(fmt_layer)
+        .with(filter_layer)
+        .init();
+    color_eyre::install()?;
 
-    let mut encoder = renderer
-        .rendering_context
-        .gpu_context
-        .device()
-        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    let texture_format = wgpu::TextureFormat::Rgba8UnormSrgb;
 
-    encoder.copy_texture_to_buffer(
-        wgpu::ImageCopyTexture {
-            aspect: wgpu::TextureAspect::All,
-            texture: &texture,
-            mip_level: 0,
-            origin: wgpu::Origin3d::ZERO,
-        },
-        wgpu::ImageCopyBuffer {
-            buffer: &output_buffer,
-            layout: wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(padded_bytes_per_row),
-                rows_per_image: Some(OUTPUT_HEIGH),
-            },
+    let texture_descriptor = wgpu::TextureDescriptor {
+        size: wgpu::Extent3d {
+            width: OUTPUT_WIDTH,
+            height: OUTPUT_HEIGH,
+            depth_or_array_layers: 1,
         },
-        texture_descriptor.size,
-    );
-    renderer
-        .rendering_context
-        .gpu_context
-        .queue()
-        .submit(iter::once(encoder.finish()));
-
-    let buffer_slice = output_buffer.slice(..);
-
-    buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-        result.expect("GPU didn't copy data to output buffer");
-    });
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: texture_format,
+        usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
+        label: None,
+        view_formats: &[],
+    };
+
+    let gpu_context = gpu_context_with(required_features, required_limits).await?;
+    let texture = gpu_context.device().create_texture(&texture_descriptor);
+
+    let primary_camera = primary_camera(texture_format);
+
+    for variant in variants {
+        let mut renderer = Renderer::new(&gpu_context, primary_camera.clone()).unwrap();
+
+        render(variant, &mut renderer);
+
+        renderer.render(&texture).unwrap();
 
-    renderer
-        .rendering_context
-        .gpu_context
-        .device()
-        .poll(wgpu::Maintain::Wait);
-
-    let padded_data = buffer_slice.get_mapped_range();
-    let data = padded_data
-        .chunks(padded_bytes_per_row as _)
-        .flat_map(|chunk| &chunk[..unpadded_bytes_per_row as _])
-        .copied()
-        .collect::<Vec<_>>();
-    let buffer = ImageBuffer::<Rgba<u8>, _>::from_raw(OUTPUT_WIDTH, OUTPUT_WIDTH, data)
-        .ok_or_eyre("Could not create an image buffer")?;
-    let name = get_program_stem()? + ".png";
-    println!("Saving rendered image to {}", name);
-    buffer.save(name)?;
+        let data = renderer.capture_frame(&texture);
+        let buffer = ImageBuffer::<Rgba<u8>, _>::from_raw(OUTPUT_WIDTH, OUTPUT_WIDTH, data)
+            .ok_or_eyre("Could not create an image buffer")?;
+        let name = match *variant {
+            "" => get_program_stem()? + ".png",
+            variant => format!("{}_{variant}.png", get_program_stem()?),
+        };
+        println!("Saving rendered image to {}", name);
+        buffer.save(name)?;
+    }
     Ok(())
 }
@@ -0,0 +1,41 @@
+use glam::{vec2, vec3, Mat4, Vec2};
+use renderer::prelude::*;
+
+mod shared;
+
+fn draw_scene(renderer: &mut Renderer) {
+    renderer.draw_rectangle(
+        &Transform::from_translation(&vec3(-80.0, 0.0, 0.0)),
+        &Rectangle::new(vec2(100.0, 100.0), RED),
+    );
+    renderer.draw_circle(
+        &Transform::from_translation(&vec3(80.0, 0.0, 0.0)),
+        &Circle::new(50., BLUE),
+    );
+}
+
+fn main() -> color_eyre::eyre::Result<()> {
+    pollster::block_on(shared::run(|renderer| {
+        let projection = CameraProjection::Orthographic(Orthographic {
+            depth: 2.0,
+            scale: 1.0,
+        });
+        // The same scene seen by two players, the right one looking at it
+        // from a different spot.
+        let left = renderer
+            .create_camera(
+                projection.clone(),
+                Viewport::new(Vec2::ZERO, vec2(0.5, 1.0)),
+            )
+            .unwrap();
+        let right = renderer
+            .create_camera(projection, Viewport::new(vec2(0.5, 0.0), Vec2::ONE))
+            .unwrap();
+        renderer.set_camera_matrix(&right, &Mat4::from_translation(vec3(-80.0, 0.0, 0.0)));
+        for camera in [left, right] {
+            renderer.set_active_camera(Some(camera));
+            draw_scene(renderer);
+        }
+    }))?;
+    Ok(())
+}
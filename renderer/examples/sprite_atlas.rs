@@ -0,0 +1,69 @@
+use std::f32::consts::PI;
+
+use glam::{uvec2, vec2, vec3, UVec2};
+use renderer::prelude::*;
+
+mod shared;
+
+/// Texels across a tile of the atlas.
+const TILE: u32 = 16;
+const TILE_COUNT: u32 = 4;
+
+/// An atlas of four tiles side by side: a checkerboard, a disc, diagonal
+/// stripes and a frame.
+fn atlas() -> Vec<u8> {
+    let mut rgba = Vec::new();
+    for y in 0..TILE {
+        for x in 0..TILE * TILE_COUNT {
+            let (tile, x) = (x / TILE, x % TILE);
+            let (dx, dy) = (x as f32 - 7.5, y as f32 - 7.5);
+            let on = match tile {
+                0 => (x / 4 + y / 4) % 2 == 0,
+                1 => dx * dx + dy * dy < 49.0,
+                2 => (x + y) % 6 < 3,
+                _ => x < 2 || y < 2 || x >= TILE - 2 || y >= TILE - 2,
+            };
+            let texel = if on {
+                [255, 255, 255, 255]
+            } else {
+                [0, 0, 0, 0]
+            };
+            rgba.extend(texel);
+        }
+    }
+    rgba
+}
+
+fn main() -> color_eyre::eyre::Result<()> {
+    pollster::block_on(shared::run(|renderer| {
+        let atlas_size = uvec2(TILE * TILE_COUNT, TILE);
+        let texture_id = renderer.load_texture(atlas_size, &atlas());
+        let tint = [RED, GREEN, BLUE, YELLOW];
+        // Every tile on its own, tinted, the last one also rotated.
+        for tile in 0..TILE_COUNT {
+            let uv_rect =
+                UvRect::from_texels(uvec2(tile * TILE, 0), UVec2::splat(TILE), atlas_size);
+            let sprite = Sprite::new(texture_id, vec2(100.0, 100.0))
+                .with_uv_rect(uv_rect)
+                .with_tint(tint[tile as usize]);
+            let rotation = if tile == TILE_COUNT - 1 {
+                PI / 8.0
+            } else {
+                0.0
+            };
+            renderer.draw_sprite(
+                &Transform::from_translation_rotation_z(
+                    &vec3(-195.0 + tile as f32 * 130.0, 80.0, 0.0),
+                    rotation,
+                ),
+                &sprite,
+            );
+        }
+        // The whole atlas, stretched like the texture.
+        renderer.draw_sprite(
+            &Transform::from_translation(&vec3(0.0, -120.0, 0.0)),
+            &Sprite::new(texture_id, vec2(400.0, 100.0)),
+        );
+    }))?;
+    Ok(())
+}
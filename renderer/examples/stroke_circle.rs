@@ -1,6 +1,6 @@
 // TODO: Think about renaming this file... shoud we still use stroke?
 use glam::vec3;
-use renderer::{circle_rendering::CircleLine, colors::GREEN, transform::Transform};
+use renderer::prelude::*;
 
 mod shared;
 
@@ -1,6 +1,6 @@
 // TODO: Think about renaming this file... shoud we still use stroke?
 use glam::{vec2, vec3};
-use renderer::{colors::RED, rectangle_rendering::RectangleLine, transform::Transform};
+use renderer::prelude::*;
 
 mod shared;
 
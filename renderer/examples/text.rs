@@ -0,0 +1,25 @@
+use glam::vec3;
+use renderer::prelude::*;
+
+mod shared;
+
+fn main() -> color_eyre::eyre::Result<()> {
+    pollster::block_on(shared::run(|renderer| {
+        renderer.draw_text(vec3(0.0, 0.0, 0.0), "Hello", 64.0, WHITE);
+        // Labels next to particles, the way the inspector would place them.
+        for (index, x) in [-150.0, 0.0, 150.0].into_iter().enumerate() {
+            let position = vec3(x, -120.0, 0.0);
+            renderer.draw_circle(
+                &Transform::from_translation(&position),
+                &Circle::new(20.0, BLUE),
+            );
+            renderer.draw_text(
+                position + vec3(0.0, 35.0, 0.0),
+                &format!("#{index}"),
+                16.0,
+                YELLOW,
+            );
+        }
+    }))?;
+    Ok(())
+}
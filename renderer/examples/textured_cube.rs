@@ -0,0 +1,42 @@
+use glam::{uvec2, vec3, Mat4, Quat, Vec3};
+use image::{Rgba, RgbaImage};
+use renderer::prelude::*;
+
+mod shared;
+
+fn main() -> color_eyre::eyre::Result<()> {
+    let image = RgbaImage::from_fn(64, 64, |x, y| {
+        if (x / 8 + y / 8) % 2 == 0 {
+            Rgba([(x * 4) as u8, 80, (y * 4) as u8, 255])
+        } else {
+            Rgba([240, 240, 240, 255])
+        }
+    });
+
+    pollster::block_on(shared::run(|renderer| {
+        renderer.set_primary_camera_projection(&CameraProjection::Perspective(Perspective {
+            fovy: 45f32.to_radians(),
+            znear: 0.1,
+            zfar: 100.0,
+            scale: 1.0,
+        }));
+        renderer.set_primary_camera_matrix(&Mat4::look_at_rh(
+            vec3(4.0, -5.0, 4.0),
+            Vec3::ZERO,
+            Vec3::Z,
+        ));
+
+        let (vertices, normals, uvs, indices) = shared::cube();
+        let mesh_bundle = MeshBundle {
+            mesh_id: renderer.add_mesh_textured(&vertices, &normals, &uvs, &indices),
+            pipeline_id: renderer.create_3d_pipeline_textured().unwrap(),
+            texture_id: Some(renderer.load_texture(uvec2(image.width(), image.height()), &image)),
+            transparent: false,
+        };
+        renderer.draw_mesh(
+            &Transform::from_rotation(&Quat::from_rotation_z(0.3)),
+            &mesh_bundle,
+        );
+    }))?;
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use glam::vec3;
+use renderer::prelude::*;
+
+mod shared;
+
+fn main() -> color_eyre::eyre::Result<()> {
+    pollster::block_on(shared::run(|renderer| {
+        // Each circle is a bit closer to the camera than the previous one, so
+        // that the depth test lets it blend over the circles drawn before.
+        for (i, (x, y, color)) in [(-60.0, -40.0, RED), (60.0, -40.0, GREEN), (0.0, 60.0, BLUE)]
+            .into_iter()
+            .enumerate()
+        {
+            renderer.draw_circle(
+                &Transform::from_translation(&vec3(x, y, 0.1 * i as f32)),
+                &Circle::new(120., color.truncate().extend(0.5)),
+            )
+        }
+    }))?;
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use glam::{vec3, Mat4, Vec3, Vec4};
+use renderer::prelude::*;
+
+mod shared;
+
+/// Square in the xy plane, facing the camera on the positive z axis.
+fn plane(renderer: &mut Renderer, pipeline_id: PipelineId, size: f32, color: Vec4) -> MeshBundle {
+    let vertices = [
+        vec3(-size, -size, 0.0),
+        vec3(size, -size, 0.0),
+        vec3(size, size, 0.0),
+        vec3(-size, size, 0.0),
+    ];
+    MeshBundle {
+        mesh_id: renderer.add_mesh_colored(
+            &vertices,
+            &[Vec3::Z; 4],
+            &[color; 4],
+            &[0, 1, 2, 0, 2, 3],
+        ),
+        pipeline_id,
+        texture_id: None,
+        transparent: color.w < 1.0,
+    }
+}
+
+fn main() -> color_eyre::eyre::Result<()> {
+    // Both images look the same: transparent meshes are sorted back to
+    // front whatever order they are drawn in, after the opaque backdrop.
+    pollster::block_on(shared::run_variants(
+        &["in_order", "reversed"],
+        |variant, renderer| {
+            renderer.set_primary_camera_projection(&CameraProjection::Perspective(Perspective {
+                fovy: 45f32.to_radians(),
+                znear: 0.1,
+                zfar: 100.0,
+                scale: 1.0,
+            }));
+            renderer.set_primary_camera_matrix(&Mat4::look_at_rh(
+                vec3(1.0, 1.0, 10.0),
+                Vec3::ZERO,
+                Vec3::Y,
+            ));
+            renderer.set_directional_light(&DirectionalLight {
+                direction: Vec3::NEG_Z,
+                color: Vec3::ONE,
+                ambient: Vec3::ZERO,
+            });
+
+            let pipeline_id = renderer.create_3d_pipeline_vertex_colored().unwrap();
+            let mut meshes = vec![(
+                Transform::from_translation(&vec3(0.0, 0.0, -2.0)),
+                plane(renderer, pipeline_id, 3.0, LIGHTGRAY),
+            )];
+            for (position, color) in [
+                (vec3(-0.8, -0.5, -1.0), RED),
+                (vec3(0.0, 0.5, 0.0), GREEN),
+                (vec3(0.8, -0.5, 1.0), BLUE),
+            ] {
+                meshes.push((
+                    Transform::from_translation(&position),
+                    plane(renderer, pipeline_id, 1.2, color.truncate().extend(0.5)),
+                ));
+            }
+            if variant == "reversed" {
+                meshes.reverse();
+            }
+            for (transform, mesh_bundle) in &meshes {
+                renderer.draw_mesh(transform, mesh_bundle);
+            }
+        },
+    ))?;
+    Ok(())
+}
@@ -1,4 +1,5 @@
 #import model_matrix::to_model_matrix;
+#import antialiasing::{edge_coverage, quad_growth};
 
 @group(0) @binding(0)
 var<uniform> perspective: mat4x4<f32>;
@@ -13,14 +14,14 @@ struct InstanceInput {
     @location(2) affine_matrix_2: vec3<f32>,
     @location(3) affine_matrix_3: vec3<f32>,
     @location(4) translation_vector: vec3<f32>,
-    @location(5) color: vec3<f32>,
+    @location(5) color: vec4<f32>,
     @location(6) radius: f32,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) uv_coords: vec2<f32>,
-    @location(1) color: vec3<f32>,
+    @location(1) color: vec4<f32>,
 }
 
 @vertex
@@ -36,10 +37,17 @@ fn vs_main(
         instance.affine_matrix_3,
         instance.translation_vector
     );
-    let world_position = model_matrix * vec4<f32>(model.position.x * instance.radius, model.position.y * instance.radius, 0.0, 1.0);
+    let view_projection = perspective * camera;
+    let growth = quad_growth(
+        view_projection * model_matrix * vec4<f32>(0.0, 0.0, 0.0, 1.0),
+        view_projection * model_matrix * vec4<f32>(instance.radius, 0.0, 0.0, 1.0),
+        view_projection * model_matrix * vec4<f32>(0.0, instance.radius, 0.0, 1.0),
+    );
+    let position = model.position * growth;
+    let world_position = model_matrix * vec4<f32>(position * instance.radius, 0.0, 1.0);
 
-    out.clip_position = perspective * camera * world_position;
-    out.uv_coords = model.position;
+    out.clip_position = view_projection * world_position;
+    out.uv_coords = position;
     out.color = instance.color;
 
     return out;
@@ -47,10 +55,11 @@ fn vs_main(
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let circle_sd: f32 = abs(length(in.uv_coords)) - 1.0;
+    let circle_sd: f32 = length(in.uv_coords) - 1.0;
+    let coverage = edge_coverage(circle_sd);
 
-    if circle_sd > 0.0 {
+    if coverage <= 0.0 {
         discard;
     }
-    return vec4<f32>(in.color, 1.0);
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
 }
@@ -0,0 +1,57 @@
+#import antialiasing::{edge_coverage, quad_growth};
+
+@group(0) @binding(0)
+var<uniform> perspective: mat4x4<f32>;
+@group(0) @binding(1)
+var<uniform> camera: mat4x4<f32>;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+}
+struct InstanceInput {
+    @location(1) translation: vec3<f32>,
+    @location(2) radius: f32,
+    @location(3) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv_coords: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    // The radius already includes the transform's scale and rotation around
+    // the z-axis does not change a circle, so translation is all that is left.
+    let view_projection = perspective * camera;
+    let growth = quad_growth(
+        view_projection * vec4<f32>(instance.translation, 1.0),
+        view_projection * vec4<f32>(instance.translation + vec3<f32>(instance.radius, 0.0, 0.0), 1.0),
+        view_projection * vec4<f32>(instance.translation + vec3<f32>(0.0, instance.radius, 0.0), 1.0),
+    );
+    let position = model.position * growth;
+    let world_position = vec4<f32>(instance.translation + vec3<f32>(position * instance.radius, 0.0), 1.0);
+
+    out.clip_position = view_projection * world_position;
+    out.uv_coords = position;
+    out.color = instance.color;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let circle_sd: f32 = length(in.uv_coords) - 1.0;
+    let coverage = edge_coverage(circle_sd);
+
+    if coverage <= 0.0 {
+        discard;
+    }
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
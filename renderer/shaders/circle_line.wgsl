@@ -1,4 +1,5 @@
 #import model_matrix::to_model_matrix;
+#import antialiasing::{edge_coverage, quad_growth};
 
 @group(0) @binding(0)
 var<uniform> projection: mat4x4<f32>;
@@ -13,16 +14,19 @@ struct InstanceInput {
     @location(2) affine_matrix_2: vec3<f32>,
     @location(3) affine_matrix_3: vec3<f32>,
     @location(4) translation_vector: vec3<f32>,
-    @location(5) color: vec3<f32>,
+    @location(5) color: vec4<f32>,
     @location(6) radius: f32,
     @location(7) border_size: f32,
+    @location(8) border_units: u32,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) sdf_position: vec2<f32>,
-    @location(1) color: vec3<f32>,
-    @location(2) half_border: f32
+    @location(1) color: vec4<f32>,
+    @location(2) half_border: f32,
+    @location(3) border_size: f32,
+    @location(4) @interpolate(flat) border_units: u32,
 }
 
 @vertex
@@ -38,23 +42,37 @@ fn vs_main(
         instance.affine_matrix_3,
         instance.translation_vector
     );
-    let world_position = model_matrix * vec4<f32>(model.position.x * instance.radius, model.position.y * instance.radius, 0.0, 1.0);
+    let view_projection = projection * camera;
+    let growth = quad_growth(
+        view_projection * model_matrix * vec4<f32>(0.0, 0.0, 0.0, 1.0),
+        view_projection * model_matrix * vec4<f32>(instance.radius, 0.0, 0.0, 1.0),
+        view_projection * model_matrix * vec4<f32>(0.0, instance.radius, 0.0, 1.0),
+    );
+    let position = model.position * growth;
+    let world_position = model_matrix * vec4<f32>(position * instance.radius, 0.0, 1.0);
 
-    out.clip_position = projection * camera * world_position;
-    out.sdf_position = model.position;
+    out.clip_position = view_projection * world_position;
+    out.sdf_position = position;
     out.color = instance.color;
     out.half_border = (instance.border_size / instance.radius)/2.0;
+    out.border_size = instance.border_size;
+    out.border_units = instance.border_units;
 
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let circle_sd: f32 = abs(length(in.sdf_position) - 1.0 + in.half_border) - in.half_border;
+    let distance = length(in.sdf_position);
+    // Size of a screen pixel in the circle's unit space, used for borders
+    // given in pixels so they keep their thickness under zoom.
+    let pixel_size = length(vec2<f32>(dpdx(distance), dpdy(distance)));
+    let half_border = select(in.half_border, in.border_size * pixel_size / 2.0, in.border_units == 1u);
+    let circle_sd: f32 = abs(distance - 1.0 + half_border) - half_border;
+    let coverage = edge_coverage(circle_sd);
 
-    if circle_sd > 0.0 {
+    if coverage <= 0.0 {
         discard;
     }
-    return vec4<f32>(in.color, 1.0);
-
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
 }
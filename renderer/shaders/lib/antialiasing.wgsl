@@ -0,0 +1,25 @@
+#define_import_path antialiasing
+
+@group(0) @binding(3)
+var<uniform> viewport_size: vec2<f32>;
+
+// Coverage of a pixel by a shape given the signed distance to its edge,
+// negative inside. The edge fades out over about a pixel instead of being cut
+// off, whatever the distance is measured in. Has to be called in uniform
+// control flow, before any discard.
+fn edge_coverage(signed_distance: f32) -> f32 {
+    return clamp(0.5 - signed_distance / fwidth(signed_distance), 0.0, 1.0);
+}
+
+// Factor to grow the quad of a circle by, so that it covers another pixel
+// around the circle and the faded edge isn't cut off. Takes the clip space
+// positions of the center and of the points a radius away from it along the
+// circle's x and y axes.
+fn quad_growth(center: vec4<f32>, x_edge: vec4<f32>, y_edge: vec4<f32>) -> f32 {
+    let to_pixels = viewport_size / 2.0;
+    let center_ndc = center.xy / center.w;
+    let radius_x = length((x_edge.xy / x_edge.w - center_ndc) * to_pixels);
+    let radius_y = length((y_edge.xy / y_edge.w - center_ndc) * to_pixels);
+    // Capped for circles seen almost edge on.
+    return min(1.0 + 1.0 / min(radius_x, radius_y), 2.0);
+}
@@ -0,0 +1,16 @@
+#define_import_path lighting
+
+struct DirectionalLight {
+    direction: vec3<f32>,
+    color: vec3<f32>,
+    ambient: vec3<f32>,
+}
+
+@group(0) @binding(2)
+var<uniform> directional_light: DirectionalLight;
+
+// Diffuse Lambert shading of a surface with the given normal and color.
+fn lambert(normal: vec3<f32>, albedo: vec3<f32>) -> vec3<f32> {
+    let diffuse_strength = max(dot(normalize(normal), -directional_light.direction), 0.0);
+    return albedo * (directional_light.ambient + directional_light.color * diffuse_strength);
+}
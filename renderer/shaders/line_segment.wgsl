@@ -4,6 +4,8 @@
 var<uniform> projection: mat4x4<f32>;
 @group(0) @binding(1)
 var<uniform> camera: mat4x4<f32>;
+@group(0) @binding(3)
+var<uniform> viewport_size: vec2<f32>;
 
 struct VertexInput {
     @location(0) position: vec2<f32>,
@@ -16,13 +18,20 @@ struct InstanceInput {
     @location(4) translation_vector: vec3<f32>,
     @location(5) p1: vec3<f32>,
     @location(6) p2: vec3<f32>,
-    @location(7) color: vec3<f32>,
+    @location(7) color: vec4<f32>,
     @location(8) width: f32,
+    @location(9) dash_length: f32,
+    @location(10) gap_length: f32,
+    @location(11) width_units: u32,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(1) color: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    // Distance from p1 along the line.
+    @location(2) distance: f32,
+    @location(3) dash_length: f32,
+    @location(4) gap_length: f32,
 }
 
 @vertex
@@ -35,8 +44,10 @@ fn vs_main(
     let delta = (instance.p2 - instance.p1);
     let center = (delta / 2.0) + instance.p1;
 
+    // Pitch is the elevation above the xy-plane. Measuring it against the x
+    // component alone flipped lines pointing towards -x, which then got culled.
     let yaw = atan2(delta.y, delta.x);
-    let pitch = atan2(delta.z, delta.x);
+    let pitch = atan2(delta.z, length(delta.xy));
 
     let cos_yaw = cos(yaw);
     let sin_yaw = sin(yaw);
@@ -44,9 +55,9 @@ fn vs_main(
     let sin_pitch = sin(pitch);
 
     let translation_matrix = mat4x4<f32>(
-        vec4(cos_yaw * cos_pitch, sin_yaw, -cos_yaw * sin_pitch, 0.0),
-        vec4(-sin_yaw * cos_pitch, cos_yaw, sin_yaw * sin_pitch, 0.0),
-        vec4(sin_pitch, 0.0, cos_pitch, 0.0),
+        vec4(cos_yaw * cos_pitch, sin_yaw * cos_pitch, sin_pitch, 0.0),
+        vec4(-sin_yaw, cos_yaw, 0.0, 0.0),
+        vec4(-cos_yaw * sin_pitch, -sin_yaw * sin_pitch, cos_pitch, 0.0),
         vec4(center.x, center.y, center.z, 1.0)
     );
 
@@ -64,16 +75,37 @@ fn vs_main(
         instance.translation_vector
     );
 
-    //let world_position = translation_matrix_pitch * translation_matrix_yaw * scale_matrix * vec4<f32>(model.position, 0.0, 1.0);
-    let world_position = model_matrix * translation_matrix * scale_matrix * vec4<f32>(model.position, 0.0, 1.0);
-
-    out.clip_position = projection * camera * world_position;
+    if instance.width_units == 1u {
+        // Widths in pixels are applied on screen, across the projected line,
+        // so that zooming doesn't change them.
+        let clip_p1 = projection * camera * model_matrix * vec4<f32>(instance.p1, 1.0);
+        let clip_p2 = projection * camera * model_matrix * vec4<f32>(instance.p2, 1.0);
+        let screen_delta = (clip_p2.xy / clip_p2.w - clip_p1.xy / clip_p1.w) * viewport_size;
+        var direction = vec2<f32>(1.0, 0.0);
+        if length(screen_delta) > 0.0 {
+            direction = normalize(screen_delta);
+        }
+        let side = vec2<f32>(-direction.y, direction.x);
+        let clip_position = mix(clip_p1, clip_p2, (model.position.x + 1.0) / 2.0);
+        let offset = side * model.position.y * instance.width / viewport_size;
+        out.clip_position = clip_position + vec4<f32>(offset * clip_position.w, 0.0, 0.0);
+    } else {
+        let world_position = model_matrix * translation_matrix * scale_matrix * vec4<f32>(model.position, 0.0, 1.0);
+        out.clip_position = projection * camera * world_position;
+    }
     out.color = instance.color;
+    out.distance = (model.position.x + 1.0) / 2.0 * length(delta);
+    out.dash_length = instance.dash_length;
+    out.gap_length = instance.gap_length;
 
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(in.color, 1.0);
+    if in.dash_length > 0.0 && in.gap_length > 0.0
+        && in.distance % (in.dash_length + in.gap_length) > in.dash_length {
+        discard;
+    }
+    return in.color;
 }
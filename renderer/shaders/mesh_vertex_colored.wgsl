@@ -0,0 +1,47 @@
+#import model_matrix::to_model_mesh_matrix;
+#import lighting::lambert;
+
+@group(0) @binding(0)
+var<uniform> projection: mat4x4<f32>;
+@group(0) @binding(1)
+var<uniform> camera: mat4x4<f32>;
+@group(1) @binding(0)
+var<uniform> transform: Transform;
+
+struct Transform {
+    affine1: vec4<f32>,
+    affine2: vec4<f32>,
+    affine3: vec4<f32>,
+}
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    let model_matrix = to_model_mesh_matrix(transform.affine1, transform.affine2, transform.affine3);
+    let world_position = model_matrix * vec4<f32>(model.position, 1.0);
+    let normal = normalize((model_matrix * vec4<f32>(model.normal, 0.0)).xyz);
+
+    out.color = vec4<f32>(lambert(normal, model.color.rgb), model.color.a);
+    out.clip_position = projection * camera * world_position;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
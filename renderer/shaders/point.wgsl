@@ -0,0 +1,56 @@
+@group(0) @binding(0)
+var<uniform> projection: mat4x4<f32>;
+@group(0) @binding(1)
+var<uniform> camera: mat4x4<f32>;
+@group(0) @binding(3)
+var<uniform> viewport_size: vec2<f32>;
+
+struct InstanceInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) size: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(1) color: vec4<f32>,
+    // Position within the dot, from -1 to 1 along both axes.
+    @location(2) corner: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    // Two counter clockwise triangles covering the dot.
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+    );
+    let corner = corners[vertex_index];
+
+    // The size is in pixels, so the dot is spread on screen around the
+    // projected position.
+    let center = projection * camera * vec4<f32>(instance.position, 1.0);
+    let offset = corner * instance.size / viewport_size;
+    out.clip_position = center + vec4<f32>(offset * center.w, 0.0, 0.0);
+    out.color = instance.color;
+    out.corner = corner;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    if dot(in.corner, in.corner) > 1.0 {
+        discard;
+    }
+    return in.color;
+}
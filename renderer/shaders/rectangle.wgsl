@@ -14,12 +14,12 @@ struct InstanceInput {
     @location(3) affine_matrix_3: vec3<f32>,
     @location(4) translation_vector: vec3<f32>,
     @location(5) size: vec2<f32>,
-    @location(6) color: vec3<f32>,
+    @location(6) color: vec4<f32>,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(1) color: vec3<f32>,
+    @location(1) color: vec4<f32>,
 }
 
 @vertex
@@ -45,5 +45,5 @@ fn vs_main(
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(in.color, 1.0);
+    return in.color;
 }
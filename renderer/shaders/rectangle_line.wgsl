@@ -14,15 +14,18 @@ struct InstanceInput {
     @location(3) affine_matrix_3: vec3<f32>,
     @location(4) translation_vector: vec3<f32>,
     @location(5) size: vec2<f32>,
-    @location(6) color: vec3<f32>,
+    @location(6) color: vec4<f32>,
     @location(7) border_size: f32,
+    @location(8) border_units: u32,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(1) sdf_position: vec2<f32>,
-    @location(2) color: vec3<f32>,
+    @location(2) color: vec4<f32>,
     @location(3) half_border: vec2<f32>,
+    @location(4) border_size: f32,
+    @location(5) @interpolate(flat) border_units: u32,
 }
 
 @vertex
@@ -46,16 +49,25 @@ fn vs_main(
 
     out.sdf_position = vec2<f32>(model.position.x, model.position.y);
     out.half_border = (instance.border_size / half_size) / 2.0;
+    out.border_size = instance.border_size;
+    out.border_units = instance.border_units;
 
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let sd = abs(abs(in.sdf_position) - 1.0 + in.half_border) - in.half_border;
+    // Size of a screen pixel along each axis of the rectangle's unit space,
+    // used for borders given in pixels so they keep their thickness under zoom.
+    let pixel_size = vec2<f32>(
+        length(vec2<f32>(dpdx(in.sdf_position.x), dpdy(in.sdf_position.x))),
+        length(vec2<f32>(dpdx(in.sdf_position.y), dpdy(in.sdf_position.y))),
+    );
+    let half_border = select(in.half_border, in.border_size * pixel_size / 2.0, in.border_units == 1u);
+    let sd = abs(abs(in.sdf_position) - 1.0 + half_border) - half_border;
 
     if sd.x > 0.0 && sd.y > 0.0 {
         discard;
     }
-    return vec4<f32>(in.color, 1.0);
+    return in.color;
 }
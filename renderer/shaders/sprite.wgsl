@@ -0,0 +1,65 @@
+#import model_matrix::to_model_matrix;
+
+@group(0) @binding(0)
+var<uniform> projection: mat4x4<f32>;
+@group(0) @binding(1)
+var<uniform> camera: mat4x4<f32>;
+@group(1) @binding(0)
+var sprite_texture: texture_2d<f32>;
+@group(1) @binding(1)
+var sprite_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+}
+struct InstanceInput {
+    @location(1) affine_matrix_1: vec3<f32>,
+    @location(2) affine_matrix_2: vec3<f32>,
+    @location(3) affine_matrix_3: vec3<f32>,
+    @location(4) translation_vector: vec3<f32>,
+    @location(5) size: vec2<f32>,
+    @location(6) uv_min: vec2<f32>,
+    @location(7) uv_max: vec2<f32>,
+    @location(8) tint: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) tint: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    let model_matrix = to_model_matrix(
+        instance.affine_matrix_1,
+        instance.affine_matrix_2,
+        instance.affine_matrix_3,
+        instance.translation_vector
+    );
+    let world_position = model_matrix * vec4<f32>(model.position * instance.size / 2.0, 0.0, 1.0);
+
+    out.clip_position = projection * camera * world_position;
+    // Texture rows go from the top down, the quad's y goes up.
+    let corner = vec2<f32>(model.position.x + 1.0, 1.0 - model.position.y) / 2.0;
+    out.uv = mix(instance.uv_min, instance.uv_max, corner);
+    out.tint = instance.tint;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(sprite_texture, sprite_sampler, in.uv) * in.tint;
+    // Fully transparent texels would still hide what's behind them in the
+    // depth buffer.
+    if color.a == 0.0 {
+        discard;
+    }
+    return color;
+}
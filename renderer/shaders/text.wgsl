@@ -0,0 +1,73 @@
+@group(0) @binding(0)
+var<uniform> projection: mat4x4<f32>;
+@group(0) @binding(1)
+var<uniform> camera: mat4x4<f32>;
+@group(0) @binding(3)
+var<uniform> viewport_size: vec2<f32>;
+
+// Every glyph of the font side by side, one texel per font pixel.
+@group(1) @binding(0)
+var atlas: texture_2d<f32>;
+
+const GLYPH_SIZE: vec2<f32> = vec2<f32>(5.0, 8.0);
+
+struct InstanceInput {
+    @location(0) position: vec3<f32>,
+    @location(1) offset: vec2<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) size: f32,
+    @location(4) index: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(1) color: vec4<f32>,
+    // Position within the glyph in font pixels, from its top left corner.
+    @location(2) glyph_position: vec2<f32>,
+    @location(3) @interpolate(flat) index: u32,
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    // Two counter clockwise triangles covering the glyph.
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+    );
+    let corner = corners[vertex_index];
+
+    // The size is in pixels, so the glyph is spread on screen around the
+    // projected position of the text.
+    let half_extent = GLYPH_SIZE * instance.size / GLYPH_SIZE.y / 2.0;
+    let pixels = instance.offset + corner * half_extent;
+    let center = projection * camera * vec4<f32>(instance.position, 1.0);
+    let offset = 2.0 * pixels / viewport_size;
+    out.clip_position = center + vec4<f32>(offset * center.w, 0.0, 0.0);
+    out.color = instance.color;
+    // The atlas rows go from the top down, clip space y goes up.
+    let uv = vec2<f32>(corner.x + 1.0, 1.0 - corner.y) / 2.0;
+    out.glyph_position = uv * GLYPH_SIZE;
+    out.index = instance.index;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Clamped, so that the edges never sample the neighbouring glyphs.
+    let pixel = min(floor(in.glyph_position), GLYPH_SIZE - 1.0);
+    let texel = vec2<i32>(pixel) + vec2<i32>(i32(in.index) * i32(GLYPH_SIZE.x), 0);
+    if textureLoad(atlas, texel, 0).r < 0.5 {
+        discard;
+    }
+    return in.color;
+}
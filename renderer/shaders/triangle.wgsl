@@ -0,0 +1,54 @@
+#import model_matrix::to_model_matrix;
+
+@group(0) @binding(0)
+var<uniform> projection: mat4x4<f32>;
+@group(0) @binding(1)
+var<uniform> camera: mat4x4<f32>;
+
+struct InstanceInput {
+    @location(1) affine_matrix_1: vec3<f32>,
+    @location(2) affine_matrix_2: vec3<f32>,
+    @location(3) affine_matrix_3: vec3<f32>,
+    @location(4) translation_vector: vec3<f32>,
+    @location(5) a: vec3<f32>,
+    @location(6) b: vec3<f32>,
+    @location(7) c: vec3<f32>,
+    @location(8) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    var position = instance.a;
+    if vertex_index == 1u {
+        position = instance.b;
+    } else if vertex_index == 2u {
+        position = instance.c;
+    }
+
+    let model_matrix = to_model_matrix(
+        instance.affine_matrix_1,
+        instance.affine_matrix_2,
+        instance.affine_matrix_3,
+        instance.translation_vector
+    );
+
+    out.clip_position = projection * camera * model_matrix * vec4<f32>(position, 1.0);
+    out.color = instance.color;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
@@ -0,0 +1,82 @@
+use crate::{
+    camera::Camera, circle_rendering::CircleRendering, line_rendering::LineRenderering,
+    mesh_rendering::MeshRendering, point_rendering::PointRendering,
+    polygon_rendering::PolygonRendering, rectangle_rendering::RectangleRendering,
+    rendering_context::RenderingContext, sprite_rendering::SpriteRendering,
+    text_rendering::TextRendering, triangle_rendering::TriangleRendering,
+};
+
+/// Everything drawn through one camera during a frame.
+pub struct Batches {
+    pub circle_rendering: CircleRendering,
+    pub rectangle_rendering: RectangleRendering,
+    pub line_rendering: LineRenderering,
+    pub triangle_rendering: TriangleRendering,
+    pub polygon_rendering: PolygonRendering,
+    pub sprite_rendering: SpriteRendering,
+    pub point_rendering: PointRendering,
+    pub text_rendering: TextRendering,
+    pub mesh_rendering: MeshRendering,
+}
+
+impl Batches {
+    pub fn new(rendering_context: &mut RenderingContext) -> eyre::Result<Self> {
+        let mesh_rendering = MeshRendering::new(rendering_context);
+        Self::with_mesh_rendering(rendering_context, mesh_rendering)
+    }
+
+    /// Empty batches for another camera, able to draw the same meshes.
+    pub fn for_another_camera(
+        &self,
+        rendering_context: &mut RenderingContext,
+    ) -> eyre::Result<Self> {
+        let mesh_rendering = self.mesh_rendering.for_another_camera(rendering_context);
+        Self::with_mesh_rendering(rendering_context, mesh_rendering)
+    }
+
+    fn with_mesh_rendering(
+        rendering_context: &mut RenderingContext,
+        mesh_rendering: MeshRendering,
+    ) -> eyre::Result<Self> {
+        Ok(Self {
+            circle_rendering: CircleRendering::new(rendering_context)?,
+            rectangle_rendering: RectangleRendering::new(rendering_context)?,
+            line_rendering: LineRenderering::new(rendering_context)?,
+            triangle_rendering: TriangleRendering::new(rendering_context)?,
+            polygon_rendering: PolygonRendering::new(rendering_context)?,
+            sprite_rendering: SpriteRendering::new(
+                rendering_context,
+                mesh_rendering.texture_bind_group_layout(),
+            )?,
+            point_rendering: PointRendering::new(rendering_context)?,
+            text_rendering: TextRendering::new(rendering_context)?,
+            mesh_rendering,
+        })
+    }
+
+    pub fn render<'a>(
+        &'a mut self,
+        rendering_context: &'a RenderingContext,
+        camera: &'a Camera,
+        render_pass: &mut wgpu::RenderPass<'a>,
+    ) {
+        self.circle_rendering
+            .render(rendering_context, camera, render_pass);
+        self.rectangle_rendering
+            .render(rendering_context, camera, render_pass);
+        self.line_rendering
+            .render(rendering_context, camera, render_pass);
+        self.triangle_rendering
+            .render(rendering_context, camera, render_pass);
+        self.polygon_rendering
+            .render(rendering_context, camera, render_pass);
+        self.sprite_rendering
+            .render(rendering_context, camera, render_pass);
+        self.point_rendering
+            .render(rendering_context, camera, render_pass);
+        self.text_rendering
+            .render(rendering_context, camera, render_pass);
+        self.mesh_rendering
+            .render(rendering_context, camera, render_pass);
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
     marker::PhantomData,
+    mem::size_of,
     ops::{Range, RangeBounds},
 };
 
@@ -45,12 +46,36 @@ impl<T: NoUninit> WriteableBuffer<T> {
     }
 }
 
+/// When a [`WriteableVecBuffer`] gives back memory it no longer needs.
+///
+/// Usage is sampled on every write. Once `frames` writes have been made, the
+/// buffer is reallocated to the highest usage seen among them if that stayed
+/// below `threshold` of its capacity, so a short spike in the number of drawn
+/// instances does not keep its memory allocated forever.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShrinkPolicy {
+    pub frames: u32,
+    pub threshold: f32,
+}
+
+impl Default for ShrinkPolicy {
+    fn default() -> Self {
+        Self {
+            frames: 120,
+            threshold: 0.25,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct WriteableVecBuffer<T: NoUninit> {
     buffer: Buffer,
     count: usize,
     name: String,
     usage: BufferUsages,
+    /// Highest usage since the last shrink check.
+    high_water: usize,
+    frames_sampled: u32,
     phantom_data: PhantomData<T>,
 }
 
@@ -70,12 +95,36 @@ impl<T: NoUninit> WriteableVecBuffer<T> {
             buffer,
             name: name.to_string(),
             usage,
+            high_water: 0,
+            frames_sampled: 0,
             phantom_data: PhantomData,
         }
     }
 
-    pub fn write_data(&mut self, gpu_context: &GpuContext, new_data: &[T]) {
+    /// Writes `new_data` at the start of the buffer, growing it when needed
+    /// and shrinking it according to `shrink_policy`. Should be called once
+    /// per frame, also with no data, so that idle frames count as low usage.
+    pub fn write_data(
+        &mut self,
+        gpu_context: &GpuContext,
+        new_data: &[T],
+        shrink_policy: &ShrinkPolicy,
+    ) {
         let new_len = new_data.len();
+        self.high_water = self.high_water.max(new_len);
+        self.frames_sampled += 1;
+        if self.frames_sampled >= shrink_policy.frames {
+            let high_water = self.high_water;
+            self.high_water = 0;
+            self.frames_sampled = 0;
+            if (high_water as f32) < shrink_policy.threshold * self.count as f32 {
+                self.reallocate(gpu_context, high_water.max(1));
+            }
+        }
+
+        if new_data.is_empty() {
+            return;
+        }
         let byte_data: &[u8] = must_cast_slice(new_data);
         if self.count < new_len {
             let buffer =
@@ -112,6 +161,21 @@ impl<T: NoUninit> WriteableVecBuffer<T> {
         }
     }
 
+    fn reallocate(&mut self, gpu_context: &GpuContext, count: usize) {
+        self.buffer = gpu_context.device().create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&self.name),
+            size: (count * size_of::<T>()) as wgpu::BufferAddress,
+            usage: self.usage,
+            mapped_at_creation: false,
+        });
+        self.count = count;
+    }
+
+    /// Number of elements the buffer has room for.
+    pub fn capacity(&self) -> usize {
+        self.count
+    }
+
     pub fn buffer(&self) -> &Buffer {
         &self.buffer
     }
@@ -177,3 +241,43 @@ impl<T: IndexFormatTrait + NoUninit> IndexBuffer<T> {
         self.buffer.slice(bounds)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{gpu_context, require_gpu};
+
+    #[test]
+    fn buffer_shrinks_after_sustained_low_usage() {
+        let gpu_context = require_gpu!(gpu_context());
+        let policy = ShrinkPolicy {
+            frames: 10,
+            threshold: 0.25,
+        };
+        let mut buffer = WriteableVecBuffer::new(
+            &gpu_context,
+            "test buffer",
+            &[0u32; 1],
+            BufferUsages::VERTEX,
+        );
+
+        buffer.write_data(&gpu_context, &[7u32; 1000], &policy);
+        assert_eq!(buffer.capacity(), 1000);
+        for _ in 0..(policy.frames - 1) {
+            buffer.write_data(&gpu_context, &[7u32; 10], &policy);
+        }
+        // The spike was part of the first window, the buffer has to stay.
+        assert_eq!(buffer.capacity(), 1000);
+
+        for frame in 0..policy.frames {
+            buffer.write_data(&gpu_context, &vec![7u32; 10 + frame as usize], &policy);
+        }
+        assert_eq!(buffer.capacity(), 19);
+
+        // Usage close to the capacity keeps the buffer as it is.
+        for _ in 0..policy.frames {
+            buffer.write_data(&gpu_context, &[7u32; 15], &policy);
+        }
+        assert_eq!(buffer.capacity(), 19);
+    }
+}
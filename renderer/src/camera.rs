@@ -1,4 +1,5 @@
-use glam::{Mat4, Vec2};
+use glam::{vec2, Mat4, Vec2, Vec3, Vec4};
+use thiserror::Error;
 use wgpu::{BindGroupLayoutEntry, ShaderStages};
 use wgpu::{BufferUsages, DepthStencilState};
 
@@ -12,45 +13,127 @@ pub struct PrimaryCamera {
     pub projection: CameraProjection,
     pub surface_format: wgpu::TextureFormat,
     pub size: Vec2,
+    /// Depth buffer configuration, `None` disables depth testing. The depth
+    /// texture uses the `format` of this target state, e.g. `Depth32Float` or
+    /// `Depth24Plus`.
     pub depth_buffer: Option<wgpu::ColorTargetState>,
+    pub depth_mode: DepthMode,
+    /// Number of MSAA samples per pixel, 1 disables multisampling. Every
+    /// device supports 1 and 4.
+    pub sample_count: u32,
+}
+
+#[derive(Error, Debug)]
+#[error("{0} samples per pixel are not supported, only 1 and 4 are")]
+pub struct UnsupportedSampleCount(pub u32);
+
+/// Checks the sample count is one every device supports.
+pub fn check_sample_count(sample_count: u32) -> Result<(), UnsupportedSampleCount> {
+    match sample_count {
+        1 | 4 => Ok(()),
+        _ => Err(UnsupportedSampleCount(sample_count)),
+    }
+}
+
+/// How depth values are mapped into the depth buffer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DepthMode {
+    /// Near plane maps to 0.0 and far plane to 1.0, the buffer is cleared to
+    /// 1.0 and closer fragments win with `Less`.
+    #[default]
+    Standard,
+    /// Near plane maps to 1.0 and far plane to 0.0, the buffer is cleared to
+    /// 0.0 and closer fragments win with `Greater`. Combined with a floating
+    /// point depth format this spreads precision much more evenly over the
+    /// view distance, which keeps large scenes from z-fighting.
+    ReversedZ,
+}
+
+impl DepthMode {
+    pub fn depth_compare(&self) -> wgpu::CompareFunction {
+        match self {
+            DepthMode::Standard => wgpu::CompareFunction::Less,
+            DepthMode::ReversedZ => wgpu::CompareFunction::Greater,
+        }
+    }
+
+    pub fn clear_value(&self) -> f32 {
+        match self {
+            DepthMode::Standard => 1.0,
+            DepthMode::ReversedZ => 0.0,
+        }
+    }
+
+    /// Remaps the clip space depth of `projection` to follow this mode.
+    pub fn adjust_projection(&self, projection: Mat4) -> Mat4 {
+        match self {
+            DepthMode::Standard => projection,
+            // z' = w - z
+            DepthMode::ReversedZ => {
+                Mat4::from_cols(
+                    Vec4::X,
+                    Vec4::Y,
+                    Vec4::new(0.0, 0.0, -1.0, 0.0),
+                    Vec4::new(0.0, 0.0, 1.0, 1.0),
+                ) * projection
+            }
+        }
+    }
+}
+
+/// Part of the render target a camera draws into, as fractions of the
+/// target's size with the origin in the top left corner. Unlike pixels, the
+/// fractions keep e.g. split screen halves intact when the window is resized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Viewport {
+    /// The whole render target.
+    pub const FULL: Viewport = Viewport {
+        min: Vec2::ZERO,
+        max: Vec2::ONE,
+    };
+
+    pub fn new(min: Vec2, max: Vec2) -> Self {
+        Self { min, max }
+    }
+
+    /// Position and size of the viewport in pixels of a target of `target_size`.
+    pub fn to_pixels(&self, target_size: Vec2) -> (Vec2, Vec2) {
+        (self.min * target_size, (self.max - self.min) * target_size)
+    }
 }
 
 pub struct Camera {
     projection_matrix_buffer: WriteableBuffer<Mat4>,
     camera_transform_buffer: WriteableBuffer<Mat4>,
+    /// Size in pixels of what the camera renders into, for shaders sizing
+    /// things in pixels.
+    viewport_size_buffer: WriteableBuffer<Vec2>,
+    camera_matrix: Mat4,
     bing_group_layout_id: BindGroupLayoutId,
     bing_group: wgpu::BindGroup, // TODO: Make it into BindGrpuId
     projection: CameraProjection,
+    depth_mode: DepthMode,
     gpu_context: GpuContext,
     size: Vec2,
     surface_format: wgpu::TextureFormat,
     depth_texture: Option<(wgpu::ColorTargetState, wgpu::Texture, wgpu::TextureView)>,
+    sample_count: u32,
+    /// Multisampled color target resolved into the surface texture, only
+    /// present with more than one sample.
+    msaa_texture: Option<(wgpu::Texture, wgpu::TextureView)>,
 }
 
 impl Camera {
-    pub fn new(
-        gpu_context: &GpuContext,
-        resource_store: &mut ResourceStore,
-        projection: CameraProjection,
-        surface_format: wgpu::TextureFormat,
-        size: Vec2,
-        depth_texture_config: Option<wgpu::ColorTargetState>,
-    ) -> Self {
-        let projection_matrix_buffer: WriteableBuffer<Mat4> = WriteableBuffer::new(
-            gpu_context,
-            "projectino matrix buffer",
-            &projection.make_projection_matrix(size),
-            BufferUsages::UNIFORM,
-        );
-        let camera_identity_matrix = glam::Mat4::IDENTITY;
-        let camera_transform_buffer: WriteableBuffer<Mat4> = WriteableBuffer::new(
-            gpu_context,
-            "camera matrix buffer",
-            &camera_identity_matrix,
-            BufferUsages::UNIFORM,
-        );
-
-        let bind_group_layout_descriptor = wgpu::BindGroupLayoutDescriptor {
+    /// Layout of the bind group holding the projection and camera matrices,
+    /// the directional light and the viewport size in pixels. All cameras
+    /// share it, so that pipelines work with any of them.
+    pub fn build_bind_group_layout(resource_store: &mut ResourceStore) -> BindGroupLayoutId {
+        resource_store.build_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("camera bind group"),
             entries: &[
                 BindGroupLayoutEntry {
@@ -73,11 +156,63 @@ impl Camera {
                     },
                     count: None,
                 },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
-        };
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        gpu_context: &GpuContext,
+        resource_store: &ResourceStore,
+        bing_group_layout_id: BindGroupLayoutId,
+        light_buffer: &wgpu::Buffer,
+        projection: CameraProjection,
+        surface_format: wgpu::TextureFormat,
+        size: Vec2,
+        depth_texture_config: Option<wgpu::ColorTargetState>,
+        depth_mode: DepthMode,
+        sample_count: u32,
+    ) -> Self {
+        let projection_matrix_buffer: WriteableBuffer<Mat4> = WriteableBuffer::new(
+            gpu_context,
+            "projectino matrix buffer",
+            &depth_mode.adjust_projection(projection.make_projection_matrix(size)),
+            BufferUsages::UNIFORM,
+        );
+        let camera_matrix = glam::Mat4::IDENTITY;
+        let camera_transform_buffer: WriteableBuffer<Mat4> = WriteableBuffer::new(
+            gpu_context,
+            "camera matrix buffer",
+            &camera_matrix,
+            BufferUsages::UNIFORM,
+        );
+        let viewport_size_buffer: WriteableBuffer<Vec2> = WriteableBuffer::new(
+            gpu_context,
+            "viewport size buffer",
+            &size,
+            BufferUsages::UNIFORM,
+        );
 
-        let bing_group_layout_id =
-            resource_store.build_bind_group_layout(&bind_group_layout_descriptor);
         let bing_group = gpu_context
             .device()
             .create_bind_group(&wgpu::BindGroupDescriptor {
@@ -92,12 +227,20 @@ impl Camera {
                         binding: 1,
                         resource: camera_transform_buffer.buffer().as_entire_binding(),
                     },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: light_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: viewport_size_buffer.buffer().as_entire_binding(),
+                    },
                 ],
             });
 
         let depth_texture = depth_texture_config.map(|depth_texture_config| {
             let depth_texture =
-                Self::build_depth_texture(gpu_context, &size, &depth_texture_config);
+                Self::build_depth_texture(gpu_context, &size, &depth_texture_config, sample_count);
             let depth_texture_view =
                 depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
             (
@@ -106,55 +249,113 @@ impl Camera {
                 depth_texture_view,
             )
         });
+        let msaa_texture =
+            Self::build_msaa_texture(gpu_context, &size, surface_format, sample_count);
 
         Self {
             projection_matrix_buffer,
             camera_transform_buffer,
+            viewport_size_buffer,
+            camera_matrix,
             projection,
+            depth_mode,
             bing_group_layout_id,
             bing_group,
             gpu_context: gpu_context.clone(),
             size,
             surface_format,
             depth_texture,
+            sample_count,
+            msaa_texture,
         }
     }
 
     pub fn on_resize(&mut self, new_size: Vec2, gpu_context: &GpuContext) {
         self.size = new_size;
-        self.projection_matrix_buffer.write_data(
-            &self.gpu_context,
-            &self.projection.make_projection_matrix(new_size),
-        );
+        self.projection_matrix_buffer
+            .write_data(&self.gpu_context, &self.projection_matrix());
+        self.viewport_size_buffer
+            .write_data(&self.gpu_context, &new_size);
+        self.rebuild_textures(gpu_context);
+    }
+
+    /// Recreates the depth and multisampled color textures with
+    /// `sample_count` samples. Pipelines drawing through the camera have to
+    /// be rebuilt with the same count.
+    pub fn set_sample_count(&mut self, sample_count: u32, gpu_context: &GpuContext) {
+        self.sample_count = sample_count;
+        self.rebuild_textures(gpu_context);
+    }
+
+    fn rebuild_textures(&mut self, gpu_context: &GpuContext) {
+        let size = self.size;
+        let sample_count = self.sample_count;
         self.depth_texture.iter_mut().for_each(
             |(depth_texture_config, depth_texture, depth_texture_view)| {
-                *depth_texture =
-                    Self::build_depth_texture(gpu_context, &new_size, depth_texture_config);
+                *depth_texture = Self::build_depth_texture(
+                    gpu_context,
+                    &size,
+                    depth_texture_config,
+                    sample_count,
+                );
                 *depth_texture_view =
                     depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
             },
         );
+        self.msaa_texture =
+            Self::build_msaa_texture(gpu_context, &size, self.surface_format, sample_count);
     }
 
     pub fn on_scale_factor_change(&mut self, scale_factor: f32) {
         self.projection.set_scale(scale_factor);
-        self.projection_matrix_buffer.write_data(
-            &self.gpu_context,
-            &self.projection.make_projection_matrix(self.size),
-        );
+        self.projection_matrix_buffer
+            .write_data(&self.gpu_context, &self.projection_matrix());
     }
 
     pub fn set_camera_matrix(&mut self, matrix: &Mat4) {
+        self.camera_matrix = *matrix;
         self.camera_transform_buffer
             .write_data(&self.gpu_context, matrix);
     }
 
     pub fn set_camera_projection(&mut self, projection: &CameraProjection) {
         self.projection = projection.clone();
-        self.projection_matrix_buffer.write_data(
-            &self.gpu_context,
-            &self.projection.make_projection_matrix(self.size),
+        self.projection_matrix_buffer
+            .write_data(&self.gpu_context, &self.projection_matrix());
+    }
+
+    fn projection_matrix(&self) -> Mat4 {
+        self.depth_mode
+            .adjust_projection(self.projection.make_projection_matrix(self.size))
+    }
+
+    /// Ray from the near plane through the pixel at `cursor`, given in pixels
+    /// of the camera with the origin in its top left corner. Returns the
+    /// ray's origin and its normalized direction in world space. Rays of
+    /// orthographic projections are parallel to the view axis, those of
+    /// perspective ones fan out from the eye.
+    pub fn screen_to_world_ray(&self, cursor: Vec2) -> (Vec3, Vec3) {
+        let ndc = vec2(
+            2.0 * cursor.x / self.size.x - 1.0,
+            1.0 - 2.0 * cursor.y / self.size.y,
         );
+        let clip_to_world = (self.projection_matrix() * self.camera_matrix).inverse();
+        let (near_depth, far_depth) = match self.depth_mode {
+            DepthMode::Standard => (0.0, 1.0),
+            DepthMode::ReversedZ => (1.0, 0.0),
+        };
+        let near = clip_to_world.project_point3(ndc.extend(near_depth));
+        let far = clip_to_world.project_point3(ndc.extend(far_depth));
+        (near, (far - near).normalize())
+    }
+
+    /// How far in front of the camera `point` is, along its view axis.
+    pub fn view_depth(&self, point: Vec3) -> f32 {
+        -self.camera_matrix.transform_point3(point).z
+    }
+
+    pub fn depth_mode(&self) -> DepthMode {
+        self.depth_mode
     }
 
     pub fn bing_group(&self) -> &wgpu::BindGroup {
@@ -165,6 +366,10 @@ impl Camera {
         &self.bing_group_layout_id
     }
 
+    pub fn size(&self) -> Vec2 {
+        self.size
+    }
+
     pub fn surface_format(&self) -> wgpu::TextureFormat {
         self.surface_format
     }
@@ -175,24 +380,76 @@ impl Camera {
         &self.depth_texture
     }
 
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// View to render into instead of the surface texture when multisampling.
+    pub fn msaa_view(&self) -> Option<&wgpu::TextureView> {
+        self.msaa_texture.as_ref().map(|(_, view)| view)
+    }
+
+    pub fn multisample_state(&self) -> wgpu::MultisampleState {
+        wgpu::MultisampleState {
+            count: self.sample_count,
+            ..Default::default()
+        }
+    }
+
+    fn build_msaa_texture(
+        gpu_context: &GpuContext,
+        size: &Vec2,
+        surface_format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Option<(wgpu::Texture, wgpu::TextureView)> {
+        if sample_count <= 1 {
+            return None;
+        }
+        let texture = gpu_context
+            .device()
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("camera msaa texture"),
+                size: wgpu::Extent3d {
+                    width: size.x as u32,
+                    height: size.y as u32,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count,
+                dimension: wgpu::TextureDimension::D2,
+                format: surface_format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Some((texture, view))
+    }
+
     fn build_depth_texture(
         gpu_context: &GpuContext,
         size: &Vec2,
         depth_buffer_config: &wgpu::ColorTargetState,
+        sample_count: u32,
     ) -> wgpu::Texture {
         let depth_texture_size = wgpu::Extent3d {
             width: size.x as u32,
             height: size.y as u32,
             depth_or_array_layers: 1,
         };
+        // Multisampled textures can't be sampled as usual anyway, and some
+        // backends fail to resolve when only some attachments are bindable.
+        let usage = match sample_count {
+            1 => wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            _ => wgpu::TextureUsages::RENDER_ATTACHMENT,
+        };
         let depth_texture_description = wgpu::TextureDescriptor {
             label: Some("camera depth texture"),
             size: depth_texture_size,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: wgpu::TextureDimension::D2,
             format: depth_buffer_config.format,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            usage,
             view_formats: &[depth_buffer_config.format],
         };
         gpu_context
@@ -206,9 +463,146 @@ impl Camera {
             .map(|(depth_texture_config, _, _)| wgpu::DepthStencilState {
                 format: depth_texture_config.format,
                 depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
+                depth_compare: self.depth_mode.depth_compare(),
                 stencil: wgpu::StencilState::default(),
                 bias: wgpu::DepthBiasState::default(),
             })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use glam::{vec2, vec3};
+
+    use super::*;
+    use crate::{
+        colors::{BLUE, RED},
+        projection::Orthographic,
+        projection::Perspective,
+        rectangle_rendering::Rectangle,
+        test_utils::{gpu_context, render_offscreen, require_gpu, test_camera},
+        transform::Transform,
+        Renderer,
+    };
+
+    /// Draws a red quad in front of a blue one, both far away from a camera
+    /// with a tiny near plane. The farther quad is drawn first so a depth
+    /// buffer that cannot tell them apart rejects the nearer one.
+    fn render_distant_quads(depth_mode: DepthMode) -> Option<[u8; 4]> {
+        let primary_camera = PrimaryCamera {
+            projection: CameraProjection::Perspective(Perspective {
+                fovy: std::f32::consts::FRAC_PI_2,
+                znear: 0.1,
+                zfar: 1.0e6,
+                scale: 1.0,
+            }),
+            depth_buffer: Some(wgpu::ColorTargetState {
+                format: wgpu::TextureFormat::Depth32Float,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            }),
+            depth_mode,
+            ..test_camera(vec2(8.0, 8.0))
+        };
+        let pixels = render_offscreen(primary_camera, |renderer| {
+            let far = Transform::from_translation(&vec3(0.0, 0.0, -105_000.0));
+            let near = Transform::from_translation(&vec3(0.0, 0.0, -100_000.0));
+            let quad = vec2(1.0e6, 1.0e6);
+            renderer.draw_rectangle(&far, &Rectangle::new(quad, BLUE));
+            renderer.draw_rectangle(&near, &Rectangle::new(quad, RED));
+        })?;
+        Some(pixels[pixels.len() / 2 + 4])
+    }
+
+    #[test]
+    fn reversed_z_sorts_distant_geometry() {
+        let (standard, reversed) = require_gpu!(Option::zip(
+            render_distant_quads(DepthMode::Standard),
+            render_distant_quads(DepthMode::ReversedZ),
+        ));
+        // Both quads land on the same standard depth value just below 1.0.
+        assert!(reversed[0] > reversed[2], "reversed-Z pixel {reversed:?}");
+        assert!(standard[2] > standard[0], "standard pixel {standard:?}");
+    }
+
+    fn renderer_looking_down_y(
+        gpu_context: &Arc<GpuContext>,
+        projection: CameraProjection,
+        depth_mode: DepthMode,
+    ) -> Renderer {
+        let primary_camera = PrimaryCamera {
+            projection,
+            depth_mode,
+            ..test_camera(vec2(200.0, 100.0))
+        };
+        let mut renderer = Renderer::new(gpu_context, primary_camera).unwrap();
+        renderer.set_primary_camera_matrix(&Mat4::look_at_rh(
+            vec3(1.0, -5.0, 2.0),
+            vec3(1.0, 0.0, 2.0),
+            Vec3::Z,
+        ));
+        renderer
+    }
+
+    #[test]
+    fn center_ray_points_down_the_view_axis() {
+        let gpu_context = require_gpu!(gpu_context());
+        let perspective = CameraProjection::Perspective(Perspective {
+            fovy: std::f32::consts::FRAC_PI_2,
+            znear: 0.5,
+            zfar: 100.0,
+            scale: 1.0,
+        });
+        let orthographic = CameraProjection::Orthographic(Orthographic {
+            depth: 20.0,
+            scale: 1.0,
+        });
+        for (projection, depth_mode) in [
+            (perspective.clone(), DepthMode::Standard),
+            (perspective, DepthMode::ReversedZ),
+            (orthographic.clone(), DepthMode::Standard),
+            (orthographic, DepthMode::ReversedZ),
+        ] {
+            let renderer = renderer_looking_down_y(&gpu_context, projection.clone(), depth_mode);
+            let (origin, direction) = renderer.screen_to_world_ray(vec2(100.0, 50.0));
+            assert!(
+                direction.abs_diff_eq(Vec3::Y, 1e-5),
+                "{projection:?} {direction}"
+            );
+            assert!((origin.x - 1.0).abs() < 1e-4 && (origin.z - 2.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn rays_follow_the_cursor() {
+        let gpu_context = require_gpu!(gpu_context());
+        let orthographic = CameraProjection::Orthographic(Orthographic {
+            depth: 20.0,
+            scale: 2.0,
+        });
+        let renderer = renderer_looking_down_y(&gpu_context, orthographic, DepthMode::Standard);
+        // Orthographic rays are parallel, 2 pixels per world unit.
+        let (origin, direction) = renderer.screen_to_world_ray(vec2(140.0, 10.0));
+        assert!(direction.abs_diff_eq(Vec3::Y, 1e-5));
+        assert!(
+            (origin.x - 21.0).abs() < 1e-4 && (origin.z - 22.0).abs() < 1e-4,
+            "{origin}"
+        );
+
+        let perspective = CameraProjection::Perspective(Perspective {
+            fovy: std::f32::consts::FRAC_PI_2,
+            znear: 0.5,
+            zfar: 100.0,
+            scale: 1.0,
+        });
+        let renderer = renderer_looking_down_y(&gpu_context, perspective, DepthMode::Standard);
+        // Top edge of a 90 degree field of view.
+        let (_, direction) = renderer.screen_to_world_ray(vec2(100.0, 0.0));
+        assert!(
+            direction.abs_diff_eq(vec3(0.0, 1.0, 1.0).normalize(), 1e-5),
+            "{direction}"
+        );
+    }
+}
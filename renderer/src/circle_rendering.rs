@@ -1,10 +1,15 @@
 use crate::buffers::{WriteableBuffer, WriteableVecBuffer};
+use crate::camera::Camera;
 use crate::include_wgsl;
-use crate::primitives::quad::{QUAD_2D_INDICES, QUAD_2D_VERICES};
+use crate::primitives::{
+    quad::{QUAD_2D_INDICES, QUAD_2D_VERICES},
+    BorderUnits,
+};
 use crate::resource_store::PipelineId;
 use crate::transform::{Transform, TransformGpu};
 use bytemuck::{Pod, Zeroable};
-use glam::{Vec2, Vec3};
+use glam::{Vec2, Vec3, Vec4};
+use slotmap::{new_key_type, SlotMap};
 use wgpu::vertex_attr_array;
 
 use crate::resource_store::pipeline_layout::PipelineLayoutDescriptor;
@@ -16,35 +21,91 @@ use crate::{buffers::IndexBuffer, rendering_context::RenderingContext};
 #[derive(Debug, Copy, Clone, Zeroable, Pod)]
 #[repr(C, packed)]
 pub struct Circle {
-    color: Vec3, // TODO: Maybe the collor should be with alpha????
+    color: Vec4,
     radius: f32,
 }
 
 #[derive(Debug, Copy, Clone, Zeroable, Pod)]
 #[repr(C, packed)]
 pub struct CircleLine {
-    color: Vec3,
+    color: Vec4,
     radius: f32,
     border: f32,
+    border_units: u32,
 }
 
 impl Circle {
-    pub fn new(radius: f32, color: Vec3) -> Self {
+    pub fn new(radius: f32, color: Vec4) -> Self {
         Self { radius, color }
     }
 }
 
+/// Per-instance data of a circle whose transform has no rotation out of the
+/// x-y plane. Compared to a full [`TransformGpu`] plus [`Circle`] it needs less
+/// than half the bytes, which adds up with many thousands of circles.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+struct CompactCircle {
+    translation: Vec3,
+    // Already multiplied by the transform's scale.
+    radius: f32,
+    color: Vec4,
+}
+
+// SAFETY: `repr(C)` struct of `f32`s only whose `Vec4` starts at a 16 byte
+// offset, so there is no padding and any bit pattern is valid.
+unsafe impl Zeroable for CompactCircle {}
+unsafe impl Pod for CompactCircle {}
+
+impl CompactCircle {
+    /// Returns `None` when the transform tilts the circle out of the x-y
+    /// plane, such circles need the full transform.
+    fn new(transform: &Transform, circle: &Circle) -> Option<Self> {
+        transform.is_rotation_around_z().then(|| CompactCircle {
+            translation: transform.translation(),
+            radius: circle.radius * transform.scale(),
+            color: circle.color,
+        })
+    }
+}
+
 impl CircleLine {
-    pub fn new(radius: f32, color: Vec3, border: f32) -> Self {
+    pub fn new(radius: f32, color: Vec4, border: f32) -> Self {
         Self {
             radius,
             color,
             border,
+            border_units: BorderUnits::World.to_gpu(),
         }
     }
+
+    /// Interprets the border width in the given units, world units by default.
+    pub fn with_border_units(self, border_units: BorderUnits) -> Self {
+        Self {
+            border_units: border_units.to_gpu(),
+            ..self
+        }
+    }
+}
+
+new_key_type! {
+    /// Handle of circles added by [`Renderer::add_static_circles`](crate::Renderer::add_static_circles).
+    pub struct StaticCirclesId;
+}
+
+/// Circles uploaded once and drawn every frame until they are removed.
+struct StaticCircles {
+    compact_circles_buffer: WriteableVecBuffer<CompactCircle>,
+    compact_circles_count: usize,
+    circles_buffer: WriteableVecBuffer<Circle>,
+    circles_transforms_buffer: WriteableVecBuffer<TransformGpu>,
+    circles_count: usize,
 }
 
 pub struct CircleRendering {
+    static_circles: SlotMap<StaticCirclesId, StaticCircles>,
+    compact_circles_buffer: WriteableVecBuffer<CompactCircle>,
+    compact_circles: Vec<CompactCircle>,
     circles_buffer: WriteableVecBuffer<Circle>,
     circles: Vec<Circle>,
     circles_transforms: Vec<TransformGpu>,
@@ -55,6 +116,7 @@ pub struct CircleRendering {
     circle_lines_transforms_buffer: WriteableVecBuffer<TransformGpu>,
     quad_vertex_buffer: WriteableBuffer<[Vec2; 4]>,
     quad_index_buffer: IndexBuffer<u16>,
+    compact_circles_pipeline: PipelineId,
     circles_pipeline: PipelineId,
     circle_lines_pipeline: PipelineId,
 }
@@ -68,6 +130,13 @@ impl CircleRendering {
             &circles,
             wgpu::BufferUsages::VERTEX,
         );
+        let compact_circles = Vec::new();
+        let compact_circles_buffer = WriteableVecBuffer::new(
+            &rendering_context.gpu_context,
+            "compact circles buffer",
+            &compact_circles,
+            wgpu::BufferUsages::VERTEX,
+        );
         let circle_lines = Vec::new();
         let circle_lines_buffer = WriteableVecBuffer::new(
             &rendering_context.gpu_context,
@@ -94,6 +163,9 @@ impl CircleRendering {
         let circle_shader_id = rendering_context
             .resource_store
             .build_shader(&include_wgsl!("../shaders/circle.wgsl"))?;
+        let compact_circle_shader_id = rendering_context
+            .resource_store
+            .build_shader(&include_wgsl!("../shaders/circle_compact.wgsl"))?;
         let circle_line_shader_id = rendering_context
             .resource_store
             .build_shader(&include_wgsl!("../shaders/circle_line.wgsl"))?;
@@ -112,10 +184,7 @@ impl CircleRendering {
 
         let targets: Vec<Option<wgpu::ColorTargetState>> = vec![Some(wgpu::ColorTargetState {
             format: rendering_context.primary_camera.surface_format(),
-            blend: Some(wgpu::BlendState {
-                color: wgpu::BlendComponent::REPLACE,
-                alpha: wgpu::BlendComponent::REPLACE,
-            }),
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
             write_mask: wgpu::ColorWrites::ALL,
         })];
 
@@ -150,7 +219,7 @@ impl CircleRendering {
                             VertexBufferLayout {
                                 array_stride: std::mem::size_of::<Circle>() as u64,
                                 step_mode: wgpu::VertexStepMode::Instance,
-                                attributes: vertex_attr_array![5 => Float32x3, 6 => Float32]
+                                attributes: vertex_attr_array![5 => Float32x4, 6 => Float32]
                                     .to_vec(),
                             },
                         ],
@@ -165,7 +234,7 @@ impl CircleRendering {
                         conservative: false,
                     },
                     depth_stencil: rendering_context.primary_camera.depth_stencil(),
-                    multisample: wgpu::MultisampleState::default(),
+                    multisample: rendering_context.primary_camera.multisample_state(),
                     fragment: Some(FragmentState {
                         module: circle_shader_id,
                         targets: targets.clone(),
@@ -173,6 +242,47 @@ impl CircleRendering {
                     multiview: None,
                 });
 
+        let compact_circles_pipeline =
+            rendering_context
+                .resource_store
+                .build_render_pipeline(&RenderPipelineDescriptor {
+                    label: "compact circle pipeline".to_string(),
+                    layout: Some(circle_pipeline_layout_id),
+                    vertex: VertexState {
+                        module: compact_circle_shader_id,
+                        buffers: vec![
+                            VertexBufferLayout {
+                                array_stride: std::mem::size_of::<Vec2>() as u64,
+                                step_mode: wgpu::VertexStepMode::Vertex,
+                                attributes: vertex_attr_array![0 => Float32x2].to_vec(),
+                            },
+                            VertexBufferLayout {
+                                array_stride: std::mem::size_of::<CompactCircle>() as u64,
+                                step_mode: wgpu::VertexStepMode::Instance,
+                                attributes:
+                                    vertex_attr_array![1 => Float32x3, 2 => Float32, 3 => Float32x4]
+                                        .to_vec(),
+                            },
+                        ],
+                    },
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: Some(wgpu::Face::Back),
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: rendering_context.primary_camera.depth_stencil(),
+                    multisample: rendering_context.primary_camera.multisample_state(),
+                    fragment: Some(FragmentState {
+                        module: compact_circle_shader_id,
+                        targets: targets.clone(),
+                    }),
+                    multiview: None,
+                });
+
         let circle_line_pipeline_layout_id = rendering_context
             .resource_store
             .build_pipeline_layout(&PipelineLayoutDescriptor {
@@ -204,7 +314,7 @@ impl CircleRendering {
                                 array_stride: std::mem::size_of::<CircleLine>() as u64,
                                 step_mode: wgpu::VertexStepMode::Instance,
                                 attributes:
-                                    vertex_attr_array![5 => Float32x3, 6 => Float32, 7 => Float32]
+                                    vertex_attr_array![5 => Float32x4, 6 => Float32, 7 => Float32, 8 => Uint32]
                                         .to_vec(),
                             },
                         ],
@@ -219,7 +329,7 @@ impl CircleRendering {
                         conservative: false,
                     },
                     depth_stencil: rendering_context.primary_camera.depth_stencil(),
-                    multisample: wgpu::MultisampleState::default(),
+                    multisample: rendering_context.primary_camera.multisample_state(),
                     fragment: Some(FragmentState {
                         module: circle_line_shader_id,
                         targets: targets.clone(),
@@ -228,6 +338,10 @@ impl CircleRendering {
                 });
 
         Ok(Self {
+            static_circles: SlotMap::with_key(),
+            compact_circles_buffer,
+            compact_circles,
+            compact_circles_pipeline,
             circles_buffer,
             circles,
             circle_lines_buffer,
@@ -244,8 +358,13 @@ impl CircleRendering {
     }
 
     pub fn add_circle(&mut self, transform: &Transform, circle: &Circle) {
-        self.circles.push(*circle);
-        self.circles_transforms.push(transform.into());
+        match CompactCircle::new(transform, circle) {
+            Some(compact_circle) => self.compact_circles.push(compact_circle),
+            None => {
+                self.circles.push(*circle);
+                self.circles_transforms.push(transform.into());
+            }
+        }
     }
 
     pub fn add_circle_line(&mut self, transform: &Transform, circle: &CircleLine) {
@@ -253,73 +372,361 @@ impl CircleRendering {
         self.circle_lines_transforms.push(transform.into());
     }
 
+    /// Uploads `circles` once, they are drawn every frame before the circles
+    /// added for that frame alone.
+    pub fn add_static_circles(
+        &mut self,
+        rendering_context: &RenderingContext,
+        circles: &[(Transform, Circle)],
+    ) -> StaticCirclesId {
+        let mut compact_circles = Vec::new();
+        let mut full_circles = Vec::new();
+        let mut full_circles_transforms = Vec::new();
+        for (transform, circle) in circles {
+            match CompactCircle::new(transform, circle) {
+                Some(compact_circle) => compact_circles.push(compact_circle),
+                None => {
+                    full_circles.push(*circle);
+                    full_circles_transforms.push(TransformGpu::from(transform));
+                }
+            }
+        }
+        let gpu_context = &rendering_context.gpu_context;
+        self.static_circles.insert(StaticCircles {
+            compact_circles_buffer: WriteableVecBuffer::new(
+                gpu_context,
+                "static compact circles buffer",
+                &compact_circles,
+                wgpu::BufferUsages::VERTEX,
+            ),
+            compact_circles_count: compact_circles.len(),
+            circles_buffer: WriteableVecBuffer::new(
+                gpu_context,
+                "static circles buffer",
+                &full_circles,
+                wgpu::BufferUsages::VERTEX,
+            ),
+            circles_transforms_buffer: WriteableVecBuffer::new(
+                gpu_context,
+                "static circle transforms buffer",
+                &full_circles_transforms,
+                wgpu::BufferUsages::VERTEX,
+            ),
+            circles_count: full_circles.len(),
+        })
+    }
+
+    /// Stops drawing circles added by [`CircleRendering::add_static_circles`].
+    /// Returns `false` when they were already removed.
+    pub fn remove_static_circles(&mut self, static_circles_id: StaticCirclesId) -> bool {
+        self.static_circles.remove(static_circles_id).is_some()
+    }
+
+    pub fn clear_static_circles(&mut self) {
+        self.static_circles.clear();
+    }
+
+    /// Draws `instance_count` quads with `pipeline`, reading the instances
+    /// from `instance_buffers` bound after the quad vertices.
+    fn draw_quads<'a>(
+        &'a self,
+        rendering_context: &'a RenderingContext,
+        camera: &'a Camera,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        pipeline: PipelineId,
+        instance_buffers: &[wgpu::BufferSlice<'a>],
+        instance_count: usize,
+    ) {
+        if instance_count == 0 {
+            return;
+        }
+        let pipeline = &rendering_context
+            .resource_store
+            .get_render_pipeline(pipeline);
+
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, camera.bing_group(), &[]);
+        render_pass.set_vertex_buffer(0, self.quad_vertex_buffer.slice(..));
+        for (slot, instance_buffer) in instance_buffers.iter().enumerate() {
+            render_pass.set_vertex_buffer(slot as u32 + 1, *instance_buffer);
+        }
+        render_pass.set_index_buffer(
+            self.quad_index_buffer.slice(..),
+            self.quad_index_buffer.index_format(),
+        );
+        render_pass.draw_indexed(
+            self.quad_index_buffer.draw_count(),
+            0,
+            0..(instance_count as u32),
+        );
+    }
+
     pub fn render<'a>(
         &'a mut self,
         rendering_context: &'a RenderingContext,
+        camera: &'a Camera,
         render_pass: &mut wgpu::RenderPass<'a>,
     ) {
-        if !self.circles.is_empty() {
-            self.circles_buffer
-                .write_data(&rendering_context.gpu_context, &self.circles);
-            self.circles_transforms_buffer
-                .write_data(&rendering_context.gpu_context, &self.circles_transforms);
+        let gpu_context = &rendering_context.gpu_context;
+        let shrink_policy = &rendering_context.buffer_shrink_policy;
+        self.compact_circles_buffer
+            .write_data(gpu_context, &self.compact_circles, shrink_policy);
+        self.circles_buffer
+            .write_data(gpu_context, &self.circles, shrink_policy);
+        self.circles_transforms_buffer.write_data(
+            gpu_context,
+            &self.circles_transforms,
+            shrink_policy,
+        );
+        self.circle_lines_buffer
+            .write_data(gpu_context, &self.circle_lines, shrink_policy);
+        self.circle_lines_transforms_buffer.write_data(
+            gpu_context,
+            &self.circle_lines_transforms,
+            shrink_policy,
+        );
+        let compact_circles_count = self.compact_circles.len();
+        let circles_count = self.circles.len();
+        let circle_lines_count = self.circle_lines.len();
+        self.compact_circles.clear();
+        self.circles.clear();
+        self.circles_transforms.clear();
+        self.circle_lines.clear();
+        self.circle_lines_transforms.clear();
 
-            let pipeline = &rendering_context
-                .resource_store
-                .get_render_pipeline(self.circles_pipeline);
-
-            render_pass.set_pipeline(pipeline);
-            render_pass.set_bind_group(0, rendering_context.primary_camera.bing_group(), &[]);
-            render_pass.set_vertex_buffer(0, self.quad_vertex_buffer.slice(..));
-            render_pass.set_vertex_buffer(1, self.circles_transforms_buffer.slice(..));
-            render_pass.set_vertex_buffer(2, self.circles_buffer.slice(..));
-            render_pass.set_index_buffer(
-                self.quad_index_buffer.slice(..),
-                self.quad_index_buffer.index_format(),
+        let this = &*self;
+        for static_circles in this.static_circles.values() {
+            this.draw_quads(
+                rendering_context,
+                camera,
+                render_pass,
+                this.compact_circles_pipeline,
+                &[static_circles.compact_circles_buffer.slice(..)],
+                static_circles.compact_circles_count,
             );
-            render_pass.draw_indexed(
-                self.quad_index_buffer.draw_count(),
-                0,
-                0..(self.circles.len() as u32),
+            this.draw_quads(
+                rendering_context,
+                camera,
+                render_pass,
+                this.circles_pipeline,
+                &[
+                    static_circles.circles_transforms_buffer.slice(..),
+                    static_circles.circles_buffer.slice(..),
+                ],
+                static_circles.circles_count,
             );
-
-            // TODO: Think about some memory releasing strategy. Spike in number of
-            // circles will lead to space leak.
-            self.circles.clear();
-            self.circles_transforms.clear();
         }
+        this.draw_quads(
+            rendering_context,
+            camera,
+            render_pass,
+            this.compact_circles_pipeline,
+            &[this.compact_circles_buffer.slice(..)],
+            compact_circles_count,
+        );
+        this.draw_quads(
+            rendering_context,
+            camera,
+            render_pass,
+            this.circles_pipeline,
+            &[
+                this.circles_transforms_buffer.slice(..),
+                this.circles_buffer.slice(..),
+            ],
+            circles_count,
+        );
+        this.draw_quads(
+            rendering_context,
+            camera,
+            render_pass,
+            this.circle_lines_pipeline,
+            &[
+                this.circle_lines_transforms_buffer.slice(..),
+                this.circle_lines_buffer.slice(..),
+            ],
+            circle_lines_count,
+        );
+    }
+}
 
-        if !self.circle_lines.is_empty() {
-            self.circle_lines_buffer
-                .write_data(&rendering_context.gpu_context, &self.circle_lines);
-            self.circle_lines_transforms_buffer.write_data(
-                &rendering_context.gpu_context,
-                &self.circle_lines_transforms,
-            );
+#[cfg(test)]
+mod tests {
+    use std::{f32::consts::PI, mem::size_of};
 
-            let pipeline = &rendering_context
-                .resource_store
-                .get_render_pipeline(self.circle_lines_pipeline);
-
-            render_pass.set_pipeline(pipeline);
-            render_pass.set_bind_group(0, rendering_context.primary_camera.bing_group(), &[]);
-            render_pass.set_vertex_buffer(0, self.quad_vertex_buffer.slice(..));
-            render_pass.set_vertex_buffer(1, self.circle_lines_transforms_buffer.slice(..));
-            render_pass.set_vertex_buffer(2, self.circle_lines_buffer.slice(..));
-            render_pass.set_index_buffer(
-                self.quad_index_buffer.slice(..),
-                self.quad_index_buffer.index_format(),
+    use glam::{vec3, Quat};
+
+    use super::*;
+    use crate::{
+        colors::RED,
+        test_utils::{require_gpu, test_camera},
+    };
+
+    #[test]
+    fn compact_circles_halve_instance_bytes() {
+        let full = size_of::<TransformGpu>() + size_of::<Circle>();
+        let compact = size_of::<CompactCircle>();
+        assert_eq!(full, 68);
+        assert_eq!(compact, 32);
+    }
+
+    #[test]
+    fn only_tilted_circles_need_full_transform() {
+        let circle = Circle::new(2.0, RED);
+        let flat = Transform::from_translation_rotation_scale(
+            &vec3(1.0, 2.0, 3.0),
+            &Quat::from_rotation_z(PI / 3.0),
+            1.5,
+        );
+        let compact = CompactCircle::new(&flat, &circle).unwrap();
+        assert_eq!(compact.translation, vec3(1.0, 2.0, 3.0));
+        assert_eq!(compact.radius, 3.0);
+
+        let tilted = Transform::from_rotation_x(PI / 4.0);
+        assert!(CompactCircle::new(&tilted, &circle).is_none());
+    }
+
+    #[test]
+    fn compact_and_full_circles_render_alike() {
+        use crate::test_utils::render_offscreen;
+
+        let render = |transform: Transform| {
+            let primary_camera = test_camera(glam::vec2(16.0, 16.0));
+            render_offscreen(primary_camera, |renderer| {
+                renderer.draw_circle(&transform, &Circle::new(3.0, RED))
+            })
+        };
+        let (compact, full) = require_gpu!(Option::zip(
+            render(Transform::from_scale(2.0)),
+            // A barely noticeable tilt forces the full transform path.
+            render(Transform::from_translation_rotation_scale(
+                &Vec3::ZERO,
+                &Quat::from_rotation_x(0.01),
+                2.0,
+            )),
+        ));
+        assert_eq!(compact, full);
+        assert_eq!(compact[8 * 16 + 8], [230, 41, 56, 255]);
+        assert_eq!(compact[0], [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn draw_circle_matches_golden() {
+        use crate::{
+            colors::{GREEN, WHITE},
+            test_utils::{assert_image_matches, render_offscreen, to_image},
+        };
+
+        let primary_camera = test_camera(glam::vec2(32.0, 32.0));
+        let pixels = require_gpu!(render_offscreen(primary_camera, |renderer| {
+            renderer.draw_circle(&Transform::IDENTITY, &Circle::new(10.0, RED));
+            renderer.draw_circle(
+                &Transform::from_translation(&vec3(9.0, 9.0, 0.0)),
+                &Circle::new(5.0, GREEN),
             );
-            render_pass.draw_indexed(
-                self.quad_index_buffer.draw_count(),
-                0,
-                0..(self.circle_lines.len() as u32),
+            renderer.draw_circle(
+                &Transform::from_translation(&vec3(-8.0, -8.0, 0.0)),
+                &Circle::new(4.0, WHITE.truncate().extend(0.5)),
             );
+        }));
+        assert_image_matches(&to_image(&pixels, glam::uvec2(32, 32)), "circle.png", 2);
+    }
 
-            // TODO: Think about some memory releasing strategy. Spike in number of
-            // circles will lead to space leak.
-            self.circle_lines.clear();
-            self.circle_lines_transforms.clear();
-        }
+    #[test]
+    fn static_circles_stay_until_removed() {
+        use crate::test_utils::render_offscreen_frames;
+
+        // Adds the circles in the first frame and, when `remove_in` is set,
+        // removes them in that frame. Returns the center pixel of the last.
+        let render = |frame_count: usize, remove_in: Option<usize>| {
+            let primary_camera = test_camera(glam::vec2(16.0, 16.0));
+            let mut static_circles_id = None;
+            render_offscreen_frames(primary_camera, frame_count, |frame, renderer| {
+                if frame == 0 {
+                    static_circles_id = Some(renderer.add_static_circles(&[
+                        (Transform::IDENTITY, Circle::new(4.0, RED)),
+                        // Tilted, so that it takes the full transform path.
+                        (Transform::from_rotation_x(0.01), Circle::new(2.0, RED)),
+                    ]));
+                }
+                if remove_in == Some(frame) {
+                    assert!(renderer.remove_static_circles(static_circles_id.unwrap()));
+                    assert!(!renderer.remove_static_circles(static_circles_id.unwrap()));
+                }
+            })
+            .map(|pixels| pixels[8 * 16 + 8])
+        };
+        let kept = require_gpu!(render(3, None));
+        assert_eq!(kept, [230, 41, 56, 255]);
+        assert_eq!(render(3, Some(2)).unwrap(), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn circle_edges_are_antialiased() {
+        use crate::test_utils::render_offscreen;
+
+        let primary_camera = test_camera(glam::vec2(16.0, 16.0));
+        let pixels = require_gpu!(render_offscreen(primary_camera, |renderer| {
+            renderer.draw_circle(&Transform::IDENTITY, &Circle::new(5.25, RED))
+        }));
+        // The center of the third pixel of the middle row is a quarter of a
+        // pixel outside of the circle, so it is partly covered.
+        let [edge, ..] = pixels[8 * 16 + 2];
+        assert!(
+            (50..=65).contains(&edge),
+            "edge pixel {:?}",
+            pixels[8 * 16 + 2]
+        );
+        assert_eq!(pixels[8 * 16 + 3], [230, 41, 56, 255]);
+        assert_eq!(pixels[8 * 16 + 1], [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn translucent_circles_blend_with_background() {
+        use crate::{colors::WHITE, test_utils::render_offscreen};
+
+        let primary_camera = test_camera(glam::vec2(16.0, 16.0));
+        let pixels = require_gpu!(render_offscreen(primary_camera, |renderer| {
+            renderer.draw_circle(
+                &Transform::IDENTITY,
+                &Circle::new(6.0, WHITE.truncate().extend(0.5)),
+            )
+        }));
+        let [r, g, b, _] = pixels[8 * 16 + 8];
+        assert!(r > 0 && r < 255, "center pixel {:?}", pixels[8 * 16 + 8]);
+        assert_eq!((r, r), (g, b));
+    }
+
+    #[test]
+    fn pixel_borders_keep_thickness_under_zoom() {
+        use crate::{
+            camera::PrimaryCamera,
+            projection::{CameraProjection, Orthographic},
+            test_utils::render_offscreen,
+        };
+
+        // Thickness of the ring where it crosses the left half of the middle
+        // row, adding up how much of each antialiased pixel it covers.
+        let border_thickness = |scale: f32, border_units: BorderUnits| {
+            let primary_camera = PrimaryCamera {
+                projection: CameraProjection::Orthographic(Orthographic { depth: 2.0, scale }),
+                ..test_camera(glam::vec2(64.0, 64.0))
+            };
+            let circle_line = CircleLine::new(10.0, RED, 2.0).with_border_units(border_units);
+            render_offscreen(primary_camera, |renderer| {
+                renderer.draw_circle_line(&Transform::IDENTITY, &circle_line)
+            })
+            .map(|pixels| {
+                let coverage: f32 = pixels[32 * 64..32 * 64 + 32]
+                    .iter()
+                    .map(|pixel| pixel[0] as f32 / 230.0)
+                    .sum();
+                coverage.round() as usize
+            })
+        };
+        let pixels_near = require_gpu!(border_thickness(1.0, BorderUnits::Pixels));
+        assert_eq!(pixels_near, 2);
+        assert_eq!(border_thickness(2.0, BorderUnits::Pixels), Some(2));
+        assert_eq!(border_thickness(1.0, BorderUnits::World), Some(2));
+        assert_eq!(border_thickness(2.0, BorderUnits::World), Some(4));
     }
 }
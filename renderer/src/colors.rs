@@ -1,51 +1,51 @@
-use glam::{vec3, Vec3};
+use glam::{vec4, Vec4};
 
-pub const LIGHTGRAY: Vec3 = vec3(0.78, 0.78, 0.78);
-pub const GRAY: Vec3 = vec3(0.51, 0.51, 0.51);
-pub const DARKGRAY: Vec3 = vec3(0.31, 0.31, 0.31);
-pub const YELLOW: Vec3 = vec3(0.99, 0.98, 0.00);
-pub const GOLD: Vec3 = vec3(1.00, 0.80, 0.00);
-pub const ORANGE: Vec3 = vec3(1.00, 0.63, 0.00);
-pub const PINK: Vec3 = vec3(1.00, 0.43, 0.76);
-pub const RED: Vec3 = vec3(0.90, 0.16, 0.22);
-pub const MAROON: Vec3 = vec3(0.75, 0.13, 0.22);
-pub const GREEN: Vec3 = vec3(0.00, 0.89, 0.19);
-pub const LIME: Vec3 = vec3(0.00, 0.62, 0.18);
-pub const DARKGREEN: Vec3 = vec3(0.00, 0.46, 0.17);
-pub const SKYBLUE: Vec3 = vec3(0.40, 0.75, 1.00);
-pub const BLUE: Vec3 = vec3(0.00, 0.47, 0.95);
-pub const DARKBLUE: Vec3 = vec3(0.00, 0.32, 0.67);
-pub const PURPLE: Vec3 = vec3(0.78, 0.48, 1.00);
-pub const VIOLET: Vec3 = vec3(0.53, 0.24, 0.75);
-pub const DARKPURPLE: Vec3 = vec3(0.44, 0.12, 0.49);
-pub const BEIGE: Vec3 = vec3(0.83, 0.69, 0.51);
-pub const BROWN: Vec3 = vec3(0.50, 0.42, 0.31);
-pub const DARKBROWN: Vec3 = vec3(0.30, 0.25, 0.18);
-pub const WHITE: Vec3 = vec3(1.00, 1.00, 1.00);
-pub const BLACK: Vec3 = vec3(0.00, 0.00, 0.00);
-pub const BLANK: Vec3 = vec3(0.00, 0.00, 0.00);
-pub const MAGENTA: Vec3 = vec3(1.00, 0.00, 1.00);
-pub const DARKRED: Vec3 = vec3(0.46, 0.08, 0.12);
-pub const ALICE_BLUE: Vec3 = vec3(0.94, 0.97, 1.0);
-pub const ANTIQUE_WHITE: Vec3 = vec3(0.98, 0.92, 0.84);
-pub const AQUAMARINE: Vec3 = vec3(0.49, 1.0, 0.83);
-pub const AZURE: Vec3 = vec3(0.94, 1.0, 1.0);
-pub const BISQUE: Vec3 = vec3(1.0, 0.89, 0.77);
-pub const CRIMSON: Vec3 = vec3(0.86, 0.08, 0.24);
-pub const CYAN: Vec3 = vec3(0.0, 1.0, 1.0);
-pub const DARK_GRAY: Vec3 = vec3(0.25, 0.25, 0.25);
-pub const DARK_GREEN: Vec3 = vec3(0.0, 0.5, 0.0);
-pub const FUCHSIA: Vec3 = vec3(1.0, 0.0, 1.0);
-pub const INDIGO: Vec3 = vec3(0.29, 0.0, 0.51);
-pub const LIME_GREEN: Vec3 = vec3(0.2, 0.8, 0.2);
-pub const MIDNIGHT_BLUE: Vec3 = vec3(0.1, 0.1, 0.44);
-pub const NAVY: Vec3 = vec3(0.0, 0.0, 0.5);
-pub const OLIVE: Vec3 = vec3(0.5, 0.5, 0.0);
-pub const ORANGE_RED: Vec3 = vec3(1.0, 0.27, 0.0);
-pub const SALMON: Vec3 = vec3(0.98, 0.5, 0.45);
-pub const SEA_GREEN: Vec3 = vec3(0.18, 0.55, 0.34);
-pub const SILVER: Vec3 = vec3(0.75, 0.75, 0.75);
-pub const TEAL: Vec3 = vec3(0.0, 0.5, 0.5);
-pub const TOMATO: Vec3 = vec3(1.0, 0.39, 0.28);
-pub const TURQUOISE: Vec3 = vec3(0.25, 0.88, 0.82);
-pub const YELLOW_GREEN: Vec3 = vec3(0.6, 0.8, 0.2);
+pub const LIGHTGRAY: Vec4 = vec4(0.78, 0.78, 0.78, 1.0);
+pub const GRAY: Vec4 = vec4(0.51, 0.51, 0.51, 1.0);
+pub const DARKGRAY: Vec4 = vec4(0.31, 0.31, 0.31, 1.0);
+pub const YELLOW: Vec4 = vec4(0.99, 0.98, 0.00, 1.0);
+pub const GOLD: Vec4 = vec4(1.00, 0.80, 0.00, 1.0);
+pub const ORANGE: Vec4 = vec4(1.00, 0.63, 0.00, 1.0);
+pub const PINK: Vec4 = vec4(1.00, 0.43, 0.76, 1.0);
+pub const RED: Vec4 = vec4(0.90, 0.16, 0.22, 1.0);
+pub const MAROON: Vec4 = vec4(0.75, 0.13, 0.22, 1.0);
+pub const GREEN: Vec4 = vec4(0.00, 0.89, 0.19, 1.0);
+pub const LIME: Vec4 = vec4(0.00, 0.62, 0.18, 1.0);
+pub const DARKGREEN: Vec4 = vec4(0.00, 0.46, 0.17, 1.0);
+pub const SKYBLUE: Vec4 = vec4(0.40, 0.75, 1.00, 1.0);
+pub const BLUE: Vec4 = vec4(0.00, 0.47, 0.95, 1.0);
+pub const DARKBLUE: Vec4 = vec4(0.00, 0.32, 0.67, 1.0);
+pub const PURPLE: Vec4 = vec4(0.78, 0.48, 1.00, 1.0);
+pub const VIOLET: Vec4 = vec4(0.53, 0.24, 0.75, 1.0);
+pub const DARKPURPLE: Vec4 = vec4(0.44, 0.12, 0.49, 1.0);
+pub const BEIGE: Vec4 = vec4(0.83, 0.69, 0.51, 1.0);
+pub const BROWN: Vec4 = vec4(0.50, 0.42, 0.31, 1.0);
+pub const DARKBROWN: Vec4 = vec4(0.30, 0.25, 0.18, 1.0);
+pub const WHITE: Vec4 = vec4(1.00, 1.00, 1.00, 1.0);
+pub const BLACK: Vec4 = vec4(0.00, 0.00, 0.00, 1.0);
+pub const BLANK: Vec4 = vec4(0.00, 0.00, 0.00, 0.0);
+pub const MAGENTA: Vec4 = vec4(1.00, 0.00, 1.00, 1.0);
+pub const DARKRED: Vec4 = vec4(0.46, 0.08, 0.12, 1.0);
+pub const ALICE_BLUE: Vec4 = vec4(0.94, 0.97, 1.0, 1.0);
+pub const ANTIQUE_WHITE: Vec4 = vec4(0.98, 0.92, 0.84, 1.0);
+pub const AQUAMARINE: Vec4 = vec4(0.49, 1.0, 0.83, 1.0);
+pub const AZURE: Vec4 = vec4(0.94, 1.0, 1.0, 1.0);
+pub const BISQUE: Vec4 = vec4(1.0, 0.89, 0.77, 1.0);
+pub const CRIMSON: Vec4 = vec4(0.86, 0.08, 0.24, 1.0);
+pub const CYAN: Vec4 = vec4(0.0, 1.0, 1.0, 1.0);
+pub const DARK_GRAY: Vec4 = vec4(0.25, 0.25, 0.25, 1.0);
+pub const DARK_GREEN: Vec4 = vec4(0.0, 0.5, 0.0, 1.0);
+pub const FUCHSIA: Vec4 = vec4(1.0, 0.0, 1.0, 1.0);
+pub const INDIGO: Vec4 = vec4(0.29, 0.0, 0.51, 1.0);
+pub const LIME_GREEN: Vec4 = vec4(0.2, 0.8, 0.2, 1.0);
+pub const MIDNIGHT_BLUE: Vec4 = vec4(0.1, 0.1, 0.44, 1.0);
+pub const NAVY: Vec4 = vec4(0.0, 0.0, 0.5, 1.0);
+pub const OLIVE: Vec4 = vec4(0.5, 0.5, 0.0, 1.0);
+pub const ORANGE_RED: Vec4 = vec4(1.0, 0.27, 0.0, 1.0);
+pub const SALMON: Vec4 = vec4(0.98, 0.5, 0.45, 1.0);
+pub const SEA_GREEN: Vec4 = vec4(0.18, 0.55, 0.34, 1.0);
+pub const SILVER: Vec4 = vec4(0.75, 0.75, 0.75, 1.0);
+pub const TEAL: Vec4 = vec4(0.0, 0.5, 0.5, 1.0);
+pub const TOMATO: Vec4 = vec4(1.0, 0.39, 0.28, 1.0);
+pub const TURQUOISE: Vec4 = vec4(0.25, 0.88, 0.82, 1.0);
+pub const YELLOW_GREEN: Vec4 = vec4(0.6, 0.8, 0.2, 1.0);
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use thiserror::Error;
 use wgpu::{Device, Queue};
 
 #[derive(Clone)]
@@ -8,16 +9,80 @@ pub struct GpuContext {
 }
 
 struct GpuContextInner {
+    adapter_info: wgpu::AdapterInfo,
     pub device: wgpu::Device,
     // Sends data and encoded commands to GPU
     pub queue: wgpu::Queue,
 }
 
+#[derive(Error, Debug)]
+pub enum DeviceRequestError {
+    #[error("The adapter does not support the requested features {0:?}")]
+    MissingFeatures(wgpu::Features),
+    #[error(
+        "The adapter supports at most {supported} for the {name} limit, {requested} was requested"
+    )]
+    LimitExceeded {
+        name: &'static str,
+        requested: u64,
+        supported: u64,
+    },
+    #[error("Could not request device {0}")]
+    RequestDevice(#[from] wgpu::RequestDeviceError),
+}
+
 impl GpuContext {
-    pub fn new(device: Device, queue: Queue) -> Self {
+    /// `adapter_info` describes the adapter `device` was requested from.
+    pub fn new(adapter_info: wgpu::AdapterInfo, device: Device, queue: Queue) -> Self {
         Self {
-            context: Arc::new(GpuContextInner { device, queue }),
+            context: Arc::new(GpuContextInner {
+                adapter_info,
+                device,
+                queue,
+            }),
+        }
+    }
+
+    /// Creates a device on `adapter` with `required_features` and
+    /// `required_limits`. Unlike `wgpu::Adapter::request_device`, an adapter
+    /// that can't satisfy them is reported with the missing features or the
+    /// first limit it exceeds.
+    pub async fn request(
+        adapter: &wgpu::Adapter,
+        label: Option<&str>,
+        required_features: wgpu::Features,
+        required_limits: wgpu::Limits,
+    ) -> Result<Self, DeviceRequestError> {
+        let missing_features = required_features - adapter.features();
+        if !missing_features.is_empty() {
+            return Err(DeviceRequestError::MissingFeatures(missing_features));
+        }
+        let mut exceeded = None;
+        required_limits.check_limits_with_fail_fn(
+            &adapter.limits(),
+            true,
+            |name, requested, supported| {
+                exceeded = Some(DeviceRequestError::LimitExceeded {
+                    name,
+                    requested,
+                    supported,
+                })
+            },
+        );
+        if let Some(error) = exceeded {
+            return Err(error);
         }
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label,
+                    required_features,
+                    required_limits,
+                },
+                None,
+            )
+            .await?;
+        Ok(Self::new(adapter.get_info(), device, queue))
     }
 
     pub fn device(&self) -> &wgpu::Device {
@@ -26,4 +91,92 @@ impl GpuContext {
     pub fn queue(&self) -> &wgpu::Queue {
         &self.context.queue
     }
+
+    /// Limits the device was created with, e.g. the alignment of dynamic
+    /// uniform buffer offsets. These are the requested limits rather than the
+    /// best ones the adapter supports.
+    pub fn limits(&self) -> wgpu::Limits {
+        self.context.device.limits()
+    }
+
+    /// Features the device was created with.
+    pub fn features(&self) -> wgpu::Features {
+        self.context.device.features()
+    }
+
+    /// Name, vendor, backend and kind of the adapter the device runs on.
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        &self.context.adapter_info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::require_gpu;
+
+    fn adapter() -> Option<wgpu::Adapter> {
+        let instance = wgpu::Instance::default();
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+    }
+
+    #[test]
+    fn limits_are_the_requested_ones() {
+        let adapter = require_gpu!(adapter());
+        let requested = wgpu::Limits::downlevel_webgl2_defaults();
+        let gpu_context = pollster::block_on(GpuContext::request(
+            &adapter,
+            None,
+            wgpu::Features::empty(),
+            requested.clone(),
+        ))
+        .unwrap();
+        assert_eq!(gpu_context.limits(), requested);
+        assert_eq!(gpu_context.features(), wgpu::Features::empty());
+        assert_eq!(gpu_context.adapter_info().name, adapter.get_info().name);
+    }
+
+    #[test]
+    fn unsupported_features_are_named() {
+        let adapter = require_gpu!(adapter());
+        let result = pollster::block_on(GpuContext::request(
+            &adapter,
+            None,
+            wgpu::Features::all(),
+            wgpu::Limits::downlevel_webgl2_defaults(),
+        ));
+        match result {
+            Err(DeviceRequestError::MissingFeatures(missing)) => {
+                assert_eq!(missing, wgpu::Features::all() - adapter.features())
+            }
+            other => panic!("expected missing features, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn exceeded_limits_are_named() {
+        let adapter = require_gpu!(adapter());
+        let supported = adapter.limits().max_texture_dimension_2d;
+        let result = pollster::block_on(GpuContext::request(
+            &adapter,
+            None,
+            wgpu::Features::empty(),
+            wgpu::Limits {
+                max_texture_dimension_2d: supported + 1,
+                ..adapter.limits()
+            },
+        ));
+        match result {
+            Err(DeviceRequestError::LimitExceeded {
+                name,
+                requested,
+                supported: reported,
+            }) => {
+                assert_eq!(name, "max_texture_dimension_2d");
+                assert_eq!(requested, supported as u64 + 1);
+                assert_eq!(reported, supported as u64);
+            }
+            other => panic!("expected an exceeded limit, got {:?}", other.err()),
+        }
+    }
 }
@@ -1,40 +1,73 @@
+mod batches;
 pub mod buffers;
 pub mod camera;
 pub mod circle_rendering;
 pub mod colors;
 pub mod file_watcher;
 pub mod gpu_context;
+pub mod light;
 pub mod line_rendering;
 pub mod mesh_rendering;
+pub mod offscreen;
+pub mod point_rendering;
+pub mod polygon_rendering;
+pub mod prelude;
 pub mod primitives;
 pub mod projection;
 pub mod rectangle_rendering;
 pub mod rendering_context;
 pub mod resource_store;
 pub mod scene_node;
+pub mod sprite_rendering;
+#[cfg(test)]
+mod test_utils;
+pub mod text_rendering;
 pub mod transform;
+pub mod triangle_rendering;
 
 use std::sync::Arc;
 
-use glam::{Mat4, Vec2, Vec3};
-use mesh_rendering::{MeshBundle, MeshRendering};
-use resource_store::{GpuMeshId, PipelineId, ReloadError};
+use batches::Batches;
+use glam::{Mat4, UVec2, UVec3, Vec2, Vec3, Vec4};
+use mesh_rendering::MeshBundle;
+use resource_store::{
+    compute_pipeline::ComputePipelineDescriptor, ComputePipelineId, GpuMeshId, PipelineId,
+    ReloadError, TextureId, UnknownGpuMeshError,
+};
 use scene_node::SceneNode;
+use slotmap::{new_key_type, SlotMap};
 use thiserror::Error;
 use transform::Transform;
 
 use crate::{
-    camera::PrimaryCamera,
-    circle_rendering::{Circle, CircleLine, CircleRendering},
+    camera::{
+        check_sample_count, Camera, DepthMode, PrimaryCamera, UnsupportedSampleCount, Viewport,
+    },
+    circle_rendering::{Circle, CircleLine, StaticCirclesId},
     gpu_context::GpuContext,
-    line_rendering::{Line, LineRenderering},
-    projection::CameraProjection,
-    rectangle_rendering::{Rectangle, RectangleLine, RectangleRendering},
+    light::DirectionalLight,
+    line_rendering::{arrow_parts, bezier_segments, Line},
+    offscreen::OffscreenTarget,
+    point_rendering::Point,
+    polygon_rendering::Polygon,
+    projection::{CameraProjection, Orthographic},
+    rectangle_rendering::{Rectangle, RectangleLine},
     rendering_context::RenderingContext,
     resource_store::shader::ShaderSource,
+    sprite_rendering::Sprite,
 };
 
-pub struct CameraId;
+new_key_type! {
+    /// Handle of a camera created by [`Renderer::create_camera`].
+    pub struct CameraId;
+}
+
+/// A camera besides the primary one, drawing into a part of the target.
+struct SecondaryCamera {
+    camera: Camera,
+    viewport: Viewport,
+    batches: Batches,
+}
 
 pub struct Renderer {
     // TODO: This needs a bit of an discusion... I is public beccause you need
@@ -42,10 +75,14 @@ pub struct Renderer {
     // other hand. Primary camera has function which allow modification witthout
     // getting a reference to RenderingContext which holds the primary camera...
     pub rendering_context: RenderingContext,
-    circle_rendering: CircleRendering,
-    rectangle_rendering: RectangleRendering,
-    line_rendering: LineRenderering,
-    mesh_rendering: MeshRendering,
+    primary_batches: Batches,
+    cameras: SlotMap<CameraId, SecondaryCamera>,
+    /// Camera receiving the draw calls, `None` for the primary one.
+    active_camera: Option<CameraId>,
+    clear_color: wgpu::Color,
+    directional_light: DirectionalLight,
+    color_load_op: wgpu::LoadOp<wgpu::Color>,
+    depth_load_op: wgpu::LoadOp<f32>,
 }
 
 #[derive(Error, Debug)]
@@ -54,48 +91,226 @@ pub enum RenderError {
     RenderError(#[from] ReloadError),
 }
 
+#[derive(Error, Debug)]
+#[error("Wireframes need a device created with the POLYGON_MODE_LINE feature")]
+pub struct WireframeUnsupported;
+
+#[derive(Error, Debug)]
+pub enum PushConstantsError {
+    #[error("Push constants need a device created with the PUSH_CONSTANTS feature")]
+    Unsupported,
+    #[error("Push constants span {size} bytes, the device allows at most {max_size}")]
+    TooLarge { size: u32, max_size: u32 },
+}
+
 impl Renderer {
     pub fn new(gpu_context: &Arc<GpuContext>, primary_camera: PrimaryCamera) -> eyre::Result<Self> {
         let mut rendering_context = RenderingContext::new(gpu_context, primary_camera)?;
-        let circle_rendering = CircleRendering::new(&mut rendering_context)?;
-        let rectangle_rendering = RectangleRendering::new(&mut rendering_context)?;
-        let line_rendering = LineRenderering::new(&mut rendering_context)?;
-        let mesh_rendering = MeshRendering::new(&mut rendering_context);
+        let primary_batches = Batches::new(&mut rendering_context)?;
+        let depth_load_op =
+            wgpu::LoadOp::Clear(rendering_context.primary_camera.depth_mode().clear_value());
         Ok(Self {
             rendering_context,
-            circle_rendering,
-            rectangle_rendering,
-            line_rendering,
-            mesh_rendering,
+            primary_batches,
+            cameras: SlotMap::with_key(),
+            active_camera: None,
+            clear_color: wgpu::Color::BLACK,
+            directional_light: DirectionalLight::default(),
+            color_load_op: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+            depth_load_op,
         })
     }
 
+    /// Creates a renderer that draws into an [`OffscreenTarget`] instead of a
+    /// window surface, e.g. for server-side rendering or image-diff tests.
+    ///
+    /// The primary camera is orthographic with one world unit per pixel and
+    /// the origin in the middle of the target, it has a `Depth32Float` depth
+    /// buffer and no multisampling. Pass [`OffscreenTarget::texture`] to
+    /// [`Renderer::render`] and read the frame back with
+    /// [`OffscreenTarget::read_pixels`]; `format` needs four 8-bit channels.
+    pub fn new_headless(
+        gpu_context: &Arc<GpuContext>,
+        format: wgpu::TextureFormat,
+        size: UVec2,
+    ) -> eyre::Result<(Self, OffscreenTarget)> {
+        let primary_camera = PrimaryCamera {
+            projection: CameraProjection::Orthographic(Orthographic {
+                depth: 2.0,
+                scale: 1.0,
+            }),
+            surface_format: format,
+            size: size.as_vec2(),
+            depth_buffer: Some(wgpu::ColorTargetState {
+                format: wgpu::TextureFormat::Depth32Float,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            }),
+            depth_mode: DepthMode::Standard,
+            sample_count: 1,
+        };
+        let renderer = Self::new(gpu_context, primary_camera)?;
+        let target = OffscreenTarget::new(gpu_context, size, format);
+        Ok((renderer, target))
+    }
+
+    /// Sets how each frame starts: whether the previous color and depth
+    /// contents are cleared or kept. Loading the color lets frames accumulate,
+    /// e.g. for motion trails. By default both are cleared, color to
+    /// [`Renderer::clear_color`] and depth to the far plane of the camera's
+    /// [`DepthMode`](camera::DepthMode).
+    pub fn set_load_ops(&mut self, color: wgpu::LoadOp<wgpu::Color>, depth: wgpu::LoadOp<f32>) {
+        if let wgpu::LoadOp::Clear(clear_color) = color {
+            self.clear_color = clear_color;
+        }
+        self.color_load_op = color;
+        self.depth_load_op = depth;
+    }
+
+    /// Sets the background color each frame is cleared to, black by default.
+    /// Also switches the color load op back to clearing, see [`Renderer::set_load_ops`].
+    pub fn set_clear_color(&mut self, color: wgpu::Color) {
+        self.clear_color = color;
+        self.color_load_op = wgpu::LoadOp::Clear(color);
+    }
+
+    pub fn clear_color(&self) -> wgpu::Color {
+        self.clear_color
+    }
+
     // Thinking about consuming the Circle because it needs to be recreated in
     // the next render cycle anyway. On the other hand if it is an reference
     // then user can draw the same circle multiple times without much hassle.
     pub fn draw_circle(&mut self, transform: &Transform, circle: &Circle) {
-        self.circle_rendering.add_circle(transform, circle);
+        self.active_batches()
+            .circle_rendering
+            .add_circle(transform, circle);
     }
 
     pub fn draw_circle_line(&mut self, transform: &Transform, circle_line: &CircleLine) {
-        self.circle_rendering
+        self.active_batches()
+            .circle_rendering
             .add_circle_line(transform, circle_line);
     }
 
+    /// Uploads `circles` once and draws them every frame through the primary
+    /// camera, below the circles drawn for that frame alone, until they are
+    /// removed. Meant for static scenes which would otherwise have to draw
+    /// the same thousands of circles again each frame.
+    pub fn add_static_circles(&mut self, circles: &[(Transform, Circle)]) -> StaticCirclesId {
+        self.primary_batches
+            .circle_rendering
+            .add_static_circles(&self.rendering_context, circles)
+    }
+
+    /// Stops drawing circles added by [`Renderer::add_static_circles`].
+    /// Returns `false` when they were already removed.
+    pub fn remove_static_circles(&mut self, static_circles_id: StaticCirclesId) -> bool {
+        self.primary_batches
+            .circle_rendering
+            .remove_static_circles(static_circles_id)
+    }
+
+    /// Removes every circle added by [`Renderer::add_static_circles`].
+    pub fn clear_static_circles(&mut self) {
+        self.primary_batches.circle_rendering.clear_static_circles();
+    }
+
     pub fn draw_rectangle(&mut self, transform: &Transform, rectangle: &Rectangle) {
-        self.rectangle_rendering.add_rectangle(transform, rectangle);
+        self.active_batches()
+            .rectangle_rendering
+            .add_rectangle(transform, rectangle);
     }
 
     pub fn draw_rectangle_line(&mut self, transform: &Transform, rectangle_line: &RectangleLine) {
-        self.rectangle_rendering
+        self.active_batches()
+            .rectangle_rendering
             .add_rectangle_line(transform, rectangle_line);
     }
 
+    /// Draws a filled convex polygon, concave ones are not triangulated
+    /// correctly.
+    pub fn draw_polygon(&mut self, transform: &Transform, polygon: &Polygon) {
+        self.active_batches()
+            .polygon_rendering
+            .add_polygon(transform, polygon);
+    }
+
     pub fn draw_line(&mut self, transform: &Transform, line_segment: &Line) {
-        self.line_rendering
+        self.active_batches()
+            .line_rendering
             .add_line_segment(transform, line_segment);
     }
 
+    /// Draws a line `width` world units wide through `points`, with the
+    /// corners between its segments joined, e.g. for trajectories and graphs.
+    /// Polylines with less than two distinct points are skipped.
+    pub fn draw_polyline(&mut self, points: &[Vec3], color: Vec4, width: f32) {
+        self.active_batches()
+            .line_rendering
+            .add_polyline(points, color, width);
+    }
+
+    /// Draws a round dot `size` pixels across at `position`, e.g. to mark
+    /// contact points. Its size doesn't change with zoom.
+    pub fn draw_point(&mut self, position: Vec3, size: f32, color: Vec4) {
+        self.active_batches()
+            .point_rendering
+            .add_point(&Point::new(position, size, color));
+    }
+
+    /// Draws a textured rectangle, see [`Sprite`]. Sprites sharing a texture
+    /// are drawn in one call as long as no other texture comes between them.
+    pub fn draw_sprite(&mut self, transform: &Transform, sprite: &Sprite) {
+        self.active_batches()
+            .sprite_rendering
+            .add_sprite(transform, sprite);
+    }
+
+    /// Draws a single line of `text` centered on `position` in the built-in
+    /// bitmap font, e.g. to label particles. `size` is the height of the
+    /// text in pixels and doesn't change with zoom. Characters outside of
+    /// printable ASCII are drawn as `?`.
+    pub fn draw_text(&mut self, position: Vec3, text: &str, size: f32, color: Vec4) {
+        self.active_batches()
+            .text_rendering
+            .add_text(position, text, size, color);
+    }
+
+    /// Draws an arrow from `from` to `to` as a line of the given `width`
+    /// capped with a filled head `head_size` long. Zero-length arrows are
+    /// skipped.
+    pub fn draw_arrow(&mut self, from: Vec3, to: Vec3, color: Vec4, width: f32, head_size: f32) {
+        if let Some((shaft, head)) = arrow_parts(from, to, color, width, head_size) {
+            let batches = self.active_batches();
+            batches
+                .line_rendering
+                .add_line_segment(&Transform::IDENTITY, &shaft);
+            batches
+                .triangle_rendering
+                .add_triangle(&Transform::IDENTITY, &head);
+        }
+    }
+
+    /// Draws the cubic Bézier curve with control points `p0` to `p3` as
+    /// `segments` straight lines of the given `width`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_bezier(
+        &mut self,
+        p0: Vec3,
+        p1: Vec3,
+        p2: Vec3,
+        p3: Vec3,
+        color: Vec4,
+        width: f32,
+        segments: u32,
+    ) {
+        let line_rendering = &mut self.active_batches().line_rendering;
+        for line in bezier_segments([p0, p1, p2, p3], color, width, segments) {
+            line_rendering.add_line_segment(&Transform::IDENTITY, &line);
+        }
+    }
+
     // This is probably something that could be made transparent.
     pub fn add_mesh(&mut self, vertices: &[Vec3], normals: &[Vec3], indices: &[u32]) -> GpuMeshId {
         self.rendering_context
@@ -103,26 +318,244 @@ impl Renderer {
             .build_gpu_mesh(vertices, normals, indices)
     }
 
+    /// Like [`Renderer::add_mesh`], with a color for every vertex. Draw it with
+    /// a pipeline from [`Renderer::create_3d_pipeline_vertex_colored`].
+    pub fn add_mesh_colored(
+        &mut self,
+        vertices: &[Vec3],
+        normals: &[Vec3],
+        colors: &[Vec4],
+        indices: &[u32],
+    ) -> GpuMeshId {
+        self.rendering_context
+            .resource_store
+            .build_gpu_mesh_colored(vertices, normals, colors, indices)
+    }
+
+    /// Like [`Renderer::add_mesh`], with texture coordinates for every vertex.
+    /// Draw it with a pipeline from [`Renderer::create_3d_pipeline_textured`].
+    pub fn add_mesh_textured(
+        &mut self,
+        vertices: &[Vec3],
+        normals: &[Vec3],
+        uvs: &[Vec2],
+        indices: &[u32],
+    ) -> GpuMeshId {
+        self.rendering_context
+            .resource_store
+            .build_gpu_mesh_textured(vertices, normals, uvs, indices)
+    }
+
+    /// Uploads an sRGB image of `size` texels, given as RGBA bytes row by row,
+    /// for use in a [`MeshBundle`] or a [`Sprite`]. It is filtered linearly.
+    pub fn load_texture(&mut self, size: UVec2, rgba: &[u8]) -> TextureId {
+        self.primary_batches
+            .mesh_rendering
+            .load_texture(&mut self.rendering_context, size, rgba)
+    }
+
+    /// Frees a mesh created by [`Renderer::add_mesh`]. Meshes drawn this frame
+    /// have to stay alive until [`Renderer::render`], drawing a removed mesh
+    /// panics.
+    pub fn remove_mesh(&mut self, mesh_id: GpuMeshId) -> Result<(), UnknownGpuMeshError> {
+        self.rendering_context
+            .resource_store
+            .remove_gpu_mesh(mesh_id)
+    }
+
     // This is probably something that could be made transparent.
     pub fn create_3d_pipeline(&mut self, shader: &ShaderSource) -> eyre::Result<PipelineId> {
-        self.mesh_rendering
+        self.primary_batches
+            .mesh_rendering
             .create_3d_pipeline(&mut self.rendering_context, shader)
     }
 
+    /// Pipeline drawing meshes from [`Renderer::add_mesh_colored`] in their
+    /// vertex colors, lit by the [`DirectionalLight`].
+    pub fn create_3d_pipeline_vertex_colored(&mut self) -> eyre::Result<PipelineId> {
+        self.primary_batches
+            .mesh_rendering
+            .create_3d_pipeline_vertex_colored(&mut self.rendering_context)
+    }
+
+    /// Pipeline drawing meshes from [`Renderer::add_mesh_textured`] with the
+    /// texture of their [`MeshBundle`], lit by the [`DirectionalLight`].
+    pub fn create_3d_pipeline_textured(&mut self) -> eyre::Result<PipelineId> {
+        self.primary_batches
+            .mesh_rendering
+            .create_3d_pipeline_textured(&mut self.rendering_context)
+    }
+
+    /// Pipeline like [`Renderer::create_3d_pipeline`] whose shader also reads
+    /// `var<push_constant>` data in `push_constant_ranges`, set per draw with
+    /// [`Renderer::draw_mesh_with_push_constants`]. Push constants are cheaper
+    /// than uniforms for small per mesh data such as a tint, but the device
+    /// has to be created with the `PUSH_CONSTANTS` feature and a
+    /// `max_push_constant_size` limit covering the ranges.
+    pub fn create_3d_pipeline_with_push_constants(
+        &mut self,
+        shader: &ShaderSource,
+        push_constant_ranges: &[wgpu::PushConstantRange],
+    ) -> eyre::Result<PipelineId> {
+        let device = self.rendering_context.gpu_context.device();
+        if !device.features().contains(wgpu::Features::PUSH_CONSTANTS) {
+            return Err(PushConstantsError::Unsupported.into());
+        }
+        let size = push_constants_size(push_constant_ranges);
+        let max_size = device.limits().max_push_constant_size;
+        if size > max_size {
+            return Err(PushConstantsError::TooLarge { size, max_size }.into());
+        }
+        self.primary_batches
+            .mesh_rendering
+            .create_3d_pipeline_with_push_constants(
+                &mut self.rendering_context,
+                shader,
+                push_constant_ranges.to_vec(),
+            )
+    }
+
+    /// Makes `source` importable by the shaders built afterwards, e.g. with
+    /// `#import name::function`, so apps can share WGSL between their own
+    /// pipelines. Modules it imports, the built-in ones or ones registered
+    /// earlier, have to exist already.
+    pub fn register_shader_lib(&mut self, name: &str, source: &str) -> eyre::Result<()> {
+        self.rendering_context
+            .resource_store
+            .register_shader_lib(name, source)?;
+        Ok(())
+    }
+
+    /// Compute pipeline running the `cs_main` entry point of `shader`, its bind
+    /// group layout is derived from the shader. Like the render pipelines it
+    /// is rebuilt when a watched shader file changes.
+    pub fn create_compute_pipeline(
+        &mut self,
+        shader: &ShaderSource,
+    ) -> eyre::Result<ComputePipelineId> {
+        let resource_store = &mut self.rendering_context.resource_store;
+        let shader_id = resource_store.build_shader(shader)?;
+        Ok(
+            resource_store.build_compute_pipeline(&ComputePipelineDescriptor {
+                label: "compute pipeline".to_string(),
+                layout: None,
+                module: shader_id,
+            }),
+        )
+    }
+
+    /// Runs `workgroups` workgroups of the compute pipeline right away, with
+    /// `storage_buffers` bound in order to bindings 0, 1, ... of group 0. It
+    /// doesn't wait for the GPU, mapping a buffer for reading does.
+    pub fn dispatch_compute(
+        &self,
+        pipeline_id: ComputePipelineId,
+        storage_buffers: &[&wgpu::Buffer],
+        workgroups: UVec3,
+    ) {
+        let device = self.rendering_context.gpu_context.device();
+        let pipeline = self
+            .rendering_context
+            .resource_store
+            .get_compute_pipeline(pipeline_id);
+        let entries: Vec<wgpu::BindGroupEntry> = storage_buffers
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect();
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("compute storage buffers"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &entries,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("compute encoder"),
+        });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("compute pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            compute_pass.dispatch_workgroups(workgroups.x, workgroups.y, workgroups.z);
+        }
+        self.rendering_context
+            .gpu_context
+            .queue()
+            .submit(std::iter::once(encoder.finish()));
+    }
+
     pub fn draw_mesh(&mut self, transform: &Transform, mesh_bundle: &MeshBundle) {
-        self.mesh_rendering.add_mesh_bundle(transform, mesh_bundle);
+        assert!(
+            self.rendering_context
+                .resource_store
+                .contains_gpu_mesh(mesh_bundle.mesh_id),
+            "{}",
+            UnknownGpuMeshError(mesh_bundle.mesh_id)
+        );
+        self.active_batches()
+            .mesh_rendering
+            .add_mesh_bundle(transform, mesh_bundle);
+    }
+
+    /// Draws a mesh of a pipeline from
+    /// [`Renderer::create_3d_pipeline_with_push_constants`], with
+    /// `push_constants` holding the bytes of all its push constant ranges,
+    /// laid out by their offsets.
+    pub fn draw_mesh_with_push_constants(
+        &mut self,
+        transform: &Transform,
+        mesh_bundle: &MeshBundle,
+        push_constants: &[u8],
+    ) {
+        assert!(
+            self.rendering_context
+                .resource_store
+                .contains_gpu_mesh(mesh_bundle.mesh_id),
+            "{}",
+            UnknownGpuMeshError(mesh_bundle.mesh_id)
+        );
+        let ranges = self
+            .rendering_context
+            .mesh_push_constant_ranges
+            .get(mesh_bundle.pipeline_id)
+            .expect("The mesh pipeline has no push constants");
+        assert_eq!(
+            push_constants.len(),
+            push_constants_size(ranges) as usize,
+            "Push constants don't match the size of the pipeline's ranges"
+        );
+        self.active_batches()
+            .mesh_rendering
+            .add_mesh_bundle_with_push_constants(transform, mesh_bundle, push_constants);
     }
 
     pub fn draw_scene_node(&mut self, scene_node: &SceneNode) {
+        let batches = self.active_batches();
         SceneNode::draw_nodes(
             scene_node,
-            &mut self.line_rendering,
-            &mut self.rectangle_rendering,
-            &mut self.mesh_rendering,
-            &mut self.circle_rendering,
+            &mut batches.line_rendering,
+            &mut batches.rectangle_rendering,
+            &mut batches.mesh_rendering,
+            &mut batches.circle_rendering,
         );
     }
 
+    /// Draws every node of a scene kept between frames, together with their
+    /// children, with the active camera. Like the other `draw_*` calls it
+    /// only fills the batches of the next frame, so the scene is submitted
+    /// once per frame while the nodes themselves are built only once.
+    pub fn submit_scene(&mut self, scene: &[SceneNode]) {
+        for node in scene {
+            self.draw_scene_node(node);
+        }
+    }
+
     pub fn draw_instanced_mesh(&mut self, _transform: &[Transform], _mesh_bundle: &MeshBundle) {
         todo!()
     }
@@ -133,15 +566,23 @@ impl Renderer {
     //    and from scale that would be passed in.
     //  Not sure which design is better.
     pub fn on_resize(&mut self, new_size: Vec2) {
+        let gpu_context = &self.rendering_context.gpu_context;
         self.rendering_context
             .primary_camera
-            .on_resize(new_size, &self.rendering_context.gpu_context);
+            .on_resize(new_size, gpu_context);
+        for secondary in self.cameras.values_mut() {
+            let (_, size) = secondary.viewport.to_pixels(new_size);
+            secondary.camera.on_resize(size, gpu_context);
+        }
     }
 
     pub fn on_scale_factor_change(&mut self, scale_factor: f64) {
         self.rendering_context
             .primary_camera
             .on_scale_factor_change(scale_factor as f32);
+        for secondary in self.cameras.values_mut() {
+            secondary.camera.on_scale_factor_change(scale_factor as f32);
+        }
     }
 
     pub fn render(&mut self, target_texture: &wgpu::Texture) -> Result<(), RenderError> {
@@ -153,73 +594,143 @@ impl Renderer {
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("GPU Encoder"),
             });
+        let depth_texture_view = self
+            .rendering_context
+            .primary_camera
+            .depth_buffer()
+            .as_ref()
+            .map(|(_depth_texture_config, _depth_texture, depth_texture_view)| depth_texture_view);
+        // With multisampling everything is drawn into the multisampled texture
+        // and resolved into the target at the end of every pass.
+        let (color_view, resolve_target) = match self.rendering_context.primary_camera.msaa_view() {
+            Some(msaa_view) => (msaa_view, Some(&texture_view)),
+            None => (&texture_view, None),
+        };
         {
-            let color_attachments = [Some(wgpu::RenderPassColorAttachment {
-                view: &texture_view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.0,
-                        g: 0.0,
-                        b: 0.0,
-                        a: 1.0,
-                    }),
-                    store: wgpu::StoreOp::Store,
-                },
-            })];
+            let mut render_pass = begin_render_pass(
+                &mut encoder,
+                color_view,
+                resolve_target,
+                depth_texture_view,
+                self.color_load_op,
+                self.depth_load_op,
+            );
+            self.primary_batches.render(
+                &self.rendering_context,
+                &self.rendering_context.primary_camera,
+                &mut render_pass,
+            );
+        }
 
-            let depth_stencil_attachment = self
-                .rendering_context
+        // Other cameras draw over the primary one in their order of creation,
+        // each into its viewport and with the depth buffer cleared, so that
+        // e.g. a minimap is not hidden behind the scene it shows.
+        let target_size = Vec2::new(
+            target_texture.width() as f32,
+            target_texture.height() as f32,
+        );
+        let clear_depth = wgpu::LoadOp::Clear(
+            self.rendering_context
                 .primary_camera
-                .depth_buffer()
-                .as_ref()
-                .map(
-                    |(_depth_texture_config, _depth_texture, depth_texture_view)| {
-                        wgpu::RenderPassDepthStencilAttachment {
-                            view: depth_texture_view,
-                            depth_ops: Some(wgpu::Operations {
-                                load: wgpu::LoadOp::Clear(1.0),
-                                store: wgpu::StoreOp::Store,
-                            }),
-                            stencil_ops: None,
-                        }
-                    },
-                );
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Shapes Renderer Pass"),
-                color_attachments: &color_attachments,
-                depth_stencil_attachment,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-            self.circle_rendering
-                .render(&self.rendering_context, &mut render_pass);
-            self.rectangle_rendering
-                .render(&self.rendering_context, &mut render_pass);
-            self.line_rendering
-                .render(&self.rendering_context, &mut render_pass);
-            self.mesh_rendering
-                .render(&self.rendering_context, &mut render_pass);
+                .depth_mode()
+                .clear_value(),
+        );
+        for secondary in self.cameras.values_mut() {
+            let mut render_pass = begin_render_pass(
+                &mut encoder,
+                color_view,
+                resolve_target,
+                depth_texture_view,
+                wgpu::LoadOp::Load,
+                clear_depth,
+            );
+            let (position, size) = secondary.viewport.to_pixels(target_size);
+            render_pass.set_viewport(position.x, position.y, size.x, size.y, 0.0, 1.0);
+            secondary
+                .batches
+                .render(&self.rendering_context, &secondary.camera, &mut render_pass);
         }
 
         self.rendering_context
             .gpu_context
             .queue()
             .submit(std::iter::once(encoder.finish()));
-        // TODO: Think about how far to push the errors.
-        self.rendering_context
-            .resource_store
-            .reload_if_necessary()?;
+        self.rendering_context.resource_store.reload_if_necessary();
         Ok(())
     }
 
-    // For later use???
+    /// Returns the error of the last failed shader hot reload, if any, and
+    /// clears it. The shader keeps rendering with its last good version.
+    pub fn take_shader_reload_error(&mut self) -> Option<ReloadError> {
+        self.rendering_context.resource_store.take_reload_error()
+    }
+
+    /// Adds a camera drawing into `viewport` of the render target, over the
+    /// primary camera and the cameras created before it. It starts with an
+    /// identity camera matrix and receives draw calls once made active with
+    /// [`Renderer::set_active_camera`].
     pub fn create_camera(
         &mut self,
-        _transform: &Transform,
-        _projection: CameraProjection,
-    ) -> CameraId {
-        todo!()
+        projection: CameraProjection,
+        viewport: Viewport,
+    ) -> eyre::Result<CameraId> {
+        assert_valid_viewport(&viewport);
+        let primary_camera = &self.rendering_context.primary_camera;
+        let (_, size) = viewport.to_pixels(primary_camera.size());
+        let camera = Camera::new(
+            &self.rendering_context.gpu_context,
+            &self.rendering_context.resource_store,
+            *primary_camera.bing_group_layout(),
+            self.rendering_context.light_buffer.buffer(),
+            projection,
+            primary_camera.surface_format(),
+            size,
+            // All cameras share the depth and multisampled color textures of
+            // the primary one.
+            None,
+            primary_camera.depth_mode(),
+            1,
+        );
+        let batches = self
+            .primary_batches
+            .for_another_camera(&mut self.rendering_context)?;
+        Ok(self.cameras.insert(SecondaryCamera {
+            camera,
+            viewport,
+            batches,
+        }))
+    }
+
+    /// Removes the camera together with anything drawn through it this frame.
+    /// If it was active, the primary camera becomes active again.
+    pub fn remove_camera(&mut self, camera_id: &CameraId) {
+        self.cameras.remove(*camera_id);
+        if self.active_camera == Some(*camera_id) {
+            self.active_camera = None;
+        }
+    }
+
+    /// Selects the camera subsequent draw calls go through, `None` for the
+    /// primary camera.
+    pub fn set_active_camera(&mut self, camera_id: Option<CameraId>) {
+        if let Some(camera_id) = camera_id {
+            assert!(
+                self.cameras.contains_key(camera_id),
+                "{camera_id:?} does not refer to an existing camera"
+            );
+        }
+        self.active_camera = camera_id;
+    }
+
+    pub fn active_camera(&self) -> Option<CameraId> {
+        self.active_camera
+    }
+
+    fn active_batches(&mut self) -> &mut Batches {
+        match self.active_camera {
+            Some(camera_id) => &mut self.cameras[camera_id].batches,
+            None => &mut self.primary_batches,
+        }
     }
 
     pub fn set_primary_camera_projection(&mut self, projection: &CameraProjection) {
@@ -234,15 +745,749 @@ impl Renderer {
             .set_camera_matrix(matrix)
     }
 
-    pub fn set_camera_projection(&mut self, _camera_id: &CameraId, _projection: &CameraProjection) {
-        todo!()
+    /// World space ray through the pixel at `cursor` of the primary camera,
+    /// e.g. for picking objects with the mouse. `cursor` is in physical pixels
+    /// with the origin in the top left corner, as reported by winit. Returns
+    /// the ray's origin on the near plane and its normalized direction.
+    pub fn screen_to_world_ray(&self, cursor: Vec2) -> (Vec3, Vec3) {
+        self.rendering_context
+            .primary_camera
+            .screen_to_world_ray(cursor)
     }
 
-    pub fn set_camera_matrix(&mut self, _camera_id: &CameraId, _matrix: &Mat4) {
-        todo!()
+    /// Reads `texture`, usually the one just passed to [`Renderer::render`],
+    /// back to the CPU and returns its pixels row by row as RGBA bytes, e.g. to
+    /// save a screenshot. Blocks until the GPU is done.
+    ///
+    /// The texture needs the `COPY_SRC` usage and a format with four 8-bit
+    /// channels, BGRA formats are converted to RGBA.
+    pub fn capture_frame(&self, texture: &wgpu::Texture) -> Vec<u8> {
+        let gpu_context = &self.rendering_context.gpu_context;
+        let (output_buffer, padded_bytes_per_row) = offscreen::build_readback_buffer(
+            gpu_context,
+            glam::uvec2(texture.width(), texture.height()),
+            "frame capture buffer",
+        );
+        let mut pixels =
+            offscreen::read_texture(gpu_context, texture, &output_buffer, padded_bytes_per_row);
+        if matches!(
+            texture.format(),
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        ) {
+            pixels.chunks_mut(4).for_each(|pixel| pixel.swap(0, 2));
+        }
+        pixels
+    }
+
+    /// Switches the number of MSAA samples per pixel, 1 turns multisampling
+    /// off. Recreates the render textures and every pipeline, so it is meant
+    /// for settings changes rather than every frame.
+    pub fn set_sample_count(&mut self, sample_count: u32) -> Result<(), UnsupportedSampleCount> {
+        check_sample_count(sample_count)?;
+        if sample_count == self.sample_count() {
+            return Ok(());
+        }
+        self.rendering_context
+            .primary_camera
+            .set_sample_count(sample_count, &self.rendering_context.gpu_context);
+        self.rendering_context
+            .resource_store
+            .set_pipelines_sample_count(sample_count);
+        Ok(())
+    }
+
+    /// Draws meshes as wireframes, showing their triangles, or filled again.
+    /// Rebuilds the mesh pipelines, so it is meant for toggling while
+    /// debugging rather than every frame.
+    pub fn set_wireframe(&mut self, enabled: bool) -> Result<(), WireframeUnsupported> {
+        let polygon_mode = if enabled {
+            let features = self.rendering_context.gpu_context.features();
+            if !features.contains(wgpu::Features::POLYGON_MODE_LINE) {
+                return Err(WireframeUnsupported);
+            }
+            wgpu::PolygonMode::Line
+        } else {
+            wgpu::PolygonMode::Fill
+        };
+        if polygon_mode == self.rendering_context.mesh_polygon_mode {
+            return Ok(());
+        }
+        self.rendering_context.mesh_polygon_mode = polygon_mode;
+        let mesh_pipelines: Vec<PipelineId> = self
+            .rendering_context
+            .transparent_mesh_pipelines
+            .iter()
+            .flat_map(|(opaque, transparent)| [opaque, *transparent])
+            .collect();
+        self.rendering_context
+            .resource_store
+            .set_pipelines_polygon_mode(mesh_pipelines, polygon_mode);
+        Ok(())
+    }
+
+    pub fn wireframe(&self) -> bool {
+        self.rendering_context.mesh_polygon_mode == wgpu::PolygonMode::Line
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.rendering_context.primary_camera.sample_count()
+    }
+
+    /// Sets the light the 3d shaders importing `lighting` shade with, for
+    /// all cameras.
+    pub fn set_directional_light(&mut self, directional_light: &DirectionalLight) {
+        self.directional_light = *directional_light;
+        self.rendering_context.light_buffer.write_data(
+            &self.rendering_context.gpu_context,
+            &directional_light.into(),
+        );
+    }
+
+    pub fn directional_light(&self) -> &DirectionalLight {
+        &self.directional_light
+    }
+
+    pub fn set_camera_projection(&mut self, camera_id: &CameraId, projection: &CameraProjection) {
+        self.cameras[*camera_id]
+            .camera
+            .set_camera_projection(projection);
+    }
+
+    pub fn set_camera_matrix(&mut self, camera_id: &CameraId, matrix: &Mat4) {
+        self.cameras[*camera_id].camera.set_camera_matrix(matrix);
+    }
+
+    /// Like [`Renderer::screen_to_world_ray`] for a camera created by
+    /// [`Renderer::create_camera`], `cursor` is still in pixels of the whole
+    /// render target.
+    pub fn camera_screen_to_world_ray(&self, camera_id: &CameraId, cursor: Vec2) -> (Vec3, Vec3) {
+        let secondary = &self.cameras[*camera_id];
+        let (offset, _) = secondary
+            .viewport
+            .to_pixels(self.rendering_context.primary_camera.size());
+        secondary.camera.screen_to_world_ray(cursor - offset)
+    }
+
+    pub fn set_camera_viewport(&mut self, camera_id: &CameraId, viewport: Viewport) {
+        assert_valid_viewport(&viewport);
+        let (_, size) = viewport.to_pixels(self.rendering_context.primary_camera.size());
+        let secondary = &mut self.cameras[*camera_id];
+        secondary.viewport = viewport;
+        secondary
+            .camera
+            .on_resize(size, &self.rendering_context.gpu_context);
     }
 
     pub fn wgpu_limits() -> wgpu::Limits {
         RenderingContext::wgpu_limits()
     }
 }
+
+/// Bytes the push constant ranges span, from offset 0 to the end of the last.
+fn push_constants_size(ranges: &[wgpu::PushConstantRange]) -> u32 {
+    ranges
+        .iter()
+        .map(|range| range.range.end)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Panics unless the viewport is a non-empty part of the render target,
+/// wgpu rejects anything else.
+fn assert_valid_viewport(viewport: &Viewport) {
+    assert!(
+        viewport.min.cmpge(Vec2::ZERO).all()
+            && viewport.max.cmple(Vec2::ONE).all()
+            && viewport.min.cmplt(viewport.max).all(),
+        "{viewport:?} is not a non-empty part of the render target"
+    );
+}
+
+fn begin_render_pass<'a>(
+    encoder: &'a mut wgpu::CommandEncoder,
+    texture_view: &'a wgpu::TextureView,
+    resolve_target: Option<&'a wgpu::TextureView>,
+    depth_texture_view: Option<&'a wgpu::TextureView>,
+    color_load_op: wgpu::LoadOp<wgpu::Color>,
+    depth_load_op: wgpu::LoadOp<f32>,
+) -> wgpu::RenderPass<'a> {
+    let color_attachments = [Some(wgpu::RenderPassColorAttachment {
+        view: texture_view,
+        resolve_target,
+        ops: wgpu::Operations {
+            load: color_load_op,
+            store: wgpu::StoreOp::Store,
+        },
+    })];
+    let depth_stencil_attachment =
+        depth_texture_view.map(|view| wgpu::RenderPassDepthStencilAttachment {
+            view,
+            depth_ops: Some(wgpu::Operations {
+                load: depth_load_op,
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        });
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Shapes Renderer Pass"),
+        color_attachments: &color_attachments,
+        depth_stencil_attachment,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec3;
+
+    use super::*;
+    use crate::{
+        colors::{GREEN, RED},
+        test_utils::{
+            self, render_offscreen, render_offscreen_frames, require_gpu, test_camera,
+            TEXTURE_FORMAT,
+        },
+    };
+
+    fn render_two_frames(load_color: bool) -> Option<Vec<[u8; 4]>> {
+        let primary_camera = test_camera(glam::vec2(16.0, 16.0));
+        render_offscreen_frames(primary_camera, 2, |frame, renderer| {
+            if frame == 0 {
+                renderer.draw_circle(
+                    &Transform::from_translation(&vec3(-4.0, 0.0, 0.0)),
+                    &Circle::new(3.0, RED),
+                );
+            } else {
+                if load_color {
+                    renderer.set_load_ops(wgpu::LoadOp::Load, wgpu::LoadOp::Load);
+                }
+                renderer.draw_rectangle(
+                    &Transform::from_translation(&vec3(4.0, 0.0, 0.0)),
+                    &Rectangle::new(glam::vec2(6.0, 6.0), GREEN),
+                );
+            }
+        })
+    }
+
+    #[test]
+    fn headless_renderer_draws_a_circle() {
+        let gpu_context = require_gpu!(test_utils::gpu_context());
+        let (mut renderer, target) =
+            Renderer::new_headless(&gpu_context, TEXTURE_FORMAT, UVec2::new(16, 16)).unwrap();
+        renderer.draw_circle(
+            &Transform::from_translation(&vec3(-4.0, 0.0, 0.0)),
+            &Circle::new(3.0, RED),
+        );
+        renderer.render(target.texture()).unwrap();
+
+        let pixels = target.read_pixels(&gpu_context);
+        let pixel = |x: usize, y: usize| &pixels[(y * 16 + x) * 4..][..4];
+        assert_eq!(pixel(4, 8), [230, 41, 56, 255]);
+        assert_eq!(pixel(12, 8), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn loaded_color_persists_across_frames() {
+        let (loaded, cleared) = require_gpu!(Option::zip(
+            render_two_frames(true),
+            render_two_frames(false)
+        ));
+        let red = [230, 41, 56, 255];
+        let green = [0, 227, 48, 255];
+        assert_eq!(loaded[8 * 16 + 4], red);
+        assert_eq!(loaded[8 * 16 + 11], green);
+        assert_eq!(cleared[8 * 16 + 4], [0, 0, 0, 255]);
+        assert_eq!(cleared[8 * 16 + 11], green);
+    }
+
+    #[test]
+    fn cameras_draw_only_into_their_viewports() {
+        let projection = CameraProjection::Orthographic(Orthographic {
+            depth: 2.0,
+            scale: 1.0,
+        });
+        let primary_camera = PrimaryCamera {
+            projection: projection.clone(),
+            ..test_camera(glam::vec2(32.0, 16.0))
+        };
+        let pixels = require_gpu!(render_offscreen(primary_camera, |renderer| {
+            let left = renderer
+                .create_camera(
+                    projection.clone(),
+                    Viewport::new(Vec2::ZERO, glam::vec2(0.5, 1.0)),
+                )
+                .unwrap();
+            let right = renderer
+                .create_camera(projection, Viewport::new(glam::vec2(0.5, 0.0), Vec2::ONE))
+                .unwrap();
+            renderer.set_active_camera(Some(left));
+            renderer.draw_rectangle(
+                &Transform::IDENTITY,
+                &Rectangle::new(glam::vec2(4.0, 4.0), RED),
+            );
+            // Falls outside of the left viewport and must not leak into the
+            // right one.
+            renderer.draw_rectangle(
+                &Transform::from_translation(&vec3(12.0, 0.0, 0.0)),
+                &Rectangle::new(glam::vec2(4.0, 4.0), RED),
+            );
+            renderer.set_active_camera(Some(right));
+            renderer.draw_rectangle(
+                &Transform::IDENTITY,
+                &Rectangle::new(glam::vec2(4.0, 4.0), GREEN),
+            );
+        }));
+        let red = [230, 41, 56, 255];
+        let green = [0, 227, 48, 255];
+        assert_eq!(pixels[8 * 32 + 8], red);
+        assert_eq!(pixels[8 * 32 + 24], green);
+        assert_eq!(pixels[8 * 32 + 16], [0, 0, 0, 255]);
+        assert_eq!(pixels[8 * 32 + 20], [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn submitted_scene_is_drawn_every_frame() {
+        let primary_camera = test_camera(glam::vec2(16.0, 16.0));
+        // The green square is a child of the red circle, placed relative to it.
+        let scene = [SceneNode::from_circle_children(
+            Transform::from_translation(&vec3(-4.0, 0.0, 0.0)),
+            Circle::new(3.0, RED),
+            vec![SceneNode::from_rectangle(
+                Transform::from_translation(&vec3(8.0, 0.0, 0.0)),
+                Rectangle::new(glam::vec2(6.0, 6.0), GREEN),
+            )],
+        )];
+        let pixels = require_gpu!(render_offscreen_frames(primary_camera, 2, |_, renderer| {
+            renderer.submit_scene(&scene);
+        }));
+        let red = [230, 41, 56, 255];
+        let green = [0, 227, 48, 255];
+        assert_eq!(pixels[8 * 16 + 4], red);
+        assert_eq!(pixels[8 * 16 + 11], green);
+    }
+
+    fn render_rotated_square(sample_count: u32) -> Option<Vec<[u8; 4]>> {
+        let primary_camera = PrimaryCamera {
+            depth_buffer: Some(wgpu::ColorTargetState {
+                format: wgpu::TextureFormat::Depth32Float,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            }),
+            ..test_camera(glam::vec2(16.0, 16.0))
+        };
+        render_offscreen(primary_camera, |renderer| {
+            renderer.set_sample_count(sample_count).unwrap();
+            renderer.draw_rectangle(
+                &Transform::from_translation_rotation(
+                    &Vec3::ZERO,
+                    &glam::Quat::from_rotation_z(0.4),
+                ),
+                &Rectangle::new(glam::vec2(10.0, 10.0), RED),
+            );
+        })
+    }
+
+    #[test]
+    fn multisampling_smooths_edges() {
+        let (aliased, smooth) = require_gpu!(Option::zip(
+            render_rotated_square(1),
+            render_rotated_square(4)
+        ));
+        let red = [230, 41, 56, 255];
+        let black = [0, 0, 0, 255];
+        assert!(aliased.iter().all(|pixel| *pixel == red || *pixel == black));
+        assert!(smooth.iter().any(|pixel| *pixel != red && *pixel != black));
+        assert_eq!(smooth[8 * 16 + 8], red);
+        assert_eq!(smooth[0], black);
+    }
+
+    #[test]
+    fn unsupported_sample_count_is_rejected() {
+        let pixels = require_gpu!(render_offscreen(
+            test_camera(glam::vec2(4.0, 4.0)),
+            |renderer| {
+                assert!(renderer.set_sample_count(3).is_err());
+                assert_eq!(renderer.sample_count(), 1);
+            },
+        ));
+        assert_eq!(pixels.len(), 16);
+    }
+
+    #[test]
+    fn wireframe_needs_polygon_mode_line() {
+        let gpu_context = require_gpu!(test_utils::gpu_context());
+        let mut renderer = Renderer::new(&gpu_context, test_camera(glam::vec2(4.0, 4.0))).unwrap();
+        renderer.create_3d_pipeline_vertex_colored().unwrap();
+        // The test device is created without any optional features.
+        assert!(renderer.set_wireframe(true).is_err());
+        assert!(!renderer.wireframe());
+        assert!(renderer.set_wireframe(false).is_ok());
+    }
+
+    #[test]
+    fn captured_frame_matches_clear_color() {
+        let gpu_context = require_gpu!(test_utils::gpu_context());
+        // An odd width makes the rows need padding in the readback buffer.
+        let size = glam::uvec2(13, 5);
+        for format in [
+            wgpu::TextureFormat::Rgba8Unorm,
+            wgpu::TextureFormat::Bgra8Unorm,
+        ] {
+            let target = offscreen::OffscreenTarget::new(&gpu_context, size, format);
+            let mut renderer = Renderer::new(
+                &gpu_context,
+                PrimaryCamera {
+                    surface_format: format,
+                    ..test_camera(size.as_vec2())
+                },
+            )
+            .unwrap();
+            renderer.set_clear_color(wgpu::Color {
+                r: 1.0,
+                g: 0.0,
+                b: 0.2,
+                a: 1.0,
+            });
+            renderer.render(target.texture()).unwrap();
+
+            let pixels = renderer.capture_frame(target.texture());
+            assert_eq!(pixels.len(), 13 * 5 * 4);
+            assert!(
+                pixels.chunks(4).all(|pixel| pixel == [255, 0, 51, 255]),
+                "{format:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn textured_mesh_samples_its_texture() {
+        let primary_camera = test_camera(glam::vec2(8.0, 8.0));
+        let pixels = require_gpu!(render_offscreen(primary_camera, |renderer| {
+            // A quad covering the left half of the view, mapped onto the
+            // center of the red texel of a texture with a green one next to it.
+            let vertices = [
+                vec3(-4.0, -4.0, 0.0),
+                vec3(0.0, -4.0, 0.0),
+                vec3(0.0, 4.0, 0.0),
+                vec3(-4.0, 4.0, 0.0),
+            ];
+            let uvs = [glam::vec2(0.25, 0.5); 4];
+            let mesh_bundle = MeshBundle {
+                mesh_id: renderer.add_mesh_textured(
+                    &vertices,
+                    &[Vec3::Z; 4],
+                    &uvs,
+                    &[0, 1, 2, 0, 2, 3],
+                ),
+                pipeline_id: renderer.create_3d_pipeline_textured().unwrap(),
+                texture_id: Some(
+                    renderer.load_texture(glam::uvec2(2, 1), &[255, 0, 0, 255, 0, 255, 0, 255]),
+                ),
+                transparent: false,
+            };
+            renderer.draw_mesh(&Transform::IDENTITY, &mesh_bundle);
+        }));
+        let [r, g, b, _] = pixels[4 * 8 + 1];
+        assert!(r > 200 && g == 0 && b == 0, "{:?}", pixels[4 * 8 + 1]);
+        assert_eq!(pixels[4 * 8 + 6], [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn polygons_are_filled_in_their_own_colors() {
+        let primary_camera = test_camera(glam::vec2(8.0, 8.0));
+        let pixels = require_gpu!(render_offscreen(primary_camera, |renderer| {
+            // A clockwise triangle on the left and a counter clockwise
+            // pentagon on the right.
+            renderer.draw_polygon(
+                &Transform::IDENTITY,
+                &Polygon::new(
+                    &[
+                        glam::vec2(-4.0, -4.0),
+                        glam::vec2(-4.0, 4.0),
+                        glam::vec2(0.0, 4.0),
+                    ],
+                    RED,
+                ),
+            );
+            renderer.draw_polygon(
+                &Transform::from_translation(&vec3(2.0, 0.0, 0.0)),
+                &Polygon::new(
+                    &[
+                        glam::vec2(-2.0, -4.0),
+                        glam::vec2(2.0, -4.0),
+                        glam::vec2(2.0, 4.0),
+                        glam::vec2(0.0, 4.0),
+                        glam::vec2(-2.0, 0.0),
+                    ],
+                    GREEN,
+                ),
+            );
+        }));
+        assert_eq!(pixels[6 * 8], [230, 41, 56, 255]);
+        assert_eq!(pixels[8 + 6], [0, 227, 48, 255]);
+        // Below the triangle's diagonal and left of the pentagon.
+        assert_eq!(pixels[6 * 8 + 2], [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn directional_light_shades_meshes() {
+        let render_lit = |direction: Vec3| {
+            let primary_camera = test_camera(glam::vec2(4.0, 4.0));
+            render_offscreen(primary_camera, |renderer| {
+                renderer.set_directional_light(&DirectionalLight {
+                    direction,
+                    color: Vec3::ONE,
+                    ambient: Vec3::splat(0.25),
+                });
+                let vertices = [
+                    vec3(-2.0, -2.0, 0.0),
+                    vec3(2.0, -2.0, 0.0),
+                    vec3(2.0, 2.0, 0.0),
+                    vec3(-2.0, 2.0, 0.0),
+                ];
+                let mesh_bundle = MeshBundle {
+                    mesh_id: renderer.add_mesh_colored(
+                        &vertices,
+                        &[Vec3::Z; 4],
+                        &[colors::WHITE; 4],
+                        &[0, 1, 2, 0, 2, 3],
+                    ),
+                    pipeline_id: renderer.create_3d_pipeline_vertex_colored().unwrap(),
+                    texture_id: None,
+                    transparent: false,
+                };
+                renderer.draw_mesh(&Transform::IDENTITY, &mesh_bundle);
+            })
+        };
+        let facing = require_gpu!(render_lit(Vec3::NEG_Z));
+        // Only the ambient light reaches a surface parallel to the light.
+        let grazing = render_lit(Vec3::X).unwrap();
+        assert_eq!(facing[2 * 4 + 2], [255, 255, 255, 255]);
+        let [r, g, b, _] = grazing[2 * 4 + 2];
+        assert!((63..=65).contains(&r) && r == g && g == b, "{r} {g} {b}");
+    }
+
+    fn render_translucent_quads(near_first: bool) -> Option<Vec<[u8; 4]>> {
+        let primary_camera = PrimaryCamera {
+            depth_buffer: Some(wgpu::ColorTargetState {
+                format: wgpu::TextureFormat::Depth32Float,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            }),
+            ..test_camera(glam::vec2(4.0, 4.0))
+        };
+        render_offscreen(primary_camera, |renderer| {
+            renderer.set_directional_light(&DirectionalLight {
+                direction: Vec3::NEG_Z,
+                color: Vec3::ONE,
+                ambient: Vec3::ZERO,
+            });
+            let vertices = [
+                vec3(-2.0, -2.0, 0.0),
+                vec3(2.0, -2.0, 0.0),
+                vec3(2.0, 2.0, 0.0),
+                vec3(-2.0, 2.0, 0.0),
+            ];
+            let pipeline_id = renderer.create_3d_pipeline_vertex_colored().unwrap();
+            let mut quad = |color: Vec4| MeshBundle {
+                mesh_id: renderer.add_mesh_colored(
+                    &vertices,
+                    &[Vec3::Z; 4],
+                    &[color; 4],
+                    &[0, 1, 2, 0, 2, 3],
+                ),
+                pipeline_id,
+                texture_id: None,
+                transparent: true,
+            };
+            let near = (
+                Transform::from_translation(&vec3(0.0, 0.0, 0.5)),
+                quad(glam::vec4(0.0, 1.0, 0.0, 0.5)),
+            );
+            let far = (
+                Transform::from_translation(&vec3(0.0, 0.0, -0.5)),
+                quad(glam::vec4(1.0, 0.0, 0.0, 0.5)),
+            );
+            let quads = if near_first { [near, far] } else { [far, near] };
+            for (transform, mesh_bundle) in &quads {
+                renderer.draw_mesh(transform, mesh_bundle);
+            }
+        })
+    }
+
+    #[test]
+    fn transparent_meshes_blend_back_to_front() {
+        let (near_first, far_first) = require_gpu!(Option::zip(
+            render_translucent_quads(true),
+            render_translucent_quads(false),
+        ));
+        assert_eq!(near_first, far_first);
+        // The near green quad blends over the far red one, which the depth
+        // test would have hidden had the green one written depth first.
+        let [r, g, b, _] = near_first[2 * 4 + 2];
+        assert!(g > r && r > 0 && b == 0, "{r} {g} {b}");
+    }
+
+    #[test]
+    fn compute_shader_doubles_storage_buffer() {
+        use wgpu::util::DeviceExt;
+
+        let gpu_context = require_gpu!(test_utils::gpu_context());
+        let mut renderer = Renderer::new(&gpu_context, test_camera(Vec2::new(4.0, 4.0))).unwrap();
+        // More values than one workgroup of 64 invocations handles.
+        let values: Vec<f32> = (0..100).map(|i| i as f32).collect();
+        let size = std::mem::size_of_val(values.as_slice()) as wgpu::BufferAddress;
+        let device = gpu_context.device();
+        let storage_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("test values"),
+            contents: bytemuck::cast_slice(&values),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("test values readback"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let pipeline_id = renderer
+            .create_compute_pipeline(&include_wgsl!("../examples/shaders/double.wgsl"))
+            .unwrap();
+        renderer.dispatch_compute(pipeline_id, &[&storage_buffer], glam::uvec3(2, 1, 1));
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("test readback encoder"),
+        });
+        encoder.copy_buffer_to_buffer(&storage_buffer, 0, &readback_buffer, 0, size);
+        gpu_context.queue().submit(Some(encoder.finish()));
+        let buffer_slice = readback_buffer.slice(..);
+        buffer_slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+        device.poll(wgpu::Maintain::Wait);
+        let doubled: Vec<f32> = bytemuck::cast_slice(&buffer_slice.get_mapped_range()).to_vec();
+
+        let expected: Vec<f32> = values.iter().map(|value| 2.0 * value).collect();
+        assert_eq!(doubled, expected);
+    }
+
+    #[test]
+    fn mesh_transforms_bind_without_validation_errors() {
+        let gpu_context = require_gpu!(test_utils::gpu_context());
+        let (mut renderer, target) =
+            Renderer::new_headless(&gpu_context, TEXTURE_FORMAT, UVec2::new(8, 8)).unwrap();
+        gpu_context
+            .device()
+            .push_error_scope(wgpu::ErrorFilter::Validation);
+        let pipeline_id = renderer.create_3d_pipeline_vertex_colored().unwrap();
+        let mesh_bundle = MeshBundle {
+            mesh_id: renderer.add_mesh_colored(
+                &[
+                    vec3(-1.0, -1.0, 0.0),
+                    vec3(1.0, -1.0, 0.0),
+                    vec3(0.0, 1.0, 0.0),
+                ],
+                &[Vec3::Z; 3],
+                &[GREEN; 3],
+                &[0, 1, 2],
+            ),
+            pipeline_id,
+            texture_id: None,
+            transparent: false,
+        };
+        // A single bundle fits the initial uniform buffer, three grow it.
+        for bundle_count in [1, 3] {
+            for i in 0..bundle_count {
+                renderer.draw_mesh(
+                    &Transform::from_translation(&vec3(i as f32 * 2.0, 0.0, 0.0)),
+                    &mesh_bundle,
+                );
+            }
+            renderer.render(target.texture()).unwrap();
+        }
+        target.read_pixels(&gpu_context);
+        let error = pollster::block_on(gpu_context.device().pop_error_scope());
+        assert!(error.is_none(), "{error:?}");
+    }
+
+    #[test]
+    fn push_constants_need_the_feature() {
+        let gpu_context = require_gpu!(test_utils::gpu_context());
+        let mut renderer = Renderer::new(&gpu_context, test_camera(Vec2::new(4.0, 4.0))).unwrap();
+        // The test device is created without any optional features.
+        let error = renderer
+            .create_3d_pipeline_with_push_constants(
+                &include_wgsl!("../examples/shaders/tinted_mesh.wgsl"),
+                &[wgpu::PushConstantRange {
+                    stages: wgpu::ShaderStages::FRAGMENT,
+                    range: 0..16,
+                }],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<PushConstantsError>(),
+            Some(PushConstantsError::Unsupported)
+        ));
+    }
+
+    #[test]
+    fn push_constants_tint_each_mesh() {
+        let Some(gpu_context) = test_utils::gpu_context_with(
+            wgpu::Features::PUSH_CONSTANTS,
+            wgpu::Limits {
+                max_push_constant_size: 16,
+                ..Renderer::wgpu_limits()
+            },
+        ) else {
+            // Only a missing adapter fails the test, push constants are optional.
+            require_gpu!(test_utils::gpu_context());
+            eprintln!("the GPU adapter doesn't support push constants, skipping");
+            return;
+        };
+        let primary_camera = test_camera(Vec2::new(4.0, 4.0));
+        let target =
+            offscreen::OffscreenTarget::new(&gpu_context, UVec2::new(4, 4), TEXTURE_FORMAT);
+        let mut renderer = Renderer::new(&gpu_context, primary_camera).unwrap();
+        renderer.set_directional_light(&DirectionalLight {
+            direction: Vec3::NEG_Z,
+            color: Vec3::ONE,
+            ambient: Vec3::ZERO,
+        });
+        let pipeline_id = renderer
+            .create_3d_pipeline_with_push_constants(
+                &include_wgsl!("../examples/shaders/tinted_mesh.wgsl"),
+                &[wgpu::PushConstantRange {
+                    stages: wgpu::ShaderStages::FRAGMENT,
+                    range: 0..16,
+                }],
+            )
+            .unwrap();
+        // One half of the target each.
+        let mesh_bundle = MeshBundle {
+            mesh_id: renderer.add_mesh(
+                &[
+                    vec3(-1.0, -2.0, 0.0),
+                    vec3(1.0, -2.0, 0.0),
+                    vec3(1.0, 2.0, 0.0),
+                    vec3(-1.0, 2.0, 0.0),
+                ],
+                &[Vec3::Z; 4],
+                &[0, 1, 2, 0, 2, 3],
+            ),
+            pipeline_id,
+            texture_id: None,
+            transparent: false,
+        };
+        for (x, tint) in [(-1.0, Vec4::X + Vec4::W), (1.0, Vec4::Z + Vec4::W)] {
+            renderer.draw_mesh_with_push_constants(
+                &Transform::from_translation(&vec3(x, 0.0, 0.0)),
+                &mesh_bundle,
+                bytemuck::bytes_of(&tint),
+            );
+        }
+        renderer.render(target.texture()).unwrap();
+
+        let pixels = target.read_pixels(&gpu_context);
+        let pixel = |x: usize, y: usize| &pixels[(y * 4 + x) * 4..(y * 4 + x) * 4 + 4];
+        assert_eq!(pixel(0, 1), [255, 0, 0, 255]);
+        assert_eq!(pixel(3, 2), [0, 0, 255, 255]);
+    }
+}
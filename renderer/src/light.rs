@@ -0,0 +1,73 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{vec3, Vec3};
+
+/// Light shining on the whole scene from one direction, like the sun. Used
+/// by the 3d shaders through the `lighting` shader lib.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirectionalLight {
+    /// Direction the light travels in, it doesn't have to be normalized.
+    pub direction: Vec3,
+    pub color: Vec3,
+    /// Light reaching the surfaces facing away from the light too.
+    pub ambient: Vec3,
+}
+
+impl Default for DirectionalLight {
+    fn default() -> Self {
+        Self {
+            direction: vec3(-0.3, -0.5, -1.0),
+            color: Vec3::splat(0.8),
+            ambient: Vec3::splat(0.2),
+        }
+    }
+}
+
+/// [`DirectionalLight`] laid out like the `DirectionalLight` WGSL struct,
+/// where every `vec3` is aligned to 16 bytes.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct DirectionalLightGpu {
+    direction: Vec3,
+    _padding_1: f32,
+    color: Vec3,
+    _padding_2: f32,
+    ambient: Vec3,
+    _padding_3: f32,
+}
+
+// SAFETY: `repr(C)` struct of `f32`s only, every `Vec3` is followed by an
+// explicit padding `f32`, so there is no implicit padding and any bit pattern
+// is valid.
+unsafe impl Zeroable for DirectionalLightGpu {}
+unsafe impl Pod for DirectionalLightGpu {}
+
+impl From<&DirectionalLight> for DirectionalLightGpu {
+    fn from(light: &DirectionalLight) -> Self {
+        Self {
+            direction: light.direction.normalize_or_zero(),
+            _padding_1: 0.0,
+            color: light.color,
+            _padding_2: 0.0,
+            ambient: light.ambient,
+            _padding_3: 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::size_of;
+
+    use super::*;
+
+    #[test]
+    fn gpu_light_matches_wgsl_layout() {
+        assert_eq!(size_of::<DirectionalLightGpu>(), 48);
+        let light = DirectionalLightGpu::from(&DirectionalLight {
+            direction: vec3(0.0, 0.0, -2.0),
+            ..Default::default()
+        });
+        let floats: &[f32] = bytemuck::cast_slice(bytemuck::bytes_of(&light));
+        assert_eq!(&floats[0..4], &[0.0, 0.0, -1.0, 0.0]);
+    }
+}
@@ -1,11 +1,15 @@
 use bytemuck::{Pod, Zeroable};
-use glam::{Vec2, Vec3};
+use glam::{Vec2, Vec3, Vec4};
 use wgpu::vertex_attr_array;
 
 use crate::{
     buffers::{IndexBuffer, WriteableBuffer, WriteableVecBuffer},
+    camera::Camera,
     include_wgsl,
-    primitives::quad::{QUAD_2D_INDICES, QUAD_2D_VERICES},
+    primitives::{
+        quad::{QUAD_2D_INDICES, QUAD_2D_VERICES},
+        BorderUnits,
+    },
     rendering_context::RenderingContext,
     resource_store::{
         pipeline_layout::PipelineLayoutDescriptor,
@@ -15,26 +19,196 @@ use crate::{
         PipelineId,
     },
     transform::{Transform, TransformGpu},
+    triangle_rendering::Triangle,
 };
 
+/// Largest fraction of an arrow's length that its head may take up.
+const MAX_HEAD_FRACTION: f32 = 0.5;
+/// Longest a miter join may get, as a multiple of the polyline's width, before
+/// the corner is beveled instead. The same default as SVG's `stroke-miterlimit`.
+const MITER_LIMIT: f32 = 4.0;
+
 #[derive(Debug, Copy, Clone, Zeroable, Pod)]
 #[repr(C, packed)]
 pub struct Line {
     pub from: Vec3,
     pub to: Vec3,
-    pub color: Vec3,
+    pub color: Vec4,
+    /// Width in the units of `width_units`. World widths are measured in the
+    /// space of the line's transform, so they scale with it and with zoom.
     pub width: f32,
+    /// Length of the dashes along the line, 0 draws a solid line.
+    pub dash_length: f32,
+    /// Length of the gaps between the dashes, 0 draws a solid line.
+    pub gap_length: f32,
+    width_units: u32,
 }
 
 impl Line {
-    pub fn new(from: Vec3, to: Vec3, color: Vec3, width: f32) -> Self {
+    pub fn new(from: Vec3, to: Vec3, color: Vec4, width: f32) -> Self {
         Self {
             from,
             to,
             color,
             width,
+            dash_length: 0.0,
+            gap_length: 0.0,
+            width_units: BorderUnits::World.to_gpu(),
+        }
+    }
+
+    /// Interprets the width in the given units, world units by default.
+    pub fn with_width_units(self, width_units: BorderUnits) -> Self {
+        Self {
+            width_units: width_units.to_gpu(),
+            ..self
+        }
+    }
+
+    /// Draws the line as dashes of `dash_length` separated by `gap_length`,
+    /// both in world units, starting with a dash at `from`. Dotted lines are
+    /// dashes about as long as the line is wide.
+    pub fn with_dashes(self, dash_length: f32, gap_length: f32) -> Self {
+        Self {
+            dash_length,
+            gap_length,
+            ..self
+        }
+    }
+}
+
+/// Splits an arrow into its shaft and a filled head whose base is as wide as
+/// the head is long. Short arrows shrink the head proportionally so it never
+/// covers more than [`MAX_HEAD_FRACTION`] of the arrow. Zero-length arrows have
+/// no direction and yield `None`.
+pub fn arrow_parts(
+    from: Vec3,
+    to: Vec3,
+    color: Vec4,
+    width: f32,
+    head_size: f32,
+) -> Option<(Line, Triangle)> {
+    let delta = to - from;
+    let length = delta.length();
+    if length <= f32::EPSILON {
+        return None;
+    }
+    let direction = delta / length;
+    let head_length = head_size.min(length * MAX_HEAD_FRACTION);
+    // Arrows mostly live in the xy-plane, so widen the head within it unless
+    // the arrow points along the z-axis.
+    let side = direction
+        .cross(Vec3::Z)
+        .try_normalize()
+        .unwrap_or_else(|| direction.cross(Vec3::X).normalize());
+    let base = to - direction * head_length;
+    let half_base = side * (head_length / 2.0);
+
+    Some((
+        Line::new(from, base, color, width),
+        Triangle::new(to, base + half_base, base - half_base, color),
+    ))
+}
+
+/// Tessellates the cubic Bézier curve with control points `p0` to `p3` into
+/// `segments` straight lines of equal parameter step.
+///
+/// # Panics
+///
+/// Panics when `segments` is zero.
+pub fn bezier_segments(
+    [p0, p1, p2, p3]: [Vec3; 4],
+    color: Vec4,
+    width: f32,
+    segments: u32,
+) -> impl Iterator<Item = Line> {
+    assert!(segments > 0, "A Bézier curve needs at least one segment");
+    let point = move |i: u32| {
+        let t = i as f32 / segments as f32;
+        let s = 1.0 - t;
+        s * s * s * p0 + 3.0 * s * s * t * p1 + 3.0 * s * t * t * p2 + t * t * t * p3
+    };
+    (0..segments).map(move |i| Line::new(point(i), point(i + 1), color, width))
+}
+
+/// Unit vector perpendicular to `direction` within the xy-plane, or to the
+/// x-axis for directions along the z-axis.
+fn side(direction: Vec3) -> Vec3 {
+    Vec3::Z
+        .cross(direction)
+        .try_normalize()
+        .unwrap_or_else(|| direction.cross(Vec3::X).normalize())
+}
+
+/// Tessellates a polyline `width` wide through `points` into a triangle list.
+/// Every segment is a quad and the corners between them are mitered, or
+/// beveled where the miter would exceed [`MITER_LIMIT`]. Like arrows, the
+/// corners are joined within the xy-plane. Repeated points are skipped and
+/// polylines with less than two distinct points yield no triangles.
+pub fn polyline_triangles(points: &[Vec3], width: f32) -> Vec<Vec3> {
+    let mut distinct: Vec<Vec3> = Vec::with_capacity(points.len());
+    for &point in points {
+        if distinct
+            .last()
+            .is_none_or(|last| last.distance(point) > f32::EPSILON)
+        {
+            distinct.push(point);
         }
     }
+    if distinct.len() < 2 {
+        return Vec::new();
+    }
+
+    let half_width = width / 2.0;
+    let directions: Vec<Vec3> = distinct
+        .windows(2)
+        .map(|w| (w[1] - w[0]).normalize())
+        .collect();
+    let sides: Vec<Vec3> = directions.iter().copied().map(side).collect();
+    let edge = |point: Vec3, offset: Vec3| (point + offset, point - offset);
+
+    let mut triangles = Vec::with_capacity(directions.len() * 9);
+    let mut quad = |(start_left, start_right): (Vec3, Vec3),
+                    (end_left, end_right): (Vec3, Vec3)| {
+        triangles.extend([start_left, start_right, end_right]);
+        triangles.extend([start_left, end_right, end_left]);
+    };
+    let mut start = edge(distinct[0], sides[0] * half_width);
+    let mut bevels = Vec::new();
+    for (i, &point) in distinct.iter().enumerate().skip(1) {
+        let side = sides[i - 1];
+        let Some(&next_side) = sides.get(i) else {
+            quad(start, edge(point, side * half_width));
+            break;
+        };
+        // The miter bisects the corner, and has to be longer the sharper the
+        // corner is to keep the segments' edges parallel to them.
+        let miter = (side + next_side)
+            .try_normalize()
+            .map(|miter| miter / miter.dot(side))
+            .filter(|miter| miter.length() <= MITER_LIMIT);
+        match miter {
+            Some(miter) => {
+                let corner = edge(point, miter * half_width);
+                quad(start, corner);
+                start = corner;
+            }
+            None => {
+                let end = edge(point, side * half_width);
+                let next_start = edge(point, next_side * half_width);
+                quad(start, end);
+                // Only the outside of the corner has a gap to fill.
+                if directions[i - 1].cross(directions[i]).z > 0.0 {
+                    bevels.extend([point, end.1, next_start.1]);
+                } else {
+                    bevels.extend([point, end.0, next_start.0]);
+                }
+                start = next_start;
+            }
+        }
+    }
+    triangles.extend(bevels);
+    triangles
 }
 
 pub struct LineRenderering {
@@ -45,6 +219,12 @@ pub struct LineRenderering {
     line_segment_pipeline: PipelineId,
     quad_vertex_buffer: WriteableBuffer<[Vec2; 4]>,
     quad_index_buffer: IndexBuffer<u16>,
+    /// Triangles of the polylines, tessellated by [`polyline_triangles`].
+    polyline_vertices: Vec<Vec3>,
+    polyline_vertices_buffer: WriteableVecBuffer<Vec3>,
+    polyline_colors: Vec<Vec4>,
+    polyline_colors_buffer: WriteableVecBuffer<Vec4>,
+    polyline_pipeline: PipelineId,
 }
 
 impl LineRenderering {
@@ -82,10 +262,7 @@ impl LineRenderering {
 
         let targets: Vec<Option<wgpu::ColorTargetState>> = vec![Some(wgpu::ColorTargetState {
             format: rendering_context.primary_camera.surface_format(),
-            blend: Some(wgpu::BlendState {
-                color: wgpu::BlendComponent::REPLACE,
-                alpha: wgpu::BlendComponent::REPLACE,
-            }),
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
             write_mask: wgpu::ColorWrites::ALL,
         })];
 
@@ -119,7 +296,7 @@ impl LineRenderering {
                             VertexBufferLayout {
                                 array_stride: std::mem::size_of::<Line>() as u64,
                                 step_mode: wgpu::VertexStepMode::Instance,
-                                attributes: vertex_attr_array![5 => Float32x3, 6 => Float32x3, 7 => Float32x3, 8 => Float32]
+                                attributes: vertex_attr_array![5 => Float32x3, 6 => Float32x3, 7 => Float32x4, 8 => Float32, 9 => Float32, 10 => Float32, 11 => Uint32]
                                     .to_vec(),
                             },
                         ],
@@ -134,7 +311,7 @@ impl LineRenderering {
                         conservative: false,
                     },
                     depth_stencil: rendering_context.primary_camera.depth_stencil(),
-                    multisample: wgpu::MultisampleState::default(),
+                    multisample: rendering_context.primary_camera.multisample_state(),
                     fragment: Some(FragmentState {
                         module: line_segment_shader_id,
                         targets: targets.clone(),
@@ -142,6 +319,63 @@ impl LineRenderering {
                     multiview: None,
                 });
 
+        let polyline_vertices = Vec::new();
+        let polyline_vertices_buffer = WriteableVecBuffer::new(
+            &rendering_context.gpu_context,
+            "polyline vertices buffer",
+            &polyline_vertices,
+            wgpu::BufferUsages::VERTEX,
+        );
+        let polyline_colors = Vec::new();
+        let polyline_colors_buffer = WriteableVecBuffer::new(
+            &rendering_context.gpu_context,
+            "polyline colors buffer",
+            &polyline_colors,
+            wgpu::BufferUsages::VERTEX,
+        );
+        let polyline_shader_id = rendering_context
+            .resource_store
+            .build_shader(&include_wgsl!("../shaders/polyline.wgsl"))?;
+        let polyline_pipeline =
+            rendering_context
+                .resource_store
+                .build_render_pipeline(&RenderPipelineDescriptor {
+                    label: "polyline pipeline".to_string(),
+                    layout: Some(line_segment_pipeline_layout_id),
+                    vertex: VertexState {
+                        module: polyline_shader_id,
+                        buffers: vec![
+                            VertexBufferLayout {
+                                array_stride: std::mem::size_of::<Vec3>() as u64,
+                                step_mode: wgpu::VertexStepMode::Vertex,
+                                attributes: vertex_attr_array![0 => Float32x3].to_vec(),
+                            },
+                            VertexBufferLayout {
+                                array_stride: std::mem::size_of::<Vec4>() as u64,
+                                step_mode: wgpu::VertexStepMode::Vertex,
+                                attributes: vertex_attr_array![1 => Float32x4].to_vec(),
+                            },
+                        ],
+                    },
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        // Which way the triangles wind depends on the turns.
+                        cull_mode: None,
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: rendering_context.primary_camera.depth_stencil(),
+                    multisample: rendering_context.primary_camera.multisample_state(),
+                    fragment: Some(FragmentState {
+                        module: polyline_shader_id,
+                        targets,
+                    }),
+                    multiview: None,
+                });
+
         Ok(Self {
             line_segments,
             line_segments_buffer,
@@ -150,6 +384,11 @@ impl LineRenderering {
             quad_index_buffer,
             line_segments_transforms,
             line_segments_transforms_buffer,
+            polyline_vertices,
+            polyline_vertices_buffer,
+            polyline_colors,
+            polyline_colors_buffer,
+            polyline_pipeline,
         })
     }
 
@@ -158,25 +397,36 @@ impl LineRenderering {
         self.line_segments_transforms.push(transform.into());
     }
 
+    pub fn add_polyline(&mut self, points: &[Vec3], color: Vec4, width: f32) {
+        let triangles = polyline_triangles(points, width);
+        self.polyline_colors
+            .extend(std::iter::repeat_n(color, triangles.len()));
+        self.polyline_vertices.extend(triangles);
+    }
+
     pub fn render<'a>(
         &'a mut self,
         rendering_context: &'a RenderingContext,
+        camera: &'a Camera,
         render_pass: &mut wgpu::RenderPass<'a>,
     ) {
+        self.line_segments_buffer.write_data(
+            &rendering_context.gpu_context,
+            &self.line_segments,
+            &rendering_context.buffer_shrink_policy,
+        );
+        self.line_segments_transforms_buffer.write_data(
+            &rendering_context.gpu_context,
+            &self.line_segments_transforms,
+            &rendering_context.buffer_shrink_policy,
+        );
         if !self.line_segments.is_empty() {
-            self.line_segments_buffer
-                .write_data(&rendering_context.gpu_context, &self.line_segments);
-            self.line_segments_transforms_buffer.write_data(
-                &rendering_context.gpu_context,
-                &self.line_segments_transforms,
-            );
-
             let pipeline = &rendering_context
                 .resource_store
                 .get_render_pipeline(self.line_segment_pipeline);
 
             render_pass.set_pipeline(pipeline);
-            render_pass.set_bind_group(0, rendering_context.primary_camera.bing_group(), &[]);
+            render_pass.set_bind_group(0, camera.bing_group(), &[]);
             render_pass.set_vertex_buffer(0, self.quad_vertex_buffer.slice(..));
             render_pass.set_vertex_buffer(1, self.line_segments_transforms_buffer.slice(..));
             render_pass.set_vertex_buffer(2, self.line_segments_buffer.slice(..));
@@ -190,10 +440,243 @@ impl LineRenderering {
                 0..(self.line_segments.len() as u32),
             );
 
-            // TODO: Think about some memory releasing strategy. Spike in number of
-            // circles will lead to space leak.
             self.line_segments.clear();
             self.line_segments_transforms.clear();
         }
+
+        self.polyline_vertices_buffer.write_data(
+            &rendering_context.gpu_context,
+            &self.polyline_vertices,
+            &rendering_context.buffer_shrink_policy,
+        );
+        self.polyline_colors_buffer.write_data(
+            &rendering_context.gpu_context,
+            &self.polyline_colors,
+            &rendering_context.buffer_shrink_policy,
+        );
+        if !self.polyline_vertices.is_empty() {
+            let pipeline = &rendering_context
+                .resource_store
+                .get_render_pipeline(self.polyline_pipeline);
+
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, camera.bing_group(), &[]);
+            render_pass.set_vertex_buffer(0, self.polyline_vertices_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, self.polyline_colors_buffer.slice(..));
+            render_pass.draw(0..(self.polyline_vertices.len() as u32), 0..1);
+
+            self.polyline_vertices.clear();
+            self.polyline_colors.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec3;
+
+    use super::*;
+    use crate::{
+        colors::RED,
+        test_utils::{require_gpu, test_camera},
+    };
+
+    #[test]
+    fn zero_length_arrow_draws_nothing() {
+        let point = vec3(1.0, 2.0, 3.0);
+        assert!(arrow_parts(point, point, RED, 1.0, 5.0).is_none());
+    }
+
+    #[test]
+    fn arrow_head_sits_at_the_tip() {
+        let (shaft, head) = arrow_parts(Vec3::ZERO, vec3(10.0, 0.0, 0.0), RED, 1.0, 2.0).unwrap();
+        assert_eq!({ shaft.from }, Vec3::ZERO);
+        assert_eq!({ shaft.to }, vec3(8.0, 0.0, 0.0));
+        assert_eq!({ head.a }, vec3(10.0, 0.0, 0.0));
+        assert_eq!(head.b.distance(head.c), 2.0);
+        assert_eq!((head.b + head.c) / 2.0, vec3(8.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn short_arrow_scales_head_down() {
+        let (shaft, head) = arrow_parts(Vec3::ZERO, vec3(0.0, 2.0, 0.0), RED, 1.0, 5.0).unwrap();
+        assert_eq!({ shaft.to }, vec3(0.0, 1.0, 0.0));
+        assert_eq!(head.b.distance(head.c), 1.0);
+    }
+
+    #[test]
+    fn arrow_along_z_still_has_a_head() {
+        let (_, head) = arrow_parts(Vec3::ZERO, vec3(0.0, 0.0, 4.0), RED, 1.0, 2.0).unwrap();
+        assert_eq!(head.b.distance(head.c), 2.0);
+    }
+
+    #[test]
+    fn bezier_segments_follow_the_curve() {
+        let lines: Vec<Line> = bezier_segments(
+            [
+                Vec3::ZERO,
+                vec3(0.0, 1.0, 0.0),
+                vec3(1.0, 1.0, 0.0),
+                vec3(1.0, 0.0, 0.0),
+            ],
+            RED,
+            1.0,
+            4,
+        )
+        .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!({ lines[0].from }, Vec3::ZERO);
+        assert_eq!({ lines[3].to }, vec3(1.0, 0.0, 0.0));
+        assert_eq!({ lines[1].to }, vec3(0.5, 0.75, 0.0));
+        for pair in lines.windows(2) {
+            assert_eq!({ pair[0].to }, { pair[1].from });
+        }
+    }
+
+    #[test]
+    fn polyline_needs_two_distinct_points() {
+        assert!(polyline_triangles(&[], 1.0).is_empty());
+        assert!(polyline_triangles(&[Vec3::ONE, Vec3::ONE], 1.0).is_empty());
+        let points = [Vec3::ZERO, Vec3::ZERO, Vec3::X, Vec3::X];
+        assert_eq!(polyline_triangles(&points, 1.0).len(), 6);
+    }
+
+    #[test]
+    fn right_angle_polyline_is_mitered() {
+        let points = [vec3(-4.0, 0.0, 0.0), Vec3::ZERO, vec3(0.0, 4.0, 0.0)];
+        let triangles = polyline_triangles(&points, 2.0);
+        // Two quads sharing the corner's edge, no bevel.
+        assert_eq!(triangles.len(), 12);
+        let has_vertex = |vertex: Vec3| triangles.iter().any(|v| v.distance(vertex) < 1e-5);
+        assert!(has_vertex(vec3(1.0, -1.0, 0.0)));
+        assert!(has_vertex(vec3(-1.0, 1.0, 0.0)));
+    }
+
+    #[test]
+    fn sharp_polyline_corner_is_beveled() {
+        let points = [vec3(-4.0, 0.0, 0.0), Vec3::ZERO, vec3(-4.0, 0.5, 0.0)];
+        let triangles = polyline_triangles(&points, 2.0);
+        // Two quads and the bevel filling the outside of the corner.
+        assert_eq!(triangles.len(), 15);
+        assert!(triangles
+            .iter()
+            .all(|v| v.distance(Vec3::ZERO) <= 4.0 + 1e-5 || v.x < -3.0));
+    }
+
+    #[test]
+    fn degenerate_bezier_matches_straight_line() {
+        use crate::test_utils::render_offscreen;
+
+        let primary_camera = test_camera(glam::vec2(32.0, 32.0));
+        let (from, to) = (vec3(-12.0, -5.0, 0.0), vec3(12.0, 7.0, 0.0));
+        let line = require_gpu!(render_offscreen(primary_camera.clone(), |renderer| {
+            renderer.draw_line(&Transform::IDENTITY, &Line::new(from, to, RED, 3.0))
+        }));
+        let bezier = render_offscreen(primary_camera, |renderer| {
+            renderer.draw_bezier(
+                from,
+                from.lerp(to, 0.25),
+                from.lerp(to, 0.75),
+                to,
+                RED,
+                3.0,
+                8,
+            )
+        })
+        .unwrap();
+        assert!(line.contains(&[230, 41, 56, 255]));
+        assert_eq!(line, bezier);
+    }
+
+    #[test]
+    fn arrow_renders_shaft_and_filled_head() {
+        use crate::test_utils::render_offscreen;
+
+        let primary_camera = test_camera(glam::vec2(16.0, 16.0));
+        let pixels = require_gpu!(render_offscreen(primary_camera, |renderer| {
+            // Pointing towards -x, which the line shader used to cull.
+            renderer.draw_arrow(vec3(7.0, 0.0, 0.0), vec3(-7.0, 0.0, 0.0), RED, 2.0, 6.0)
+        }));
+        let red = [230, 41, 56, 255];
+        let black = [0, 0, 0, 255];
+        // Shaft.
+        assert_eq!(pixels[8 * 16 + 12], red);
+        assert_eq!(pixels[10 * 16 + 12], black);
+        // Head is wider than the shaft.
+        assert_eq!(pixels[9 * 16 + 4], red);
+        assert_eq!(pixels[6 * 16 + 4], red);
+        assert_eq!(pixels[11 * 16 + 4], black);
+    }
+
+    #[test]
+    fn polyline_corner_is_filled() {
+        use crate::test_utils::render_offscreen;
+
+        let primary_camera = test_camera(glam::vec2(16.0, 16.0));
+        let points = [vec3(-6.0, 0.0, 0.0), Vec3::ZERO, vec3(0.0, 6.0, 0.0)];
+        let pixels = require_gpu!(render_offscreen(primary_camera, |renderer| {
+            renderer.draw_polyline(&points, RED, 4.0)
+        }));
+        let red = [230, 41, 56, 255];
+        let black = [0, 0, 0, 255];
+        // Both segments.
+        assert_eq!(pixels[9 * 16 + 4], red);
+        assert_eq!(pixels[4 * 16 + 9], red);
+        // The outer corner, which separate lines would leave empty.
+        assert_eq!(pixels[9 * 16 + 9], red);
+        assert_eq!(pixels[9 * 16 + 8], red);
+        // Past the miter.
+        assert_eq!(pixels[11 * 16 + 11], black);
+    }
+
+    #[test]
+    fn dashed_line_leaves_gaps() {
+        use crate::test_utils::render_offscreen;
+
+        let primary_camera = test_camera(glam::vec2(16.0, 4.0));
+        let pixels = require_gpu!(render_offscreen(primary_camera, |renderer| {
+            renderer.draw_line(
+                &Transform::IDENTITY,
+                &Line::new(vec3(-8.0, 0.0, 0.0), vec3(8.0, 0.0, 0.0), RED, 2.0)
+                    .with_dashes(3.0, 1.0),
+            )
+        }));
+        let row: Vec<bool> = pixels[16..32].iter().map(|p| p[0] > 0).collect();
+        // Dashes cover three of every four pixels.
+        for (x, drawn) in row.iter().enumerate() {
+            assert_eq!(*drawn, x % 4 != 3, "pixel {x} in {row:?}");
+        }
+    }
+
+    #[test]
+    fn world_widths_scale_with_zoom_and_pixel_widths_dont() {
+        use crate::{
+            camera::PrimaryCamera,
+            projection::{CameraProjection, Orthographic},
+            test_utils::render_offscreen,
+        };
+
+        // Thickness of a horizontal line where it crosses the middle column.
+        let line_thickness = |scale: f32, width_units: BorderUnits| {
+            let primary_camera = PrimaryCamera {
+                projection: CameraProjection::Orthographic(Orthographic { depth: 2.0, scale }),
+                ..test_camera(glam::vec2(32.0, 32.0))
+            };
+            let line = Line::new(vec3(-6.0, 0.0, 0.0), vec3(6.0, 0.0, 0.0), RED, 2.0)
+                .with_width_units(width_units);
+            render_offscreen(primary_camera, |renderer| {
+                renderer.draw_line(&Transform::IDENTITY, &line)
+            })
+            .map(|pixels| {
+                (0..32)
+                    .filter(|y| pixels[y * 32 + 16] != [0, 0, 0, 255])
+                    .count()
+            })
+        };
+        let world_near = require_gpu!(line_thickness(1.0, BorderUnits::World));
+        assert_eq!(world_near, 2);
+        assert_eq!(line_thickness(2.0, BorderUnits::World), Some(4));
+        assert_eq!(line_thickness(1.0, BorderUnits::Pixels), Some(2));
+        assert_eq!(line_thickness(2.0, BorderUnits::Pixels), Some(2));
     }
 }
@@ -1,10 +1,12 @@
-use std::mem::size_of;
+use std::{cmp::Ordering, mem::size_of, num::NonZeroU64};
 
 use bytemuck::bytes_of;
-use glam::Vec3;
+use glam::{UVec2, Vec2, Vec3, Vec4};
 use wgpu::vertex_attr_array;
 
 use crate::{
+    camera::Camera,
+    include_wgsl,
     rendering_context::RenderingContext,
     resource_store::{
         pipeline_layout::PipelineLayoutDescriptor,
@@ -12,7 +14,7 @@ use crate::{
             FragmentState, RenderPipelineDescriptor, VertexBufferLayout, VertexState,
         },
         shader::ShaderSource,
-        BindGroupLayoutId, GpuMeshId, PipelineId,
+        BindGroupLayoutId, GpuMeshId, PipelineId, TextureId,
     },
     transform::{Transform, TransformGpu},
 };
@@ -21,11 +23,28 @@ use crate::{
 pub struct MeshBundle {
     pub mesh_id: GpuMeshId,
     pub pipeline_id: PipelineId,
+    /// Texture for pipelines made by `create_3d_pipeline_textured`.
+    pub texture_id: Option<TextureId>,
+    /// Blends the mesh over what is behind it by its alpha. Transparent meshes
+    /// are drawn after the opaque ones, back to front, and do not write depth.
+    pub transparent: bool,
+}
+
+/// Per vertex data a 3d pipeline reads besides positions and normals, always
+/// from the third vertex buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MeshAttributes {
+    Plain,
+    Colored,
+    Textured,
 }
 
 pub struct MeshRendering {
-    bundles: Vec<(Transform, MeshBundle)>,
+    /// Bundles of the frame with the push constant bytes they are drawn with,
+    /// empty for pipelines without push constants.
+    bundles: Vec<(Transform, MeshBundle, Vec<u8>)>,
     transform_uniform_bind_group_layout: BindGroupLayoutId,
+    texture_bind_group_layout: BindGroupLayoutId,
     transform_uniform_bind_group: wgpu::BindGroup,
     transform_uniform_buffer: wgpu::Buffer,
     transform_uniform_buffer_size: usize,
@@ -39,6 +58,54 @@ fn ceil_to_next_multiple(value: usize, step: u32) -> u64 {
 const TRANSFORMS_UNIFORM_BUFFER_NAME: &str = "3d mesh transforms uniform buffer";
 const TRANSFORMS_UNIFORM_BIND_GROUP_NAME: &str = "3d mesh transforms bind group";
 
+/// Bytes of the transform a draw binds, at a dynamic offset into the buffer.
+fn transform_binding_size() -> NonZeroU64 {
+    NonZeroU64::new(size_of::<TransformGpu>() as u64).expect("TransformGpu isn't zero sized")
+}
+
+/// Distance between the transforms of consecutive bundles in the uniform
+/// buffer, dynamic offsets have to be aligned to the device's limit.
+fn transform_stride(rendering_context: &RenderingContext) -> u64 {
+    ceil_to_next_multiple(
+        size_of::<TransformGpu>(),
+        rendering_context
+            .gpu_context
+            .limits()
+            .min_uniform_buffer_offset_alignment,
+    )
+}
+
+/// Uniform buffer with room for the transforms of `capacity` bundles, with
+/// its bind group.
+fn build_transform_uniform(
+    rendering_context: &RenderingContext,
+    bind_group_layout_id: BindGroupLayoutId,
+    capacity: usize,
+) -> (wgpu::Buffer, wgpu::BindGroup) {
+    let device = rendering_context.gpu_context.device();
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(TRANSFORMS_UNIFORM_BUFFER_NAME),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        size: transform_stride(rendering_context) * capacity as u64,
+        mapped_at_creation: false,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some(TRANSFORMS_UNIFORM_BIND_GROUP_NAME),
+        layout: rendering_context
+            .resource_store
+            .get_bing_group_layout(bind_group_layout_id),
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                buffer: &buffer,
+                offset: 0,
+                size: Some(transform_binding_size()),
+            }),
+        }],
+    });
+    (buffer, bind_group)
+}
+
 impl MeshRendering {
     pub fn new(rendering_context: &mut RenderingContext) -> Self {
         let transform_uniform_bind_group_layout = rendering_context
@@ -51,166 +118,304 @@ impl MeshRendering {
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: true,
-                        min_binding_size: None,
+                        min_binding_size: Some(transform_binding_size()),
                     },
                     count: None,
                 }],
             });
-        let transform_uniform_buffer =
-            rendering_context
-                .gpu_context
-                .device()
-                .create_buffer(&wgpu::BufferDescriptor {
-                    label: Some(TRANSFORMS_UNIFORM_BUFFER_NAME),
-                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                    size: 1, // This is a bit of a hack to make sure the first
-                    // bind group created correctly, which means we
-                    // don't need to use `Option` n bothw buffer and
-                    // bind group.
-                    mapped_at_creation: false,
-                });
-        let transform_uniform_bind_group = rendering_context
-            .gpu_context
-            .device()
-            .create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some(TRANSFORMS_UNIFORM_BIND_GROUP_NAME),
-                layout: rendering_context
-                    .resource_store
-                    .get_bing_group_layout(transform_uniform_bind_group_layout),
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Buffer(
-                        transform_uniform_buffer.as_entire_buffer_binding(),
-                    ),
-                }],
-            });
+        let texture_bind_group_layout = rendering_context.resource_store.build_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                label: Some("3d mesh texture layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            },
+        );
+        Self::with_layouts(
+            rendering_context,
+            transform_uniform_bind_group_layout,
+            texture_bind_group_layout,
+        )
+    }
+
+    /// Mesh batches of another camera. They share the layouts, so that
+    /// pipelines and textures made through either work with both.
+    pub fn for_another_camera(&self, rendering_context: &mut RenderingContext) -> Self {
+        Self::with_layouts(
+            rendering_context,
+            self.transform_uniform_bind_group_layout,
+            self.texture_bind_group_layout,
+        )
+    }
+
+    fn with_layouts(
+        rendering_context: &mut RenderingContext,
+        transform_uniform_bind_group_layout: BindGroupLayoutId,
+        texture_bind_group_layout: BindGroupLayoutId,
+    ) -> Self {
+        // Room for a single bundle, so that the bind group is valid before
+        // the first frame grows the buffer as needed.
+        let (transform_uniform_buffer, transform_uniform_bind_group) =
+            build_transform_uniform(rendering_context, transform_uniform_bind_group_layout, 1);
 
         Self {
             bundles: Vec::new(),
             transform_uniform_bind_group_layout,
+            texture_bind_group_layout,
             transform_uniform_buffer,
-            transform_uniform_buffer_size: 0,
+            transform_uniform_buffer_size: 1,
             transform_uniform_bind_group,
         }
     }
 
     pub fn add_mesh_bundle(&mut self, transform: &Transform, mesh_bundle: &MeshBundle) {
-        self.bundles.push((*transform, mesh_bundle.clone()));
+        self.bundles
+            .push((*transform, mesh_bundle.clone(), Vec::new()));
+    }
+
+    pub fn add_mesh_bundle_with_push_constants(
+        &mut self,
+        transform: &Transform,
+        mesh_bundle: &MeshBundle,
+        push_constants: &[u8],
+    ) {
+        self.bundles
+            .push((*transform, mesh_bundle.clone(), push_constants.to_vec()));
     }
 
     pub fn create_3d_pipeline(
         &self,
         rendering_context: &mut RenderingContext,
         shader: &ShaderSource,
+    ) -> eyre::Result<PipelineId> {
+        self.build_3d_pipeline(rendering_context, shader, MeshAttributes::Plain, Vec::new())
+    }
+
+    /// Like [`MeshRendering::create_3d_pipeline`], with the pipeline layout
+    /// declaring `push_constant_ranges` for per draw data of the shader.
+    pub fn create_3d_pipeline_with_push_constants(
+        &self,
+        rendering_context: &mut RenderingContext,
+        shader: &ShaderSource,
+        push_constant_ranges: Vec<wgpu::PushConstantRange>,
+    ) -> eyre::Result<PipelineId> {
+        self.build_3d_pipeline(
+            rendering_context,
+            shader,
+            MeshAttributes::Plain,
+            push_constant_ranges,
+        )
+    }
+
+    /// Pipeline for meshes built with per vertex colors, which it shades with
+    /// a simple directional light.
+    pub fn create_3d_pipeline_vertex_colored(
+        &self,
+        rendering_context: &mut RenderingContext,
+    ) -> eyre::Result<PipelineId> {
+        self.build_3d_pipeline(
+            rendering_context,
+            &include_wgsl!("../shaders/mesh_vertex_colored.wgsl"),
+            MeshAttributes::Colored,
+            Vec::new(),
+        )
+    }
+
+    /// Pipeline for meshes built with texture coordinates, drawn with the
+    /// texture of their [`MeshBundle`] and a simple directional light.
+    pub fn create_3d_pipeline_textured(
+        &self,
+        rendering_context: &mut RenderingContext,
+    ) -> eyre::Result<PipelineId> {
+        self.build_3d_pipeline(
+            rendering_context,
+            &include_wgsl!("../shaders/mesh_textured.wgsl"),
+            MeshAttributes::Textured,
+            Vec::new(),
+        )
+    }
+
+    /// Layout of the bind groups of textures from [`MeshRendering::load_texture`].
+    pub fn texture_bind_group_layout(&self) -> BindGroupLayoutId {
+        self.texture_bind_group_layout
+    }
+
+    /// Uploads an sRGB image of `size` given as RGBA bytes row by row.
+    pub fn load_texture(
+        &self,
+        rendering_context: &mut RenderingContext,
+        size: UVec2,
+        rgba: &[u8],
+    ) -> TextureId {
+        rendering_context
+            .resource_store
+            .build_texture(size, rgba, self.texture_bind_group_layout)
+    }
+
+    fn build_3d_pipeline(
+        &self,
+        rendering_context: &mut RenderingContext,
+        shader: &ShaderSource,
+        attributes: MeshAttributes,
+        push_constant_ranges: Vec<wgpu::PushConstantRange>,
     ) -> eyre::Result<PipelineId> {
         let shader_id = rendering_context.resource_store.build_shader(shader)?;
 
+        let mut bind_group_layouts = vec![
+            *rendering_context.primary_camera.bing_group_layout(),
+            self.transform_uniform_bind_group_layout,
+        ];
+        if attributes == MeshAttributes::Textured {
+            bind_group_layouts.push(self.texture_bind_group_layout);
+        }
+
         let pipeline_layout_id =
             rendering_context
                 .resource_store
                 .build_pipeline_layout(&PipelineLayoutDescriptor {
                     label: "3d mesh pipeline layout".to_string(),
-                    bind_group_layouts: vec![
-                        *rendering_context.primary_camera.bing_group_layout(),
-                        self.transform_uniform_bind_group_layout,
-                    ],
-                    push_constant_ranges: Vec::new(),
+                    bind_group_layouts,
+                    push_constant_ranges: push_constant_ranges.clone(),
                 });
 
-        let targets: Vec<Option<wgpu::ColorTargetState>> = vec![Some(wgpu::ColorTargetState {
-            format: rendering_context.primary_camera.surface_format(),
-            blend: Some(wgpu::BlendState {
-                color: wgpu::BlendComponent::REPLACE,
-                alpha: wgpu::BlendComponent::REPLACE,
+        let target = |blend| {
+            vec![Some(wgpu::ColorTargetState {
+                format: rendering_context.primary_camera.surface_format(),
+                blend: Some(blend),
+                write_mask: wgpu::ColorWrites::ALL,
+            })]
+        };
+
+        let mut buffers = vec![
+            VertexBufferLayout {
+                array_stride: std::mem::size_of::<Vec3>() as u64,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: vertex_attr_array![0 => Float32x3].to_vec(),
+            },
+            VertexBufferLayout {
+                array_stride: std::mem::size_of::<Vec3>() as u64,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: vertex_attr_array![
+                    1 => Float32x3,
+                ]
+                .to_vec(),
+            },
+        ];
+        match attributes {
+            MeshAttributes::Plain => {}
+            MeshAttributes::Colored => buffers.push(VertexBufferLayout {
+                array_stride: std::mem::size_of::<Vec4>() as u64,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: vertex_attr_array![2 => Float32x4].to_vec(),
+            }),
+            MeshAttributes::Textured => buffers.push(VertexBufferLayout {
+                array_stride: std::mem::size_of::<Vec2>() as u64,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: vertex_attr_array![2 => Float32x2].to_vec(),
             }),
-            write_mask: wgpu::ColorWrites::ALL,
-        })];
+        }
 
-        Ok(rendering_context
-            .resource_store
-            .build_render_pipeline(&RenderPipelineDescriptor {
-                label: "3d mesh pipeline".to_string(),
-                layout: Some(pipeline_layout_id),
-                vertex: VertexState {
-                    module: shader_id,
-                    buffers: vec![
-                        VertexBufferLayout {
-                            array_stride: std::mem::size_of::<Vec3>() as u64,
-                            step_mode: wgpu::VertexStepMode::Vertex,
-                            attributes: vertex_attr_array![0 => Float32x3].to_vec(),
-                        },
-                        VertexBufferLayout {
-                            array_stride: std::mem::size_of::<Vec3>() as u64,
-                            step_mode: wgpu::VertexStepMode::Vertex,
-                            attributes: vertex_attr_array![
-                                1 => Float32x3,
-                            ]
-                            .to_vec(),
-                        },
-                    ],
-                },
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    unclipped_depth: false,
-                    conservative: false,
-                },
-                depth_stencil: rendering_context.primary_camera.depth_stencil(),
-                multisample: wgpu::MultisampleState::default(),
-                fragment: Some(FragmentState {
-                    module: shader_id,
-                    targets: targets.clone(),
-                }),
-                multiview: None,
-            }))
+        let opaque = RenderPipelineDescriptor {
+            label: "3d mesh pipeline".to_string(),
+            layout: Some(pipeline_layout_id),
+            vertex: VertexState {
+                module: shader_id,
+                buffers,
+            },
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: rendering_context.mesh_polygon_mode,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: rendering_context.primary_camera.depth_stencil(),
+            multisample: rendering_context.primary_camera.multisample_state(),
+            fragment: Some(FragmentState {
+                module: shader_id,
+                targets: target(wgpu::BlendState::REPLACE),
+            }),
+            multiview: None,
+        };
+        let transparent = RenderPipelineDescriptor {
+            label: "3d transparent mesh pipeline".to_string(),
+            depth_stencil: opaque.depth_stencil.clone().map(|depth_stencil| {
+                wgpu::DepthStencilState {
+                    depth_write_enabled: false,
+                    ..depth_stencil
+                }
+            }),
+            fragment: Some(FragmentState {
+                module: shader_id,
+                targets: target(wgpu::BlendState::ALPHA_BLENDING),
+            }),
+            ..opaque.clone()
+        };
+
+        let resource_store = &mut rendering_context.resource_store;
+        let pipeline_id = resource_store.build_render_pipeline(&opaque);
+        let transparent_pipeline_id = resource_store.build_render_pipeline(&transparent);
+        rendering_context
+            .transparent_mesh_pipelines
+            .insert(pipeline_id, transparent_pipeline_id);
+        if !push_constant_ranges.is_empty() {
+            rendering_context
+                .mesh_push_constant_ranges
+                .insert(pipeline_id, push_constant_ranges);
+        }
+        Ok(pipeline_id)
     }
 
     pub fn render<'a>(
         &'a mut self,
         rendering_context: &'a RenderingContext,
+        camera: &'a Camera,
         render_pass: &mut wgpu::RenderPass<'a>,
     ) {
         if !self.bundles.is_empty() {
-            let aligned_size = ceil_to_next_multiple(
-                size_of::<TransformGpu>(),
-                RenderingContext::wgpu_limits().min_uniform_buffer_offset_alignment,
-            );
+            // Opaque meshes keep their order and go first, the transparent
+            // ones follow from the farthest to the nearest, so that each
+            // blends over everything behind it.
+            self.bundles
+                .sort_by(|(transform_a, a, _), (transform_b, b, _)| {
+                    a.transparent.cmp(&b.transparent).then_with(|| {
+                        if a.transparent {
+                            let depth_a = camera.view_depth(transform_a.translation());
+                            let depth_b = camera.view_depth(transform_b.translation());
+                            depth_b.total_cmp(&depth_a)
+                        } else {
+                            Ordering::Equal
+                        }
+                    })
+                });
+            let aligned_size = transform_stride(rendering_context);
             if self.transform_uniform_buffer_size < self.bundles.len() {
-                self.transform_uniform_buffer = rendering_context
-                    .gpu_context
-                    .device()
-                    .create_buffer(&wgpu::BufferDescriptor {
-                        label: Some(TRANSFORMS_UNIFORM_BUFFER_NAME),
-                        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                        size: aligned_size * self.bundles.len() as u64,
-                        mapped_at_creation: false,
-                    });
+                (
+                    self.transform_uniform_buffer,
+                    self.transform_uniform_bind_group,
+                ) = build_transform_uniform(
+                    rendering_context,
+                    self.transform_uniform_bind_group_layout,
+                    self.bundles.len(),
+                );
                 self.transform_uniform_buffer_size = self.bundles.len();
-                self.transform_uniform_bind_group = rendering_context
-                    .gpu_context
-                    .device()
-                    .create_bind_group(&wgpu::BindGroupDescriptor {
-                        label: Some(TRANSFORMS_UNIFORM_BIND_GROUP_NAME),
-                        layout: rendering_context
-                            .resource_store
-                            .get_bing_group_layout(self.transform_uniform_bind_group_layout),
-                        entries: &[wgpu::BindGroupEntry {
-                            binding: 0,
-                            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                                buffer: &self.transform_uniform_buffer,
-                                offset: 0,
-                                size: Some(
-                                    // I don't think the unwrap can ever fail here because the
-                                    // size_of will always return positive value.
-                                    std::num::NonZeroU64::new(size_of::<TransformGpu>() as u64)
-                                        .unwrap(),
-                                ),
-                            }),
-                        }],
-                    });
             }
             for (i, bundle) in self.bundles.iter().enumerate() {
                 let transform: TransformGpu = bundle.0.into();
@@ -222,16 +427,21 @@ impl MeshRendering {
             }
 
             for (i, bundle) in self.bundles.iter().enumerate() {
+                let pipeline_id = if bundle.1.transparent {
+                    rendering_context.transparent_mesh_pipelines[bundle.1.pipeline_id]
+                } else {
+                    bundle.1.pipeline_id
+                };
                 let pipeline = &rendering_context
                     .resource_store
-                    .get_render_pipeline(bundle.1.pipeline_id);
+                    .get_render_pipeline(pipeline_id);
 
                 let gpu_mesh = rendering_context
                     .resource_store
                     .get_gpu_mesh(bundle.1.mesh_id);
 
                 render_pass.set_pipeline(pipeline);
-                render_pass.set_bind_group(0, rendering_context.primary_camera.bing_group(), &[]);
+                render_pass.set_bind_group(0, camera.bing_group(), &[]);
                 render_pass.set_bind_group(
                     1,
                     &self.transform_uniform_bind_group,
@@ -239,6 +449,34 @@ impl MeshRendering {
                 );
                 render_pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
                 render_pass.set_vertex_buffer(1, gpu_mesh.normal_buffer.slice(..));
+                if let Some(attributes_buffer) = gpu_mesh
+                    .color_buffer
+                    .as_ref()
+                    .map(|buffer| buffer.slice(..))
+                    .or_else(|| gpu_mesh.uv_buffer.as_ref().map(|buffer| buffer.slice(..)))
+                {
+                    render_pass.set_vertex_buffer(2, attributes_buffer);
+                }
+                if let Some(texture_id) = bundle.1.texture_id {
+                    render_pass.set_bind_group(
+                        2,
+                        &rendering_context
+                            .resource_store
+                            .get_texture(texture_id)
+                            .bind_group,
+                        &[],
+                    );
+                }
+                if !bundle.2.is_empty() {
+                    for range in &rendering_context.mesh_push_constant_ranges[bundle.1.pipeline_id]
+                    {
+                        render_pass.set_push_constants(
+                            range.stages,
+                            range.range.start,
+                            &bundle.2[range.range.start as usize..range.range.end as usize],
+                        );
+                    }
+                }
                 render_pass.set_index_buffer(
                     gpu_mesh.index_buffer.slice(..),
                     gpu_mesh.index_buffer.index_format(),
@@ -0,0 +1,144 @@
+use glam::UVec2;
+
+use crate::gpu_context::GpuContext;
+
+/// Bytes of a single pixel in the formats supported by [`OffscreenTarget`].
+const PIXEL_SIZE: u32 = 4;
+
+/// A texture to render into without a window, together with a buffer its
+/// contents can be read back through.
+///
+/// Only formats with four 8-bit channels, such as `Rgba8Unorm` or
+/// `Rgba8UnormSrgb`, are supported.
+pub struct OffscreenTarget {
+    texture: wgpu::Texture,
+    output_buffer: wgpu::Buffer,
+    size: UVec2,
+    padded_bytes_per_row: u32,
+}
+
+impl OffscreenTarget {
+    pub fn new(gpu_context: &GpuContext, size: UVec2, format: wgpu::TextureFormat) -> Self {
+        let texture = gpu_context
+            .device()
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("offscreen target texture"),
+                size: wgpu::Extent3d {
+                    width: size.x,
+                    height: size.y,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            });
+        let (output_buffer, padded_bytes_per_row) =
+            build_readback_buffer(gpu_context, size, "offscreen target output buffer");
+        Self {
+            texture,
+            output_buffer,
+            size,
+            padded_bytes_per_row,
+        }
+    }
+
+    /// Texture to pass to [`Renderer::render`](crate::Renderer::render).
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// Copies the texture back to the CPU, blocking until the GPU is done, and
+    /// returns its pixels row by row with four bytes per pixel.
+    pub fn read_pixels(&self, gpu_context: &GpuContext) -> Vec<u8> {
+        read_texture(
+            gpu_context,
+            &self.texture,
+            &self.output_buffer,
+            self.padded_bytes_per_row,
+        )
+    }
+}
+
+/// Creates a buffer a texture of `size` can be copied into, returning it with
+/// the number of bytes its rows take.
+pub(crate) fn build_readback_buffer(
+    gpu_context: &GpuContext,
+    size: UVec2,
+    label: &str,
+) -> (wgpu::Buffer, u32) {
+    // wgpu requires texture -> buffer copies to be aligned using
+    // wgpu::COPY_BYTES_PER_ROW_ALIGNMENT, the padding is stripped again
+    // when reading the pixels.
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let padded_bytes_per_row = (PIXEL_SIZE * size.x).div_ceil(align) * align;
+    let output_buffer = gpu_context.device().create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size: (padded_bytes_per_row * size.y) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    (output_buffer, padded_bytes_per_row)
+}
+
+/// Copies `texture` into `output_buffer` and returns its pixels row by row
+/// without the padding, blocking until the GPU is done.
+pub(crate) fn read_texture(
+    gpu_context: &GpuContext,
+    texture: &wgpu::Texture,
+    output_buffer: &wgpu::Buffer,
+    padded_bytes_per_row: u32,
+) -> Vec<u8> {
+    assert_eq!(
+        texture.format().block_copy_size(None),
+        Some(PIXEL_SIZE),
+        "only formats with four bytes per pixel can be read back"
+    );
+    let extent = texture.size();
+    let mut encoder =
+        gpu_context
+            .device()
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("texture readback encoder"),
+            });
+    encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture {
+            aspect: wgpu::TextureAspect::All,
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+        },
+        wgpu::ImageCopyBuffer {
+            buffer: output_buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(extent.height),
+            },
+        },
+        extent,
+    );
+    gpu_context.queue().submit(Some(encoder.finish()));
+
+    let buffer_slice = output_buffer.slice(..);
+    buffer_slice.map_async(wgpu::MapMode::Read, |result| {
+        result.expect("GPU didn't copy data to output buffer");
+    });
+    gpu_context.device().poll(wgpu::Maintain::Wait);
+
+    let unpadded_bytes_per_row = (PIXEL_SIZE * extent.width) as usize;
+    let pixels = buffer_slice
+        .get_mapped_range()
+        .chunks(padded_bytes_per_row as usize)
+        .flat_map(|row| &row[..unpadded_bytes_per_row])
+        .copied()
+        .collect();
+    output_buffer.unmap();
+    pixels
+}
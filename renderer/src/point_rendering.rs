@@ -0,0 +1,191 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{Vec3, Vec4};
+use wgpu::vertex_attr_array;
+
+use crate::{
+    buffers::WriteableVecBuffer,
+    camera::Camera,
+    include_wgsl,
+    rendering_context::RenderingContext,
+    resource_store::{
+        pipeline_layout::PipelineLayoutDescriptor,
+        render_pipeline::{
+            FragmentState, RenderPipelineDescriptor, VertexBufferLayout, VertexState,
+        },
+        PipelineId,
+    },
+};
+
+/// Round dot at a world position, `size` pixels across whatever the zoom,
+/// e.g. for marking contact points.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[repr(C, packed)]
+pub struct Point {
+    pub position: Vec3,
+    pub color: Vec4,
+    pub size: f32,
+}
+
+// SAFETY: `Point` is `repr(C, packed)` and consists solely of `f32` values,
+// so there is no padding and every bit pattern is valid.
+unsafe impl Zeroable for Point {}
+unsafe impl Pod for Point {}
+
+impl Point {
+    pub fn new(position: Vec3, size: f32, color: Vec4) -> Self {
+        Self {
+            position,
+            color,
+            size,
+        }
+    }
+}
+
+pub struct PointRendering {
+    points: Vec<Point>,
+    points_buffer: WriteableVecBuffer<Point>,
+    point_pipeline: PipelineId,
+}
+
+impl PointRendering {
+    pub fn new(rendering_context: &mut RenderingContext) -> eyre::Result<Self> {
+        let points = Vec::new();
+        let points_buffer = WriteableVecBuffer::new(
+            &rendering_context.gpu_context,
+            "points buffer",
+            &points,
+            wgpu::BufferUsages::VERTEX,
+        );
+
+        let point_shader_id = rendering_context
+            .resource_store
+            .build_shader(&include_wgsl!("../shaders/point.wgsl"))?;
+
+        let targets: Vec<Option<wgpu::ColorTargetState>> = vec![Some(wgpu::ColorTargetState {
+            format: rendering_context.primary_camera.surface_format(),
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::ALL,
+        })];
+
+        let point_pipeline_layout_id =
+            rendering_context
+                .resource_store
+                .build_pipeline_layout(&PipelineLayoutDescriptor {
+                    label: "point pipeline layout".to_string(),
+                    bind_group_layouts: vec![*rendering_context.primary_camera.bing_group_layout()],
+                    push_constant_ranges: Vec::new(),
+                });
+
+        let point_pipeline =
+            rendering_context
+                .resource_store
+                .build_render_pipeline(&RenderPipelineDescriptor {
+                    label: "point pipeline".to_string(),
+                    layout: Some(point_pipeline_layout_id),
+                    vertex: VertexState {
+                        module: point_shader_id,
+                        buffers: vec![VertexBufferLayout {
+                            array_stride: std::mem::size_of::<Point>() as u64,
+                            step_mode: wgpu::VertexStepMode::Instance,
+                            attributes:
+                                vertex_attr_array![0 => Float32x3, 1 => Float32x4, 2 => Float32]
+                                    .to_vec(),
+                        }],
+                    },
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: Some(wgpu::Face::Back),
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: rendering_context.primary_camera.depth_stencil(),
+                    multisample: rendering_context.primary_camera.multisample_state(),
+                    fragment: Some(FragmentState {
+                        module: point_shader_id,
+                        targets,
+                    }),
+                    multiview: None,
+                });
+
+        Ok(Self {
+            points,
+            points_buffer,
+            point_pipeline,
+        })
+    }
+
+    pub fn add_point(&mut self, point: &Point) {
+        self.points.push(*point);
+    }
+
+    pub fn render<'a>(
+        &'a mut self,
+        rendering_context: &'a RenderingContext,
+        camera: &'a Camera,
+        render_pass: &mut wgpu::RenderPass<'a>,
+    ) {
+        self.points_buffer.write_data(
+            &rendering_context.gpu_context,
+            &self.points,
+            &rendering_context.buffer_shrink_policy,
+        );
+        if !self.points.is_empty() {
+            let pipeline = &rendering_context
+                .resource_store
+                .get_render_pipeline(self.point_pipeline);
+
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, camera.bing_group(), &[]);
+            render_pass.set_vertex_buffer(0, self.points_buffer.slice(..));
+            render_pass.draw(0..6, 0..(self.points.len() as u32));
+
+            self.points.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec3;
+
+    use crate::{
+        camera::PrimaryCamera,
+        colors::RED,
+        projection::{CameraProjection, Orthographic},
+        test_utils::{render_offscreen, require_gpu, test_camera},
+    };
+
+    #[test]
+    fn points_keep_their_pixel_size_under_zoom() {
+        // Pixels covered by a dot 4 pixels across, drawn off center.
+        let covered = |scale: f32| {
+            let primary_camera = PrimaryCamera {
+                projection: CameraProjection::Orthographic(Orthographic { depth: 2.0, scale }),
+                ..test_camera(glam::vec2(32.0, 32.0))
+            };
+            render_offscreen(primary_camera, |renderer| {
+                renderer.draw_point(vec3(4.0, 4.0, 0.0), 4.0, RED)
+            })
+            .map(|pixels| {
+                (0..32 * 32)
+                    .filter(|i| pixels[*i] != [0, 0, 0, 255])
+                    .collect::<Vec<usize>>()
+            })
+        };
+        let near = require_gpu!(covered(1.0));
+        let far = covered(2.0).unwrap();
+        assert_eq!(near.len(), far.len());
+        // A round dot, missing the corners of its bounding square.
+        assert!(near.len() > 4 && near.len() < 16, "{near:?}");
+        // The dot sits at its world position, which zooming moves away from
+        // the center.
+        let center_x = |pixels: &[usize]| {
+            pixels.iter().map(|i| i % 32).sum::<usize>() as f32 / pixels.len() as f32
+        };
+        assert_eq!(center_x(&near), 19.5);
+        assert_eq!(center_x(&far), 23.5);
+    }
+}
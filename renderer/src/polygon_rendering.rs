@@ -0,0 +1,261 @@
+use std::ops::Range;
+
+use glam::{Vec2, Vec4};
+use wgpu::vertex_attr_array;
+
+use crate::{
+    buffers::WriteableVecBuffer,
+    camera::Camera,
+    include_wgsl,
+    rendering_context::RenderingContext,
+    resource_store::{
+        pipeline_layout::PipelineLayoutDescriptor,
+        render_pipeline::{
+            FragmentState, RenderPipelineDescriptor, VertexBufferLayout, VertexState,
+        },
+        PipelineId,
+    },
+    transform::{Transform, TransformGpu},
+};
+
+/// Filled convex polygon given by its corners in local space, in either
+/// winding order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    vertices: Vec<Vec2>,
+    color: Vec4,
+}
+
+impl Polygon {
+    /// # Panics
+    ///
+    /// Panics when given less than three vertices.
+    pub fn new(vertices: &[Vec2], color: Vec4) -> Self {
+        assert!(
+            vertices.len() >= 3,
+            "A polygon needs at least three vertices, got {}",
+            vertices.len()
+        );
+        Self {
+            vertices: vertices.to_vec(),
+            color,
+        }
+    }
+
+    pub fn vertices(&self) -> &[Vec2] {
+        &self.vertices
+    }
+
+    pub fn color(&self) -> Vec4 {
+        self.color
+    }
+}
+
+/// Indices triangulating a convex polygon of `count` vertices starting at
+/// `first` as a fan around its first vertex.
+fn fan_indices(first: u32, count: u32) -> impl Iterator<Item = u32> {
+    (1..count - 1).flat_map(move |i| [first, first + i, first + i + 1])
+}
+
+pub struct PolygonRendering {
+    vertices: Vec<Vec2>,
+    vertices_buffer: WriteableVecBuffer<Vec2>,
+    indices: Vec<u32>,
+    indices_buffer: WriteableVecBuffer<u32>,
+    /// Range of `indices` belonging to each polygon.
+    polygons_indices: Vec<Range<u32>>,
+    colors: Vec<Vec4>,
+    colors_buffer: WriteableVecBuffer<Vec4>,
+    polygons_transforms: Vec<TransformGpu>,
+    polygons_transforms_buffer: WriteableVecBuffer<TransformGpu>,
+    polygon_pipeline: PipelineId,
+}
+
+impl PolygonRendering {
+    pub fn new(rendering_context: &mut RenderingContext) -> eyre::Result<Self> {
+        let vertices = Vec::new();
+        let vertices_buffer = WriteableVecBuffer::new(
+            &rendering_context.gpu_context,
+            "polygon vertices buffer",
+            &vertices,
+            wgpu::BufferUsages::VERTEX,
+        );
+        let indices = Vec::new();
+        let indices_buffer = WriteableVecBuffer::new(
+            &rendering_context.gpu_context,
+            "polygon indices buffer",
+            &indices,
+            wgpu::BufferUsages::INDEX,
+        );
+        let colors = Vec::new();
+        let colors_buffer = WriteableVecBuffer::new(
+            &rendering_context.gpu_context,
+            "polygon colors buffer",
+            &colors,
+            wgpu::BufferUsages::VERTEX,
+        );
+
+        let polygons_transforms = Vec::new();
+        let polygons_transforms_buffer = WriteableVecBuffer::new(
+            &rendering_context.gpu_context,
+            "polygon transforms buffer",
+            &polygons_transforms,
+            wgpu::BufferUsages::VERTEX,
+        );
+
+        let polygon_shader_id = rendering_context
+            .resource_store
+            .build_shader(&include_wgsl!("../shaders/polygon.wgsl"))?;
+
+        let targets: Vec<Option<wgpu::ColorTargetState>> = vec![Some(wgpu::ColorTargetState {
+            format: rendering_context.primary_camera.surface_format(),
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::ALL,
+        })];
+
+        let polygon_pipeline_layout_id =
+            rendering_context
+                .resource_store
+                .build_pipeline_layout(&PipelineLayoutDescriptor {
+                    label: "polygon pipeline layout".to_string(),
+                    bind_group_layouts: vec![*rendering_context.primary_camera.bing_group_layout()],
+                    push_constant_ranges: Vec::new(),
+                });
+
+        let polygon_pipeline =
+            rendering_context
+                .resource_store
+                .build_render_pipeline(&RenderPipelineDescriptor {
+                    label: "polygon pipeline".to_string(),
+                    layout: Some(polygon_pipeline_layout_id),
+                    vertex: VertexState {
+                        module: polygon_shader_id,
+                        buffers: vec![
+                            VertexBufferLayout {
+                                array_stride: std::mem::size_of::<Vec2>() as u64,
+                                step_mode: wgpu::VertexStepMode::Vertex,
+                                attributes: vertex_attr_array![0 => Float32x2].to_vec(),
+                            },
+                            VertexBufferLayout {
+                                array_stride: std::mem::size_of::<TransformGpu>() as u64,
+                                step_mode: wgpu::VertexStepMode::Instance,
+                                attributes: TransformGpu::vertex_attributes(1, 2, 3, 4),
+                            },
+                            VertexBufferLayout {
+                                array_stride: std::mem::size_of::<Vec4>() as u64,
+                                step_mode: wgpu::VertexStepMode::Instance,
+                                attributes: vertex_attr_array![5 => Float32x4].to_vec(),
+                            },
+                        ],
+                    },
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        // Vertices can come in either winding order.
+                        cull_mode: None,
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: rendering_context.primary_camera.depth_stencil(),
+                    multisample: rendering_context.primary_camera.multisample_state(),
+                    fragment: Some(FragmentState {
+                        module: polygon_shader_id,
+                        targets,
+                    }),
+                    multiview: None,
+                });
+
+        Ok(Self {
+            vertices,
+            vertices_buffer,
+            indices,
+            indices_buffer,
+            polygons_indices: Vec::new(),
+            colors,
+            colors_buffer,
+            polygons_transforms,
+            polygons_transforms_buffer,
+            polygon_pipeline,
+        })
+    }
+
+    pub fn add_polygon(&mut self, transform: &Transform, polygon: &Polygon) {
+        let first_index = self.indices.len() as u32;
+        self.indices.extend(fan_indices(
+            self.vertices.len() as u32,
+            polygon.vertices.len() as u32,
+        ));
+        self.polygons_indices
+            .push(first_index..self.indices.len() as u32);
+        self.vertices.extend_from_slice(&polygon.vertices);
+        self.colors.push(polygon.color);
+        self.polygons_transforms.push(transform.into());
+    }
+
+    pub fn render<'a>(
+        &'a mut self,
+        rendering_context: &'a RenderingContext,
+        camera: &'a Camera,
+        render_pass: &mut wgpu::RenderPass<'a>,
+    ) {
+        let shrink_policy = &rendering_context.buffer_shrink_policy;
+        let gpu_context = &rendering_context.gpu_context;
+        self.vertices_buffer
+            .write_data(gpu_context, &self.vertices, shrink_policy);
+        self.indices_buffer
+            .write_data(gpu_context, &self.indices, shrink_policy);
+        self.colors_buffer
+            .write_data(gpu_context, &self.colors, shrink_policy);
+        self.polygons_transforms_buffer.write_data(
+            gpu_context,
+            &self.polygons_transforms,
+            shrink_policy,
+        );
+        if !self.polygons_indices.is_empty() {
+            let pipeline = &rendering_context
+                .resource_store
+                .get_render_pipeline(self.polygon_pipeline);
+
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, camera.bing_group(), &[]);
+            render_pass.set_vertex_buffer(0, self.vertices_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, self.polygons_transforms_buffer.slice(..));
+            render_pass.set_vertex_buffer(2, self.colors_buffer.slice(..));
+            render_pass.set_index_buffer(self.indices_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            // Polygons differ in their vertex count, so each one is drawn on
+            // its own as the instance holding its transform and color.
+            for (instance, indices) in self.polygons_indices.iter().enumerate() {
+                let instance = instance as u32;
+                render_pass.draw_indexed(indices.clone(), 0, instance..instance + 1);
+            }
+
+            self.vertices.clear();
+            self.indices.clear();
+            self.polygons_indices.clear();
+            self.colors.clear();
+            self.polygons_transforms.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fan_covers_every_vertex() {
+        assert_eq!(fan_indices(3, 3).collect::<Vec<_>>(), vec![3, 4, 5]);
+        assert_eq!(
+            fan_indices(0, 5).collect::<Vec<_>>(),
+            vec![0, 1, 2, 0, 2, 3, 0, 3, 4]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "at least three vertices")]
+    fn polygon_needs_three_vertices() {
+        Polygon::new(&[Vec2::ZERO, Vec2::X], Vec4::ONE);
+    }
+}
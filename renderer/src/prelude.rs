@@ -0,0 +1,52 @@
+//! Commonly used renderer types, re-exported so downstream code can get going
+//! with a single `use renderer::prelude::*;`.
+//!
+//! The prelude contains:
+//! - the [`Renderer`] itself together with [`RenderError`] and [`GpuContext`],
+//!   with [`DeviceRequestError`] for devices the adapter can't provide and
+//!   [`OffscreenTarget`] for rendering without a window,
+//! - camera setup: [`PrimaryCamera`], [`DepthMode`], [`CameraProjection`],
+//!   [`Orthographic`] and [`Perspective`], plus [`CameraId`] and [`Viewport`]
+//!   for additional cameras,
+//! - the drawable primitives: [`Circle`], [`CircleLine`], [`Rectangle`],
+//!   [`RectangleLine`], [`Polygon`], [`Line`], [`Sprite`] and [`MeshBundle`],
+//!   with [`BorderUnits`] for the outline widths and [`UvRect`] for the part
+//!   of a texture a sprite shows, plus [`StaticCirclesId`] for circles drawn
+//!   every frame,
+//! - scene composition: [`Transform`] and [`SceneNode`],
+//! - [`DirectionalLight`] for shading 3d meshes,
+//! - resource handles used by custom meshes: [`ShaderSource`], [`GpuMeshId`],
+//!   [`PipelineId`] and [`TextureId`], plus [`ComputePipelineId`] for compute
+//!   shaders,
+//! - every color constant from [`colors`](crate::colors).
+//!
+//! ```no_run
+//! use renderer::prelude::*;
+//!
+//! fn draw(renderer: &mut Renderer) {
+//!     renderer.draw_circle(
+//!         &Transform::from_translation(&glam::vec3(0.0, 0.0, 0.0)),
+//!         &Circle::new(100., GREEN),
+//!     );
+//! }
+//! ```
+
+pub use crate::{
+    camera::{DepthMode, PrimaryCamera, Viewport},
+    circle_rendering::{Circle, CircleLine, StaticCirclesId},
+    colors::*,
+    gpu_context::{DeviceRequestError, GpuContext},
+    light::DirectionalLight,
+    line_rendering::Line,
+    mesh_rendering::MeshBundle,
+    offscreen::OffscreenTarget,
+    polygon_rendering::Polygon,
+    primitives::BorderUnits,
+    projection::{CameraProjection, Orthographic, Perspective},
+    rectangle_rendering::{Rectangle, RectangleLine},
+    resource_store::{shader::ShaderSource, ComputePipelineId, GpuMeshId, PipelineId, TextureId},
+    scene_node::SceneNode,
+    sprite_rendering::{Sprite, UvRect},
+    transform::Transform,
+    CameraId, RenderError, Renderer,
+};
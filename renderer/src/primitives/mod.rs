@@ -1 +1,22 @@
 pub mod quad;
+
+/// Units in which the border width of outlined primitives and the width of
+/// lines are given.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum BorderUnits {
+    /// World units, so the width scales with the camera zoom.
+    #[default]
+    World,
+    /// Screen pixels, so the width stays the same under any zoom.
+    Pixels,
+}
+
+impl BorderUnits {
+    /// Value passed to the shaders, which can't take enums directly.
+    pub(crate) fn to_gpu(self) -> u32 {
+        match self {
+            BorderUnits::World => 0,
+            BorderUnits::Pixels => 1,
+        }
+    }
+}
@@ -1,10 +1,14 @@
 use crate::buffers::WriteableBuffer;
+use crate::camera::Camera;
 use crate::include_wgsl;
-use crate::primitives::quad::{QUAD_2D_INDICES, QUAD_2D_VERICES};
+use crate::primitives::{
+    quad::{QUAD_2D_INDICES, QUAD_2D_VERICES},
+    BorderUnits,
+};
 use crate::resource_store::PipelineId;
 use crate::transform::{Transform, TransformGpu};
 use bytemuck::{Pod, Zeroable};
-use glam::{Vec2, Vec3};
+use glam::{Vec2, Vec4};
 use wgpu::vertex_attr_array;
 
 use crate::resource_store::pipeline_layout::PipelineLayoutDescriptor;
@@ -20,29 +24,39 @@ use crate::{
 #[repr(C, packed)]
 pub struct Rectangle {
     size: Vec2,
-    color: Vec3,
+    color: Vec4,
 }
 
 #[derive(Debug, Copy, Clone, Zeroable, Pod)]
 #[repr(C, packed)]
 pub struct RectangleLine {
     size: Vec2,
-    color: Vec3,
+    color: Vec4,
     border: f32,
+    border_units: u32,
 }
 
 impl Rectangle {
-    pub fn new(size: Vec2, color: Vec3) -> Self {
+    pub fn new(size: Vec2, color: Vec4) -> Self {
         Self { size, color }
     }
 }
 
 impl RectangleLine {
-    pub fn new(size: Vec2, color: Vec3, border: f32) -> Self {
+    pub fn new(size: Vec2, color: Vec4, border: f32) -> Self {
         Self {
             size,
             color,
             border,
+            border_units: BorderUnits::World.to_gpu(),
+        }
+    }
+
+    /// Interprets the border width in the given units, world units by default.
+    pub fn with_border_units(self, border_units: BorderUnits) -> Self {
+        Self {
+            border_units: border_units.to_gpu(),
+            ..self
         }
     }
 }
@@ -115,10 +129,7 @@ impl RectangleRendering {
 
         let targets: Vec<Option<wgpu::ColorTargetState>> = vec![Some(wgpu::ColorTargetState {
             format: rendering_context.primary_camera.surface_format(),
-            blend: Some(wgpu::BlendState {
-                color: wgpu::BlendComponent::REPLACE,
-                alpha: wgpu::BlendComponent::REPLACE,
-            }),
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
             write_mask: wgpu::ColorWrites::ALL,
         })];
 
@@ -153,7 +164,7 @@ impl RectangleRendering {
                             VertexBufferLayout {
                                 array_stride: std::mem::size_of::<Rectangle>() as u64,
                                 step_mode: wgpu::VertexStepMode::Instance,
-                                attributes: vertex_attr_array![5 => Float32x2, 6 => Float32x3]
+                                attributes: vertex_attr_array![5 => Float32x2, 6 => Float32x4]
                                     .to_vec(),
                             },
                         ],
@@ -168,7 +179,7 @@ impl RectangleRendering {
                         conservative: false,
                     },
                     depth_stencil: rendering_context.primary_camera.depth_stencil(),
-                    multisample: wgpu::MultisampleState::default(),
+                    multisample: rendering_context.primary_camera.multisample_state(),
                     fragment: Some(FragmentState {
                         module: rectangle_shader_id,
                         targets: targets.clone(),
@@ -207,7 +218,7 @@ impl RectangleRendering {
                                 array_stride: std::mem::size_of::<RectangleLine>() as u64,
                                 step_mode: wgpu::VertexStepMode::Instance,
                                 attributes:
-                                    vertex_attr_array![5 => Float32x2, 6 => Float32x3, 7 => Float32]
+                                    vertex_attr_array![5 => Float32x2, 6 => Float32x4, 7 => Float32, 8 => Uint32]
                                         .to_vec(),
                             },
                         ],
@@ -222,7 +233,7 @@ impl RectangleRendering {
                         conservative: false,
                     },
                     depth_stencil: rendering_context.primary_camera.depth_stencil(),
-                    multisample: wgpu::MultisampleState::default(),
+                    multisample: rendering_context.primary_camera.multisample_state(),
                     fragment: Some(FragmentState {
                         module: rectangle_line_shader_id,
                         targets: targets.clone(),
@@ -259,20 +270,26 @@ impl RectangleRendering {
     pub fn render<'a>(
         &'a mut self,
         rendering_context: &'a RenderingContext,
+        camera: &'a Camera,
         render_pass: &mut wgpu::RenderPass<'a>,
     ) {
+        self.rectangles_buffer.write_data(
+            &rendering_context.gpu_context,
+            &self.rectangles,
+            &rendering_context.buffer_shrink_policy,
+        );
+        self.rectangles_transforms_buffer.write_data(
+            &rendering_context.gpu_context,
+            &self.rectangles_transforms,
+            &rendering_context.buffer_shrink_policy,
+        );
         if !self.rectangles.is_empty() {
-            self.rectangles_buffer
-                .write_data(&rendering_context.gpu_context, &self.rectangles);
-            self.rectangles_transforms_buffer
-                .write_data(&rendering_context.gpu_context, &self.rectangles_transforms);
-
             let pipeline = &rendering_context
                 .resource_store
                 .get_render_pipeline(self.rectangles_pipeline);
 
             render_pass.set_pipeline(pipeline);
-            render_pass.set_bind_group(0, rendering_context.primary_camera.bing_group(), &[]);
+            render_pass.set_bind_group(0, camera.bing_group(), &[]);
             render_pass.set_vertex_buffer(0, self.quad_vertex_buffer.slice(..));
             render_pass.set_vertex_buffer(1, self.rectangles_transforms_buffer.slice(..));
             render_pass.set_vertex_buffer(2, self.rectangles_buffer.slice(..));
@@ -286,26 +303,27 @@ impl RectangleRendering {
                 0..(self.rectangles.len() as u32),
             );
 
-            // TODO: Think about some memory releasing strategy. Spike in number of
-            // rectangles will lead to space leak.
             self.rectangles.clear();
             self.rectangles_transforms.clear();
         }
 
+        self.rectangle_lines_buffer.write_data(
+            &rendering_context.gpu_context,
+            &self.rectangle_lines,
+            &rendering_context.buffer_shrink_policy,
+        );
+        self.rectangle_lines_transforms_buffer.write_data(
+            &rendering_context.gpu_context,
+            &self.rectangle_lines_transforms,
+            &rendering_context.buffer_shrink_policy,
+        );
         if !self.rectangle_lines.is_empty() {
-            self.rectangle_lines_buffer
-                .write_data(&rendering_context.gpu_context, &self.rectangle_lines);
-            self.rectangle_lines_transforms_buffer.write_data(
-                &rendering_context.gpu_context,
-                &self.rectangle_lines_transforms,
-            );
-
             let pipeline = &rendering_context
                 .resource_store
                 .get_render_pipeline(self.rectangle_lines_pipeline);
 
             render_pass.set_pipeline(pipeline);
-            render_pass.set_bind_group(0, rendering_context.primary_camera.bing_group(), &[]);
+            render_pass.set_bind_group(0, camera.bing_group(), &[]);
             render_pass.set_vertex_buffer(0, self.quad_vertex_buffer.slice(..));
             render_pass.set_vertex_buffer(1, self.rectangle_lines_transforms_buffer.slice(..));
             render_pass.set_vertex_buffer(2, self.rectangle_lines_buffer.slice(..));
@@ -319,8 +337,6 @@ impl RectangleRendering {
                 0..(self.rectangle_lines.len() as u32),
             );
 
-            // TODO: Think about some memory releasing strategy. Spike in number of
-            // rectangles will lead to space leak.
             self.rectangle_lines.clear();
             self.rectangle_lines_transforms.clear();
         }
@@ -1,32 +1,65 @@
 use std::sync::Arc;
 
+use slotmap::SecondaryMap;
+
 use crate::{
-    camera::{Camera, PrimaryCamera},
+    buffers::{ShrinkPolicy, WriteableBuffer},
+    camera::{check_sample_count, Camera, PrimaryCamera},
     gpu_context::GpuContext,
-    resource_store::ResourceStore,
+    light::{DirectionalLight, DirectionalLightGpu},
+    resource_store::{PipelineId, ResourceStore},
 };
 
 pub struct RenderingContext {
     pub gpu_context: Arc<GpuContext>,
     pub primary_camera: Camera,
     pub resource_store: ResourceStore,
+    /// Directional light bound to group 0 together with the camera matrices.
+    pub light_buffer: WriteableBuffer<DirectionalLightGpu>,
+    /// How the instance buffers of the renderings release unused memory.
+    pub buffer_shrink_policy: ShrinkPolicy,
+    /// Alpha blended variants, without depth writes, of the 3d mesh
+    /// pipelines, used for transparent [`crate::mesh_rendering::MeshBundle`]s.
+    pub transparent_mesh_pipelines: SecondaryMap<PipelineId, PipelineId>,
+    /// How the 3d mesh pipelines rasterize triangles, `Line` for wireframes.
+    pub mesh_polygon_mode: wgpu::PolygonMode,
+    /// Push constant ranges of the 3d mesh pipelines declaring any, the
+    /// per draw data of their meshes is split across them.
+    pub mesh_push_constant_ranges: SecondaryMap<PipelineId, Vec<wgpu::PushConstantRange>>,
 }
 
 impl RenderingContext {
     pub fn new(gpu_context: &Arc<GpuContext>, primary_camera: PrimaryCamera) -> eyre::Result<Self> {
+        check_sample_count(primary_camera.sample_count)?;
         let mut resource_store = ResourceStore::new(gpu_context)?;
+        let camera_bind_group_layout = Camera::build_bind_group_layout(&mut resource_store);
+        let light_buffer = WriteableBuffer::new(
+            gpu_context,
+            "directional light buffer",
+            &DirectionalLightGpu::from(&DirectionalLight::default()),
+            wgpu::BufferUsages::UNIFORM,
+        );
         let primary_camera = Camera::new(
             gpu_context,
-            &mut resource_store,
+            &resource_store,
+            camera_bind_group_layout,
+            light_buffer.buffer(),
             primary_camera.projection,
             primary_camera.surface_format,
             primary_camera.size,
             primary_camera.depth_buffer,
+            primary_camera.depth_mode,
+            primary_camera.sample_count,
         );
         Ok(Self {
             gpu_context: gpu_context.clone(),
             primary_camera,
             resource_store,
+            light_buffer,
+            buffer_shrink_policy: ShrinkPolicy::default(),
+            transparent_mesh_pipelines: SecondaryMap::new(),
+            mesh_polygon_mode: wgpu::PolygonMode::Fill,
+            mesh_push_constant_ranges: SecondaryMap::new(),
         })
     }
 
@@ -0,0 +1,94 @@
+use slotmap::{new_key_type, SecondaryMap, SlotMap};
+
+use super::pipeline_layout::{PipelineLayoutId, PipelineLayoutStore};
+use super::reload_command::RebuildCommand;
+use super::shader::{ShaderId, ShaderStore};
+use crate::gpu_context::GpuContext;
+
+#[derive(Clone, Debug)]
+pub struct ComputePipelineDescriptor {
+    pub label: String,
+    /// The layout of bind groups for this pipeline, derived from the shader
+    /// when `None`.
+    pub layout: Option<PipelineLayoutId>,
+    /// The compiled compute stage, its entry point is `cs_main`.
+    pub module: ShaderId,
+}
+
+new_key_type! {
+    pub struct ComputePipelineId;
+}
+
+pub struct ComputePipelineStore {
+    store: SlotMap<ComputePipelineId, wgpu::ComputePipeline>,
+    pipeline_descriptors: SecondaryMap<ComputePipelineId, ComputePipelineDescriptor>,
+    gpu_context: GpuContext,
+}
+
+impl ComputePipelineStore {
+    pub fn new(gpu_context: &GpuContext) -> Self {
+        Self {
+            store: SlotMap::with_key(),
+            gpu_context: gpu_context.clone(),
+            pipeline_descriptors: SecondaryMap::new(),
+        }
+    }
+
+    pub fn build_compute_pipeline(
+        &mut self,
+        compute_pipeline_descriptor: &ComputePipelineDescriptor,
+        shader_store: &mut ShaderStore,
+        pipeline_layout_store: &PipelineLayoutStore,
+    ) -> ComputePipelineId {
+        let compute_pipeline = self.build(
+            compute_pipeline_descriptor,
+            shader_store,
+            pipeline_layout_store,
+        );
+        let id = self.store.insert(compute_pipeline);
+        self.pipeline_descriptors
+            .insert(id, compute_pipeline_descriptor.clone());
+        shader_store.register_dependant(
+            compute_pipeline_descriptor.module,
+            RebuildCommand::ComputePipeline(id),
+        );
+        id
+    }
+
+    fn build(
+        &self,
+        compute_pipeline_descriptor: &ComputePipelineDescriptor,
+        shader_store: &ShaderStore,
+        pipeline_layout_store: &PipelineLayoutStore,
+    ) -> wgpu::ComputePipeline {
+        self.gpu_context
+            .device()
+            .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(&compute_pipeline_descriptor.label),
+                layout: compute_pipeline_descriptor
+                    .layout
+                    .as_ref()
+                    .map(|v| pipeline_layout_store.get_pipeline_layout(*v)),
+                module: shader_store.get_shader(compute_pipeline_descriptor.module),
+                entry_point: "cs_main",
+            })
+    }
+
+    pub fn get_compute_pipeline(&self, pipeline_id: ComputePipelineId) -> &wgpu::ComputePipeline {
+        &self.store[pipeline_id]
+    }
+
+    pub fn rebuild(
+        &mut self,
+        shader_store: &ShaderStore,
+        pipeline_layout_store: &PipelineLayoutStore,
+        pipeline_id: ComputePipelineId,
+    ) {
+        let compute_pipeline = self.build(
+            &self.pipeline_descriptors[pipeline_id],
+            shader_store,
+            pipeline_layout_store,
+        );
+        self.store[pipeline_id] = compute_pipeline;
+    }
+}
@@ -1,5 +1,6 @@
-use glam::Vec3;
+use glam::{Vec2, Vec3, Vec4};
 use slotmap::{new_key_type, SlotMap};
+use thiserror::Error;
 
 use crate::{
     buffers::{IndexBuffer, WriteableVecBuffer},
@@ -10,6 +11,12 @@ use crate::{
 pub struct GpuMesh {
     pub vertex_buffer: WriteableVecBuffer<Vec3>,
     pub normal_buffer: WriteableVecBuffer<Vec3>,
+    /// Per vertex colors, only meshes built by
+    /// [`GpuMeshStore::build_gpu_mesh_colored`] have them.
+    pub color_buffer: Option<WriteableVecBuffer<Vec4>>,
+    /// Texture coordinates, only meshes built by
+    /// [`GpuMeshStore::build_gpu_mesh_textured`] have them.
+    pub uv_buffer: Option<WriteableVecBuffer<Vec2>>,
     pub index_buffer: IndexBuffer<u32>,
 }
 
@@ -17,6 +24,10 @@ new_key_type! {
     pub struct GpuMeshId;
 }
 
+#[derive(Error, Debug)]
+#[error("{0:?} does not refer to a GPU mesh, it was either removed or never built")]
+pub struct UnknownGpuMeshError(pub GpuMeshId);
+
 pub struct GpuMeshStore {
     store: SlotMap<GpuMeshId, GpuMesh>,
     gpu_context: GpuContext,
@@ -53,11 +64,128 @@ impl GpuMeshStore {
         self.store.insert(GpuMesh {
             vertex_buffer,
             normal_buffer,
+            color_buffer: None,
+            uv_buffer: None,
             index_buffer,
         })
     }
 
+    /// Builds a mesh with a color for every vertex, to be drawn with a
+    /// pipeline made by `create_3d_pipeline_vertex_colored`.
+    pub fn build_gpu_mesh_colored(
+        &mut self,
+        vertices: &[Vec3],
+        normals: &[Vec3],
+        colors: &[Vec4],
+        indices: &[u32],
+    ) -> GpuMeshId {
+        assert_eq!(
+            vertices.len(),
+            colors.len(),
+            "every vertex needs exactly one color"
+        );
+        let gpu_mesh_id = self.build_gpu_mesh(vertices, normals, indices);
+        self.store[gpu_mesh_id].color_buffer = Some(WriteableVecBuffer::new(
+            &self.gpu_context,
+            "mesh colors buffer",
+            colors,
+            wgpu::BufferUsages::VERTEX,
+        ));
+        gpu_mesh_id
+    }
+
+    /// Builds a mesh with texture coordinates for every vertex, to be drawn
+    /// with a pipeline made by `create_3d_pipeline_textured`.
+    pub fn build_gpu_mesh_textured(
+        &mut self,
+        vertices: &[Vec3],
+        normals: &[Vec3],
+        uvs: &[Vec2],
+        indices: &[u32],
+    ) -> GpuMeshId {
+        assert_eq!(
+            vertices.len(),
+            uvs.len(),
+            "every vertex needs exactly one texture coordinate"
+        );
+        let gpu_mesh_id = self.build_gpu_mesh(vertices, normals, indices);
+        self.store[gpu_mesh_id].uv_buffer = Some(WriteableVecBuffer::new(
+            &self.gpu_context,
+            "mesh texture coordinates buffer",
+            uvs,
+            wgpu::BufferUsages::VERTEX,
+        ));
+        gpu_mesh_id
+    }
+
+    /// Drops the buffers of the mesh. The id becomes invalid and the slot is
+    /// reused with a new version, so a stale id never refers to another mesh.
+    pub fn remove_gpu_mesh(&mut self, gpu_mesh_id: GpuMeshId) -> Result<(), UnknownGpuMeshError> {
+        self.store
+            .remove(gpu_mesh_id)
+            .map(drop)
+            .ok_or(UnknownGpuMeshError(gpu_mesh_id))
+    }
+
+    pub fn contains_gpu_mesh(&self, gpu_mesh_id: GpuMeshId) -> bool {
+        self.store.contains_key(gpu_mesh_id)
+    }
+
     pub fn get_gpu_mesh(&self, gpu_mesh_id: GpuMeshId) -> &GpuMesh {
-        &self.store[gpu_mesh_id]
+        self.store
+            .get(gpu_mesh_id)
+            .unwrap_or_else(|| panic!("{}", UnknownGpuMeshError(gpu_mesh_id)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.store.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.store.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use glam::vec3;
+
+    use super::*;
+    use crate::test_utils::{gpu_context, require_gpu};
+
+    fn build_triangle(store: &mut GpuMeshStore) -> GpuMeshId {
+        let vertices = [
+            vec3(0.0, 0.0, 0.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+        ];
+        store.build_gpu_mesh(&vertices, &[Vec3::Z; 3], &[0, 1, 2])
+    }
+
+    #[test]
+    fn removed_meshes_free_their_slot() {
+        let gpu_context = require_gpu!(gpu_context());
+        let mut store = GpuMeshStore::new(&gpu_context);
+        let first = build_triangle(&mut store);
+        let second = build_triangle(&mut store);
+        assert_eq!(store.len(), 2);
+
+        store.remove_gpu_mesh(first).unwrap();
+        assert_eq!(store.len(), 1);
+        assert!(!store.contains_gpu_mesh(first));
+        assert!(store.contains_gpu_mesh(second));
+        assert!(store.remove_gpu_mesh(first).is_err());
+        let stale_use = panic::catch_unwind(AssertUnwindSafe(|| {
+            store.get_gpu_mesh(first);
+        }));
+        assert!(stale_use.is_err());
+
+        // The freed slot gets reused, but the old id stays invalid.
+        let third = build_triangle(&mut store);
+        assert_eq!(store.len(), 2);
+        assert_ne!(first, third);
+        assert!(!store.contains_gpu_mesh(first));
     }
 }
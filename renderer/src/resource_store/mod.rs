@@ -1,15 +1,18 @@
 pub mod bind_group_layout;
+pub mod compute_pipeline;
 pub mod gpu_mesh;
 pub mod pipeline_layout;
 pub mod reload_command;
 pub mod render_pipeline;
 pub mod shader;
 pub mod shader_include;
+pub mod texture;
 
 use std::env;
 
-use glam::Vec3;
+use glam::{UVec2, Vec2, Vec3, Vec4};
 use thiserror::Error;
+use tracing::error;
 
 use crate::{
     file_watcher::{FileWatcher, FileWatcherError},
@@ -18,25 +21,32 @@ use crate::{
 
 use self::{
     bind_group_layout::BindGroupLayoutStore,
+    compute_pipeline::{ComputePipelineDescriptor, ComputePipelineStore},
     gpu_mesh::{GpuMesh, GpuMeshStore},
     pipeline_layout::{PipelineLayoutDescriptor, PipelineLayoutStore},
     render_pipeline::{RenderPipelineDescriptor, RenderPipelineStore},
-    shader::{BuildShaderError, ShaderSource, ShaderStore},
+    shader::{BuildShaderError, InitializationError, ShaderSource, ShaderStore},
+    texture::{GpuTexture, TextureStore},
 };
 
 pub use self::bind_group_layout::BindGroupLayoutId;
-pub use self::gpu_mesh::GpuMeshId;
+pub use self::compute_pipeline::ComputePipelineId;
+pub use self::gpu_mesh::{GpuMeshId, UnknownGpuMeshError};
 pub use self::pipeline_layout::PipelineLayoutId;
 pub use self::render_pipeline::PipelineId;
 pub use self::shader::ShaderId;
+pub use self::texture::TextureId;
 
 pub struct ResourceStore {
     file_watcher: FileWatcher,
     shader_store: ShaderStore,
     render_pipeline_store: RenderPipelineStore,
+    compute_pipeline_store: ComputePipelineStore,
     pipeline_layout_store: PipelineLayoutStore,
     bind_group_layout_store: BindGroupLayoutStore,
     gpu_mesh_store: GpuMeshStore,
+    texture_store: TextureStore,
+    last_reload_error: Option<ReloadError>,
 }
 
 #[derive(Error, Debug)]
@@ -61,17 +71,22 @@ impl ResourceStore {
         let pipeline_layout_store = PipelineLayoutStore::new(gpu_context);
         let shader_store = ShaderStore::new(gpu_context)?;
         let render_pipeline_store = RenderPipelineStore::new(gpu_context);
+        let compute_pipeline_store = ComputePipelineStore::new(gpu_context);
         let gpu_mesh_store = GpuMeshStore::new(gpu_context);
+        let texture_store = TextureStore::new(gpu_context);
         let pwd = env::current_dir()?;
         let file_watcher = FileWatcher::new(pwd)?;
 
         Ok(Self {
             shader_store,
             render_pipeline_store,
+            compute_pipeline_store,
             pipeline_layout_store,
             bind_group_layout_store,
             gpu_mesh_store,
+            texture_store,
             file_watcher,
+            last_reload_error: None,
         })
     }
 
@@ -119,6 +134,15 @@ impl ResourceStore {
         self.shader_store.get_shader(shader_id)
     }
 
+    /// See [`ShaderStore::add_library_module`].
+    pub fn register_shader_lib(
+        &mut self,
+        name: &str,
+        source: &str,
+    ) -> Result<(), InitializationError> {
+        self.shader_store.add_library_module(name, source)
+    }
+
     pub fn build_render_pipeline(
         &mut self,
         bind_group_layout_descriptor: &RenderPipelineDescriptor,
@@ -134,6 +158,43 @@ impl ResourceStore {
         self.render_pipeline_store.get_render_pipeline(pipeline_id)
     }
 
+    pub fn build_compute_pipeline(
+        &mut self,
+        compute_pipeline_descriptor: &ComputePipelineDescriptor,
+    ) -> ComputePipelineId {
+        self.compute_pipeline_store.build_compute_pipeline(
+            compute_pipeline_descriptor,
+            &mut self.shader_store,
+            &self.pipeline_layout_store,
+        )
+    }
+
+    pub fn get_compute_pipeline(&self, pipeline_id: ComputePipelineId) -> &wgpu::ComputePipeline {
+        self.compute_pipeline_store
+            .get_compute_pipeline(pipeline_id)
+    }
+
+    pub fn set_pipelines_sample_count(&mut self, sample_count: u32) {
+        self.render_pipeline_store.set_sample_count(
+            &self.shader_store,
+            &self.pipeline_layout_store,
+            sample_count,
+        );
+    }
+
+    pub fn set_pipelines_polygon_mode(
+        &mut self,
+        pipeline_ids: impl IntoIterator<Item = PipelineId>,
+        polygon_mode: wgpu::PolygonMode,
+    ) {
+        self.render_pipeline_store.set_polygon_mode(
+            &self.shader_store,
+            &self.pipeline_layout_store,
+            pipeline_ids,
+            polygon_mode,
+        );
+    }
+
     pub fn build_gpu_mesh(
         &mut self,
         vertices: &[Vec3],
@@ -144,16 +205,86 @@ impl ResourceStore {
             .build_gpu_mesh(vertices, normals, indices)
     }
 
+    pub fn build_gpu_mesh_colored(
+        &mut self,
+        vertices: &[Vec3],
+        normals: &[Vec3],
+        colors: &[Vec4],
+        indices: &[u32],
+    ) -> GpuMeshId {
+        self.gpu_mesh_store
+            .build_gpu_mesh_colored(vertices, normals, colors, indices)
+    }
+
+    pub fn build_gpu_mesh_textured(
+        &mut self,
+        vertices: &[Vec3],
+        normals: &[Vec3],
+        uvs: &[Vec2],
+        indices: &[u32],
+    ) -> GpuMeshId {
+        self.gpu_mesh_store
+            .build_gpu_mesh_textured(vertices, normals, uvs, indices)
+    }
+
+    /// Frees the buffers of the mesh, any further use of `gpu_mesh_id` panics.
+    pub fn remove_gpu_mesh(&mut self, gpu_mesh_id: GpuMeshId) -> Result<(), UnknownGpuMeshError> {
+        self.gpu_mesh_store.remove_gpu_mesh(gpu_mesh_id)
+    }
+
+    pub fn contains_gpu_mesh(&self, gpu_mesh_id: GpuMeshId) -> bool {
+        self.gpu_mesh_store.contains_gpu_mesh(gpu_mesh_id)
+    }
+
     pub fn get_gpu_mesh(&self, gpu_mesh_id: GpuMeshId) -> &GpuMesh {
         self.gpu_mesh_store.get_gpu_mesh(gpu_mesh_id)
     }
 
-    pub fn reload_if_necessary(&mut self) -> Result<(), ReloadError> {
-        let mut dependants = self.file_watcher.process_updates();
+    pub fn gpu_mesh_count(&self) -> usize {
+        self.gpu_mesh_store.len()
+    }
+
+    /// Uploads an RGBA image, its bind group follows `bind_group_layout_id`.
+    pub fn build_texture(
+        &mut self,
+        size: UVec2,
+        rgba: &[u8],
+        bind_group_layout_id: BindGroupLayoutId,
+    ) -> TextureId {
+        self.texture_store.build_texture(
+            size,
+            rgba,
+            self.bind_group_layout_store
+                .get_bing_group_layout(bind_group_layout_id),
+        )
+    }
+
+    pub fn get_texture(&self, texture_id: TextureId) -> &GpuTexture {
+        self.texture_store.get_texture(texture_id)
+    }
+
+    /// Rebuilds the resources whose files changed. A shader that fails to
+    /// build keeps its last good module and its dependants aren't rebuilt,
+    /// the error is logged and kept for [`ResourceStore::take_reload_error`].
+    pub fn reload_if_necessary(&mut self) {
+        let dependants = self.file_watcher.process_updates();
+        self.rebuild_dependants(dependants);
+    }
+
+    /// Runs `dependants` and everything that depends on them in turn.
+    fn rebuild_dependants(&mut self, mut dependants: Vec<reload_command::RebuildCommand>) {
         while let Some(dependant) = dependants.pop() {
             let new_dependants = match dependant {
                 reload_command::RebuildCommand::Shader(shader_id) => {
-                    self.shader_store.rebuild(shader_id)
+                    match self.shader_store.rebuild(shader_id) {
+                        Ok(new_dependants) => new_dependants,
+                        Err(err) => {
+                            let err = ReloadError::from(err);
+                            error!("{err}");
+                            self.last_reload_error = Some(err);
+                            Vec::new()
+                        }
+                    }
                 }
                 reload_command::RebuildCommand::Pipeline(pipeline_id) => {
                     self.render_pipeline_store.rebuild(
@@ -161,13 +292,94 @@ impl ResourceStore {
                         &self.pipeline_layout_store,
                         pipeline_id,
                     );
-                    Ok(Vec::new())
+                    Vec::new()
+                }
+                reload_command::RebuildCommand::ComputePipeline(pipeline_id) => {
+                    self.compute_pipeline_store.rebuild(
+                        &self.shader_store,
+                        &self.pipeline_layout_store,
+                        pipeline_id,
+                    );
+                    Vec::new()
                 }
-            }?;
+            };
             for new_dependant in new_dependants {
                 dependants.push(new_dependant.clone());
             }
         }
-        Ok(())
+    }
+
+    /// Returns the most recent reload error, if any, and clears it.
+    pub fn take_reload_error(&mut self) -> Option<ReloadError> {
+        self.last_reload_error.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{gpu_context, require_gpu, TEXTURE_FORMAT};
+    use reload_command::RebuildCommand;
+    use render_pipeline::{FragmentState, VertexState};
+    use shader::StaticShaderFile;
+
+    #[test]
+    fn shader_change_rebuilds_the_pipelines_using_it() {
+        let gpu_context = require_gpu!(gpu_context());
+        let mut resource_store = ResourceStore::new(&gpu_context).unwrap();
+        let shader_id = resource_store
+            .build_shader(&ShaderSource::StaticFile(StaticShaderFile {
+                source: "
+                    @vertex
+                    fn vs_main() -> @builtin(position) vec4<f32> {
+                        return vec4(0.0, 0.0, 0.0, 1.0);
+                    }
+
+                    @fragment
+                    fn fs_main() -> @location(0) vec4<f32> {
+                        return vec4(1.0);
+                    }",
+                file_path: "pipeline_dependant_test.wgsl",
+            }))
+            .unwrap();
+        let pipeline_layout_id = resource_store.build_pipeline_layout(&PipelineLayoutDescriptor {
+            label: "test pipeline layout".to_string(),
+            bind_group_layouts: Vec::new(),
+            push_constant_ranges: Vec::new(),
+        });
+        // Like the mesh pipelines, one shader provides both stages.
+        let pipeline_id = resource_store.build_render_pipeline(&RenderPipelineDescriptor {
+            label: "test pipeline".to_string(),
+            layout: Some(pipeline_layout_id),
+            vertex: VertexState {
+                module: shader_id,
+                buffers: Vec::new(),
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(FragmentState {
+                module: shader_id,
+                targets: vec![Some(TEXTURE_FORMAT.into())],
+            }),
+            multiview: None,
+        });
+        let shader_module_id = resource_store.get_shader(shader_id).global_id();
+        let pipeline_global_id = resource_store.get_render_pipeline(pipeline_id).global_id();
+
+        assert_eq!(
+            resource_store.shader_store.rebuild(shader_id).unwrap(),
+            vec![RebuildCommand::Pipeline(pipeline_id)]
+        );
+        resource_store.rebuild_dependants(vec![RebuildCommand::Shader(shader_id)]);
+
+        assert_ne!(
+            resource_store.get_shader(shader_id).global_id(),
+            shader_module_id
+        );
+        assert_ne!(
+            resource_store.get_render_pipeline(pipeline_id).global_id(),
+            pipeline_global_id
+        );
     }
 }
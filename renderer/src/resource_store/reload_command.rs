@@ -1,7 +1,8 @@
-use super::{PipelineId, ShaderId};
+use super::{ComputePipelineId, PipelineId, ShaderId};
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum RebuildCommand {
     Shader(ShaderId),
     Pipeline(PipelineId),
+    ComputePipeline(ComputePipelineId),
 }
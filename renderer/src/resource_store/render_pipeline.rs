@@ -166,4 +166,34 @@ impl RenderPipelineStore {
         );
         self.store[pipeline_id] = render_pipeline;
     }
+
+    /// Rebuilds every pipeline to draw with `sample_count` samples per pixel.
+    pub fn set_sample_count(
+        &mut self,
+        shader_store: &ShaderStore,
+        pipeline_layout_store: &PipelineLayoutStore,
+        sample_count: u32,
+    ) {
+        let pipeline_ids: Vec<PipelineId> = self.store.keys().collect();
+        for pipeline_id in pipeline_ids {
+            self.pipeline_descriptors[pipeline_id].multisample.count = sample_count;
+            self.rebuild(shader_store, pipeline_layout_store, pipeline_id);
+        }
+    }
+
+    /// Rebuilds the given pipelines to rasterize their polygons with `polygon_mode`.
+    pub fn set_polygon_mode(
+        &mut self,
+        shader_store: &ShaderStore,
+        pipeline_layout_store: &PipelineLayoutStore,
+        pipeline_ids: impl IntoIterator<Item = PipelineId>,
+        polygon_mode: wgpu::PolygonMode,
+    ) {
+        for pipeline_id in pipeline_ids {
+            self.pipeline_descriptors[pipeline_id]
+                .primitive
+                .polygon_mode = polygon_mode;
+            self.rebuild(shader_store, pipeline_layout_store, pipeline_id);
+        }
+    }
 }
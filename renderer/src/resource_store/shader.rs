@@ -2,13 +2,14 @@ use std::collections::HashMap;
 use std::io;
 use std::{borrow::Cow, env, io::Read, path::PathBuf};
 
+pub use naga_oil::compose::ShaderDefValue;
 use naga_oil::compose::{
     get_preprocessor_data, ComposableModuleDescriptor, Composer, ComposerError, ImportDefinition,
     NagaModuleDescriptor, ShaderLanguage, ShaderType,
 };
 use slotmap::{new_key_type, SecondaryMap, SlotMap};
 use thiserror::Error;
-use wgpu::ShaderModuleDescriptor;
+use wgpu::{naga::valid::Capabilities, ShaderModuleDescriptor};
 
 use super::reload_command::RebuildCommand;
 use crate::file_watcher::FileWatcher;
@@ -18,7 +19,11 @@ new_key_type! {
     pub struct ShaderId;
 }
 
-const DEFAULT_SHADER_LIB: &[&str] = &[include_str!("../../shaders/lib/model_matrix.wgsl")];
+const DEFAULT_SHADER_LIB: &[&str] = &[
+    include_str!("../../shaders/lib/model_matrix.wgsl"),
+    include_str!("../../shaders/lib/lighting.wgsl"),
+    include_str!("../../shaders/lib/antialiasing.wgsl"),
+];
 
 pub struct ShaderStore {
     store: SlotMap<ShaderId, wgpu::ShaderModule>,
@@ -38,6 +43,37 @@ pub struct StaticShaderFile {
 pub enum ShaderSource {
     ShaderFile(PathBuf),
     StaticFile(StaticShaderFile),
+    /// `source` compiled with `#ifdef`-style defines, see
+    /// [`ShaderSource::with_defs`].
+    WithDefs {
+        source: Box<ShaderSource>,
+        defs: HashMap<String, ShaderDefValue>,
+    },
+}
+
+impl ShaderSource {
+    /// Compiles the shader with the given preprocessor defines, so that one
+    /// file can produce several variants, e.g. with `#ifdef LIGHTING`. The
+    /// defines are kept when the shader is hot reloaded. Defines given later
+    /// override earlier ones of the same name.
+    pub fn with_defs(self, defs: impl IntoIterator<Item = (String, ShaderDefValue)>) -> Self {
+        match self {
+            ShaderSource::WithDefs {
+                source,
+                defs: mut existing,
+            } => {
+                existing.extend(defs);
+                ShaderSource::WithDefs {
+                    source,
+                    defs: existing,
+                }
+            }
+            source => ShaderSource::WithDefs {
+                source: Box::new(source),
+                defs: defs.into_iter().collect(),
+            },
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -63,11 +99,22 @@ pub enum InitializationError {
     ImportError { module_name: String, caller: String },
     #[error("Missing `#define_import_path` in a lib shader shader_str: `{shader_str}`")]
     MissingDefinitionOfImportPath { shader_str: String },
+    #[error("Module `{module_name}` is already registered")]
+    ModuleAlreadyRegistered { module_name: String },
 }
 
 impl ShaderStore {
     pub fn new(gpu_context: &GpuContext) -> Result<Self, InitializationError> {
-        let mut naga_oil_composer = Composer::default();
+        // Shaders may declare `var<push_constant>` only when the device
+        // supports them, otherwise naga rejects them already.
+        let mut capabilities = Capabilities::default();
+        if gpu_context
+            .features()
+            .contains(wgpu::Features::PUSH_CONSTANTS)
+        {
+            capabilities |= Capabilities::PUSH_CONSTANT;
+        }
+        let mut naga_oil_composer = Composer::default().with_capabilities(capabilities);
         Self::load_shader_lib(&mut naga_oil_composer, DEFAULT_SHADER_LIB)?;
 
         Ok(Self {
@@ -79,6 +126,27 @@ impl ShaderStore {
         })
     }
 
+    /// Registers `source` as a library module that shaders can import from
+    /// `name`, e.g. `#import name::function`, in addition to the built-in
+    /// ones. The source doesn't need a `#define_import_path`. Modules it
+    /// imports have to be registered before it, and a name can't be
+    /// registered twice since replacing a module would drop every module
+    /// importing it.
+    pub fn add_library_module(
+        &mut self,
+        name: &str,
+        source: &str,
+    ) -> Result<(), InitializationError> {
+        if self.naga_oil_composer.contains_module(name) {
+            return Err(InitializationError::ModuleAlreadyRegistered {
+                module_name: name.to_string(),
+            });
+        }
+        let (_, imports, _) = get_preprocessor_data(source);
+        let modules = HashMap::from([(name.to_string(), (source, imports))]);
+        Self::load_modules(&mut self.naga_oil_composer, &modules, "runtime-lib")
+    }
+
     fn load_shader_lib(
         naga_oil_composer: &mut Composer,
         shader_lib: &[&str],
@@ -94,17 +162,31 @@ impl ShaderStore {
                 });
             }
         }
+        Self::load_modules(naga_oil_composer, &default_shaders, "build-in-lib")
+    }
 
+    /// Adds `modules` to the composer after the modules they import, which
+    /// are either among `modules` or already in the composer, e.g. built-in
+    /// modules imported by ones registered at runtime.
+    fn load_modules(
+        naga_oil_composer: &mut Composer,
+        modules: &HashMap<String, (&str, Vec<ImportDefinition>)>,
+        path_prefix: &str,
+    ) -> Result<(), InitializationError> {
         let mut stack: Vec<(&str, &str)> = Vec::new();
 
-        for (module_name, _) in default_shaders.iter() {
+        for (module_name, _) in modules.iter() {
             if naga_oil_composer.contains_module(module_name.as_str()) {
                 continue;
             }
             stack.push(("lib_root", module_name.as_str()));
 
             while let Some((caller, module_name)) = stack.pop() {
-                if let Some((module_source, imports)) = default_shaders.get(module_name) {
+                // Modules imported by several others are pushed once for each.
+                if naga_oil_composer.contains_module(module_name) {
+                    continue;
+                }
+                if let Some((module_source, imports)) = modules.get(module_name) {
                     let mut missing_imports: Vec<&str> = Vec::new();
                     for import in imports.iter().map(|v| v.import.as_str()) {
                         if !naga_oil_composer.contains_module(import) {
@@ -115,9 +197,9 @@ impl ShaderStore {
                     if missing_imports.is_empty() {
                         naga_oil_composer.add_composable_module(ComposableModuleDescriptor {
                             source: module_source,
-                            file_path: format!("build-in-lib/{module_name}").as_str(),
+                            file_path: format!("{path_prefix}/{module_name}").as_str(),
                             language: ShaderLanguage::Wgsl,
-                            as_name: None,
+                            as_name: Some(module_name.to_string()),
                             additional_imports: &[],
                             shader_defs: HashMap::new(),
                         })?;
@@ -143,7 +225,7 @@ impl ShaderStore {
         file_watcher: &mut FileWatcher,
         shader_source: &ShaderSource,
     ) -> Result<ShaderId, BuildShaderError> {
-        let (shader_module, file_path) = self.build(shader_source)?;
+        let (shader_module, file_path) = self.build(shader_source, &HashMap::new())?;
         let shader_id = self.store.insert(shader_module);
         self.shader_sources.insert(shader_id, shader_source.clone());
         self.dependants.insert(shader_id, Vec::new());
@@ -153,12 +235,19 @@ impl ShaderStore {
         Ok(shader_id)
     }
 
+    /// Builds `shader_source` with `shader_defs` added to its own defines.
     fn build(
         &mut self,
         shader_source: &ShaderSource,
+        shader_defs: &HashMap<String, ShaderDefValue>,
     ) -> Result<(wgpu::ShaderModule, Option<PathBuf>), BuildShaderError> {
         use BuildShaderError::*;
         match shader_source {
+            ShaderSource::WithDefs { source, defs } => {
+                let mut defs = defs.clone();
+                defs.extend(shader_defs.iter().map(|(k, v)| (k.clone(), *v)));
+                self.build(source, &defs)
+            }
             ShaderSource::ShaderFile(file_path) => {
                 // TODO: In future. We should start using some kind of an asset loader so we can
                 // cross compile to web.
@@ -189,7 +278,7 @@ impl ShaderStore {
                             file: file_path.clone(),
                         })?,
                         shader_type: ShaderType::Wgsl,
-                        shader_defs: HashMap::new(),
+                        shader_defs: shader_defs.clone(),
                         additional_imports: &[],
                     })
                     .map_err(|err| NagaComposerFailed {
@@ -214,7 +303,7 @@ impl ShaderStore {
                         source: static_file.source,
                         file_path: static_file.file_path,
                         shader_type: ShaderType::Wgsl,
-                        shader_defs: HashMap::new(),
+                        shader_defs: shader_defs.clone(),
                         additional_imports: &[],
                     })
                     .map_err(|err| NagaComposerFailed {
@@ -238,18 +327,25 @@ impl ShaderStore {
         &self.store[shader_id]
     }
 
+    /// Builds the shader again from its source. On error the previously built
+    /// module stays in place, so a broken edit doesn't take rendering down.
     pub fn rebuild(
         &mut self,
         shader_id: ShaderId,
     ) -> Result<Vec<RebuildCommand>, BuildShaderError> {
         let shader_source = self.shader_sources[shader_id].clone();
-        let (shader_module, _) = self.build(&shader_source)?;
+        let (shader_module, _) = self.build(&shader_source, &HashMap::new())?;
         self.store[shader_id] = shader_module;
         Ok(self.dependants[shader_id].clone())
     }
 
+    /// Makes `reload_command` run after every rebuild of the shader, registering
+    /// the same command twice (e.g. a shader used for both stages) runs it once.
     pub fn register_dependant(&mut self, shader_id: ShaderId, reload_command: RebuildCommand) {
-        self.dependants[shader_id].push(reload_command);
+        let dependants = &mut self.dependants[shader_id];
+        if !dependants.contains(&reload_command) {
+            dependants.push(reload_command);
+        }
     }
 }
 
@@ -296,4 +392,153 @@ mod tests {
         let ret = ShaderStore::load_shader_lib(&mut naga_oil_composer, &test_shaders);
         assert!(ret.is_ok());
     }
+
+    #[test]
+    fn defines_select_shader_branches_and_survive_rebuilds() {
+        use super::*;
+        use crate::test_utils::{gpu_context, require_gpu};
+
+        let gpu_context = require_gpu!(gpu_context());
+        let mut shader_store = ShaderStore::new(&gpu_context).unwrap();
+        let mut file_watcher = FileWatcher::new(env::current_dir().unwrap()).unwrap();
+        // `shade` only exists with LIGHTING defined.
+        let source = ShaderSource::StaticFile(StaticShaderFile {
+            source: "
+                #ifdef LIGHTING
+                fn shade() -> f32 { return 1.0; }
+                #endif
+
+                @fragment
+                fn fs_main() -> @location(0) vec4<f32> {
+                #ifdef LIGHTING
+                    return vec4(shade());
+                #else
+                    return vec4(0.0);
+                #endif
+                }",
+            file_path: "defines_test.wgsl",
+        });
+        let lit = source
+            .clone()
+            .with_defs([("LIGHTING".to_string(), ShaderDefValue::Bool(true))]);
+
+        assert!(shader_store
+            .build_shader(&mut file_watcher, &source)
+            .is_ok());
+        let shader_id = shader_store.build_shader(&mut file_watcher, &lit).unwrap();
+        assert!(shader_store.rebuild(shader_id).is_ok());
+
+        // Without its body the branch refers to a missing function.
+        let broken = ShaderSource::StaticFile(StaticShaderFile {
+            source: "
+                @fragment
+                fn fs_main() -> @location(0) vec4<f32> {
+                #ifdef LIGHTING
+                    return vec4(shade());
+                #else
+                    return vec4(0.0);
+                #endif
+                }",
+            file_path: "defines_broken_test.wgsl",
+        });
+        assert!(shader_store
+            .build_shader(&mut file_watcher, &broken)
+            .is_ok());
+        assert!(shader_store
+            .build_shader(
+                &mut file_watcher,
+                &broken.with_defs([("LIGHTING".to_string(), ShaderDefValue::Bool(true))])
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn registered_lib_modules_can_be_imported() {
+        use super::*;
+        use crate::test_utils::{gpu_context, require_gpu};
+
+        let gpu_context = require_gpu!(gpu_context());
+        let mut shader_store = ShaderStore::new(&gpu_context).unwrap();
+        let mut file_watcher = FileWatcher::new(env::current_dir().unwrap()).unwrap();
+        // Importing a built-in module, and then the registered one.
+        shader_store
+            .add_library_module(
+                "app_transform",
+                "
+                #import model_matrix::to_model_matrix
+
+                fn transform_origin(m1: vec3<f32>, m2: vec3<f32>, m3: vec3<f32>, t: vec3<f32>) -> vec4<f32> {
+                    return to_model_matrix(m1, m2, m3, t) * vec4(0.0, 0.0, 0.0, 1.0);
+                }",
+            )
+            .unwrap();
+        shader_store
+            .add_library_module(
+                "app_palette",
+                "
+                #import app_transform::transform_origin
+
+                fn origin_color() -> vec4<f32> {
+                    return transform_origin(vec3(1.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0), vec3(0.0, 0.0, 1.0), vec3(0.5));
+                }",
+            )
+            .unwrap();
+        let source = ShaderSource::StaticFile(StaticShaderFile {
+            source: "
+                #import app_palette::origin_color
+
+                @fragment
+                fn fs_main() -> @location(0) vec4<f32> {
+                    return origin_color();
+                }",
+            file_path: "registered_lib_test.wgsl",
+        });
+        assert!(shader_store
+            .build_shader(&mut file_watcher, &source)
+            .is_ok());
+
+        assert!(matches!(
+            shader_store.add_library_module("app_palette", "fn other() {}"),
+            Err(InitializationError::ModuleAlreadyRegistered { module_name })
+                if module_name == "app_palette"
+        ));
+        assert_eq!(
+            shader_store
+                .add_library_module("app_broken", "fn bar() { app_missing::foo(); }")
+                .unwrap_err()
+                .to_string(),
+            "Imported module `app_missing` not found. Import located in module `app_broken`"
+        );
+    }
+
+    #[test]
+    fn failed_rebuild_keeps_the_previous_module() {
+        use super::*;
+        use crate::test_utils::{gpu_context, require_gpu};
+
+        let gpu_context = require_gpu!(gpu_context());
+        let mut shader_store = ShaderStore::new(&gpu_context).unwrap();
+        let mut file_watcher = FileWatcher::new(env::current_dir().unwrap()).unwrap();
+        let file_path =
+            env::temp_dir().join(format!("failed_rebuild_test_{}.wgsl", std::process::id()));
+        std::fs::write(
+            &file_path,
+            "@fragment fn fs_main() -> @location(0) vec4<f32> { return vec4(1.0); }",
+        )
+        .unwrap();
+        let shader_id = shader_store
+            .build_shader(
+                &mut file_watcher,
+                &ShaderSource::ShaderFile(file_path.clone()),
+            )
+            .unwrap();
+        let module_id = shader_store.get_shader(shader_id).global_id();
+
+        std::fs::write(&file_path, "@fragment fn fs_main( {").unwrap();
+        let rebuilt = shader_store.rebuild(shader_id);
+        std::fs::remove_file(&file_path).unwrap();
+
+        assert!(rebuilt.is_err());
+        assert_eq!(shader_store.get_shader(shader_id).global_id(), module_id);
+    }
 }
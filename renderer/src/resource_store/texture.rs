@@ -0,0 +1,124 @@
+use glam::UVec2;
+use slotmap::{new_key_type, SlotMap};
+
+use crate::gpu_context::GpuContext;
+
+/// Bytes of a single RGBA8 texel.
+const TEXEL_SIZE: u32 = 4;
+
+#[derive(Debug)]
+pub struct GpuTexture {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+    /// Texture and sampler bound together for the textured mesh pipelines.
+    pub bind_group: wgpu::BindGroup,
+}
+
+new_key_type! {
+    pub struct TextureId;
+}
+
+pub struct TextureStore {
+    store: SlotMap<TextureId, GpuTexture>,
+    gpu_context: GpuContext,
+}
+
+impl TextureStore {
+    pub fn new(gpu_context: &GpuContext) -> Self {
+        Self {
+            store: SlotMap::with_key(),
+            gpu_context: gpu_context.clone(),
+        }
+    }
+
+    /// Uploads an sRGB image given as `size.x * size.y` RGBA texels row by
+    /// row. The texture repeats outside of the 0..1 UV range and is filtered
+    /// linearly.
+    pub fn build_texture(
+        &mut self,
+        size: UVec2,
+        rgba: &[u8],
+        bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> TextureId {
+        assert_eq!(
+            rgba.len(),
+            (TEXEL_SIZE * size.x * size.y) as usize,
+            "texture data has to hold four bytes for every texel"
+        );
+        let extent = wgpu::Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        };
+        let texture = self
+            .gpu_context
+            .device()
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("mesh texture"),
+                size: extent,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+        self.gpu_context.queue().write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(TEXEL_SIZE * size.x),
+                rows_per_image: Some(size.y),
+            },
+            extent,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = self
+            .gpu_context
+            .device()
+            .create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("mesh texture sampler"),
+                address_mode_u: wgpu::AddressMode::Repeat,
+                address_mode_v: wgpu::AddressMode::Repeat,
+                address_mode_w: wgpu::AddressMode::Repeat,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                ..Default::default()
+            });
+        let bind_group = self
+            .gpu_context
+            .device()
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("mesh texture bind group"),
+                layout: bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    },
+                ],
+            });
+        self.store.insert(GpuTexture {
+            texture,
+            view,
+            sampler,
+            bind_group,
+        })
+    }
+
+    pub fn get_texture(&self, texture_id: TextureId) -> &GpuTexture {
+        &self.store[texture_id]
+    }
+}
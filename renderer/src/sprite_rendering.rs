@@ -0,0 +1,359 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{UVec2, Vec2, Vec4};
+use wgpu::vertex_attr_array;
+
+use crate::{
+    buffers::{IndexBuffer, WriteableBuffer, WriteableVecBuffer},
+    camera::Camera,
+    include_wgsl,
+    primitives::quad::{QUAD_2D_INDICES, QUAD_2D_VERICES},
+    rendering_context::RenderingContext,
+    resource_store::{
+        pipeline_layout::PipelineLayoutDescriptor,
+        render_pipeline::{
+            FragmentState, RenderPipelineDescriptor, VertexBufferLayout, VertexState,
+        },
+        BindGroupLayoutId, PipelineId, TextureId,
+    },
+    transform::{Transform, TransformGpu},
+};
+
+/// Part of a texture in texture coordinates, `(0, 0)` being its top left
+/// corner and `(1, 1)` the bottom right one.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct UvRect {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl UvRect {
+    /// The whole texture.
+    pub const FULL: UvRect = UvRect {
+        min: Vec2::ZERO,
+        max: Vec2::ONE,
+    };
+
+    pub fn new(min: Vec2, max: Vec2) -> Self {
+        Self { min, max }
+    }
+
+    /// Region of an atlas of `texture_size` texels, `size` texels large with
+    /// its top left corner at texel `min`. It is inset by half a texel, so
+    /// that linear filtering doesn't bleed in the neighbouring regions.
+    pub fn from_texels(min: UVec2, size: UVec2, texture_size: UVec2) -> Self {
+        let texture_size = texture_size.as_vec2();
+        Self {
+            min: (min.as_vec2() + 0.5) / texture_size,
+            max: ((min + size).as_vec2() - 0.5) / texture_size,
+        }
+    }
+}
+
+/// A textured rectangle of `size` world units, showing the `uv_rect` part of
+/// a texture from [`Renderer::load_texture`](crate::Renderer::load_texture)
+/// multiplied by `tint`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sprite {
+    pub texture_id: TextureId,
+    pub size: Vec2,
+    pub uv_rect: UvRect,
+    pub tint: Vec4,
+}
+
+impl Sprite {
+    /// Sprite showing the whole texture untinted.
+    pub fn new(texture_id: TextureId, size: Vec2) -> Self {
+        Self {
+            texture_id,
+            size,
+            uv_rect: UvRect::FULL,
+            tint: Vec4::ONE,
+        }
+    }
+
+    pub fn with_uv_rect(self, uv_rect: UvRect) -> Self {
+        Self { uv_rect, ..self }
+    }
+
+    pub fn with_tint(self, tint: Vec4) -> Self {
+        Self { tint, ..self }
+    }
+}
+
+/// Per instance data of a [`Sprite`], its texture is bound separately.
+#[derive(Debug, Copy, Clone)]
+#[repr(C, packed)]
+struct SpriteGpu {
+    size: Vec2,
+    uv_min: Vec2,
+    uv_max: Vec2,
+    tint: Vec4,
+}
+
+// SAFETY: `SpriteGpu` is `repr(C, packed)` and consists solely of `f32`
+// values, so there is no padding and every bit pattern is valid.
+unsafe impl Zeroable for SpriteGpu {}
+unsafe impl Pod for SpriteGpu {}
+
+impl From<&Sprite> for SpriteGpu {
+    fn from(sprite: &Sprite) -> Self {
+        Self {
+            size: sprite.size,
+            uv_min: sprite.uv_rect.min,
+            uv_max: sprite.uv_rect.max,
+            tint: sprite.tint,
+        }
+    }
+}
+
+pub struct SpriteRendering {
+    sprites: Vec<SpriteGpu>,
+    sprites_buffer: WriteableVecBuffer<SpriteGpu>,
+    sprites_transforms: Vec<TransformGpu>,
+    sprites_transforms_buffer: WriteableVecBuffer<TransformGpu>,
+    /// Texture of every sprite, in the order they were added.
+    sprites_textures: Vec<TextureId>,
+    quad_vertex_buffer: WriteableBuffer<[Vec2; 4]>,
+    quad_index_buffer: IndexBuffer<u16>,
+    sprite_pipeline: PipelineId,
+}
+
+impl SpriteRendering {
+    /// Textures are bound with `texture_bind_group_layout`, the one
+    /// [`MeshRendering::load_texture`](crate::mesh_rendering::MeshRendering::load_texture)
+    /// builds their bind groups with.
+    pub fn new(
+        rendering_context: &mut RenderingContext,
+        texture_bind_group_layout: BindGroupLayoutId,
+    ) -> eyre::Result<Self> {
+        let sprites = Vec::new();
+        let sprites_buffer = WriteableVecBuffer::new(
+            &rendering_context.gpu_context,
+            "sprites buffer",
+            &sprites,
+            wgpu::BufferUsages::VERTEX,
+        );
+        let sprites_transforms = Vec::new();
+        let sprites_transforms_buffer = WriteableVecBuffer::new(
+            &rendering_context.gpu_context,
+            "sprite transforms buffer",
+            &sprites_transforms,
+            wgpu::BufferUsages::VERTEX,
+        );
+
+        let quad_vertex_buffer = WriteableBuffer::new(
+            &rendering_context.gpu_context,
+            "quad vertex buffer",
+            &QUAD_2D_VERICES,
+            wgpu::BufferUsages::VERTEX,
+        );
+        let quad_index_buffer = IndexBuffer::new(
+            &rendering_context.gpu_context,
+            "quad index buffer",
+            QUAD_2D_INDICES,
+        );
+
+        let sprite_shader_id = rendering_context
+            .resource_store
+            .build_shader(&include_wgsl!("../shaders/sprite.wgsl"))?;
+
+        let targets: Vec<Option<wgpu::ColorTargetState>> = vec![Some(wgpu::ColorTargetState {
+            format: rendering_context.primary_camera.surface_format(),
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::ALL,
+        })];
+
+        let sprite_pipeline_layout_id =
+            rendering_context
+                .resource_store
+                .build_pipeline_layout(&PipelineLayoutDescriptor {
+                    label: "sprite pipeline layout".to_string(),
+                    bind_group_layouts: vec![
+                        *rendering_context.primary_camera.bing_group_layout(),
+                        texture_bind_group_layout,
+                    ],
+                    push_constant_ranges: Vec::new(),
+                });
+
+        let sprite_pipeline =
+            rendering_context
+                .resource_store
+                .build_render_pipeline(&RenderPipelineDescriptor {
+                    label: "sprite pipeline".to_string(),
+                    layout: Some(sprite_pipeline_layout_id),
+                    vertex: VertexState {
+                        module: sprite_shader_id,
+                        buffers: vec![
+                            VertexBufferLayout {
+                                array_stride: std::mem::size_of::<Vec2>() as u64,
+                                step_mode: wgpu::VertexStepMode::Vertex,
+                                attributes: vertex_attr_array![0 => Float32x2].to_vec(),
+                            },
+                            VertexBufferLayout {
+                                array_stride: std::mem::size_of::<TransformGpu>() as u64,
+                                step_mode: wgpu::VertexStepMode::Instance,
+                                attributes: TransformGpu::vertex_attributes(1, 2, 3, 4),
+                            },
+                            VertexBufferLayout {
+                                array_stride: std::mem::size_of::<SpriteGpu>() as u64,
+                                step_mode: wgpu::VertexStepMode::Instance,
+                                attributes: vertex_attr_array![
+                                    5 => Float32x2,
+                                    6 => Float32x2,
+                                    7 => Float32x2,
+                                    8 => Float32x4
+                                ]
+                                .to_vec(),
+                            },
+                        ],
+                    },
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: Some(wgpu::Face::Back),
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: rendering_context.primary_camera.depth_stencil(),
+                    multisample: rendering_context.primary_camera.multisample_state(),
+                    fragment: Some(FragmentState {
+                        module: sprite_shader_id,
+                        targets,
+                    }),
+                    multiview: None,
+                });
+
+        Ok(Self {
+            sprites,
+            sprites_buffer,
+            sprites_transforms,
+            sprites_transforms_buffer,
+            sprites_textures: Vec::new(),
+            quad_vertex_buffer,
+            quad_index_buffer,
+            sprite_pipeline,
+        })
+    }
+
+    pub fn add_sprite(&mut self, transform: &Transform, sprite: &Sprite) {
+        self.sprites.push(sprite.into());
+        self.sprites_transforms.push(transform.into());
+        self.sprites_textures.push(sprite.texture_id);
+    }
+
+    pub fn render<'a>(
+        &'a mut self,
+        rendering_context: &'a RenderingContext,
+        camera: &'a Camera,
+        render_pass: &mut wgpu::RenderPass<'a>,
+    ) {
+        self.sprites_buffer.write_data(
+            &rendering_context.gpu_context,
+            &self.sprites,
+            &rendering_context.buffer_shrink_policy,
+        );
+        self.sprites_transforms_buffer.write_data(
+            &rendering_context.gpu_context,
+            &self.sprites_transforms,
+            &rendering_context.buffer_shrink_policy,
+        );
+        if !self.sprites.is_empty() {
+            let pipeline = &rendering_context
+                .resource_store
+                .get_render_pipeline(self.sprite_pipeline);
+
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, camera.bing_group(), &[]);
+            render_pass.set_vertex_buffer(0, self.quad_vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, self.sprites_transforms_buffer.slice(..));
+            render_pass.set_vertex_buffer(2, self.sprites_buffer.slice(..));
+            render_pass.set_index_buffer(
+                self.quad_index_buffer.slice(..),
+                self.quad_index_buffer.index_format(),
+            );
+            // One draw per run of sprites sharing a texture, which keeps them
+            // in the order they were added.
+            for (start, end) in texture_runs(&self.sprites_textures) {
+                let texture = rendering_context
+                    .resource_store
+                    .get_texture(self.sprites_textures[start]);
+                render_pass.set_bind_group(1, &texture.bind_group, &[]);
+                render_pass.draw_indexed(
+                    self.quad_index_buffer.draw_count(),
+                    0,
+                    start as u32..end as u32,
+                );
+            }
+
+            self.sprites.clear();
+            self.sprites_transforms.clear();
+            self.sprites_textures.clear();
+        }
+    }
+}
+
+/// Ranges of consecutive equal textures, as start and end indices.
+fn texture_runs(textures: &[TextureId]) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for end in 1..=textures.len() {
+        if end == textures.len() || textures[end] != textures[start] {
+            runs.push((start, end));
+            start = end;
+        }
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{uvec2, vec2, vec3};
+    use slotmap::SlotMap;
+
+    use super::*;
+    use crate::test_utils::{render_offscreen, require_gpu, test_camera};
+
+    #[test]
+    fn consecutive_sprites_of_a_texture_share_a_draw() {
+        let mut ids = SlotMap::<TextureId, ()>::with_key();
+        let (a, b) = (ids.insert(()), ids.insert(()));
+        assert_eq!(texture_runs(&[]), []);
+        assert_eq!(texture_runs(&[a, a, b, a]), [(0, 2), (2, 3), (3, 4)]);
+    }
+
+    #[test]
+    fn uv_rect_from_texels_stays_inside_the_region() {
+        let uv_rect = UvRect::from_texels(uvec2(16, 0), uvec2(16, 8), uvec2(64, 16));
+        assert_eq!(
+            uv_rect,
+            UvRect::new(vec2(16.5 / 64.0, 0.5 / 16.0), vec2(31.5 / 64.0, 7.5 / 16.0))
+        );
+    }
+
+    #[test]
+    fn sprites_show_their_uv_rect_tinted() {
+        let primary_camera = test_camera(glam::vec2(16.0, 16.0));
+        // Two white texels followed by two blue ones.
+        let white = [255, 255, 255, 255];
+        let blue = [0, 0, 255, 255];
+        let rgba = [white, white, blue, blue].concat();
+        let pixels = require_gpu!(render_offscreen(primary_camera, |renderer| {
+            let texture_id = renderer.load_texture(uvec2(4, 1), &rgba);
+            let sprite = Sprite::new(texture_id, vec2(8.0, 16.0));
+            renderer.draw_sprite(
+                &Transform::from_translation(&vec3(-4.0, 0.0, 0.0)),
+                &sprite
+                    .with_uv_rect(UvRect::new(Vec2::ZERO, vec2(0.5, 1.0)))
+                    .with_tint(Vec4::X + Vec4::W),
+            );
+            renderer.draw_sprite(
+                &Transform::from_translation(&vec3(4.0, 0.0, 0.0)),
+                &sprite.with_uv_rect(UvRect::new(vec2(0.5, 0.0), Vec2::ONE)),
+            );
+        }));
+        assert_eq!(pixels[8 * 16 + 4], [255, 0, 0, 255]);
+        assert_eq!(pixels[8 * 16 + 12], blue);
+    }
+}
@@ -0,0 +1,185 @@
+use std::{path::Path, sync::Arc};
+
+use glam::{UVec2, Vec2};
+use image::RgbaImage;
+
+use crate::{
+    camera::{DepthMode, PrimaryCamera},
+    gpu_context::GpuContext,
+    offscreen::OffscreenTarget,
+    projection::{CameraProjection, Orthographic},
+    Renderer,
+};
+
+pub const TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+/// Directory of the reference images [`assert_image_matches`] compares against.
+const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/golden");
+
+/// Environment variable which, when set, lets [`require_gpu`] skip tests on
+/// machines without a GPU adapter instead of failing them.
+const SKIP_GPU_TESTS: &str = "SKIP_GPU_TESTS";
+
+/// Unwraps the result of a GPU test helper, e.g. [`gpu_context`] or
+/// [`render_offscreen`], which is `None` without a GPU adapter. A missing
+/// adapter fails the test, so that e.g. golden image tests can't pass on CI
+/// without checking anything, unless `SKIP_GPU_TESTS` is set, in which case
+/// the test returns early.
+macro_rules! require_gpu {
+    ($gpu_resource:expr) => {
+        match $gpu_resource {
+            Some(gpu_resource) => gpu_resource,
+            None => {
+                $crate::test_utils::no_gpu_adapter();
+                return;
+            }
+        }
+    };
+}
+pub(crate) use require_gpu;
+
+/// Called by [`require_gpu`] when there is no GPU adapter, panics unless
+/// `SKIP_GPU_TESTS` is set.
+pub fn no_gpu_adapter() {
+    assert!(
+        std::env::var_os(SKIP_GPU_TESTS).is_some(),
+        "no GPU adapter available, set {SKIP_GPU_TESTS}=1 to skip the GPU tests"
+    );
+    eprintln!("no GPU adapter available, skipping");
+}
+
+/// Creates a GPU context for tests, `None` when no GPU adapter is available.
+pub fn gpu_context() -> Option<Arc<GpuContext>> {
+    gpu_context_with(wgpu::Features::empty(), Renderer::wgpu_limits())
+}
+
+/// Like [`gpu_context`], with a device created with `features` and `limits`.
+/// `None` also when the adapter can't provide them.
+pub fn gpu_context_with(features: wgpu::Features, limits: wgpu::Limits) -> Option<Arc<GpuContext>> {
+    let instance = wgpu::Instance::default();
+    let adapter =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))?;
+    let gpu_context = pollster::block_on(GpuContext::request(
+        &adapter,
+        Some("test device"),
+        features,
+        limits,
+    ))
+    .ok()?;
+    Some(Arc::new(gpu_context))
+}
+
+/// The camera most tests render with: orthographic with one world unit per
+/// pixel, drawing into a [`TEXTURE_FORMAT`] target of `size` without a depth
+/// buffer or multisampling. Tests that need something else override just
+/// those fields, e.g. `PrimaryCamera { depth_buffer, ..test_camera(size) }`.
+pub fn test_camera(size: Vec2) -> PrimaryCamera {
+    PrimaryCamera {
+        projection: CameraProjection::Orthographic(Orthographic {
+            depth: 2.0,
+            scale: 1.0,
+        }),
+        surface_format: TEXTURE_FORMAT,
+        size,
+        depth_buffer: None,
+        depth_mode: DepthMode::Standard,
+        sample_count: 1,
+    }
+}
+
+/// Renders a single frame offscreen and returns its RGBA pixels row by row.
+/// Returns `None` when no GPU adapter is available, see [`require_gpu`].
+pub fn render_offscreen<F>(primary_camera: PrimaryCamera, draw: F) -> Option<Vec<[u8; 4]>>
+where
+    F: FnOnce(&mut Renderer),
+{
+    let mut draw = Some(draw);
+    render_offscreen_frames(primary_camera, 1, |_frame, renderer| {
+        if let Some(draw) = draw.take() {
+            draw(renderer)
+        }
+    })
+}
+
+/// Renders `frame_count` frames into the same target, calling `draw` with the
+/// frame index before each one, and returns the pixels of the last frame.
+pub fn render_offscreen_frames<F>(
+    primary_camera: PrimaryCamera,
+    frame_count: usize,
+    mut draw: F,
+) -> Option<Vec<[u8; 4]>>
+where
+    F: FnMut(usize, &mut Renderer),
+{
+    let gpu_context = gpu_context()?;
+    let target = OffscreenTarget::new(
+        &gpu_context,
+        primary_camera.size.as_uvec2(),
+        primary_camera.surface_format,
+    );
+    let mut renderer = Renderer::new(&gpu_context, primary_camera).unwrap();
+    for frame in 0..frame_count {
+        draw(frame, &mut renderer);
+        renderer.render(target.texture()).unwrap();
+    }
+
+    Some(
+        target
+            .read_pixels(&gpu_context)
+            .chunks(4)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+            .collect(),
+    )
+}
+
+/// Turns the pixels returned by [`render_offscreen`] into an image of `size`.
+pub fn to_image(pixels: &[[u8; 4]], size: UVec2) -> RgbaImage {
+    RgbaImage::from_raw(size.x, size.y, pixels.concat())
+        .expect("pixel count doesn't match the image size")
+}
+
+/// Asserts that `rendered` matches the reference image `golden_path`, relative
+/// to `renderer/golden`, with every channel of every pixel off by at most
+/// `tolerance`.
+///
+/// Running the tests with `UPDATE_GOLDEN=1` (re)writes the reference images
+/// instead, after checking the new ones by eye. On a mismatch the rendered
+/// frame is saved next to the reference as `<name>.actual.png`.
+pub fn assert_image_matches(rendered: &RgbaImage, golden_path: &str, tolerance: u8) {
+    let golden_path = Path::new(GOLDEN_DIR).join(golden_path);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        rendered.save(&golden_path).unwrap();
+        return;
+    }
+    let golden = image::open(&golden_path)
+        .unwrap_or_else(|err| panic!("can't read {}: {err}", golden_path.display()))
+        .into_rgba8();
+    assert_eq!(
+        rendered.dimensions(),
+        golden.dimensions(),
+        "size differs from {}",
+        golden_path.display()
+    );
+
+    let mismatches = rendered
+        .enumerate_pixels()
+        .filter(|(x, y, pixel)| {
+            let expected = golden.get_pixel(*x, *y);
+            pixel
+                .0
+                .iter()
+                .zip(expected.0)
+                .any(|(actual, expected)| actual.abs_diff(expected) > tolerance)
+        })
+        .count();
+    if mismatches > 0 {
+        let actual_path = golden_path.with_extension("actual.png");
+        rendered.save(&actual_path).unwrap();
+        panic!(
+            "{mismatches} pixels differ from {} by more than {tolerance}, the rendered frame \
+             was saved to {}",
+            golden_path.display(),
+            actual_path.display()
+        );
+    }
+}
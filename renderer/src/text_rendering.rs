@@ -0,0 +1,434 @@
+use std::ops::RangeInclusive;
+
+use bytemuck::{Pod, Zeroable};
+use glam::{Vec2, Vec3, Vec4};
+use wgpu::vertex_attr_array;
+
+use crate::{
+    buffers::WriteableVecBuffer,
+    camera::Camera,
+    include_wgsl,
+    rendering_context::RenderingContext,
+    resource_store::{
+        pipeline_layout::PipelineLayoutDescriptor,
+        render_pipeline::{
+            FragmentState, RenderPipelineDescriptor, VertexBufferLayout, VertexState,
+        },
+        PipelineId,
+    },
+};
+
+/// Font pixels across a glyph of the built-in font.
+const GLYPH_WIDTH: u32 = 5;
+/// Font pixels from the top of a glyph to the bottom of its descender. Capitals
+/// take the top seven rows.
+const GLYPH_HEIGHT: u32 = 8;
+/// Font pixels between the left edges of neighbouring glyphs.
+const GLYPH_ADVANCE: u32 = GLYPH_WIDTH + 1;
+/// Characters the font covers, the printable ASCII ones.
+const FONT_CHARS: RangeInclusive<char> = ' '..='~';
+/// Glyph drawn for characters the font doesn't cover.
+const REPLACEMENT_CHAR: char = '?';
+
+/// A single character of a text drawn by [`Renderer::draw_text`](crate::Renderer::draw_text).
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[repr(C, packed)]
+struct Glyph {
+    /// World position the text is centered on.
+    position: Vec3,
+    /// Pixels from `position` to the center of this glyph.
+    offset: Vec2,
+    color: Vec4,
+    /// Height of the text in pixels.
+    size: f32,
+    /// Index of the glyph in the atlas.
+    index: u32,
+}
+
+// SAFETY: `Glyph` is `repr(C, packed)` and consists solely of `f32` and `u32`
+// values, so there is no padding and every bit pattern is valid.
+unsafe impl Zeroable for Glyph {}
+unsafe impl Pod for Glyph {}
+
+/// Lays `text` out on a single line centered on `position`, skipping spaces.
+fn layout_text(
+    position: Vec3,
+    text: &str,
+    size: f32,
+    color: Vec4,
+) -> impl Iterator<Item = Glyph> + '_ {
+    let font_pixel = size / GLYPH_HEIGHT as f32;
+    let advance = GLYPH_ADVANCE as f32 * font_pixel;
+    let width =
+        text.chars().count() as f32 * advance - (GLYPH_ADVANCE - GLYPH_WIDTH) as f32 * font_pixel;
+    let first_center = (GLYPH_WIDTH as f32 * font_pixel - width) / 2.0;
+    text.chars()
+        .enumerate()
+        .filter(|(_, char)| *char != ' ')
+        .map(move |(column, char)| Glyph {
+            position,
+            offset: Vec2::new(first_center + column as f32 * advance, 0.0),
+            color,
+            size,
+            index: glyph_index(char),
+        })
+}
+
+fn glyph_index(char: char) -> u32 {
+    let char = if FONT_CHARS.contains(&char) {
+        char
+    } else {
+        REPLACEMENT_CHAR
+    };
+    char as u32 - *FONT_CHARS.start() as u32
+}
+
+/// Renders the built-in font into a single row of glyphs, one byte per texel.
+fn atlas_texels() -> Vec<u8> {
+    let atlas_width = GLYPH_WIDTH as usize * FONT.len();
+    let mut texels = vec![0; atlas_width * GLYPH_HEIGHT as usize];
+    for (index, glyph) in FONT.iter().enumerate() {
+        for (y, row) in glyph.iter().enumerate() {
+            for x in 0..GLYPH_WIDTH as usize {
+                if row & (1 << (GLYPH_WIDTH as usize - 1 - x)) != 0 {
+                    texels[y * atlas_width + index * GLYPH_WIDTH as usize + x] = u8::MAX;
+                }
+            }
+        }
+    }
+    texels
+}
+
+pub struct TextRendering {
+    glyphs: Vec<Glyph>,
+    glyphs_buffer: WriteableVecBuffer<Glyph>,
+    atlas_bind_group: wgpu::BindGroup,
+    text_pipeline: PipelineId,
+}
+
+impl TextRendering {
+    pub fn new(rendering_context: &mut RenderingContext) -> eyre::Result<Self> {
+        let glyphs = Vec::new();
+        let glyphs_buffer = WriteableVecBuffer::new(
+            &rendering_context.gpu_context,
+            "glyphs buffer",
+            &glyphs,
+            wgpu::BufferUsages::VERTEX,
+        );
+
+        let atlas_bind_group_layout_id = rendering_context.resource_store.build_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                label: Some("glyph atlas layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                }],
+            },
+        );
+        let atlas_size = wgpu::Extent3d {
+            width: GLYPH_WIDTH * FONT.len() as u32,
+            height: GLYPH_HEIGHT,
+            depth_or_array_layers: 1,
+        };
+        let device = rendering_context.gpu_context.device();
+        let atlas = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("glyph atlas"),
+            size: atlas_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        rendering_context.gpu_context.queue().write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &atlas,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &atlas_texels(),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(atlas_size.width),
+                rows_per_image: Some(atlas_size.height),
+            },
+            atlas_size,
+        );
+        let atlas_view = atlas.create_view(&wgpu::TextureViewDescriptor::default());
+        let atlas_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("glyph atlas bind group"),
+            layout: rendering_context
+                .resource_store
+                .get_bing_group_layout(atlas_bind_group_layout_id),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&atlas_view),
+            }],
+        });
+
+        let text_shader_id = rendering_context
+            .resource_store
+            .build_shader(&include_wgsl!("../shaders/text.wgsl"))?;
+
+        let targets: Vec<Option<wgpu::ColorTargetState>> = vec![Some(wgpu::ColorTargetState {
+            format: rendering_context.primary_camera.surface_format(),
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::ALL,
+        })];
+
+        let text_pipeline_layout_id =
+            rendering_context
+                .resource_store
+                .build_pipeline_layout(&PipelineLayoutDescriptor {
+                    label: "text pipeline layout".to_string(),
+                    bind_group_layouts: vec![
+                        *rendering_context.primary_camera.bing_group_layout(),
+                        atlas_bind_group_layout_id,
+                    ],
+                    push_constant_ranges: Vec::new(),
+                });
+
+        let text_pipeline =
+            rendering_context
+                .resource_store
+                .build_render_pipeline(&RenderPipelineDescriptor {
+                    label: "text pipeline".to_string(),
+                    layout: Some(text_pipeline_layout_id),
+                    vertex: VertexState {
+                        module: text_shader_id,
+                        buffers: vec![VertexBufferLayout {
+                            array_stride: std::mem::size_of::<Glyph>() as u64,
+                            step_mode: wgpu::VertexStepMode::Instance,
+                            attributes: vertex_attr_array![
+                                0 => Float32x3,
+                                1 => Float32x2,
+                                2 => Float32x4,
+                                3 => Float32,
+                                4 => Uint32
+                            ]
+                            .to_vec(),
+                        }],
+                    },
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: Some(wgpu::Face::Back),
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: rendering_context.primary_camera.depth_stencil(),
+                    multisample: rendering_context.primary_camera.multisample_state(),
+                    fragment: Some(FragmentState {
+                        module: text_shader_id,
+                        targets,
+                    }),
+                    multiview: None,
+                });
+
+        Ok(Self {
+            glyphs,
+            glyphs_buffer,
+            atlas_bind_group,
+            text_pipeline,
+        })
+    }
+
+    pub fn add_text(&mut self, position: Vec3, text: &str, size: f32, color: Vec4) {
+        self.glyphs.extend(layout_text(position, text, size, color));
+    }
+
+    pub fn render<'a>(
+        &'a mut self,
+        rendering_context: &'a RenderingContext,
+        camera: &'a Camera,
+        render_pass: &mut wgpu::RenderPass<'a>,
+    ) {
+        self.glyphs_buffer.write_data(
+            &rendering_context.gpu_context,
+            &self.glyphs,
+            &rendering_context.buffer_shrink_policy,
+        );
+        if !self.glyphs.is_empty() {
+            let pipeline = &rendering_context
+                .resource_store
+                .get_render_pipeline(self.text_pipeline);
+
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, camera.bing_group(), &[]);
+            render_pass.set_bind_group(1, &self.atlas_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.glyphs_buffer.slice(..));
+            render_pass.draw(0..6, 0..(self.glyphs.len() as u32));
+
+            self.glyphs.clear();
+        }
+    }
+}
+
+/// Built-in 5x8 pixel font for [`FONT_CHARS`], in order.
+/// Every glyph is a list of rows from the top, the highest of the five bits
+/// being the leftmost pixel.
+#[rustfmt::skip]
+const FONT: [[u8; GLYPH_HEIGHT as usize]; 95] = [
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000], // space
+    [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100, 0b00000], // !
+    [0b01010, 0b01010, 0b01010, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000], // "
+    [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010, 0b00000], // #
+    [0b00100, 0b01111, 0b10100, 0b01110, 0b00101, 0b11110, 0b00100, 0b00000], // $
+    [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011, 0b00000], // %
+    [0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101, 0b00000], // &
+    [0b00100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000], // '
+    [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010, 0b00000], // (
+    [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000, 0b00000], // )
+    [0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000, 0b00000], // *
+    [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000, 0b00000], // +
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000], // ,
+    [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000, 0b00000], // -
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100, 0b00000], // .
+    [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000, 0b00000], // /
+    [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110, 0b00000], // 0
+    [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000], // 1
+    [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111, 0b00000], // 2
+    [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110, 0b00000], // 3
+    [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010, 0b00000], // 4
+    [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110, 0b00000], // 5
+    [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110, 0b00000], // 6
+    [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00000], // 7
+    [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110, 0b00000], // 8
+    [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100, 0b00000], // 9
+    [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000, 0b00000], // :
+    [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b00100, 0b01000, 0b00000], // ;
+    [0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00000], // <
+    [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000], // =
+    [0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000, 0b00000], // >
+    [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100, 0b00000], // ?
+    [0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110, 0b00000], // @
+    [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001, 0b00000], // A
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110, 0b00000], // B
+    [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110, 0b00000], // C
+    [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100, 0b00000], // D
+    [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111, 0b00000], // E
+    [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000, 0b00000], // F
+    [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111, 0b00000], // G
+    [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001, 0b00000], // H
+    [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000], // I
+    [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100, 0b00000], // J
+    [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001, 0b00000], // K
+    [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111, 0b00000], // L
+    [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001, 0b00000], // M
+    [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001, 0b00000], // N
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110, 0b00000], // O
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000, 0b00000], // P
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101, 0b00000], // Q
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001, 0b00000], // R
+    [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110, 0b00000], // S
+    [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000], // T
+    [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110, 0b00000], // U
+    [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00000], // V
+    [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010, 0b00000], // W
+    [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001, 0b00000], // X
+    [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00000], // Y
+    [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111, 0b00000], // Z
+    [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110, 0b00000], // [
+    [0b00000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0b00000, 0b00000], // \
+    [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110, 0b00000], // ]
+    [0b00100, 0b01010, 0b10001, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000], // ^
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111, 0b00000], // _
+    [0b01000, 0b00100, 0b00010, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000], // `
+    [0b00000, 0b00000, 0b01110, 0b00001, 0b01111, 0b10001, 0b01111, 0b00000], // a
+    [0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b11110, 0b00000], // b
+    [0b00000, 0b00000, 0b01110, 0b10000, 0b10000, 0b10001, 0b01110, 0b00000], // c
+    [0b00001, 0b00001, 0b01101, 0b10011, 0b10001, 0b10001, 0b01111, 0b00000], // d
+    [0b00000, 0b00000, 0b01110, 0b10001, 0b11111, 0b10000, 0b01110, 0b00000], // e
+    [0b00110, 0b01001, 0b01000, 0b11100, 0b01000, 0b01000, 0b01000, 0b00000], // f
+    [0b00000, 0b00000, 0b01111, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110], // g
+    [0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001, 0b00000], // h
+    [0b00100, 0b00000, 0b01100, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000], // i
+    [0b00010, 0b00000, 0b00110, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100], // j
+    [0b10000, 0b10000, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b00000], // k
+    [0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000], // l
+    [0b00000, 0b00000, 0b11010, 0b10101, 0b10101, 0b10001, 0b10001, 0b00000], // m
+    [0b00000, 0b00000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001, 0b00000], // n
+    [0b00000, 0b00000, 0b01110, 0b10001, 0b10001, 0b10001, 0b01110, 0b00000], // o
+    [0b00000, 0b00000, 0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000], // p
+    [0b00000, 0b00000, 0b01111, 0b10001, 0b10001, 0b01111, 0b00001, 0b00001], // q
+    [0b00000, 0b00000, 0b10110, 0b11001, 0b10000, 0b10000, 0b10000, 0b00000], // r
+    [0b00000, 0b00000, 0b01111, 0b10000, 0b01110, 0b00001, 0b11110, 0b00000], // s
+    [0b01000, 0b01000, 0b11100, 0b01000, 0b01000, 0b01001, 0b00110, 0b00000], // t
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b10011, 0b01101, 0b00000], // u
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00000], // v
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b10101, 0b10101, 0b01010, 0b00000], // w
+    [0b00000, 0b00000, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b00000], // x
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110], // y
+    [0b00000, 0b00000, 0b11111, 0b00010, 0b00100, 0b01000, 0b11111, 0b00000], // z
+    [0b00010, 0b00100, 0b00100, 0b01000, 0b00100, 0b00100, 0b00010, 0b00000], // {
+    [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000], // |
+    [0b01000, 0b00100, 0b00100, 0b00010, 0b00100, 0b00100, 0b01000, 0b00000], // }
+    [0b00000, 0b00000, 0b01000, 0b10101, 0b00010, 0b00000, 0b00000, 0b00000], // ~
+];
+
+#[cfg(test)]
+mod tests {
+    use glam::vec3;
+
+    use super::*;
+    use crate::{
+        camera::PrimaryCamera,
+        colors::RED,
+        projection::{CameraProjection, Orthographic},
+        test_utils::{render_offscreen, require_gpu, test_camera},
+    };
+
+    #[test]
+    fn layout_centers_text_and_skips_spaces() {
+        let glyphs: Vec<Glyph> = layout_text(Vec3::ZERO, "a b", 16.0, RED).collect();
+        let offsets: Vec<Vec2> = glyphs.iter().map(|glyph| glyph.offset).collect();
+        // Glyphs are 10 pixels wide and 12 apart at this size.
+        assert_eq!(offsets, [Vec2::new(-12.0, 0.0), Vec2::new(12.0, 0.0)]);
+        let indices: Vec<u32> = glyphs.iter().map(|glyph| glyph.index).collect();
+        assert_eq!(indices, [glyph_index('a'), glyph_index('b')]);
+        assert_eq!(glyph_index('é'), glyph_index('?'));
+    }
+
+    #[test]
+    fn text_keeps_its_pixel_size_under_zoom() {
+        // Bounding box of the drawn pixels as (min x, min y, max x, max y).
+        let bounds = |scale: f32| {
+            let primary_camera = PrimaryCamera {
+                projection: CameraProjection::Orthographic(Orthographic { depth: 2.0, scale }),
+                ..test_camera(glam::vec2(32.0, 32.0))
+            };
+            render_offscreen(primary_camera, |renderer| {
+                renderer.draw_text(vec3(0.0, 0.0, 0.0), "H", 16.0, RED)
+            })
+            .map(|pixels| {
+                let drawn: Vec<(usize, usize)> = (0..32 * 32)
+                    .filter(|i| pixels[*i] != [0, 0, 0, 255])
+                    .map(|i| (i % 32, i / 32))
+                    .collect();
+                let xs = drawn.iter().map(|(x, _)| *x);
+                let ys = drawn.iter().map(|(_, y)| *y);
+                (
+                    xs.clone().min().unwrap(),
+                    ys.clone().min().unwrap(),
+                    xs.max().unwrap(),
+                    ys.max().unwrap(),
+                )
+            })
+        };
+        let near = require_gpu!(bounds(1.0));
+        // Two pixels per font pixel: the glyph is 10 wide and its 7 rows of
+        // capital sit in the top of a 16 pixel tall cell around the center.
+        assert_eq!(near, (11, 8, 20, 21));
+        assert_eq!(bounds(2.0).unwrap(), near);
+    }
+}
@@ -2,8 +2,12 @@ use core::panic;
 use std::ops::Mul;
 
 use bytemuck::{Pod, Zeroable};
-use glam::{Affine3A, EulerRot, Mat4, Quat, Vec3, Vec4};
+use glam::{Affine3A, EulerRot, Mat3, Mat4, Quat, Vec3, Vec4};
 
+/// Placement of a primitive or mesh in the world: translation, rotation and
+/// uniform scale. This is the transform every `draw_*` call and scene node
+/// takes; it is converted to [`TransformGpu`] only when it is written into an
+/// instance buffer.
 #[derive(Clone, Copy, Debug)]
 pub struct Transform {
     translate: Vec3,
@@ -122,6 +126,29 @@ impl Transform {
         }
     }
 
+    /// Places the transform at `eye` and rotates it so that its local `-Z`
+    /// axis (the forward axis of a right-handed camera) points towards
+    /// `target` and its local `+Y` axis is as close to `up` as possible.
+    ///
+    /// When the view direction is parallel to `up` an alternate up axis is
+    /// used instead. When `eye` equals `target` the rotation is the identity.
+    pub fn looking_at(eye: Vec3, target: Vec3, up: Vec3) -> Self {
+        let Some(forward) = (target - eye).try_normalize() else {
+            return Self::from_translation(&eye);
+        };
+        let right = forward
+            .cross(up)
+            .try_normalize()
+            .or_else(|| forward.cross(Vec3::Z).try_normalize())
+            .unwrap_or_else(|| forward.cross(Vec3::X).normalize());
+        let up = right.cross(forward);
+        Self {
+            translate: eye,
+            scale: 1.0,
+            rotate: Quat::from_mat3(&Mat3::from_cols(right, up, -forward)),
+        }
+    }
+
     pub fn from_columns(columns: &[[f32; 4]; 4]) -> Self {
         let transform = Mat4::from_cols(
          Vec4::from_array(columns[0]),
@@ -158,6 +185,39 @@ impl Transform {
     pub fn set_scale(&mut self, scale: f32) {
         self.scale = scale;
     }
+
+    pub fn translation(&self) -> Vec3 {
+        self.translate
+    }
+
+    pub fn rotation(&self) -> Quat {
+        self.rotate
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Whether the rotation only turns around the z-axis, i.e. it keeps the
+    /// x-y plane in place.
+    pub fn is_rotation_around_z(&self) -> bool {
+        const TOLERANCE: f32 = 1e-6;
+        self.rotate.x.abs() < TOLERANCE && self.rotate.y.abs() < TOLERANCE
+    }
+
+    /// Blends between `self` and `other`. Translation and scale are
+    /// interpolated linearly and rotation spherically (`Quat::slerp`).
+    ///
+    /// `t` is clamped to `[0, 1]`, so `t <= 0` returns `self` and `t >= 1`
+    /// returns `other`.
+    pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+        let t = t.clamp(0.0, 1.0);
+        Transform {
+            translate: self.translate.lerp(other.translate, t),
+            scale: self.scale + (other.scale - self.scale) * t,
+            rotate: self.rotate.slerp(other.rotate, t),
+        }
+    }
 }
 
 impl Default for Transform {
@@ -190,6 +250,9 @@ impl<'a> Mul<&'a Transform> for &'a Transform {
     }
 }
 
+/// [`Transform`] as it is laid out in instance buffers: the four columns of
+/// its affine matrix, read by shaders through [`TransformGpu::vertex_attributes`].
+/// It converts back into a [`Transform`], which is exact up to float rounding.
 #[derive(Debug, Copy, Clone, Zeroable, Pod)]
 #[repr(C, packed)]
 pub struct TransformGpu([f32; 12]);
@@ -209,14 +272,20 @@ impl From<Transform> for TransformGpu {
 
 impl<'a> From<&'a Transform> for TransformGpu {
     fn from(value: &'a Transform) -> Self {
-        TransformGpu(
-            Affine3A::from_scale_rotation_translation(
-                Vec3::splat(value.scale),
-                value.rotate,
-                value.translate,
-            )
-            .to_cols_array(),
-        )
+        (*value).into()
+    }
+}
+
+impl From<TransformGpu> for Transform {
+    fn from(value: TransformGpu) -> Self {
+        let columns = value.0;
+        let (scale, rotate, translate) =
+            Affine3A::from_cols_array(&columns).to_scale_rotation_translation();
+        Self {
+            translate,
+            scale: scale.x,
+            rotate,
+        }
     }
 }
 
@@ -251,3 +320,82 @@ impl TransformGpu {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use glam::vec3;
+
+    use super::*;
+
+    fn assert_transform_eq(a: &Transform, b: &Transform) {
+        assert!(a.translate.abs_diff_eq(b.translate, 1e-5), "{a:?} != {b:?}");
+        assert!((a.scale - b.scale).abs() < 1e-5, "{a:?} != {b:?}");
+        assert!(a.rotate.abs_diff_eq(b.rotate, 1e-5), "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn lerp_endpoints_and_midpoint() {
+        let a = Transform::from_translation_rotation_scale(
+            &vec3(1.0, 2.0, 3.0),
+            &Quat::from_rotation_z(0.0),
+            1.0,
+        );
+        let b = Transform::from_translation_rotation_scale(
+            &vec3(3.0, 6.0, -1.0),
+            &Quat::from_rotation_z(PI / 2.0),
+            3.0,
+        );
+
+        assert_transform_eq(&a.lerp(&b, 0.0), &a);
+        assert_transform_eq(&a.lerp(&b, 1.0), &b);
+        assert_transform_eq(&a.lerp(&b, -1.0), &a);
+        assert_transform_eq(&a.lerp(&b, 2.0), &b);
+
+        let mid = Transform::from_translation_rotation_scale(
+            &vec3(2.0, 4.0, 1.0),
+            &Quat::from_rotation_z(PI / 4.0),
+            2.0,
+        );
+        assert_transform_eq(&a.lerp(&b, 0.5), &mid);
+    }
+
+    #[test]
+    fn gpu_representation_round_trips() {
+        let transforms = [
+            Transform::IDENTITY,
+            Transform::from_translation(&vec3(1.0, -2.0, 3.0)),
+            Transform::from_translation_rotation_scale(
+                &vec3(-4.0, 0.5, 2.0),
+                &Quat::from_euler(EulerRot::XYZ, 0.3, -1.2, 2.5),
+                2.5,
+            ),
+        ];
+        for transform in transforms {
+            let gpu: TransformGpu = (&transform).into();
+            assert_transform_eq(&Transform::from(gpu), &transform);
+            let gpu: TransformGpu = transform.into();
+            assert_transform_eq(&gpu.into(), &transform);
+        }
+    }
+
+    #[test]
+    fn looking_at_points_forward_axis_at_target() {
+        let eye = vec3(1.0, 2.0, 3.0);
+        let target = vec3(-4.0, 0.0, 7.0);
+        let transform = Transform::looking_at(eye, target, Vec3::Y);
+        let forward = transform.rotate * Vec3::NEG_Z;
+        assert!(forward.abs_diff_eq((target - eye).normalize(), 1e-5));
+        assert!((transform.rotate * Vec3::X).dot(Vec3::Y).abs() < 1e-5);
+        assert_eq!(transform.translate, eye);
+    }
+
+    #[test]
+    fn looking_at_along_up_uses_alternate_up() {
+        let transform = Transform::looking_at(Vec3::ZERO, vec3(0.0, 5.0, 0.0), Vec3::Y);
+        let forward = transform.rotate * Vec3::NEG_Z;
+        assert!(forward.abs_diff_eq(Vec3::Y, 1e-5));
+        assert!(transform.rotate.is_normalized());
+    }
+}
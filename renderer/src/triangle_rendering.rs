@@ -0,0 +1,172 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{Vec3, Vec4};
+use wgpu::vertex_attr_array;
+
+use crate::{
+    buffers::WriteableVecBuffer,
+    camera::Camera,
+    include_wgsl,
+    rendering_context::RenderingContext,
+    resource_store::{
+        pipeline_layout::PipelineLayoutDescriptor,
+        render_pipeline::{
+            FragmentState, RenderPipelineDescriptor, VertexBufferLayout, VertexState,
+        },
+        PipelineId,
+    },
+    transform::{Transform, TransformGpu},
+};
+
+/// Filled triangle given by its three corners in local space.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[repr(C, packed)]
+pub struct Triangle {
+    pub a: Vec3,
+    pub b: Vec3,
+    pub c: Vec3,
+    pub color: Vec4,
+}
+
+// SAFETY: `Triangle` is `repr(C, packed)` and consists solely of `f32`
+// vectors, so there is no padding and every bit pattern is valid.
+unsafe impl Zeroable for Triangle {}
+unsafe impl Pod for Triangle {}
+
+impl Triangle {
+    pub fn new(a: Vec3, b: Vec3, c: Vec3, color: Vec4) -> Self {
+        Self { a, b, c, color }
+    }
+}
+
+pub struct TriangleRendering {
+    triangles: Vec<Triangle>,
+    triangles_buffer: WriteableVecBuffer<Triangle>,
+    triangles_transforms: Vec<TransformGpu>,
+    triangles_transforms_buffer: WriteableVecBuffer<TransformGpu>,
+    triangle_pipeline: PipelineId,
+}
+
+impl TriangleRendering {
+    pub fn new(rendering_context: &mut RenderingContext) -> eyre::Result<Self> {
+        let triangles = Vec::new();
+        let triangles_buffer = WriteableVecBuffer::new(
+            &rendering_context.gpu_context,
+            "triangles buffer",
+            &triangles,
+            wgpu::BufferUsages::VERTEX,
+        );
+
+        let triangles_transforms = Vec::new();
+        let triangles_transforms_buffer = WriteableVecBuffer::new(
+            &rendering_context.gpu_context,
+            "triangles transforms buffer",
+            &triangles_transforms,
+            wgpu::BufferUsages::VERTEX,
+        );
+
+        let triangle_shader_id = rendering_context
+            .resource_store
+            .build_shader(&include_wgsl!("../shaders/triangle.wgsl"))?;
+
+        let targets: Vec<Option<wgpu::ColorTargetState>> = vec![Some(wgpu::ColorTargetState {
+            format: rendering_context.primary_camera.surface_format(),
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::ALL,
+        })];
+
+        let triangle_pipeline_layout_id =
+            rendering_context
+                .resource_store
+                .build_pipeline_layout(&PipelineLayoutDescriptor {
+                    label: "triangle pipeline layout".to_string(),
+                    bind_group_layouts: vec![*rendering_context.primary_camera.bing_group_layout()],
+                    push_constant_ranges: Vec::new(),
+                });
+
+        let triangle_pipeline =
+            rendering_context
+                .resource_store
+                .build_render_pipeline(&RenderPipelineDescriptor {
+                    label: "triangle pipeline".to_string(),
+                    layout: Some(triangle_pipeline_layout_id),
+                    vertex: VertexState {
+                        module: triangle_shader_id,
+                        buffers: vec![
+                            VertexBufferLayout {
+                                array_stride: std::mem::size_of::<TransformGpu>() as u64,
+                                step_mode: wgpu::VertexStepMode::Instance,
+                                attributes: TransformGpu::vertex_attributes(1, 2, 3, 4),
+                            },
+                            VertexBufferLayout {
+                                array_stride: std::mem::size_of::<Triangle>() as u64,
+                                step_mode: wgpu::VertexStepMode::Instance,
+                                attributes: vertex_attr_array![5 => Float32x3, 6 => Float32x3, 7 => Float32x3, 8 => Float32x4]
+                                    .to_vec(),
+                            },
+                        ],
+                    },
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        // Corners can come in either winding order.
+                        cull_mode: None,
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: rendering_context.primary_camera.depth_stencil(),
+                    multisample: rendering_context.primary_camera.multisample_state(),
+                    fragment: Some(FragmentState {
+                        module: triangle_shader_id,
+                        targets,
+                    }),
+                    multiview: None,
+                });
+
+        Ok(Self {
+            triangles,
+            triangles_buffer,
+            triangles_transforms,
+            triangles_transforms_buffer,
+            triangle_pipeline,
+        })
+    }
+
+    pub fn add_triangle(&mut self, transform: &Transform, triangle: &Triangle) {
+        self.triangles.push(*triangle);
+        self.triangles_transforms.push(transform.into());
+    }
+
+    pub fn render<'a>(
+        &'a mut self,
+        rendering_context: &'a RenderingContext,
+        camera: &'a Camera,
+        render_pass: &mut wgpu::RenderPass<'a>,
+    ) {
+        self.triangles_buffer.write_data(
+            &rendering_context.gpu_context,
+            &self.triangles,
+            &rendering_context.buffer_shrink_policy,
+        );
+        self.triangles_transforms_buffer.write_data(
+            &rendering_context.gpu_context,
+            &self.triangles_transforms,
+            &rendering_context.buffer_shrink_policy,
+        );
+        if !self.triangles.is_empty() {
+            let pipeline = &rendering_context
+                .resource_store
+                .get_render_pipeline(self.triangle_pipeline);
+
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, camera.bing_group(), &[]);
+            render_pass.set_vertex_buffer(0, self.triangles_transforms_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, self.triangles_buffer.slice(..));
+            render_pass.draw(0..3, 0..(self.triangles.len() as u32));
+
+            self.triangles.clear();
+            self.triangles_transforms.clear();
+        }
+    }
+}